@@ -1,4 +1,4 @@
-use crate::utils::Type;
+use crate::utils::{Span, Type};
 use std::fmt;
 
 #[derive(Debug, PartialEq)]
@@ -7,28 +7,82 @@ pub struct Program {
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub enum Statement {
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+impl Statement {
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Statement { kind, span }
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum StatementKind {
     VarInitialization(String, Type),
     NewAssignment(String, Type, Expression),
     Assignment(String, Expression),
+    /// `a[index] := value;`
+    IndexAssignment(String, Expression, Expression),
     Print(Expression),
     Assert(Expression),
     Read(String),
-    For(String, Expression, Expression, Vec<Box<Statement>>),
+    For(String, Expression, Expression, Vec<Statement>),
+    Routine(Routine),
+    Call(String, Vec<Expression>),
+    Return(Option<Expression>),
 }
 
-impl fmt::Display for Statement {
+/// A `procedure`, or a `function` when it has a return type.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Routine {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub return_type: Option<Type>,
+    pub body: Vec<Statement>,
+}
+
+impl Routine {
+    /// `procedure` or `function`, the keyword opening and closing it.
+    pub fn keyword(&self) -> &'static str {
+        match self.return_type {
+            Some(_) => "function",
+            None => "procedure",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Parameter {
+    pub name: String,
+    pub type_def: Type,
+    pub span: Span,
+}
+
+impl fmt::Display for StatementKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Statement::VarInitialization(id, type_def) => write!(f, "var {} : {};", id, type_def),
-            Statement::NewAssignment(id, type_def, exp) => {
+            StatementKind::VarInitialization(id, type_def) => {
+                write!(f, "var {} : {};", id, type_def)
+            }
+            StatementKind::NewAssignment(id, type_def, exp) => {
                 write!(f, "var {} : {} := {};", id, type_def, exp)
-            },
-            Statement::Read(id) => write!(f, "read {}", id),
-            Statement::Assignment(id, exp) => write!(f, "{} := {};", id, exp),
-            Statement::Print(exp) => write!(f, "print {};", exp),
-            Statement::Assert(exp) => write!(f, "assert ({});", exp),
-            Statement::For(id, exp1, exp2, stmts) => {
+            }
+            StatementKind::Read(id) => write!(f, "read {}", id),
+            StatementKind::Assignment(id, exp) => write!(f, "{} := {};", id, exp),
+            StatementKind::IndexAssignment(id, index, exp) => {
+                write!(f, "{}[{}] := {};", id, index, exp)
+            }
+            StatementKind::Print(exp) => write!(f, "print {};", exp),
+            StatementKind::Assert(exp) => write!(f, "assert ({});", exp),
+            StatementKind::For(id, exp1, exp2, stmts) => {
                 let statements = stmts
                     .iter()
                     .map(|stmt| format!("\t{}", stmt))
@@ -40,33 +94,94 @@ impl fmt::Display for Statement {
                     id, exp1, exp2, statements
                 )
             }
+            StatementKind::Routine(routine) => {
+                let parameters = routine
+                    .parameters
+                    .iter()
+                    .map(|param| format!("{} : {}", param.name, param.type_def))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                let return_type = match &routine.return_type {
+                    Some(type_def) => format!(" : {}", type_def),
+                    None => String::new(),
+                };
+                let statements = routine
+                    .body
+                    .iter()
+                    .map(|stmt| format!("\t{}", stmt))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                write!(
+                    f,
+                    "{} {}({}){}\n{}\nend {}",
+                    routine.keyword(),
+                    routine.name,
+                    parameters,
+                    return_type,
+                    statements,
+                    routine.keyword()
+                )
+            }
+            StatementKind::Call(name, args) => write!(f, "{}({});", name, join(args)),
+            StatementKind::Return(Some(exp)) => write!(f, "return {};", exp),
+            StatementKind::Return(None) => write!(f, "return;"),
         }
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub enum Expression {
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Expression { kind, span }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExpressionKind {
     Identifier(String),
-    IntegerConstant(i32),
+    IntegerConstant(i64),
     StringValue(String),
     Boolean(bool),
     Unary(UnaryOperator, Box<Expression>),
     Binary(Box<Expression>, BinaryOperator, Box<Expression>),
+    Call(String, Vec<Expression>),
+    /// Element of the array variable `a[index]`.
+    Index(String, Box<Expression>),
 }
 
-impl fmt::Display for Expression {
+impl fmt::Display for ExpressionKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Expression::Identifier(id) => write!(f, "{}", id),
-            Expression::IntegerConstant(int) => write!(f, "{}", int),
-            Expression::StringValue(s) => write!(f, "\"{}\"", s),
-            Expression::Boolean(b) => write!(f, "{}", b),
-            Expression::Unary(op, exp) => write!(f, "({} {})", op, exp),
-            Expression::Binary(exp1, op, exp2) => write!(f, "({}, {}, {})", exp1, op, exp2),
+            ExpressionKind::Identifier(id) => write!(f, "{}", id),
+            ExpressionKind::IntegerConstant(int) => write!(f, "{}", int),
+            ExpressionKind::StringValue(s) => write!(f, "\"{}\"", s),
+            ExpressionKind::Boolean(b) => write!(f, "{}", b),
+            ExpressionKind::Unary(op, exp) => write!(f, "({} {})", op, exp),
+            ExpressionKind::Binary(exp1, op, exp2) => write!(f, "({}, {}, {})", exp1, op, exp2),
+            ExpressionKind::Call(name, args) => write!(f, "{}({})", name, join(args)),
+            ExpressionKind::Index(id, index) => write!(f, "{}[{}]", id, index),
         }
     }
 }
 
+fn join(args: &[Expression]) -> String {
+    args.iter()
+        .map(|arg| arg.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum UnaryOperator {
     Not,
@@ -89,7 +204,7 @@ pub enum BinaryOperator {
     And,
     Equals,
     LessThan,
-    GreaterThan
+    GreaterThan,
 }
 
 impl fmt::Display for BinaryOperator {
@@ -107,3 +222,78 @@ impl fmt::Display for BinaryOperator {
         write!(f, "{}", output)
     }
 }
+
+#[cfg(test)]
+impl Statement {
+    /// Copy of the statement with every span reset, so parser output can be
+    /// compared against hand-written trees.
+    pub fn without_spans(&self) -> Statement {
+        let kind = match &self.kind {
+            StatementKind::NewAssignment(id, type_def, exp) => {
+                StatementKind::NewAssignment(id.clone(), type_def.clone(), exp.without_spans())
+            }
+            StatementKind::Assignment(id, exp) => {
+                StatementKind::Assignment(id.clone(), exp.without_spans())
+            }
+            StatementKind::IndexAssignment(id, index, exp) => StatementKind::IndexAssignment(
+                id.clone(),
+                index.without_spans(),
+                exp.without_spans(),
+            ),
+            StatementKind::Print(exp) => StatementKind::Print(exp.without_spans()),
+            StatementKind::Assert(exp) => StatementKind::Assert(exp.without_spans()),
+            StatementKind::For(id, exp1, exp2, stmts) => StatementKind::For(
+                id.clone(),
+                exp1.without_spans(),
+                exp2.without_spans(),
+                stmts.iter().map(Statement::without_spans).collect(),
+            ),
+            StatementKind::Routine(routine) => StatementKind::Routine(Routine {
+                parameters: routine
+                    .parameters
+                    .iter()
+                    .map(|param| Parameter {
+                        span: Span::default(),
+                        ..param.clone()
+                    })
+                    .collect(),
+                body: routine.body.iter().map(Statement::without_spans).collect(),
+                ..routine.clone()
+            }),
+            StatementKind::Call(name, args) => StatementKind::Call(
+                name.clone(),
+                args.iter().map(Expression::without_spans).collect(),
+            ),
+            StatementKind::Return(exp) => {
+                StatementKind::Return(exp.as_ref().map(Expression::without_spans))
+            }
+            other => other.clone(),
+        };
+        Statement::new(kind, Span::default())
+    }
+}
+
+#[cfg(test)]
+impl Expression {
+    pub fn without_spans(&self) -> Expression {
+        let kind = match &self.kind {
+            ExpressionKind::Unary(op, exp) => {
+                ExpressionKind::Unary(op.clone(), Box::new(exp.without_spans()))
+            }
+            ExpressionKind::Binary(exp1, op, exp2) => ExpressionKind::Binary(
+                Box::new(exp1.without_spans()),
+                op.clone(),
+                Box::new(exp2.without_spans()),
+            ),
+            ExpressionKind::Call(name, args) => ExpressionKind::Call(
+                name.clone(),
+                args.iter().map(Expression::without_spans).collect(),
+            ),
+            ExpressionKind::Index(id, index) => {
+                ExpressionKind::Index(id.clone(), Box::new(index.without_spans()))
+            }
+            other => other.clone(),
+        };
+        Expression::new(kind, Span::default())
+    }
+}
@@ -0,0 +1,583 @@
+//! Translation of a checked program into a single C99 file, compiled with
+//! any C compiler, `cc -o program program.c`.
+//!
+//! Every Mini-PL variable becomes a C variable in the corresponding block,
+//! with `v_` before its name, and every procedure and function a C function
+//! with `f_` before its name. Subexpressions are computed into temporaries
+//! in the order the evaluator evaluates them, so errors and output happen in
+//! the same order. Statements record their position first, so that runtime
+//! errors, which are reported by the runtime in `runtime.c`, name the same
+//! statement as the interpreter would.
+
+use crate::ast::{
+    BinaryOperator, Expression, ExpressionKind, Program, Routine, Statement, StatementKind,
+    UnaryOperator,
+};
+use crate::backend::{mangle, with_error_codes};
+use crate::builtins::{self, SIZE};
+use crate::checker::SpannedCheckError;
+use crate::explain::Coded;
+use crate::utils::{CheckError, EvalError, IntegerWidth, Span, Type};
+use std::collections::HashMap;
+
+const RUNTIME: &str = include_str!("runtime.c");
+
+const INDENT: &str = "    ";
+
+pub struct CBackend {
+    integer_width: IntegerWidth,
+    string_limit: Option<usize>,
+}
+
+impl Default for CBackend {
+    fn default() -> Self {
+        CBackend::new()
+    }
+}
+
+impl CBackend {
+    pub fn new() -> Self {
+        CBackend {
+            integer_width: IntegerWidth::default(),
+            string_limit: None,
+        }
+    }
+
+    /// Makes integers outside `width` an error, as in an evaluator with the
+    /// same width.
+    pub fn with_integer_width(mut self, width: IntegerWidth) -> Self {
+        self.integer_width = width;
+        self
+    }
+
+    /// Makes strings longer than `limit` bytes an error.
+    pub fn with_string_limit(mut self, limit: usize) -> Self {
+        self.string_limit = Some(limit);
+        self
+    }
+
+    /// C source of `program`, which must have passed the checker. Calls to
+    /// host functions, which only exist in the interpreter, are errors.
+    pub fn emit_program(&self, program: &Program) -> Result<String, SpannedCheckError> {
+        let mut generator = Generator::default();
+        let routines: Vec<&Routine> = program
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Routine(routine) => Some(routine),
+                _ => None,
+            })
+            .collect();
+        for routine in &routines {
+            let parameters = routine
+                .parameters
+                .iter()
+                .map(|param| param.type_def.clone())
+                .collect();
+            let signature = (parameters, routine.return_type.clone());
+            generator.routines.insert(routine.name.clone(), signature);
+        }
+
+        let (min, max) = match self.integer_width {
+            IntegerWidth::I32 => ("INT32_MIN", "INT32_MAX"),
+            IntegerWidth::I64 => ("INT64_MIN", "INT64_MAX"),
+        };
+        let string_limit = match self.string_limit {
+            Some(limit) => format!("((size_t){})", limit),
+            None => "SIZE_MAX".to_string(),
+        };
+        let mut output = String::from("/* Compiled from Mini-PL. */\n\n");
+        output.push_str(&format!("#define MPL_INT_MIN ((int64_t){})\n", min));
+        output.push_str(&format!("#define MPL_INT_MAX ((int64_t){})\n", max));
+        output.push_str(&format!("#define MPL_STRING_LIMIT {}\n\n", string_limit));
+        output.push_str(&with_error_codes(RUNTIME));
+        output.push('\n');
+        for routine in &routines {
+            output.push_str(&format!("{};\n", signature(routine)));
+        }
+        for routine in &routines {
+            output.push('\n');
+            output.push_str(&generator.emit_routine(routine)?);
+        }
+        output.push_str("\nint main(void) {\n");
+        generator.depth = 1;
+        generator.scopes = vec![HashMap::new()];
+        generator.emit_block(&program.statements)?;
+        generator.line("return 0;");
+        output.push_str(&generator.take_output());
+        output.push_str("}\n");
+        Ok(output)
+    }
+}
+
+/// A variable in scope. Variables declared without a value have a flag
+/// `a_<name>` that records whether they were assigned.
+struct Variable {
+    type_def: Type,
+    flagged: bool,
+}
+
+type Signature = (Vec<Type>, Option<Type>);
+
+#[derive(Default)]
+struct Generator {
+    output: String,
+    depth: usize,
+    temporaries: usize,
+    scopes: Vec<HashMap<String, Variable>>,
+    routines: HashMap<String, Signature>,
+}
+
+type GenResult<T> = Result<T, SpannedCheckError>;
+
+impl Generator {
+    fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    fn line(&mut self, text: &str) {
+        self.output.push_str(&INDENT.repeat(self.depth));
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    /// Stores `value` in a new temporary and returns its name.
+    fn temporary(&mut self, type_def: &Type, value: String) -> String {
+        self.temporaries += 1;
+        let name = format!("t{}", self.temporaries);
+        self.line(&format!("{} {} = {};", c_type(type_def), name, value));
+        name
+    }
+
+    fn emit_routine(&mut self, routine: &Routine) -> GenResult<String> {
+        let mut scope = HashMap::new();
+        for param in &routine.parameters {
+            let variable = Variable {
+                type_def: param.type_def.clone(),
+                flagged: false,
+            };
+            scope.insert(param.name.clone(), variable);
+        }
+        self.scopes = vec![scope];
+        self.depth = 1;
+        self.line("mpl_span mpl_caller = mpl_enter();");
+        self.emit_block(&routine.body)?;
+        self.line("mpl_leave(mpl_caller);");
+        if routine.return_type.is_some() {
+            let code = EvalError::MissingReturn(routine.name.clone()).code();
+            self.line(&format!(
+                "mpl_fail(\"{}\", \"Function %s ended without returning a value\", {});",
+                code,
+                c_string(&routine.name)
+            ));
+        }
+        Ok(format!(
+            "{} {{\n{}}}\n",
+            signature(routine),
+            self.take_output()
+        ))
+    }
+
+    fn emit_block(&mut self, statements: &[Statement]) -> GenResult<()> {
+        for statement in statements {
+            self.emit_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    fn emit_statement(&mut self, statement: &Statement) -> GenResult<()> {
+        let span = statement.span;
+        if let StatementKind::Routine(_) = statement.kind {
+            return Ok(());
+        }
+        self.line(&format!(
+            "mpl_at({}, {});",
+            span.start.line, span.start.column
+        ));
+        match &statement.kind {
+            StatementKind::VarInitialization(id, type_def) => {
+                let (value, flagged) = match type_def {
+                    Type::Array(size, _) => (format!("{}_new({})", c_type(type_def), size), false),
+                    Type::Integer => ("0".to_string(), true),
+                    Type::String => ("mpl_str(\"\", 0)".to_string(), true),
+                    Type::Boolean => ("false".to_string(), true),
+                };
+                self.line(&format!(
+                    "{} {} = {};",
+                    c_type(type_def),
+                    variable(id),
+                    value
+                ));
+                if flagged {
+                    self.line(&format!("bool {} = false;", flag(id)));
+                }
+                self.declare(id, type_def, flagged);
+            }
+            StatementKind::NewAssignment(id, type_def, exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.line(&format!(
+                    "{} {} = {};",
+                    c_type(type_def),
+                    variable(id),
+                    value
+                ));
+                self.declare(id, type_def, false);
+            }
+            StatementKind::Assignment(id, exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.assign(id, value, span)?;
+            }
+            StatementKind::IndexAssignment(id, index, exp) => {
+                let (index, _) = self.emit_expression(index)?;
+                let (value, _) = self.emit_expression(exp)?;
+                let type_def = self.lookup(id, span)?.type_def.clone();
+                self.line(&format!(
+                    "{}_set({}, {}, {});",
+                    c_type(&type_def),
+                    variable(id),
+                    index,
+                    value
+                ));
+            }
+            StatementKind::Print(exp) => {
+                let (value, type_def) = self.emit_expression(exp)?;
+                let function = match type_def {
+                    Type::Integer => "mpl_print_int",
+                    Type::Boolean => "mpl_print_bool",
+                    Type::String => "mpl_print_string",
+                    Type::Array(..) => return Err(unsupported("print", type_def, exp.span)),
+                };
+                self.line(&format!("{}({});", function, value));
+            }
+            StatementKind::Assert(exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                let message = format!("Assertion failed: {}\n", exp);
+                self.line(&format!(
+                    "mpl_assert({}, {});",
+                    value,
+                    string_literal(&message)
+                ));
+            }
+            StatementKind::Read(id) => {
+                let type_def = self.lookup(id, span)?.type_def.clone();
+                let value = match type_def {
+                    Type::Integer => "mpl_read_int()",
+                    Type::String => "mpl_read_string()",
+                    _ => return Err(unsupported("read", type_def, span)),
+                };
+                self.assign(id, value.to_string(), span)?;
+            }
+            StatementKind::For(id, start, end, stmts) => {
+                let (start, _) = self.emit_expression(start)?;
+                let (end, _) = self.emit_expression(end)?;
+                // The body may assign the variables of `end`, which is
+                // evaluated once, as in the evaluator.
+                let end = self.temporary(&Type::Integer, end);
+                self.temporaries += 1;
+                let counter = format!("t{}", self.temporaries);
+                // Stopping at `end` before incrementing cannot overflow.
+                self.line(&format!(
+                    "for (int64_t {0} = {1}; {0} <= {2}; {0}++) {{",
+                    counter, start, end
+                ));
+                self.depth += 1;
+                self.assign(id, counter.clone(), span)?;
+                self.scopes.push(HashMap::new());
+                self.emit_block(stmts)?;
+                self.scopes.pop();
+                self.line(&format!("if ({} == {}) {{", counter, end));
+                self.line(&format!("{}break;", INDENT));
+                self.line("}");
+                self.depth -= 1;
+                self.line("}");
+            }
+            StatementKind::Routine(_) => {}
+            StatementKind::Call(name, args) => {
+                let (call, return_type) = self.emit_call(name, args, span)?;
+                match return_type {
+                    Some(_) => self.line(&format!("(void){};", call)),
+                    None => self.line(&format!("{};", call)),
+                }
+            }
+            StatementKind::Return(exp) => {
+                let value = match exp {
+                    Some(exp) => Some(self.emit_expression(exp)?.0),
+                    None => None,
+                };
+                self.line("mpl_leave(mpl_caller);");
+                match value {
+                    Some(value) => self.line(&format!("return {};", value)),
+                    None => self.line("return;"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Emits the computation of `exp` and returns a side-effect free C
+    /// expression for its value, with its type.
+    fn emit_expression(&mut self, exp: &Expression) -> GenResult<(String, Type)> {
+        let span = exp.span;
+        match &exp.kind {
+            ExpressionKind::IntegerConstant(int) => Ok((integer_literal(*int), Type::Integer)),
+            ExpressionKind::StringValue(s) => Ok((string_literal(s), Type::String)),
+            ExpressionKind::Boolean(b) => Ok((b.to_string(), Type::Boolean)),
+            ExpressionKind::Identifier(id) => {
+                let variable = self.lookup(id, span)?;
+                let type_def = variable.type_def.clone();
+                if variable.flagged {
+                    let check = format!("mpl_check_assigned({}, {});", flag(id), c_string(id));
+                    self.line(&check);
+                }
+                match type_def {
+                    Type::Array(..) => {
+                        let copy = format!("{}_copy({})", c_type(&type_def), self::variable(id));
+                        Ok((self.temporary(&type_def, copy), type_def))
+                    }
+                    _ => Ok((self::variable(id), type_def)),
+                }
+            }
+            ExpressionKind::Unary(UnaryOperator::Not, operand) => {
+                let (operand, _) = self.emit_expression(operand)?;
+                let value = self.temporary(&Type::Boolean, format!("!{}", operand));
+                Ok((value, Type::Boolean))
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                let (a, type_def) = self.emit_expression(left)?;
+                let (b, _) = self.emit_expression(right)?;
+                let (value, result) = match (&type_def, op) {
+                    (Type::Integer, BinaryOperator::Plus) => {
+                        (call("mpl_add", &a, &b), Type::Integer)
+                    }
+                    (Type::Integer, BinaryOperator::Minus) => {
+                        (call("mpl_sub", &a, &b), Type::Integer)
+                    }
+                    (Type::Integer, BinaryOperator::Multiplication) => {
+                        (call("mpl_mul", &a, &b), Type::Integer)
+                    }
+                    (Type::Integer, BinaryOperator::Division) => {
+                        (call("mpl_div", &a, &b), Type::Integer)
+                    }
+                    (Type::Integer, BinaryOperator::Equals)
+                    | (Type::Boolean, BinaryOperator::Equals) => {
+                        (format!("{} == {}", a, b), Type::Boolean)
+                    }
+                    (Type::Integer, BinaryOperator::LessThan) => {
+                        (format!("{} < {}", a, b), Type::Boolean)
+                    }
+                    (Type::Integer, BinaryOperator::GreaterThan) => {
+                        (format!("{} > {}", a, b), Type::Boolean)
+                    }
+                    (Type::Boolean, BinaryOperator::And) => {
+                        (format!("{} && {}", a, b), Type::Boolean)
+                    }
+                    (Type::Boolean, BinaryOperator::LessThan) => {
+                        (format!("!{} && {}", a, b), Type::Boolean)
+                    }
+                    (Type::Boolean, BinaryOperator::GreaterThan) => {
+                        (format!("{} && !{}", a, b), Type::Boolean)
+                    }
+                    (Type::String, BinaryOperator::Plus) => {
+                        (call("mpl_concat", &a, &b), Type::String)
+                    }
+                    (Type::String, BinaryOperator::Equals) => {
+                        (call("mpl_equals", &a, &b), Type::Boolean)
+                    }
+                    (Type::String, BinaryOperator::LessThan) => (
+                        format!("{} < 0", call("mpl_compare", &a, &b)),
+                        Type::Boolean,
+                    ),
+                    (Type::String, BinaryOperator::GreaterThan) => (
+                        format!("{} > 0", call("mpl_compare", &a, &b)),
+                        Type::Boolean,
+                    ),
+                    _ => return Err(unsupported(&op.to_string(), type_def, span)),
+                };
+                Ok((self.temporary(&result, value), result))
+            }
+            ExpressionKind::Call(name, args) => match self.emit_call(name, args, span)? {
+                (call, Some(type_def)) => Ok((self.temporary(&type_def, call), type_def)),
+                (_, None) => Err((CheckError::NoReturnValue(name.clone()), span)),
+            },
+            ExpressionKind::Index(id, index) => {
+                let (index, _) = self.emit_expression(index)?;
+                let type_def = self.lookup(id, span)?.type_def.clone();
+                let element = match &type_def {
+                    Type::Array(_, element) => element.as_ref().clone(),
+                    _ => return Err(unsupported("[]", type_def, span)),
+                };
+                let value = format!("{}_get({}, {})", c_type(&type_def), variable(id), index);
+                Ok((self.temporary(&element, value), element))
+            }
+        }
+    }
+
+    /// Emits the arguments of a call and returns the call itself, which is
+    /// left to the caller to place, with the return type.
+    fn emit_call(
+        &mut self,
+        name: &str,
+        args: &[Expression],
+        span: Span,
+    ) -> GenResult<(String, Option<Type>)> {
+        if let Some(builtin) = builtins::find(name) {
+            // The size of a variable is known from its type, without copying it.
+            if let (SIZE, [array]) = (name, args) {
+                if let ExpressionKind::Identifier(id) = &array.kind {
+                    if let Type::Array(size, _) = self.lookup(id, array.span)?.type_def {
+                        return Ok((integer_literal(size as i64), Some(Type::Integer)));
+                    }
+                }
+            }
+            let mut values = Vec::new();
+            for arg in args {
+                values.push(self.emit_expression(arg)?.0);
+            }
+            let call = match (builtin.name, values.as_slice()) {
+                (SIZE, [array]) => format!("(int64_t){}.size", array),
+                (name, values) => format!("mpl_{}({})", name, values.join(", ")),
+            };
+            return Ok((call, Some(builtin.return_type.clone())));
+        }
+        let return_type = match self.routines.get(name) {
+            Some((_, return_type)) => return_type.clone(),
+            None => return Err((CheckError::UndeclaredRoutine(name.to_string()), span)),
+        };
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.emit_expression(arg)?.0);
+        }
+        Ok((
+            format!("{}({})", function(name), values.join(", ")),
+            return_type,
+        ))
+    }
+
+    fn declare(&mut self, id: &str, type_def: &Type, flagged: bool) {
+        let variable = Variable {
+            type_def: type_def.clone(),
+            flagged,
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(id.to_string(), variable);
+        }
+    }
+
+    fn lookup(&self, id: &str, span: Span) -> GenResult<&Variable> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(id)) {
+            Some(variable) => Ok(variable),
+            None => Err((CheckError::UndeclaredVariable(id.to_string()), span)),
+        }
+    }
+
+    fn assign(&mut self, id: &str, value: String, span: Span) -> GenResult<()> {
+        let flagged = self.lookup(id, span)?.flagged;
+        self.line(&format!("{} = {};", variable(id), value));
+        if flagged {
+            self.line(&format!("{} = true;", flag(id)));
+        }
+        Ok(())
+    }
+}
+
+fn unsupported(op: &str, type_def: Type, span: Span) -> SpannedCheckError {
+    (
+        CheckError::UnsupportedOperation(op.to_string(), type_def),
+        span,
+    )
+}
+
+fn call(function: &str, a: &str, b: &str) -> String {
+    format!("{}({}, {})", function, a, b)
+}
+
+fn signature(routine: &Routine) -> String {
+    let parameters: Vec<String> = routine
+        .parameters
+        .iter()
+        .map(|param| format!("{} {}", c_type(&param.type_def), variable(&param.name)))
+        .collect();
+    let return_type = match &routine.return_type {
+        Some(type_def) => c_type(type_def),
+        None => "void",
+    };
+    let parameters = match parameters.is_empty() {
+        true => "void".to_string(),
+        false => parameters.join(", "),
+    };
+    format!(
+        "static {} {}({})",
+        return_type,
+        function(&routine.name),
+        parameters
+    )
+}
+
+fn c_type(type_def: &Type) -> &'static str {
+    match type_def {
+        Type::Integer => "int64_t",
+        Type::Boolean => "bool",
+        Type::String => "mpl_string",
+        Type::Array(_, element) => match element.as_ref() {
+            Type::Boolean => "mpl_bools",
+            Type::String => "mpl_strings",
+            _ => "mpl_ints",
+        },
+    }
+}
+
+fn variable(id: &str) -> String {
+    mangle("v_", id)
+}
+
+fn flag(id: &str) -> String {
+    mangle("a_", id)
+}
+
+fn function(name: &str) -> String {
+    mangle("f_", name)
+}
+
+fn integer_literal(int: i64) -> String {
+    match int {
+        i64::MIN => "INT64_MIN".to_string(),
+        int if int < 0 => format!("(-INT64_C({}))", int.unsigned_abs()),
+        int => format!("INT64_C({})", int),
+    }
+}
+
+fn string_literal(s: &str) -> String {
+    format!("mpl_str({}, {})", c_string(s), s.len())
+}
+
+/// `s` as a C string literal. Bytes outside printable ASCII are written as
+/// three-digit octal escapes, which cannot run into a following digit.
+fn c_string(s: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in s.bytes() {
+        match byte {
+            b'"' | b'\\' | b'?' => {
+                literal.push('\\');
+                literal.push(byte as char);
+            }
+            b' '..=b'~' => literal.push(byte as char),
+            byte => literal.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::c::{c_string, integer_literal};
+    use crate::backend::mangle;
+
+    #[test]
+    fn escape_for_c() {
+        assert_eq!(c_string("a\"b\\?\n1ä"), "\"a\\\"b\\\\\\?\\0121\\303\\244\"");
+        assert_eq!(integer_literal(-5), "(-INT64_C(5))");
+        assert_eq!(integer_literal(i64::MIN), "INT64_MIN");
+        assert_eq!(mangle("v_", "ä_1"), "v__u00e4__1");
+    }
+}
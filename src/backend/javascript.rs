@@ -0,0 +1,470 @@
+//! Translation of a checked program into JavaScript for Node.js, to show
+//! learners the same program in a familiar language.
+//!
+//! Like the Python translation, the code keeps the structure of the
+//! original and calls functions of `runtime.js` where JavaScript behaves
+//! differently, such as for overflow, division and string comparison.
+//! Integers are JavaScript numbers, so only `i32` integers are supported.
+//! Errors stop the program with the evaluator's message, but without its
+//! line and column.
+
+use crate::ast::{
+    BinaryOperator, Expression, ExpressionKind, Program, Routine, Statement, StatementKind,
+    UnaryOperator,
+};
+use crate::backend::{quoted, with_error_codes};
+use crate::builtins::{self, SIZE};
+use crate::checker::SpannedCheckError;
+use crate::utils::{CheckError, Span, Type};
+use std::collections::HashMap;
+
+const RUNTIME: &str = include_str!("runtime.js");
+
+const INDENT: &str = "  ";
+
+/// Names that Mini-PL programs may use but that mean something else in the
+/// generated code: keywords, built-ins and the runtime's names. They are
+/// written with a leading underscore, which Mini-PL identifiers never have.
+const RESERVED: &[&str] = &[
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+    "arguments",
+    "eval",
+    "undefined",
+    "NaN",
+    "Infinity",
+    "Array",
+    "BigInt",
+    "Boolean",
+    "Buffer",
+    "Error",
+    "Math",
+    "Number",
+    "String",
+    "process",
+    "require",
+    "fs",
+    "MIN_INT",
+    "MAX_INT",
+    "MAX_DEPTH",
+    "depth",
+    "Failure",
+    "check",
+    "div",
+    "and",
+    "compare",
+    "init",
+    "at",
+    "store",
+    "range",
+    "enter",
+    "func",
+    "procedure",
+    "write",
+    "assert",
+    "readString",
+    "parseInt64",
+    "readInt",
+    "checkBig",
+    "length",
+    "substr",
+    "quote",
+    "toInt",
+    "run",
+    "main",
+];
+
+#[derive(Default)]
+pub struct JavaScriptBackend {}
+
+impl JavaScriptBackend {
+    pub fn new() -> Self {
+        JavaScriptBackend {}
+    }
+
+    /// JavaScript source of `program`, which must have passed the checker
+    /// with `i32` integers. Calls to host functions, which only exist in the
+    /// interpreter, are errors.
+    pub fn emit_program(&self, program: &Program) -> Result<String, SpannedCheckError> {
+        let mut generator = Generator::default();
+        generator.output.push_str(&with_error_codes(RUNTIME));
+        generator.output.push_str("\n// Translated from Mini-PL.\n");
+        for statement in &program.statements {
+            if let StatementKind::Routine(routine) = &statement.kind {
+                generator.emit_routine(routine)?;
+            }
+        }
+        generator.output.push_str("\nrun(() => {\n");
+        generator.scopes = vec![HashMap::new()];
+        generator.emit_block(&program.statements)?;
+        generator.output.push_str("});\n");
+        Ok(generator.output)
+    }
+}
+
+/// A variable in scope. Variables declared without a value start as
+/// `undefined`, and reading them checks that they were assigned.
+struct Variable {
+    type_def: Type,
+    flagged: bool,
+}
+
+#[derive(Default)]
+struct Generator {
+    output: String,
+    depth: usize,
+    scopes: Vec<HashMap<String, Variable>>,
+    /// Return types of the routines declared so far.
+    routines: HashMap<String, Option<Type>>,
+}
+
+type GenResult<T> = Result<T, SpannedCheckError>;
+
+impl Generator {
+    fn line(&mut self, text: &str) {
+        self.output.push_str(&INDENT.repeat(self.depth));
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    fn emit_routine(&mut self, routine: &Routine) -> GenResult<()> {
+        self.routines
+            .insert(routine.name.clone(), routine.return_type.clone());
+        let mut scope = HashMap::new();
+        let mut parameters = Vec::new();
+        for param in &routine.parameters {
+            parameters.push(name(&param.name));
+            let variable = Variable {
+                type_def: param.type_def.clone(),
+                flagged: false,
+            };
+            scope.insert(param.name.clone(), variable);
+        }
+        let function = match routine.return_type {
+            Some(_) => format!("func({}, ", quoted(&routine.name)),
+            None => "procedure(".to_string(),
+        };
+        self.output.push('\n');
+        self.line(&format!(
+            "const {} = {}({}) => {{",
+            name(&routine.name),
+            function,
+            parameters.join(", ")
+        ));
+        self.scopes = vec![scope];
+        self.emit_block(&routine.body)?;
+        self.line("});");
+        Ok(())
+    }
+
+    /// Emits the statements one level deeper.
+    fn emit_block(&mut self, statements: &[Statement]) -> GenResult<()> {
+        self.depth += 1;
+        for statement in statements {
+            self.emit_statement(statement)?;
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn emit_statement(&mut self, statement: &Statement) -> GenResult<()> {
+        let span = statement.span;
+        match &statement.kind {
+            StatementKind::VarInitialization(id, type_def) => {
+                let value = match type_def {
+                    Type::Array(size, element) => {
+                        let fill = match element.as_ref() {
+                            Type::Boolean => "false",
+                            Type::String => "\"\"",
+                            _ => "0",
+                        };
+                        format!(" = new Array({}).fill({})", size, fill)
+                    }
+                    _ => String::new(),
+                };
+                self.line(&format!("let {}{};", name(id), value));
+                let flagged = !matches!(type_def, Type::Array(..));
+                self.declare(id, type_def, flagged);
+            }
+            StatementKind::NewAssignment(id, type_def, exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.line(&format!("let {} = {};", name(id), value));
+                self.declare(id, type_def, false);
+            }
+            StatementKind::Assignment(id, exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.lookup(id, span)?;
+                self.mark_assigned(id);
+                self.line(&format!("{} = {};", name(id), value));
+            }
+            StatementKind::IndexAssignment(id, index, exp) => {
+                let (index, _) = self.emit_expression(index)?;
+                let (value, _) = self.emit_expression(exp)?;
+                self.lookup(id, span)?;
+                self.line(&format!("store({}, {}, {});", name(id), index, value));
+            }
+            StatementKind::Print(exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.line(&format!("write({});", value));
+            }
+            StatementKind::Assert(exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                let display = quoted(&exp.to_string());
+                self.line(&format!("assert({}, {});", value, display));
+            }
+            StatementKind::Read(id) => {
+                let function = match &self.lookup(id, span)?.type_def {
+                    Type::Integer => "readInt",
+                    Type::String => "readString",
+                    type_def => return Err(unsupported("read", type_def.clone(), span)),
+                };
+                self.line(&format!("{} = {}();", name(id), function));
+                self.mark_assigned(id);
+            }
+            StatementKind::For(id, start, end, stmts) => {
+                let (start, _) = self.emit_expression(start)?;
+                let (end, _) = self.emit_expression(end)?;
+                self.lookup(id, span)?;
+                self.line(&format!(
+                    "for ({} of range({}, {})) {{",
+                    name(id),
+                    start,
+                    end
+                ));
+                self.scopes.push(HashMap::new());
+                self.mark_assigned(id);
+                self.emit_block(stmts)?;
+                self.scopes.pop();
+                self.line("}");
+            }
+            StatementKind::Routine(_) => {}
+            StatementKind::Call(id, args) => {
+                let (call, _) = self.emit_call(id, args, span)?;
+                self.line(&format!("{};", call));
+            }
+            StatementKind::Return(Some(exp)) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.line(&format!("return {};", value));
+            }
+            StatementKind::Return(None) => self.line("return;"),
+        }
+        Ok(())
+    }
+
+    /// `exp` in parentheses, unless it is a name, literal or call.
+    fn emit_operand(&mut self, exp: &Expression) -> GenResult<(String, Type)> {
+        let (value, type_def) = self.emit_expression(exp)?;
+        // Integer arithmetic and `&` become calls.
+        let atomic = match &exp.kind {
+            ExpressionKind::Unary(..) => false,
+            ExpressionKind::Binary(_, op, _) => {
+                type_def == Type::Integer || *op == BinaryOperator::And
+            }
+            _ => true,
+        };
+        match atomic {
+            true => Ok((value, type_def)),
+            false => Ok((format!("({})", value), type_def)),
+        }
+    }
+
+    fn emit_expression(&mut self, exp: &Expression) -> GenResult<(String, Type)> {
+        let span = exp.span;
+        match &exp.kind {
+            ExpressionKind::IntegerConstant(int) => Ok((int.to_string(), Type::Integer)),
+            ExpressionKind::StringValue(s) => Ok((quoted(s), Type::String)),
+            ExpressionKind::Boolean(b) => Ok((b.to_string(), Type::Boolean)),
+            ExpressionKind::Identifier(id) => {
+                let variable = self.lookup(id, span)?;
+                let type_def = variable.type_def.clone();
+                // Arrays are values, so reading one copies it.
+                let value = match (&type_def, variable.flagged) {
+                    (Type::Array(..), _) => format!("[...{}]", name(id)),
+                    (_, true) => format!("init({}, {})", name(id), quoted(id)),
+                    (_, false) => name(id),
+                };
+                self.mark_assigned(id);
+                Ok((value, type_def))
+            }
+            ExpressionKind::Unary(UnaryOperator::Not, operand) => {
+                let (operand, _) = self.emit_operand(operand)?;
+                Ok((format!("!{}", operand), Type::Boolean))
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                let (a, type_def) = self.emit_operand(left)?;
+                let (b, _) = self.emit_operand(right)?;
+                let (value, result) = match (&type_def, op) {
+                    (Type::Integer, BinaryOperator::Division) => {
+                        (format!("div({}, {})", a, b), Type::Integer)
+                    }
+                    (
+                        Type::Integer,
+                        BinaryOperator::Plus
+                        | BinaryOperator::Minus
+                        | BinaryOperator::Multiplication,
+                    ) => (format!("check({} {} {})", a, op, b), Type::Integer),
+                    (Type::String, BinaryOperator::Plus) => {
+                        (format!("{} + {}", a, b), Type::String)
+                    }
+                    (Type::Boolean, BinaryOperator::And) => {
+                        (format!("and({}, {})", a, b), Type::Boolean)
+                    }
+                    (Type::Array(..), _) => {
+                        return Err(unsupported(&op.to_string(), type_def, span))
+                    }
+                    (_, BinaryOperator::Equals) => (format!("{} === {}", a, b), Type::Boolean),
+                    (Type::String, _) => (format!("compare({}, {}) {} 0", a, b, op), Type::Boolean),
+                    (_, BinaryOperator::LessThan | BinaryOperator::GreaterThan) => {
+                        (format!("{} {} {}", a, op, b), Type::Boolean)
+                    }
+                    _ => return Err(unsupported(&op.to_string(), type_def, span)),
+                };
+                Ok((value, result))
+            }
+            ExpressionKind::Call(id, args) => match self.emit_call(id, args, span)? {
+                (call, Some(type_def)) => Ok((call, type_def)),
+                (_, None) => Err((CheckError::NoReturnValue(id.clone()), span)),
+            },
+            ExpressionKind::Index(id, index) => {
+                let (index, _) = self.emit_expression(index)?;
+                let element = match &self.lookup(id, span)?.type_def {
+                    Type::Array(_, element) => element.as_ref().clone(),
+                    type_def => return Err(unsupported("[]", type_def.clone(), span)),
+                };
+                let array = name(id);
+                Ok((format!("{0}[at({0}, {1})]", array, index), element))
+            }
+        }
+    }
+
+    fn emit_call(
+        &mut self,
+        id: &str,
+        args: &[Expression],
+        span: Span,
+    ) -> GenResult<(String, Option<Type>)> {
+        if let Some(builtin) = builtins::find(id) {
+            let return_type = Some(builtin.return_type.clone());
+            // The size of a variable does not need a copy of it.
+            if let (SIZE, [array]) = (id, args) {
+                if let ExpressionKind::Identifier(array) = &array.kind {
+                    return Ok((format!("{}.length", name(array)), return_type));
+                }
+            }
+            let mut values = Vec::new();
+            for arg in args {
+                // Some are methods of their first argument.
+                let value = match (id, values.is_empty()) {
+                    ("upper" | "lower" | "contains" | SIZE, true) => self.emit_operand(arg)?,
+                    _ => self.emit_expression(arg)?,
+                };
+                values.push(value.0);
+            }
+            let call = match (id, values.as_slice()) {
+                (SIZE, [a]) => format!("{}.length", a),
+                ("to_string", [int]) => format!("String({})", int),
+                ("to_int", [s]) => format!("toInt({})", s),
+                ("upper", [s]) => format!("{}.toUpperCase()", s),
+                ("lower", [s]) => format!("{}.toLowerCase()", s),
+                ("contains", [s, part]) => format!("{}.includes({})", s, part),
+                (id, values) => format!("{}({})", id, values.join(", ")),
+            };
+            return Ok((call, return_type));
+        }
+        let return_type = match self.routines.get(id) {
+            Some(return_type) => return_type.clone(),
+            None => return Err((CheckError::UndeclaredRoutine(id.to_string()), span)),
+        };
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.emit_expression(arg)?.0);
+        }
+        Ok((format!("{}({})", name(id), values.join(", ")), return_type))
+    }
+
+    fn declare(&mut self, id: &str, type_def: &Type, flagged: bool) {
+        let variable = Variable {
+            type_def: type_def.clone(),
+            flagged,
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(id.to_string(), variable);
+        }
+    }
+
+    /// Records that `id` has a value in the rest of the current block. Blocks
+    /// have no branches, so it needs no more checks there.
+    fn mark_assigned(&mut self, id: &str) {
+        let type_def = match self.scopes.iter().rev().find_map(|scope| scope.get(id)) {
+            Some(variable) if variable.flagged => variable.type_def.clone(),
+            _ => return,
+        };
+        self.declare(id, &type_def, false);
+    }
+
+    fn lookup(&self, id: &str, span: Span) -> GenResult<&Variable> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(id)) {
+            Some(variable) => Ok(variable),
+            None => Err((CheckError::UndeclaredVariable(id.to_string()), span)),
+        }
+    }
+}
+
+fn unsupported(op: &str, type_def: Type, span: Span) -> SpannedCheckError {
+    (
+        CheckError::UnsupportedOperation(op.to_string(), type_def),
+        span,
+    )
+}
+
+/// The JavaScript name of a Mini-PL variable or routine.
+fn name(id: &str) -> String {
+    match RESERVED.contains(&id) {
+        true => format!("_{}", id),
+        false => id.to_string(),
+    }
+}
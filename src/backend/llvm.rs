@@ -0,0 +1,728 @@
+//! Translation of a checked program into textual LLVM IR, compiled ahead of
+//! time together with the C runtime from [`LlvmBackend::runtime`]:
+//! `clang -o program program.ll runtime.c`.
+//!
+//! Every variable gets a stack slot in the entry block of its function,
+//! with `v_` before its name, and every procedure and function an LLVM
+//! function with `f_` before its name. As in the C backend, statements
+//! record their position before they run, so that runtime errors name the
+//! same statement as the interpreter would. The IR uses typed pointers,
+//! which LLVM 14 requires and later versions read as opaque pointers.
+
+use crate::ast::{
+    BinaryOperator, Expression, ExpressionKind, Program, Routine, Statement, StatementKind,
+    UnaryOperator,
+};
+use crate::backend::{mangle, with_error_codes};
+use crate::builtins::{self, SIZE};
+use crate::checker::SpannedCheckError;
+use crate::utils::{CheckError, IntegerWidth, Span, Type};
+use std::collections::{HashMap, HashSet};
+
+const C_RUNTIME: &str = include_str!("runtime.c");
+
+const RUNTIME: &str = include_str!("runtime_llvm.c");
+
+const DECLARATIONS: &str = include_str!("runtime.ll");
+
+const INDENT: &str = "  ";
+
+pub struct LlvmBackend {
+    integer_width: IntegerWidth,
+    string_limit: Option<usize>,
+}
+
+impl Default for LlvmBackend {
+    fn default() -> Self {
+        LlvmBackend::new()
+    }
+}
+
+impl LlvmBackend {
+    pub fn new() -> Self {
+        LlvmBackend {
+            integer_width: IntegerWidth::default(),
+            string_limit: None,
+        }
+    }
+
+    /// Makes integers outside `width` an error, as in an evaluator with the
+    /// same width.
+    pub fn with_integer_width(mut self, width: IntegerWidth) -> Self {
+        self.integer_width = width;
+        self
+    }
+
+    /// Makes strings longer than `limit` bytes an error.
+    pub fn with_string_limit(mut self, limit: usize) -> Self {
+        self.string_limit = Some(limit);
+        self
+    }
+
+    /// C source of the runtime that compiled programs are linked with. It
+    /// is the same for every program and option.
+    pub fn runtime() -> String {
+        with_error_codes(&RUNTIME.replace("#include \"runtime.c\"\n", C_RUNTIME))
+    }
+
+    /// LLVM IR of `program`, which must have passed the checker. Calls to
+    /// host functions, which only exist in the interpreter, are errors.
+    pub fn emit_program(&self, program: &Program) -> Result<String, SpannedCheckError> {
+        let mut generator = Generator::default();
+        let routines: Vec<&Routine> = program
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Routine(routine) => Some(routine),
+                _ => None,
+            })
+            .collect();
+        for routine in &routines {
+            let parameters = routine
+                .parameters
+                .iter()
+                .map(|param| param.type_def.clone())
+                .collect();
+            let signature = (parameters, routine.return_type.clone());
+            generator.routines.insert(routine.name.clone(), signature);
+        }
+
+        // The limit is unsigned in the runtime, so -1 means no limit.
+        let string_limit = self.string_limit.map_or(-1, |limit| limit as i64);
+        let mut output = String::from("; Compiled from Mini-PL.\n\n");
+        output.push_str(
+            &DECLARATIONS
+                .replace("{int_min}", &self.integer_width.min().to_string())
+                .replace("{int_max}", &self.integer_width.max().to_string())
+                .replace("{string_limit}", &string_limit.to_string()),
+        );
+        for routine in &routines {
+            output.push('\n');
+            output.push_str(&generator.emit_routine(routine)?);
+        }
+        output.push_str("\ndefine i32 @main() {\nentry:\n");
+        generator.scopes = vec![HashMap::new()];
+        generator.emit_block(&program.statements)?;
+        generator.instruction("ret i32 0");
+        output.push_str(&generator.take_body());
+        output.push_str("}\n\n");
+        output.push_str(&generator.constants);
+        Ok(output)
+    }
+}
+
+/// A variable in scope, with the stack slot holding its value. Variables
+/// declared without a value have a slot `a_<name>` that records whether
+/// they were assigned.
+struct Variable {
+    type_def: Type,
+    slot: String,
+    flag: Option<String>,
+}
+
+type Signature = (Vec<Type>, Option<Type>);
+
+#[derive(Default)]
+struct Generator {
+    /// Instructions of the function being emitted.
+    output: String,
+    /// Stack slots of the function being emitted, allocated on entry.
+    slots: String,
+    slot_names: HashSet<String>,
+    /// Whether the current block has ended, so that further instructions,
+    /// which are unreachable, need a new block.
+    terminated: bool,
+    temporaries: usize,
+    scopes: Vec<HashMap<String, Variable>>,
+    routines: HashMap<String, Signature>,
+    /// Global constants for string literals and names.
+    constants: String,
+    strings: HashMap<String, String>,
+    names: HashMap<String, String>,
+}
+
+type GenResult<T> = Result<T, SpannedCheckError>;
+
+impl Generator {
+    fn instruction(&mut self, text: &str) {
+        if self.terminated {
+            let label = self.label_name("dead");
+            self.output.push_str(&format!("{}:\n", label));
+            self.terminated = false;
+        }
+        self.output.push_str(INDENT);
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    /// Ends the current block with `text`, a branch or return.
+    fn terminator(&mut self, text: &str) {
+        self.instruction(text);
+        self.terminated = true;
+    }
+
+    /// Starts the block `label`, falling through from the current one.
+    fn label(&mut self, label: &str) {
+        if !self.terminated {
+            self.instruction(&format!("br label %{}", label));
+        }
+        self.output.push_str(&format!("{}:\n", label));
+        self.terminated = false;
+    }
+
+    fn label_name(&mut self, prefix: &str) -> String {
+        self.temporaries += 1;
+        format!("{}{}", prefix, self.temporaries)
+    }
+
+    /// Computes `text` into a new temporary.
+    fn value(&mut self, text: &str) -> String {
+        self.temporaries += 1;
+        let temporary = format!("%t{}", self.temporaries);
+        self.instruction(&format!("{} = {}", temporary, text));
+        temporary
+    }
+
+    /// A new stack slot for `id`, named after it where possible.
+    fn slot(&mut self, prefix: &str, id: &str, type_def: &str) -> String {
+        let base = mangle(prefix, id);
+        let mut name = base.clone();
+        let mut count = 1;
+        while !self.slot_names.insert(name.clone()) {
+            count += 1;
+            name = format!("{}.{}", base, count);
+        }
+        self.slots
+            .push_str(&format!("{}%{} = alloca {}\n", INDENT, name, type_def));
+        format!("%{}", name)
+    }
+
+    /// The body emitted since the last call, starting with its stack slots.
+    fn take_body(&mut self) -> String {
+        let mut body = std::mem::take(&mut self.slots);
+        body.push_str(&std::mem::take(&mut self.output));
+        self.slot_names.clear();
+        self.terminated = false;
+        body
+    }
+
+    /// The constant `%mpl.string` holding `s`.
+    fn string(&mut self, s: &str) -> String {
+        if let Some(global) = self.strings.get(s) {
+            return global.clone();
+        }
+        let global = format!("@s{}", self.strings.len());
+        self.constants.push_str(&format!(
+            "{0}.data = private unnamed_addr constant [{1} x i8] c\"{2}\"\n\
+             {0} = private constant %mpl.string {{ i8* getelementptr inbounds \
+             ([{1} x i8], [{1} x i8]* {0}.data, i64 0, i64 0), i64 {1} }}\n",
+            global,
+            s.len(),
+            ir_bytes(s.as_bytes())
+        ));
+        self.strings.insert(s.to_string(), global.clone());
+        global
+    }
+
+    /// A pointer to `name` as a NUL-terminated C string.
+    fn c_string(&mut self, name: &str) -> String {
+        let global = match self.names.get(name) {
+            Some(global) => global.clone(),
+            None => {
+                let global = format!("@n{}", self.names.len());
+                self.constants.push_str(&format!(
+                    "{} = private unnamed_addr constant [{} x i8] c\"{}\\00\"\n",
+                    global,
+                    name.len() + 1,
+                    ir_bytes(name.as_bytes())
+                ));
+                self.names.insert(name.to_string(), global.clone());
+                global
+            }
+        };
+        format!(
+            "getelementptr inbounds ([{0} x i8], [{0} x i8]* {1}, i64 0, i64 0)",
+            name.len() + 1,
+            global
+        )
+    }
+
+    fn emit_routine(&mut self, routine: &Routine) -> GenResult<String> {
+        let mut scope = HashMap::new();
+        let mut parameters = Vec::new();
+        for param in &routine.parameters {
+            let type_def = ir_type(&param.type_def);
+            let argument = mangle("%p_", &param.name);
+            parameters.push(format!("{} {}", type_def, argument));
+            let slot = self.slot("v_", &param.name, type_def);
+            self.instruction(&format!(
+                "store {0} {1}, {0}* {2}",
+                type_def, argument, slot
+            ));
+            let variable = Variable {
+                type_def: param.type_def.clone(),
+                slot,
+                flag: None,
+            };
+            scope.insert(param.name.clone(), variable);
+        }
+        self.scopes = vec![scope];
+        self.instruction("%caller = call i64 @minipl_enter()");
+        self.emit_block(&routine.body)?;
+        self.instruction("call void @minipl_leave(i64 %caller)");
+        let return_type = match &routine.return_type {
+            Some(return_type) => {
+                let name = self.c_string(&routine.name);
+                self.instruction(&format!("call void @minipl_missing_return(i8* {})", name));
+                self.terminator("unreachable");
+                ir_type(return_type)
+            }
+            None => {
+                self.terminator("ret void");
+                "void"
+            }
+        };
+        Ok(format!(
+            "define {} {}({}) {{\nentry:\n{}}}\n",
+            return_type,
+            mangle("@f_", &routine.name),
+            parameters.join(", "),
+            self.take_body()
+        ))
+    }
+
+    fn emit_block(&mut self, statements: &[Statement]) -> GenResult<()> {
+        for statement in statements {
+            self.emit_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    fn emit_statement(&mut self, statement: &Statement) -> GenResult<()> {
+        let span = statement.span;
+        if let StatementKind::Routine(_) = statement.kind {
+            return Ok(());
+        }
+        self.instruction(&format!(
+            "call void @minipl_at(i32 {}, i32 {})",
+            span.start.line, span.start.column
+        ));
+        match &statement.kind {
+            StatementKind::VarInitialization(id, type_def) => {
+                let slot = self.slot("v_", id, ir_type(type_def));
+                let flag = match type_def {
+                    Type::Array(size, element) => {
+                        let fill = match element.as_ref() {
+                            Type::String => {
+                                format!("ptrtoint (%mpl.string* {} to i64)", self.string(""))
+                            }
+                            _ => "0".to_string(),
+                        };
+                        let array = self.value(&format!(
+                            "call %mpl.array* @minipl_array_new(i64 {}, i64 {})",
+                            size, fill
+                        ));
+                        self.instruction(&format!(
+                            "store %mpl.array* {}, %mpl.array** {}",
+                            array, slot
+                        ));
+                        None
+                    }
+                    _ => {
+                        let flag = self.slot("a_", id, "i1");
+                        self.instruction(&format!("store i1 false, i1* {}", flag));
+                        Some(flag)
+                    }
+                };
+                self.declare(id, type_def, slot, flag);
+            }
+            StatementKind::NewAssignment(id, type_def, exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                let slot = self.slot("v_", id, ir_type(type_def));
+                self.instruction(&format!(
+                    "store {0} {1}, {0}* {2}",
+                    ir_type(type_def),
+                    value,
+                    slot
+                ));
+                self.declare(id, type_def, slot, None);
+            }
+            StatementKind::Assignment(id, exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.assign(id, &value, span)?;
+            }
+            StatementKind::IndexAssignment(id, index, exp) => {
+                let (index, _) = self.emit_expression(index)?;
+                let (value, type_def) = self.emit_expression(exp)?;
+                let item = self.item(&value, &type_def);
+                let array = self.load(id, span)?;
+                self.instruction(&format!(
+                    "call void @minipl_array_set(%mpl.array* {}, i64 {}, i64 {})",
+                    array, index, item
+                ));
+            }
+            StatementKind::Print(exp) => {
+                let (value, type_def) = self.emit_expression(exp)?;
+                match type_def {
+                    Type::Integer => {
+                        self.instruction(&format!("call void @minipl_print_int(i64 {})", value))
+                    }
+                    Type::Boolean => {
+                        let value = self.value(&format!("zext i1 {} to i32", value));
+                        self.instruction(&format!("call void @minipl_print_bool(i32 {})", value))
+                    }
+                    Type::String => self.instruction(&format!(
+                        "call void @minipl_print_string(%mpl.string* {})",
+                        value
+                    )),
+                    Type::Array(..) => return Err(unsupported("print", type_def, exp.span)),
+                }
+            }
+            StatementKind::Assert(exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                let condition = self.value(&format!("zext i1 {} to i32", value));
+                let message = self.string(&format!("Assertion failed: {}\n", exp));
+                self.instruction(&format!(
+                    "call void @minipl_assert(i32 {}, %mpl.string* {})",
+                    condition, message
+                ));
+            }
+            StatementKind::Read(id) => {
+                let call = match &self.lookup(id, span)?.type_def {
+                    Type::Integer => "call i64 @minipl_read_int()",
+                    Type::String => "call %mpl.string* @minipl_read_string()",
+                    type_def => return Err(unsupported("read", type_def.clone(), span)),
+                };
+                let value = self.value(call);
+                self.assign(id, &value, span)?;
+            }
+            StatementKind::For(id, start, end, stmts) => {
+                let (start, _) = self.emit_expression(start)?;
+                let (end, _) = self.emit_expression(end)?;
+                let counter = self.slot("for_", id, "i64");
+                let body = self.label_name("for");
+                let next = format!("{}.next", body);
+                let exit = format!("{}.exit", body);
+                self.instruction(&format!("store i64 {}, i64* {}", start, counter));
+                let empty = self.value(&format!("icmp sgt i64 {}, {}", start, end));
+                self.terminator(&format!(
+                    "br i1 {}, label %{}, label %{}",
+                    empty, exit, body
+                ));
+                self.label(&body);
+                let current = self.value(&format!("load i64, i64* {}", counter));
+                self.assign(id, &current, span)?;
+                self.scopes.push(HashMap::new());
+                self.emit_block(stmts)?;
+                self.scopes.pop();
+                // Stopping at the last value before incrementing cannot
+                // overflow.
+                let current = self.value(&format!("load i64, i64* {}", counter));
+                let last = self.value(&format!("icmp eq i64 {}, {}", current, end));
+                self.terminator(&format!("br i1 {}, label %{}, label %{}", last, exit, next));
+                self.label(&next);
+                let incremented = self.value(&format!("add i64 {}, 1", current));
+                self.instruction(&format!("store i64 {}, i64* {}", incremented, counter));
+                self.terminator(&format!("br label %{}", body));
+                self.label(&exit);
+            }
+            StatementKind::Routine(_) => {}
+            StatementKind::Call(name, args) => {
+                self.emit_call(name, args, span)?;
+            }
+            StatementKind::Return(exp) => {
+                let value = match exp {
+                    Some(exp) => Some(self.emit_expression(exp)?),
+                    None => None,
+                };
+                self.instruction("call void @minipl_leave(i64 %caller)");
+                match value {
+                    Some((value, type_def)) => {
+                        self.terminator(&format!("ret {} {}", ir_type(&type_def), value))
+                    }
+                    None => self.terminator("ret void"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The value of `exp` as an operand, with its type.
+    fn emit_expression(&mut self, exp: &Expression) -> GenResult<(String, Type)> {
+        let span = exp.span;
+        match &exp.kind {
+            ExpressionKind::IntegerConstant(int) => Ok((int.to_string(), Type::Integer)),
+            ExpressionKind::StringValue(s) => Ok((self.string(s), Type::String)),
+            ExpressionKind::Boolean(b) => Ok((b.to_string(), Type::Boolean)),
+            ExpressionKind::Identifier(id) => {
+                let type_def = self.lookup(id, span)?.type_def.clone();
+                let value = self.load(id, span)?;
+                match type_def {
+                    // Arrays are values, so reading one copies it.
+                    Type::Array(..) => {
+                        let copy = self.value(&format!(
+                            "call %mpl.array* @minipl_array_copy(%mpl.array* {})",
+                            value
+                        ));
+                        Ok((copy, type_def))
+                    }
+                    _ => Ok((value, type_def)),
+                }
+            }
+            ExpressionKind::Unary(UnaryOperator::Not, operand) => {
+                let (operand, _) = self.emit_expression(operand)?;
+                let value = self.value(&format!("xor i1 {}, true", operand));
+                Ok((value, Type::Boolean))
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                let (a, type_def) = self.emit_expression(left)?;
+                let (b, _) = self.emit_expression(right)?;
+                let predicate = match (&type_def, op) {
+                    (Type::Integer, BinaryOperator::Equals)
+                    | (Type::Boolean, BinaryOperator::Equals) => "icmp eq",
+                    (Type::Integer, BinaryOperator::LessThan) => "icmp slt",
+                    (Type::Integer, BinaryOperator::GreaterThan) => "icmp sgt",
+                    (Type::Boolean, BinaryOperator::And) => "and",
+                    // `false < true` for booleans, which are 0 and 1.
+                    (Type::Boolean, BinaryOperator::LessThan) => "icmp ult",
+                    (Type::Boolean, BinaryOperator::GreaterThan) => "icmp ugt",
+                    (Type::Integer, _) => {
+                        let function = match op {
+                            BinaryOperator::Plus => "add",
+                            BinaryOperator::Minus => "sub",
+                            BinaryOperator::Multiplication => "mul",
+                            _ => "div",
+                        };
+                        let value = self.value(&format!(
+                            "call i64 @minipl_{}(i64 {}, i64 {})",
+                            function, a, b
+                        ));
+                        return Ok((value, Type::Integer));
+                    }
+                    (Type::String, BinaryOperator::Plus) => {
+                        let value = self.value(&format!(
+                            "call %mpl.string* @minipl_concat(%mpl.string* {}, %mpl.string* {})",
+                            a, b
+                        ));
+                        return Ok((value, Type::String));
+                    }
+                    (Type::String, _) => {
+                        let (function, comparison) = match op {
+                            BinaryOperator::Equals => ("equals", "ne"),
+                            BinaryOperator::LessThan => ("compare", "slt"),
+                            BinaryOperator::GreaterThan => ("compare", "sgt"),
+                            _ => return Err(unsupported(&op.to_string(), type_def, span)),
+                        };
+                        let result = self.value(&format!(
+                            "call i32 @minipl_{}(%mpl.string* {}, %mpl.string* {})",
+                            function, a, b
+                        ));
+                        let value = self.value(&format!("icmp {} i32 {}, 0", comparison, result));
+                        return Ok((value, Type::Boolean));
+                    }
+                    _ => return Err(unsupported(&op.to_string(), type_def, span)),
+                };
+                let value = self.value(&format!(
+                    "{} {} {}, {}",
+                    predicate,
+                    ir_type(&type_def),
+                    a,
+                    b
+                ));
+                Ok((value, Type::Boolean))
+            }
+            ExpressionKind::Call(name, args) => match self.emit_call(name, args, span)? {
+                (call, Some(type_def)) => Ok((call, type_def)),
+                (_, None) => Err((CheckError::NoReturnValue(name.clone()), span)),
+            },
+            ExpressionKind::Index(id, index) => {
+                let (index, _) = self.emit_expression(index)?;
+                let element = match &self.lookup(id, span)?.type_def {
+                    Type::Array(_, element) => element.as_ref().clone(),
+                    type_def => return Err(unsupported("[]", type_def.clone(), span)),
+                };
+                let array = self.load(id, span)?;
+                let item = self.value(&format!(
+                    "call i64 @minipl_array_get(%mpl.array* {}, i64 {})",
+                    array, index
+                ));
+                let value = self.item_value(&item, &element);
+                Ok((value, element))
+            }
+        }
+    }
+
+    /// Emits a call, returning its value unless it is a procedure.
+    fn emit_call(
+        &mut self,
+        name: &str,
+        args: &[Expression],
+        span: Span,
+    ) -> GenResult<(String, Option<Type>)> {
+        if let Some(builtin) = builtins::find(name) {
+            // The size of a variable is known from its type, without copying it.
+            if let (SIZE, [array]) = (name, args) {
+                if let ExpressionKind::Identifier(id) = &array.kind {
+                    if let Type::Array(size, _) = self.lookup(id, array.span)?.type_def {
+                        return Ok((size.to_string(), Some(Type::Integer)));
+                    }
+                }
+            }
+            let mut values = Vec::new();
+            for arg in args {
+                let (value, type_def) = self.emit_expression(arg)?;
+                values.push(format!("{} {}", ir_type(&type_def), value));
+            }
+            let function = match name {
+                SIZE => "array_size",
+                name => name,
+            };
+            let value = match &builtin.return_type {
+                // The runtime returns booleans as C `int`s.
+                Type::Boolean => {
+                    let result = self.value(&format!(
+                        "call i32 @minipl_{}({})",
+                        function,
+                        values.join(", ")
+                    ));
+                    self.value(&format!("icmp ne i32 {}, 0", result))
+                }
+                return_type => self.value(&format!(
+                    "call {} @minipl_{}({})",
+                    ir_type(return_type),
+                    function,
+                    values.join(", ")
+                )),
+            };
+            return Ok((value, Some(builtin.return_type.clone())));
+        }
+        let return_type = match self.routines.get(name) {
+            Some((_, return_type)) => return_type.clone(),
+            None => return Err((CheckError::UndeclaredRoutine(name.to_string()), span)),
+        };
+        let mut values = Vec::new();
+        for arg in args {
+            let (value, type_def) = self.emit_expression(arg)?;
+            values.push(format!("{} {}", ir_type(&type_def), value));
+        }
+        let function = mangle("@f_", name);
+        match &return_type {
+            Some(type_def) => {
+                let value = self.value(&format!(
+                    "call {} {}({})",
+                    ir_type(type_def),
+                    function,
+                    values.join(", ")
+                ));
+                Ok((value, return_type))
+            }
+            None => {
+                self.instruction(&format!("call void {}({})", function, values.join(", ")));
+                Ok((String::new(), None))
+            }
+        }
+    }
+
+    /// An array element holding `value`.
+    fn item(&mut self, value: &str, type_def: &Type) -> String {
+        match type_def {
+            Type::Boolean => self.value(&format!("zext i1 {} to i64", value)),
+            Type::String => self.value(&format!("ptrtoint %mpl.string* {} to i64", value)),
+            _ => value.to_string(),
+        }
+    }
+
+    /// The value of type `type_def` held by the array element `item`.
+    fn item_value(&mut self, item: &str, type_def: &Type) -> String {
+        match type_def {
+            Type::Boolean => self.value(&format!("icmp ne i64 {}, 0", item)),
+            Type::String => self.value(&format!("inttoptr i64 {} to %mpl.string*", item)),
+            _ => item.to_string(),
+        }
+    }
+
+    fn declare(&mut self, id: &str, type_def: &Type, slot: String, flag: Option<String>) {
+        let variable = Variable {
+            type_def: type_def.clone(),
+            slot,
+            flag,
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(id.to_string(), variable);
+        }
+    }
+
+    fn lookup(&self, id: &str, span: Span) -> GenResult<&Variable> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(id)) {
+            Some(variable) => Ok(variable),
+            None => Err((CheckError::UndeclaredVariable(id.to_string()), span)),
+        }
+    }
+
+    /// Loads the value of `id`, failing if it was never assigned.
+    fn load(&mut self, id: &str, span: Span) -> GenResult<String> {
+        let variable = self.lookup(id, span)?;
+        let (slot, flag) = (variable.slot.clone(), variable.flag.clone());
+        let type_def = ir_type(&variable.type_def);
+        if let Some(flag) = flag {
+            let assigned = self.value(&format!("load i1, i1* {}", flag));
+            let assigned = self.value(&format!("zext i1 {} to i32", assigned));
+            let name = self.c_string(id);
+            self.instruction(&format!(
+                "call void @minipl_check_assigned(i32 {}, i8* {})",
+                assigned, name
+            ));
+        }
+        Ok(self.value(&format!("load {0}, {0}* {1}", type_def, slot)))
+    }
+
+    fn assign(&mut self, id: &str, value: &str, span: Span) -> GenResult<()> {
+        let variable = self.lookup(id, span)?;
+        let (slot, flag) = (variable.slot.clone(), variable.flag.clone());
+        let type_def = ir_type(&variable.type_def);
+        self.instruction(&format!("store {0} {1}, {0}* {2}", type_def, value, slot));
+        if let Some(flag) = flag {
+            self.instruction(&format!("store i1 true, i1* {}", flag));
+        }
+        Ok(())
+    }
+}
+
+fn unsupported(op: &str, type_def: Type, span: Span) -> SpannedCheckError {
+    (
+        CheckError::UnsupportedOperation(op.to_string(), type_def),
+        span,
+    )
+}
+
+fn ir_type(type_def: &Type) -> &'static str {
+    match type_def {
+        Type::Integer => "i64",
+        Type::Boolean => "i1",
+        Type::String => "%mpl.string*",
+        Type::Array(..) => "%mpl.array*",
+    }
+}
+
+/// `bytes` inside an LLVM `c"..."` constant.
+fn ir_bytes(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' => escaped.push_str(&format!("\\{:02X}", byte)),
+            b' '..=b'~' => escaped.push(*byte as char),
+            byte => escaped.push_str(&format!("\\{:02X}", byte)),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::llvm::ir_bytes;
+
+    #[test]
+    fn escape_bytes() {
+        assert_eq!(ir_bytes("a\"\\\nä".as_bytes()), "a\\22\\5C\\0A\\C3\\A4");
+    }
+}
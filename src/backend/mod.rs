@@ -0,0 +1,101 @@
+//! Translations of checked programs into other languages, so that they can
+//! be run without the interpreter. Generated programs behave like the
+//! evaluator: they produce the same output and stop with the errors it
+//! reports with `--diagnostics=plain`, but without the calls in progress
+//! and the step, output and time limits.
+
+pub mod c;
+pub mod javascript;
+pub mod llvm;
+pub mod python;
+pub mod wat;
+
+use crate::explain::Coded;
+use crate::utils::EvalError;
+
+/// `runtime` with each `{code:Name}` replaced by the code of the runtime
+/// error `Name`, so that compiled programs report the codes of the
+/// evaluator.
+pub(crate) fn with_error_codes(runtime: &str) -> String {
+    let errors = [
+        ("MismatchedTypes", EvalError::MismatchedTypes),
+        (
+            "VariableNotInitialized",
+            EvalError::VariableNotInitialized(String::new()),
+        ),
+        ("DivisionByZero", EvalError::DivisionByZero),
+        ("IntegerOverflow", EvalError::IntegerOverflow),
+        ("StringLimitExceeded", EvalError::StringLimitExceeded(0)),
+        ("MissingReturn", EvalError::MissingReturn(String::new())),
+        ("CallDepthExceeded", EvalError::CallDepthExceeded(0)),
+        ("IndexOutOfBounds", EvalError::IndexOutOfBounds(0, 0)),
+        (
+            "InvalidArgument",
+            EvalError::InvalidArgument(String::new(), String::new()),
+        ),
+    ];
+    let mut runtime = runtime.to_string();
+    for (name, err) in &errors {
+        runtime = runtime.replace(&format!("{{code:{}}}", name), err.code());
+    }
+    runtime
+}
+
+/// `name` with `prefix`, using only ASCII letters, digits and underscores.
+/// Only the first character of a Mini-PL identifier may be outside ASCII;
+/// it is written as its code point.
+pub(crate) fn mangle(prefix: &str, name: &str) -> String {
+    let mut mangled = prefix.to_string();
+    for ch in name.chars() {
+        match ch {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => mangled.push(ch),
+            ch => mangled.push_str(&format!("_u{:04x}_", ch as u32)),
+        }
+    }
+    mangled
+}
+
+/// `s` as a double-quoted literal of Python or JavaScript, which share their
+/// escapes.
+pub(crate) fn quoted(s: &str) -> String {
+    let mut literal = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            ch if ch.is_control() => literal.push_str(&format!("\\x{:02x}", ch as u32)),
+            ch => literal.push(ch),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{quoted, with_error_codes};
+
+    #[test]
+    fn quote_for_scripts() {
+        assert_eq!(quoted("a\"b\\\n\u{7}ä"), "\"a\\\"b\\\\\\n\\x07ä\"");
+    }
+
+    #[test]
+    fn replace_every_error_code() {
+        let runtimes = [
+            include_str!("runtime.c"),
+            include_str!("runtime_llvm.c"),
+            include_str!("runtime.wat"),
+            include_str!("runtime.py"),
+            include_str!("runtime.js"),
+        ];
+        for runtime in &runtimes {
+            let runtime = with_error_codes(runtime);
+            assert!(!runtime.contains("{code:"), "{}", runtime);
+        }
+        assert_eq!(with_error_codes("[{code:DivisionByZero}]"), "[E0207]");
+    }
+}
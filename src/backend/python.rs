@@ -0,0 +1,469 @@
+//! Translation of a checked program into Python 3, to show learners the
+//! same program in a familiar language.
+//!
+//! The translation reads like the original: variables, routines and loops
+//! become their Python counterparts. Where Python behaves differently, such
+//! as for overflow, division that rounds down or negative indices, the code
+//! calls functions of a short runtime from `runtime.py` that behave like
+//! the evaluator. Errors stop the program with the evaluator's message, but
+//! without its line and column.
+
+use crate::ast::{
+    BinaryOperator, Expression, ExpressionKind, Program, Routine, Statement, StatementKind,
+    UnaryOperator,
+};
+use crate::backend::{quoted, with_error_codes};
+use crate::builtins::{self, SIZE};
+use crate::checker::SpannedCheckError;
+use crate::utils::{CheckError, IntegerWidth, Span, Type};
+use std::collections::HashMap;
+
+const RUNTIME: &str = include_str!("runtime.py");
+
+const INDENT: &str = "    ";
+
+/// Names that Mini-PL programs may use but that mean something else in the
+/// generated code: keywords, built-ins and the runtime's names. They are
+/// written with a leading underscore, which Mini-PL identifiers never have.
+const RESERVED: &[&str] = &[
+    "False",
+    "None",
+    "True",
+    "and",
+    "as",
+    "assert",
+    "async",
+    "await",
+    "break",
+    "class",
+    "continue",
+    "def",
+    "del",
+    "elif",
+    "else",
+    "except",
+    "finally",
+    "for",
+    "from",
+    "global",
+    "if",
+    "import",
+    "in",
+    "is",
+    "lambda",
+    "nonlocal",
+    "not",
+    "or",
+    "pass",
+    "raise",
+    "return",
+    "try",
+    "while",
+    "with",
+    "yield",
+    "abs",
+    "int",
+    "isinstance",
+    "len",
+    "list",
+    "ord",
+    "range",
+    "str",
+    "Exception",
+    "re",
+    "sys",
+    "MIN_INT",
+    "MAX_INT",
+    "MAX_DEPTH",
+    "depth",
+    "Failure",
+    "check",
+    "div",
+    "init",
+    "at",
+    "store",
+    "enter",
+    "leave",
+    "function",
+    "procedure",
+    "write",
+    "assert_",
+    "read_string",
+    "parse_int",
+    "read_int",
+    "substr",
+    "quote",
+    "to_int",
+    "contains",
+    "run",
+    "main",
+];
+
+pub struct PythonBackend {
+    integer_width: IntegerWidth,
+}
+
+impl Default for PythonBackend {
+    fn default() -> Self {
+        PythonBackend::new()
+    }
+}
+
+impl PythonBackend {
+    pub fn new() -> Self {
+        PythonBackend {
+            integer_width: IntegerWidth::default(),
+        }
+    }
+
+    /// Width of `int`, which should match the one the program was parsed
+    /// with.
+    pub fn with_integer_width(mut self, width: IntegerWidth) -> Self {
+        self.integer_width = width;
+        self
+    }
+
+    /// Python source of `program`, which must have passed the checker.
+    /// Calls to host functions, which only exist in the interpreter, are
+    /// errors.
+    pub fn emit_program(&self, program: &Program) -> Result<String, SpannedCheckError> {
+        let mut generator = Generator::default();
+        let runtime = with_error_codes(RUNTIME)
+            .replace("{min_int}", &self.integer_width.min().to_string())
+            .replace("{max_int}", &self.integer_width.max().to_string());
+        generator.output.push_str(&runtime);
+        generator
+            .output
+            .push_str("\n\n# Translated from Mini-PL.\n");
+        for statement in &program.statements {
+            if let StatementKind::Routine(routine) = &statement.kind {
+                generator.emit_routine(routine)?;
+            }
+        }
+        generator.output.push_str("\n\ndef main():\n");
+        generator.scopes = vec![HashMap::new()];
+        generator.emit_block(&program.statements)?;
+        generator.output.push_str("\n\nrun(main)\n");
+        Ok(generator.output)
+    }
+}
+
+/// A variable in scope. Variables declared without a value start as
+/// `None`, and reading them checks that they were assigned.
+struct Variable {
+    type_def: Type,
+    flagged: bool,
+}
+
+#[derive(Default)]
+struct Generator {
+    output: String,
+    depth: usize,
+    scopes: Vec<HashMap<String, Variable>>,
+    /// Return types of the routines declared so far.
+    routines: HashMap<String, Option<Type>>,
+}
+
+type GenResult<T> = Result<T, SpannedCheckError>;
+
+impl Generator {
+    fn line(&mut self, text: &str) {
+        self.output.push_str(&INDENT.repeat(self.depth));
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    fn emit_routine(&mut self, routine: &Routine) -> GenResult<()> {
+        self.routines
+            .insert(routine.name.clone(), routine.return_type.clone());
+        let mut scope = HashMap::new();
+        let mut parameters = Vec::new();
+        for param in &routine.parameters {
+            parameters.push(name(&param.name));
+            let variable = Variable {
+                type_def: param.type_def.clone(),
+                flagged: false,
+            };
+            scope.insert(param.name.clone(), variable);
+        }
+        self.output.push_str("\n\n");
+        match routine.return_type {
+            Some(_) => self.line(&format!("@function({})", quoted(&routine.name))),
+            None => self.line("@procedure"),
+        }
+        self.line(&format!(
+            "def {}({}):",
+            name(&routine.name),
+            parameters.join(", ")
+        ));
+        self.scopes = vec![scope];
+        self.emit_block(&routine.body)
+    }
+
+    /// Emits the statements one level deeper, or `pass` if there are none.
+    fn emit_block(&mut self, statements: &[Statement]) -> GenResult<()> {
+        self.depth += 1;
+        let start = self.output.len();
+        for statement in statements {
+            self.emit_statement(statement)?;
+        }
+        if self.output.len() == start {
+            self.line("pass");
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn emit_statement(&mut self, statement: &Statement) -> GenResult<()> {
+        let span = statement.span;
+        match &statement.kind {
+            StatementKind::VarInitialization(id, type_def) => {
+                let value = match type_def {
+                    Type::Array(size, element) => {
+                        let fill = match element.as_ref() {
+                            Type::Boolean => "False",
+                            Type::String => "\"\"",
+                            _ => "0",
+                        };
+                        format!("[{}] * {}", fill, size)
+                    }
+                    _ => "None".to_string(),
+                };
+                self.line(&format!("{} = {}", name(id), value));
+                let flagged = !matches!(type_def, Type::Array(..));
+                self.declare(id, type_def, flagged);
+            }
+            StatementKind::NewAssignment(id, type_def, exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.line(&format!("{} = {}", name(id), value));
+                self.declare(id, type_def, false);
+            }
+            StatementKind::Assignment(id, exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.lookup(id, span)?;
+                self.mark_assigned(id);
+                self.line(&format!("{} = {}", name(id), value));
+            }
+            StatementKind::IndexAssignment(id, index, exp) => {
+                let (index, _) = self.emit_expression(index)?;
+                let (value, _) = self.emit_expression(exp)?;
+                self.lookup(id, span)?;
+                self.line(&format!("store({}, {}, {})", name(id), index, value));
+            }
+            StatementKind::Print(exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.line(&format!("write({})", value));
+            }
+            StatementKind::Assert(exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                let display = quoted(&exp.to_string());
+                self.line(&format!("assert_({}, {})", value, display));
+            }
+            StatementKind::Read(id) => {
+                let function = match &self.lookup(id, span)?.type_def {
+                    Type::Integer => "read_int",
+                    Type::String => "read_string",
+                    type_def => return Err(unsupported("read", type_def.clone(), span)),
+                };
+                self.line(&format!("{} = {}()", name(id), function));
+                self.mark_assigned(id);
+            }
+            StatementKind::For(id, start, end, stmts) => {
+                let (start, _) = self.emit_expression(start)?;
+                let (end, _) = self.emit_operand(end)?;
+                self.lookup(id, span)?;
+                self.line(&format!(
+                    "for {} in range({}, {} + 1):",
+                    name(id),
+                    start,
+                    end
+                ));
+                self.scopes.push(HashMap::new());
+                self.mark_assigned(id);
+                self.emit_block(stmts)?;
+                self.scopes.pop();
+            }
+            StatementKind::Routine(_) => {}
+            StatementKind::Call(id, args) => {
+                let (call, _) = self.emit_call(id, args, span)?;
+                self.line(&call);
+            }
+            StatementKind::Return(Some(exp)) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.line(&format!("return {}", value));
+            }
+            StatementKind::Return(None) => self.line("return"),
+        }
+        Ok(())
+    }
+
+    /// `exp` in parentheses, unless it is a name, literal or call.
+    fn emit_operand(&mut self, exp: &Expression) -> GenResult<(String, Type)> {
+        let (value, type_def) = self.emit_expression(exp)?;
+        // Integer arithmetic becomes a call to `check` or `div`.
+        let atomic = match exp.kind {
+            ExpressionKind::Unary(..) => false,
+            ExpressionKind::Binary(..) => type_def == Type::Integer,
+            _ => true,
+        };
+        match atomic {
+            true => Ok((value, type_def)),
+            false => Ok((format!("({})", value), type_def)),
+        }
+    }
+
+    fn emit_expression(&mut self, exp: &Expression) -> GenResult<(String, Type)> {
+        let span = exp.span;
+        match &exp.kind {
+            ExpressionKind::IntegerConstant(int) => Ok((int.to_string(), Type::Integer)),
+            ExpressionKind::StringValue(s) => Ok((quoted(s), Type::String)),
+            ExpressionKind::Boolean(true) => Ok(("True".to_string(), Type::Boolean)),
+            ExpressionKind::Boolean(false) => Ok(("False".to_string(), Type::Boolean)),
+            ExpressionKind::Identifier(id) => {
+                let variable = self.lookup(id, span)?;
+                let type_def = variable.type_def.clone();
+                // Arrays are values, so reading one copies it.
+                let value = match (&type_def, variable.flagged) {
+                    (Type::Array(..), _) => format!("list({})", name(id)),
+                    (_, true) => format!("init({}, {})", name(id), quoted(id)),
+                    (_, false) => name(id),
+                };
+                self.mark_assigned(id);
+                Ok((value, type_def))
+            }
+            ExpressionKind::Unary(UnaryOperator::Not, operand) => {
+                let (operand, _) = self.emit_operand(operand)?;
+                Ok((format!("not {}", operand), Type::Boolean))
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                let (a, type_def) = self.emit_operand(left)?;
+                let (b, _) = self.emit_operand(right)?;
+                let (value, result) = match (&type_def, op) {
+                    (Type::Integer, BinaryOperator::Division) => {
+                        (format!("div({}, {})", a, b), Type::Integer)
+                    }
+                    (
+                        Type::Integer,
+                        BinaryOperator::Plus
+                        | BinaryOperator::Minus
+                        | BinaryOperator::Multiplication,
+                    ) => (format!("check({} {} {})", a, op, b), Type::Integer),
+                    (Type::String, BinaryOperator::Plus) => {
+                        (format!("{} + {}", a, b), Type::String)
+                    }
+                    // Unlike `and`, `&` evaluates both operands.
+                    (Type::Boolean, BinaryOperator::And) => {
+                        (format!("{} & {}", a, b), Type::Boolean)
+                    }
+                    (Type::Array(..), _) => {
+                        return Err(unsupported(&op.to_string(), type_def, span))
+                    }
+                    (_, BinaryOperator::Equals) => (format!("{} == {}", a, b), Type::Boolean),
+                    (_, BinaryOperator::LessThan | BinaryOperator::GreaterThan) => {
+                        (format!("{} {} {}", a, op, b), Type::Boolean)
+                    }
+                    _ => return Err(unsupported(&op.to_string(), type_def, span)),
+                };
+                Ok((value, result))
+            }
+            ExpressionKind::Call(id, args) => match self.emit_call(id, args, span)? {
+                (call, Some(type_def)) => Ok((call, type_def)),
+                (_, None) => Err((CheckError::NoReturnValue(id.clone()), span)),
+            },
+            ExpressionKind::Index(id, index) => {
+                let (index, _) = self.emit_expression(index)?;
+                let element = match &self.lookup(id, span)?.type_def {
+                    Type::Array(_, element) => element.as_ref().clone(),
+                    type_def => return Err(unsupported("[]", type_def.clone(), span)),
+                };
+                let array = name(id);
+                Ok((format!("{0}[at({0}, {1})]", array, index), element))
+            }
+        }
+    }
+
+    fn emit_call(
+        &mut self,
+        id: &str,
+        args: &[Expression],
+        span: Span,
+    ) -> GenResult<(String, Option<Type>)> {
+        if let Some(builtin) = builtins::find(id) {
+            let return_type = Some(builtin.return_type.clone());
+            // The size of a variable does not need a copy of it.
+            if let (SIZE, [array]) = (id, args) {
+                if let ExpressionKind::Identifier(array) = &array.kind {
+                    return Ok((format!("len({})", name(array)), return_type));
+                }
+            }
+            let mut values = Vec::new();
+            for arg in args {
+                // `upper` and `lower` are methods of their argument.
+                let value = match id {
+                    "upper" | "lower" => self.emit_operand(arg)?,
+                    _ => self.emit_expression(arg)?,
+                };
+                values.push(value.0);
+            }
+            let call = match (id, values.as_slice()) {
+                ("length", [s]) | (SIZE, [s]) => format!("len({})", s),
+                ("to_string", [int]) => format!("str({})", int),
+                ("upper", [s]) => format!("{}.upper()", s),
+                ("lower", [s]) => format!("{}.lower()", s),
+                (id, values) => format!("{}({})", id, values.join(", ")),
+            };
+            return Ok((call, return_type));
+        }
+        let return_type = match self.routines.get(id) {
+            Some(return_type) => return_type.clone(),
+            None => return Err((CheckError::UndeclaredRoutine(id.to_string()), span)),
+        };
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.emit_expression(arg)?.0);
+        }
+        Ok((format!("{}({})", name(id), values.join(", ")), return_type))
+    }
+
+    fn declare(&mut self, id: &str, type_def: &Type, flagged: bool) {
+        let variable = Variable {
+            type_def: type_def.clone(),
+            flagged,
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(id.to_string(), variable);
+        }
+    }
+
+    /// Records that `id` has a value in the rest of the current block. Blocks
+    /// have no branches, so it needs no more checks there.
+    fn mark_assigned(&mut self, id: &str) {
+        let type_def = match self.scopes.iter().rev().find_map(|scope| scope.get(id)) {
+            Some(variable) if variable.flagged => variable.type_def.clone(),
+            _ => return,
+        };
+        self.declare(id, &type_def, false);
+    }
+
+    fn lookup(&self, id: &str, span: Span) -> GenResult<&Variable> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(id)) {
+            Some(variable) => Ok(variable),
+            None => Err((CheckError::UndeclaredVariable(id.to_string()), span)),
+        }
+    }
+}
+
+fn unsupported(op: &str, type_def: Type, span: Span) -> SpannedCheckError {
+    (
+        CheckError::UnsupportedOperation(op.to_string(), type_def),
+        span,
+    )
+}
+
+/// The Python name of a Mini-PL variable or routine.
+fn name(id: &str) -> String {
+    match RESERVED.contains(&id) {
+        true => format!("_{}", id),
+        false => id.to_string(),
+    }
+}
@@ -0,0 +1,448 @@
+/*
+ * Runtime of Mini-PL programs compiled to C. The generated code defines
+ * MPL_INT_MIN, MPL_INT_MAX and MPL_STRING_LIMIT before this point.
+ *
+ * Errors are printed to stderr as the interpreter prints them with
+ * --diagnostics=plain, without the calls in progress, and exit with
+ * status 1. The backend replaces each `code:Name` in braces by the code of
+ * that error. Strings are immutable and never freed, which is fine for
+ * short-lived programs.
+ */
+
+#include <ctype.h>
+#include <inttypes.h>
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#if defined(__GNUC__)
+#define MPL_NORETURN __attribute__((noreturn))
+#define MPL_UNUSED __attribute__((unused))
+#else
+#define MPL_NORETURN
+#define MPL_UNUSED
+#endif
+
+#define MPL_MAX_CALL_DEPTH 200
+
+typedef struct {
+    const char *data;
+    size_t len;
+} mpl_string;
+
+typedef struct {
+    int line;
+    int column;
+} mpl_span;
+
+static mpl_span mpl_current;
+static int mpl_depth;
+
+MPL_UNUSED static void mpl_at(int line, int column) {
+    mpl_current.line = line;
+    mpl_current.column = column;
+}
+
+/* Starts the message of a runtime error at the current statement. */
+static void mpl_error_start(void) {
+    fflush(stdout);
+    fprintf(stderr, "\n%d:%d: Error: ", mpl_current.line, mpl_current.column);
+}
+
+/*
+ * Ends the message with the code of the error, if it has one, and the
+ * summary of the interpreter, and exits.
+ */
+MPL_NORETURN static void mpl_error_end(const char *code) {
+    if (code != NULL) {
+        fprintf(stderr, " [%s]", code);
+    }
+    fputs("\nFound 1 error\n", stderr);
+    exit(1);
+}
+
+MPL_NORETURN static void mpl_fail(const char *code, const char *format, ...) {
+    va_list args;
+    mpl_error_start();
+    va_start(args, format);
+    vfprintf(stderr, format, args);
+    va_end(args);
+    mpl_error_end(code);
+}
+
+static void *mpl_alloc(size_t size) {
+    void *memory = malloc(size > 0 ? size : 1);
+    if (memory == NULL) {
+        mpl_fail(NULL, "Out of memory");
+    }
+    return memory;
+}
+
+/* Entering and leaving procedures and functions. */
+
+MPL_UNUSED static mpl_span mpl_enter(void) {
+    if (mpl_depth >= MPL_MAX_CALL_DEPTH) {
+        mpl_fail("{code:CallDepthExceeded}", "Calls nested deeper than %d",
+                 MPL_MAX_CALL_DEPTH);
+    }
+    mpl_depth++;
+    return mpl_current;
+}
+
+MPL_UNUSED static void mpl_leave(mpl_span caller) {
+    mpl_depth--;
+    mpl_current = caller;
+}
+
+MPL_UNUSED static void mpl_check_assigned(bool assigned, const char *name) {
+    if (!assigned) {
+        mpl_fail("{code:VariableNotInitialized}", "Variable %s not initialized", name);
+    }
+}
+
+/* Integers, checked against int64_t and then against the integer width. */
+
+static int64_t mpl_checked(int64_t value) {
+    if (value < MPL_INT_MIN || value > MPL_INT_MAX) {
+        mpl_fail("{code:IntegerOverflow}", "Integer overflow");
+    }
+    return value;
+}
+
+MPL_UNUSED static int64_t mpl_add(int64_t a, int64_t b) {
+    if ((b > 0 && a > INT64_MAX - b) || (b < 0 && a < INT64_MIN - b)) {
+        mpl_fail("{code:IntegerOverflow}", "Integer overflow");
+    }
+    return mpl_checked(a + b);
+}
+
+MPL_UNUSED static int64_t mpl_sub(int64_t a, int64_t b) {
+    if ((b < 0 && a > INT64_MAX + b) || (b > 0 && a < INT64_MIN + b)) {
+        mpl_fail("{code:IntegerOverflow}", "Integer overflow");
+    }
+    return mpl_checked(a - b);
+}
+
+MPL_UNUSED static int64_t mpl_mul(int64_t a, int64_t b) {
+    bool overflow;
+    if (a > 0) {
+        overflow = b > 0 ? a > INT64_MAX / b : b < INT64_MIN / a;
+    } else {
+        overflow = b > 0 ? a < INT64_MIN / b : a != 0 && b < INT64_MAX / a;
+    }
+    if (overflow) {
+        mpl_fail("{code:IntegerOverflow}", "Integer overflow");
+    }
+    return mpl_checked(a * b);
+}
+
+MPL_UNUSED static int64_t mpl_div(int64_t a, int64_t b) {
+    if (b == 0) {
+        mpl_fail("{code:DivisionByZero}", "Division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        mpl_fail("{code:IntegerOverflow}", "Integer overflow");
+    }
+    return mpl_checked(a / b);
+}
+
+/* Strings hold UTF-8 and may contain NUL bytes. */
+
+static mpl_string mpl_str(const char *data, size_t len) {
+    mpl_string s;
+    s.data = data;
+    s.len = len;
+    return s;
+}
+
+static mpl_string mpl_checked_string(mpl_string s) {
+    if (s.len > MPL_STRING_LIMIT) {
+        mpl_fail("{code:StringLimitExceeded}", "String longer than the limit of %lu bytes",
+                 (unsigned long)MPL_STRING_LIMIT);
+    }
+    return s;
+}
+
+MPL_UNUSED static mpl_string mpl_concat(mpl_string a, mpl_string b) {
+    char *data;
+    if (a.len > SIZE_MAX - b.len) {
+        mpl_fail(NULL, "Out of memory");
+    }
+    data = mpl_alloc(a.len + b.len);
+    memcpy(data, a.data, a.len);
+    memcpy(data + a.len, b.data, b.len);
+    return mpl_checked_string(mpl_str(data, a.len + b.len));
+}
+
+/* Byte-wise comparison, negative if a sorts before b. */
+MPL_UNUSED static int mpl_compare(mpl_string a, mpl_string b) {
+    int order = memcmp(a.data, b.data, a.len < b.len ? a.len : b.len);
+    if (order != 0) {
+        return order;
+    }
+    return a.len < b.len ? -1 : a.len > b.len;
+}
+
+MPL_UNUSED static bool mpl_equals(mpl_string a, mpl_string b) {
+    return a.len == b.len && memcmp(a.data, b.data, a.len) == 0;
+}
+
+/* Statements */
+
+MPL_UNUSED static void mpl_print_int(int64_t value) {
+    printf("%" PRId64, value);
+}
+
+MPL_UNUSED static void mpl_print_bool(bool value) {
+    fputs(value ? "true" : "false", stdout);
+}
+
+static void mpl_print_string(mpl_string s) {
+    fwrite(s.data, 1, s.len, stdout);
+}
+
+MPL_UNUSED static void mpl_assert(bool condition, mpl_string message) {
+    if (!condition) {
+        mpl_print_string(message);
+    }
+}
+
+/* One line of input including its line break, empty at the end of input. */
+static mpl_string mpl_read_line(void) {
+    size_t capacity = 64;
+    size_t len = 0;
+    char *data = mpl_alloc(capacity);
+    int c;
+    fflush(stdout);
+    while ((c = getchar()) != EOF) {
+        if (len == capacity) {
+            char *grown = mpl_alloc(capacity * 2);
+            memcpy(grown, data, len);
+            free(data);
+            data = grown;
+            capacity *= 2;
+        }
+        data[len++] = (char)c;
+        if (c == '\n') {
+            break;
+        }
+    }
+    return mpl_str(data, len);
+}
+
+static mpl_string mpl_trim(mpl_string s) {
+    while (s.len > 0 && isspace((unsigned char)s.data[0])) {
+        s.data++;
+        s.len--;
+    }
+    while (s.len > 0 && isspace((unsigned char)s.data[s.len - 1])) {
+        s.len--;
+    }
+    return s;
+}
+
+/* Parses an optionally signed decimal integer that fits in int64_t. */
+static bool mpl_parse_int(mpl_string s, int64_t *value) {
+    size_t i = 0;
+    bool negative = false;
+    int64_t result = 0;
+    if (s.len > 0 && (s.data[0] == '+' || s.data[0] == '-')) {
+        negative = s.data[0] == '-';
+        i++;
+    }
+    if (i == s.len) {
+        return false;
+    }
+    /* Accumulate negatively so that INT64_MIN fits. */
+    for (; i < s.len; i++) {
+        int digit = s.data[i] - '0';
+        if (digit < 0 || digit > 9 || result < (INT64_MIN + digit) / 10) {
+            return false;
+        }
+        result = result * 10 - digit;
+    }
+    if (!negative) {
+        if (result == INT64_MIN) {
+            return false;
+        }
+        result = -result;
+    }
+    *value = result;
+    return true;
+}
+
+MPL_UNUSED static mpl_string mpl_read_string(void) {
+    return mpl_checked_string(mpl_read_line());
+}
+
+MPL_UNUSED static int64_t mpl_read_int(void) {
+    int64_t value;
+    if (!mpl_parse_int(mpl_trim(mpl_read_line()), &value)) {
+        mpl_fail("{code:MismatchedTypes}", "Mismatched types");
+    }
+    return mpl_checked(value);
+}
+
+/* Built-in functions. Positions and lengths count characters. */
+
+static size_t mpl_char_count(mpl_string s) {
+    size_t count = 0;
+    size_t i;
+    for (i = 0; i < s.len; i++) {
+        if (((unsigned char)s.data[i] & 0xC0) != 0x80) {
+            count++;
+        }
+    }
+    return count;
+}
+
+/* Byte offset of character `index`, or the length at the end. */
+static size_t mpl_char_offset(mpl_string s, int64_t index) {
+    size_t i;
+    for (i = 0; i < s.len; i++) {
+        if (((unsigned char)s.data[i] & 0xC0) != 0x80 && index-- == 0) {
+            return i;
+        }
+    }
+    return s.len;
+}
+
+MPL_UNUSED static int64_t mpl_length(mpl_string s) {
+    return mpl_checked((int64_t)mpl_char_count(s));
+}
+
+MPL_UNUSED static mpl_string mpl_substr(mpl_string s, int64_t start, int64_t len) {
+    int64_t length = (int64_t)mpl_char_count(s);
+    size_t from;
+    if (start < 0 || start > length) {
+        mpl_fail("{code:InvalidArgument}",
+                 "Invalid argument to substr: start %" PRId64
+                 " is outside a string of length %" PRId64,
+                 start, length);
+    }
+    if (len < 0 || len > length - start) {
+        mpl_fail("{code:InvalidArgument}",
+                 "Invalid argument to substr: length %" PRId64 " from %" PRId64
+                 " is outside a string of length %" PRId64,
+                 len, start, length);
+    }
+    from = mpl_char_offset(s, start);
+    return mpl_str(s.data + from, mpl_char_offset(s, start + len) - from);
+}
+
+MPL_UNUSED static mpl_string mpl_to_string(int64_t value) {
+    char *data = mpl_alloc(24);
+    return mpl_checked_string(mpl_str(data, (size_t)sprintf(data, "%" PRId64, value)));
+}
+
+MPL_UNUSED static int64_t mpl_to_int(mpl_string s) {
+    int64_t value;
+    size_t i;
+    if (mpl_parse_int(mpl_trim(s), &value)) {
+        return mpl_checked(value);
+    }
+    /* The string as a quoted literal, like the interpreter's message. */
+    mpl_error_start();
+    fputs("Invalid argument to to_int: \"", stderr);
+    for (i = 0; i < s.len; i++) {
+        unsigned char c = (unsigned char)s.data[i];
+        switch (c) {
+        case '"': fputs("\\\"", stderr); break;
+        case '\\': fputs("\\\\", stderr); break;
+        case '\n': fputs("\\n", stderr); break;
+        case '\r': fputs("\\r", stderr); break;
+        case '\t': fputs("\\t", stderr); break;
+        case '\0': fputs("\\0", stderr); break;
+        default:
+            if (c < 0x20 || c == 0x7F) {
+                fprintf(stderr, "\\u{%x}", c);
+            } else {
+                fputc(c, stderr);
+            }
+        }
+    }
+    fputs("\" is not an integer", stderr);
+    mpl_error_end("{code:InvalidArgument}");
+}
+
+/* Case conversion covers ASCII letters only. */
+static mpl_string mpl_convert_case(mpl_string s, int (*convert)(int)) {
+    char *data = mpl_alloc(s.len);
+    size_t i;
+    for (i = 0; i < s.len; i++) {
+        unsigned char c = (unsigned char)s.data[i];
+        data[i] = (char)(c < 0x80 ? convert(c) : c);
+    }
+    return mpl_checked_string(mpl_str(data, s.len));
+}
+
+MPL_UNUSED static mpl_string mpl_upper(mpl_string s) {
+    return mpl_convert_case(s, toupper);
+}
+
+MPL_UNUSED static mpl_string mpl_lower(mpl_string s) {
+    return mpl_convert_case(s, tolower);
+}
+
+MPL_UNUSED static bool mpl_contains(mpl_string s, mpl_string part) {
+    size_t i;
+    for (i = 0; i + part.len <= s.len; i++) {
+        if (memcmp(s.data + i, part.data, part.len) == 0) {
+            return true;
+        }
+    }
+    return false;
+}
+
+/* Arrays. Reading an array variable copies it, so assignments and calls
+ * never share elements. */
+
+static size_t mpl_position(int64_t index, size_t size) {
+    if (index < 0 || (uint64_t)index >= size) {
+        mpl_fail("{code:IndexOutOfBounds}",
+                 "Index %" PRId64 " is out of bounds for an array of size %lu", index,
+                 (unsigned long)size);
+    }
+    return (size_t)index;
+}
+
+#define MPL_ARRAY(array, item, fill)                                          \
+    typedef struct {                                                          \
+        size_t size;                                                          \
+        item *items;                                                          \
+    } array;                                                                  \
+                                                                              \
+    MPL_UNUSED static array array##_new(size_t size) {                        \
+        array a;                                                              \
+        size_t i;                                                             \
+        a.size = size;                                                        \
+        a.items = mpl_alloc(size * sizeof(item));                             \
+        for (i = 0; i < size; i++) {                                          \
+            a.items[i] = fill;                                                \
+        }                                                                     \
+        return a;                                                             \
+    }                                                                         \
+                                                                              \
+    MPL_UNUSED static array array##_copy(array a) {                           \
+        array copy;                                                           \
+        copy.size = a.size;                                                   \
+        copy.items = mpl_alloc(a.size * sizeof(item));                        \
+        memcpy(copy.items, a.items, a.size * sizeof(item));                   \
+        return copy;                                                          \
+    }                                                                         \
+                                                                              \
+    MPL_UNUSED static item array##_get(array a, int64_t index) {              \
+        return a.items[mpl_position(index, a.size)];                          \
+    }                                                                         \
+                                                                              \
+    MPL_UNUSED static void array##_set(array a, int64_t index, item value) {  \
+        a.items[mpl_position(index, a.size)] = value;                         \
+    }
+
+MPL_ARRAY(mpl_ints, int64_t, 0)
+MPL_ARRAY(mpl_bools, bool, false)
+MPL_ARRAY(mpl_strings, mpl_string, mpl_str("", 0))
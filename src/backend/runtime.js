@@ -0,0 +1,227 @@
+// Runtime of Mini-PL programs translated to JavaScript, for Node.js. The
+// operations that behave differently in JavaScript, like overflow, division
+// and string comparison, are functions. The backend replaces each
+// `code:Name` in braces by the code of that error.
+"use strict";
+
+const fs = require("fs");
+
+const MIN_INT = -2147483648;
+const MAX_INT = 2147483647;
+const MAX_DEPTH = 200;
+let depth = 0;
+
+/** A runtime error, which stops the program, and its code. */
+class Failure extends Error {
+  constructor(code, message) {
+    super(message);
+    this.code = code;
+  }
+}
+
+/** `n`, if it fits in an `int`. */
+function check(n) {
+  if (n < MIN_INT || n > MAX_INT) {
+    throw new Failure("{code:IntegerOverflow}", "Integer overflow");
+  }
+  return n;
+}
+
+/** `a / b` rounded toward zero, as integers. */
+function div(a, b) {
+  if (b === 0) {
+    throw new Failure("{code:DivisionByZero}", "Division by zero");
+  }
+  return check(Math.trunc(a / b));
+}
+
+/** Evaluates both operands, unlike `&&`. */
+function and(a, b) {
+  return a && b;
+}
+
+/** Compares strings by code points, where `<` compares UTF-16 units. */
+function compare(a, b) {
+  const x = Array.from(a, (c) => c.codePointAt(0));
+  const y = Array.from(b, (c) => c.codePointAt(0));
+  for (let i = 0; i < Math.min(x.length, y.length); i++) {
+    if (x[i] !== y[i]) {
+      return x[i] - y[i];
+    }
+  }
+  return x.length - y.length;
+}
+
+/** The value of a variable declared without one, once it is assigned. */
+function init(value, name) {
+  if (value === undefined) {
+    throw new Failure("{code:VariableNotInitialized}", `Variable ${name} not initialized`);
+  }
+  return value;
+}
+
+/** `index`, if it is inside `array`. */
+function at(array, index) {
+  if (index < 0 || index >= array.length) {
+    throw new Failure("{code:IndexOutOfBounds}", `Index ${index} is out of bounds for an array of size ${array.length}`);
+  }
+  return index;
+}
+
+function store(array, index, value) {
+  array[at(array, index)] = value;
+}
+
+/** The numbers `start` to `end`, both included. */
+function* range(start, end) {
+  for (let i = start; i <= end; i++) {
+    yield i;
+  }
+}
+
+function enter() {
+  if (depth >= MAX_DEPTH) {
+    throw new Failure("{code:CallDepthExceeded}", `Calls nested deeper than ${MAX_DEPTH}`);
+  }
+  depth++;
+}
+
+/** Makes the function `name` fail when it ends without a value. */
+function func(name, body) {
+  return (...args) => {
+    enter();
+    const value = body(...args);
+    depth--;
+    if (value === undefined) {
+      throw new Failure("{code:MissingReturn}", `Function ${name} ended without returning a value`);
+    }
+    return value;
+  };
+}
+
+function procedure(body) {
+  return (...args) => {
+    enter();
+    body(...args);
+    depth--;
+  };
+}
+
+function write(value) {
+  fs.writeSync(1, String(value));
+}
+
+function assert(condition, expression) {
+  if (!condition) {
+    write(`Assertion failed: ${expression}\n`);
+  }
+}
+
+/** The next line of input, with its line break. */
+function readString() {
+  const bytes = [];
+  const byte = Buffer.alloc(1);
+  for (;;) {
+    let count;
+    try {
+      count = fs.readSync(0, byte, 0, 1, null);
+    } catch (error) {
+      if (error.code === "EAGAIN") {
+        continue;
+      }
+      if (error.code === "EOF") {
+        break;
+      }
+      throw error;
+    }
+    if (count === 0) {
+      break;
+    }
+    bytes.push(byte[0]);
+    if (byte[0] === 10) {
+      break;
+    }
+  }
+  return Buffer.from(bytes).toString();
+}
+
+/** `text` without surrounding whitespace as a 64-bit integer, or `null`. */
+function parseInt64(text) {
+  text = text.trim();
+  if (!/^[+-]?[0-9]+$/.test(text)) {
+    return null;
+  }
+  const value = BigInt(text);
+  if (value < -(2n ** 63n) || value >= 2n ** 63n) {
+    return null;
+  }
+  return value;
+}
+
+function readInt() {
+  const value = parseInt64(readString());
+  if (value === null) {
+    throw new Failure("{code:MismatchedTypes}", "Mismatched types");
+  }
+  return checkBig(value);
+}
+
+/** A 64-bit integer as an `int`. */
+function checkBig(value) {
+  if (value < BigInt(MIN_INT) || value > BigInt(MAX_INT)) {
+    throw new Failure("{code:IntegerOverflow}", "Integer overflow");
+  }
+  return Number(value);
+}
+
+/** The number of characters in `s`, where `s.length` counts UTF-16 units. */
+function length(s) {
+  return Array.from(s).length;
+}
+
+function substr(s, start, len) {
+  const chars = Array.from(s);
+  if (start < 0 || start > chars.length) {
+    throw new Failure("{code:InvalidArgument}", `Invalid argument to substr: start ${start} is outside a string of length ${chars.length}`);
+  }
+  if (len < 0 || len > chars.length - start) {
+    throw new Failure("{code:InvalidArgument}", `Invalid argument to substr: length ${len} from ${start} is outside a string of length ${chars.length}`);
+  }
+  return chars.slice(start, start + len).join("");
+}
+
+/** `s` as a quoted literal, like the interpreter's messages. */
+function quote(s) {
+  const escapes = { "\t": "\\t", "\r": "\\r", "\n": "\\n", "\\": "\\\\", '"': '\\"', "\0": "\\0" };
+  let quoted = "";
+  for (const c of s) {
+    if (c in escapes) {
+      quoted += escapes[c];
+    } else if (c < " " || c === "\x7f") {
+      quoted += `\\u{${c.charCodeAt(0).toString(16)}}`;
+    } else {
+      quoted += c;
+    }
+  }
+  return `"${quoted}"`;
+}
+
+function toInt(s) {
+  const value = parseInt64(s);
+  if (value === null) {
+    throw new Failure("{code:InvalidArgument}", `Invalid argument to to_int: ${quote(s)} is not an integer`);
+  }
+  return checkBig(value);
+}
+
+function run(main) {
+  try {
+    main();
+  } catch (error) {
+    if (!(error instanceof Failure)) {
+      throw error;
+    }
+    fs.writeSync(2, `\nError: ${error.message} [${error.code}]\nFound 1 error\n`);
+    process.exitCode = 1;
+  }
+}
@@ -0,0 +1,44 @@
+; Functions of the runtime in `runtime_llvm.c`. Strings and arrays are
+; pointers that only the runtime looks into, apart from the string literals.
+%mpl.string = type { i8*, i64 }
+%mpl.array = type opaque
+
+@minipl_int_min = constant i64 {int_min}
+@minipl_int_max = constant i64 {int_max}
+@minipl_string_limit = constant i64 {string_limit}
+
+declare void @minipl_at(i32, i32)
+declare i64 @minipl_enter()
+declare void @minipl_leave(i64)
+declare void @minipl_check_assigned(i32, i8*)
+declare void @minipl_missing_return(i8*) noreturn
+
+declare i64 @minipl_add(i64, i64)
+declare i64 @minipl_sub(i64, i64)
+declare i64 @minipl_mul(i64, i64)
+declare i64 @minipl_div(i64, i64)
+
+declare %mpl.string* @minipl_concat(%mpl.string*, %mpl.string*)
+declare i32 @minipl_compare(%mpl.string*, %mpl.string*)
+declare i32 @minipl_equals(%mpl.string*, %mpl.string*)
+
+declare void @minipl_print_int(i64)
+declare void @minipl_print_bool(i32)
+declare void @minipl_print_string(%mpl.string*)
+declare void @minipl_assert(i32, %mpl.string*)
+declare %mpl.string* @minipl_read_string()
+declare i64 @minipl_read_int()
+
+declare i64 @minipl_length(%mpl.string*)
+declare %mpl.string* @minipl_substr(%mpl.string*, i64, i64)
+declare %mpl.string* @minipl_to_string(i64)
+declare i64 @minipl_to_int(%mpl.string*)
+declare %mpl.string* @minipl_upper(%mpl.string*)
+declare %mpl.string* @minipl_lower(%mpl.string*)
+declare i32 @minipl_contains(%mpl.string*, %mpl.string*)
+
+declare %mpl.array* @minipl_array_new(i64, i64)
+declare %mpl.array* @minipl_array_copy(%mpl.array*)
+declare i64 @minipl_array_size(%mpl.array*)
+declare i64 @minipl_array_get(%mpl.array*, i64)
+declare void @minipl_array_set(%mpl.array*, i64, i64)
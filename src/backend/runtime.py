@@ -0,0 +1,162 @@
+# Runtime of Mini-PL programs translated to Python. The operations that
+# behave differently in Python, like overflow and division, are functions.
+# The backend replaces each `code:Name` in braces by the code of that
+# error.
+
+import re
+import sys
+
+MIN_INT = {min_int}
+MAX_INT = {max_int}
+MAX_DEPTH = 200
+depth = 0
+
+
+class Failure(Exception):
+    """A runtime error, which stops the program, and its code."""
+
+    def __init__(self, code, message):
+        super().__init__(message)
+        self.code = code
+
+
+def check(n):
+    """`n`, if it fits in an `int`."""
+    if not MIN_INT <= n <= MAX_INT:
+        raise Failure("{code:IntegerOverflow}", "Integer overflow")
+    return n
+
+
+def div(a, b):
+    """`a / b` rounded toward zero, where Python's `//` rounds down."""
+    if b == 0:
+        raise Failure("{code:DivisionByZero}", "Division by zero")
+    quotient = abs(a) // abs(b)
+    return check(quotient if (a < 0) == (b < 0) else -quotient)
+
+
+def init(value, name):
+    """The value of a variable declared without one, once it is assigned."""
+    if value is None:
+        raise Failure("{code:VariableNotInitialized}", f"Variable {name} not initialized")
+    return value
+
+
+def at(array, index):
+    """`index`, if it is inside `array`. Python would count negative
+    indices from the end."""
+    if not 0 <= index < len(array):
+        raise Failure("{code:IndexOutOfBounds}", f"Index {index} is out of bounds for an array of size {len(array)}")
+    return index
+
+
+def store(array, index, value):
+    array[at(array, index)] = value
+
+
+def enter():
+    global depth
+    if depth >= MAX_DEPTH:
+        raise Failure("{code:CallDepthExceeded}", f"Calls nested deeper than {MAX_DEPTH}")
+    depth += 1
+
+
+def leave():
+    global depth
+    depth -= 1
+
+
+def function(name):
+    """Makes the function `name` fail when it ends without a value."""
+    def decorate(body):
+        def call(*args):
+            enter()
+            value = body(*args)
+            leave()
+            if value is None:
+                raise Failure("{code:MissingReturn}", f"Function {name} ended without returning a value")
+            return value
+        return call
+    return decorate
+
+
+def procedure(body):
+    def call(*args):
+        enter()
+        body(*args)
+        leave()
+    return call
+
+
+def write(value):
+    if isinstance(value, bool):
+        value = "true" if value else "false"
+    sys.stdout.buffer.write(str(value).encode())
+
+
+def assert_(condition, expression):
+    if not condition:
+        write(f"Assertion failed: {expression}\n")
+
+
+def read_string():
+    """The next line of input, with its line break."""
+    return sys.stdin.buffer.readline().decode()
+
+
+def parse_int(text):
+    """`text` without surrounding whitespace as a 64-bit integer, or
+    `None`."""
+    text = text.strip()
+    if re.fullmatch("[+-]?[0-9]+", text) and -2**63 <= int(text) < 2**63:
+        return int(text)
+    return None
+
+
+def read_int():
+    value = parse_int(read_string())
+    if value is None:
+        raise Failure("{code:MismatchedTypes}", "Mismatched types")
+    return check(value)
+
+
+def substr(s, start, length):
+    if not 0 <= start <= len(s):
+        raise Failure("{code:InvalidArgument}", f"Invalid argument to substr: start {start} is outside a string of length {len(s)}")
+    if not 0 <= length <= len(s) - start:
+        raise Failure("{code:InvalidArgument}", f"Invalid argument to substr: length {length} from {start} is outside a string of length {len(s)}")
+    return s[start:start + length]
+
+
+def quote(s):
+    """`s` as a quoted literal, like the interpreter's messages."""
+    escapes = {"\t": "\\t", "\r": "\\r", "\n": "\\n", "\\": "\\\\", '"': '\\"', "\0": "\\0"}
+    quoted = ""
+    for c in s:
+        if c in escapes:
+            quoted += escapes[c]
+        elif c < " " or c == "\x7f":
+            quoted += f"\\u{{{ord(c):x}}}"
+        else:
+            quoted += c
+    return f'"{quoted}"'
+
+
+def to_int(s):
+    value = parse_int(s)
+    if value is None:
+        raise Failure("{code:InvalidArgument}", f"Invalid argument to to_int: {quote(s)} is not an integer")
+    return check(value)
+
+
+def contains(s, part):
+    return part in s
+
+
+def run(main):
+    try:
+        main()
+    except Failure as error:
+        sys.stdout.flush()
+        sys.stderr.write(f"\nError: {error} [{error.code}]\nFound 1 error\n")
+        sys.exit(1)
@@ -0,0 +1,566 @@
+  ;; Runtime of Mini-PL programs compiled to WebAssembly. This is a template
+  ;; completed by `wat.rs`, which replaces a quoted text in braces by the
+  ;; address of that string literal, and `heap`, `pages` and `string_limit`
+  ;; in braces by the first free address after the literals, the initial
+  ;; size of the memory and the longest allowed string in bytes. Each
+  ;; `code:Name` in braces becomes the code of that error.
+  ;;
+  ;; Strings are the address of their length in bytes, followed by their
+  ;; UTF-8 bytes. Arrays are the address of their size, followed by one i32
+  ;; per element. Memory is never freed.
+
+  ;; Writes `length` bytes at `address` to the output.
+  (import "env" "print" (func $host_print (param $address i32) (param $length i32)))
+  ;; Copies the next line of input, including its line break, to `address`
+  ;; if it is at most `capacity` bytes long, and returns its length in bytes,
+  ;; 0 at the end of input. A line that did not fit is returned again by the
+  ;; next call.
+  (import "env" "read" (func $host_read (param $address i32) (param $capacity i32) (result i32)))
+  ;; Reports the error message of `length` bytes at `address` for the
+  ;; statement at `line` and `column`. The program traps right after.
+  (import "env" "fail"
+    (func $host_fail (param $line i32) (param $column i32) (param $address i32) (param $length i32)))
+
+  (memory (export "memory") {pages})
+
+  (global $line (mut i32) (i32.const 0))
+  (global $column (mut i32) (i32.const 0))
+  (global $depth (mut i32) (i32.const 0))
+  (global $heap (mut i32) (i32.const {heap}))
+  (global $string_limit i32 (i32.const {string_limit}))
+  ;; Whether the last `$mpl_parse_int` succeeded.
+  (global $parsed (mut i32) (i32.const 0))
+
+  (func $mpl_at (param $line i32) (param $column i32)
+    (global.set $line (local.get $line))
+    (global.set $column (local.get $column)))
+
+  ;; Fails with `message` followed by `code`, unless `code` is 0.
+  (func $mpl_fail (param $message i32) (param $code i32)
+    (if (local.get $code)
+      (then
+        (local.set $message
+          (call $mpl_join
+            (call $mpl_join (local.get $message) {" ["})
+            (call $mpl_join (local.get $code) {"]"})))))
+    (call $host_fail
+      (global.get $line)
+      (global.get $column)
+      (i32.add (local.get $message) (i32.const 4))
+      (i32.load (local.get $message)))
+    (unreachable))
+
+  (func $mpl_alloc (param $size i32) (result i32)
+    (local $start i32)
+    (local $end i32)
+    (local $pages i32)
+    (local.set $start (global.get $heap))
+    (local.set $end
+      (i32.and (i32.add (i32.add (local.get $start) (local.get $size)) (i32.const 3))
+        (i32.const -4)))
+    (if (i32.lt_u (local.get $end) (local.get $start))
+      (then (call $mpl_fail {"Out of memory"} (i32.const 0))))
+    (local.set $pages
+      (i32.add (i32.shr_u (i32.sub (local.get $end) (i32.const 1)) (i32.const 16)) (i32.const 1)))
+    (if (i32.gt_u (local.get $pages) (memory.size))
+      (then
+        (if (i32.eq (memory.grow (i32.sub (local.get $pages) (memory.size))) (i32.const -1))
+          (then (call $mpl_fail {"Out of memory"} (i32.const 0))))))
+    (global.set $heap (local.get $end))
+    (local.get $start))
+
+  (func $mpl_copy (param $to i32) (param $from i32) (param $length i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $length)))
+        (i32.store8 (local.get $to) (i32.load8_u (local.get $from)))
+        (local.set $to (i32.add (local.get $to) (i32.const 1)))
+        (local.set $from (i32.add (local.get $from) (i32.const 1)))
+        (local.set $length (i32.sub (local.get $length) (i32.const 1)))
+        (br $next))))
+
+  ;; Procedure and function calls
+
+  (func $mpl_enter
+    (if (i32.ge_u (global.get $depth) (i32.const 200))
+      (then (call $mpl_fail {"Calls nested deeper than 200"} {"{code:CallDepthExceeded}"})))
+    (global.set $depth (i32.add (global.get $depth) (i32.const 1))))
+
+  (func $mpl_leave (param $line i32) (param $column i32)
+    (global.set $depth (i32.sub (global.get $depth) (i32.const 1)))
+    (call $mpl_at (local.get $line) (local.get $column)))
+
+  (func $mpl_check_assigned (param $assigned i32) (param $message i32)
+    (if (i32.eqz (local.get $assigned))
+      (then (call $mpl_fail (local.get $message) {"{code:VariableNotInitialized}"}))))
+
+  ;; Integers are computed in i64 and checked against the i32 range.
+
+  (func $mpl_checked (param $value i64) (result i32)
+    (if (i32.or (i64.lt_s (local.get $value) (i64.const -2147483648))
+          (i64.gt_s (local.get $value) (i64.const 2147483647)))
+      (then (call $mpl_fail {"Integer overflow"} {"{code:IntegerOverflow}"})))
+    (i32.wrap_i64 (local.get $value)))
+
+  (func $mpl_add (param $a i32) (param $b i32) (result i32)
+    (call $mpl_checked
+      (i64.add (i64.extend_i32_s (local.get $a)) (i64.extend_i32_s (local.get $b)))))
+
+  (func $mpl_sub (param $a i32) (param $b i32) (result i32)
+    (call $mpl_checked
+      (i64.sub (i64.extend_i32_s (local.get $a)) (i64.extend_i32_s (local.get $b)))))
+
+  (func $mpl_mul (param $a i32) (param $b i32) (result i32)
+    (call $mpl_checked
+      (i64.mul (i64.extend_i32_s (local.get $a)) (i64.extend_i32_s (local.get $b)))))
+
+  (func $mpl_div (param $a i32) (param $b i32) (result i32)
+    (if (i32.eqz (local.get $b))
+      (then (call $mpl_fail {"Division by zero"} {"{code:DivisionByZero}"})))
+    (call $mpl_checked
+      (i64.div_s (i64.extend_i32_s (local.get $a)) (i64.extend_i32_s (local.get $b)))))
+
+  ;; Strings
+
+  (func $mpl_string (param $length i32) (result i32)
+    (local $s i32)
+    (local.set $s (call $mpl_alloc (i32.add (local.get $length) (i32.const 4))))
+    (i32.store (local.get $s) (local.get $length))
+    (local.get $s))
+
+  (func $mpl_checked_string (param $s i32) (result i32)
+    (if (i32.gt_u (i32.load (local.get $s)) (global.get $string_limit))
+      (then (call $mpl_fail {"String longer than the limit of {string_limit} bytes"} {"{code:StringLimitExceeded}"})))
+    (local.get $s))
+
+  ;; Concatenation without the string limit, for error messages.
+  (func $mpl_join (param $a i32) (param $b i32) (result i32)
+    (local $s i32)
+    (local.set $s
+      (call $mpl_string (i32.add (i32.load (local.get $a)) (i32.load (local.get $b)))))
+    (call $mpl_copy
+      (i32.add (local.get $s) (i32.const 4))
+      (i32.add (local.get $a) (i32.const 4))
+      (i32.load (local.get $a)))
+    (call $mpl_copy
+      (i32.add (i32.add (local.get $s) (i32.const 4)) (i32.load (local.get $a)))
+      (i32.add (local.get $b) (i32.const 4))
+      (i32.load (local.get $b)))
+    (local.get $s))
+
+  (func $mpl_concat (param $a i32) (param $b i32) (result i32)
+    (call $mpl_checked_string (call $mpl_join (local.get $a) (local.get $b))))
+
+  ;; Byte-wise comparison, negative if a sorts before b.
+  (func $mpl_compare (param $a i32) (param $b i32) (result i32)
+    (local $i i32)
+    (local $x i32)
+    (local $y i32)
+    (block $done
+      (loop $next
+        (br_if $done
+          (i32.or (i32.eq (local.get $i) (i32.load (local.get $a)))
+            (i32.eq (local.get $i) (i32.load (local.get $b)))))
+        (local.set $x (i32.load8_u (i32.add (i32.add (local.get $a) (i32.const 4)) (local.get $i))))
+        (local.set $y (i32.load8_u (i32.add (i32.add (local.get $b) (i32.const 4)) (local.get $i))))
+        (if (i32.ne (local.get $x) (local.get $y))
+          (then (return (i32.sub (local.get $x) (local.get $y)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.sub (i32.gt_u (i32.load (local.get $a)) (i32.load (local.get $b)))
+      (i32.lt_u (i32.load (local.get $a)) (i32.load (local.get $b)))))
+
+  (func $mpl_equals (param $a i32) (param $b i32) (result i32)
+    (i32.eqz (call $mpl_compare (local.get $a) (local.get $b))))
+
+  (func $mpl_int_to_string (param $value i32) (result i32)
+    (local $magnitude i64)
+    (local $rest i64)
+    (local $length i32)
+    (local $s i32)
+    (local.set $magnitude (i64.extend_i32_s (local.get $value)))
+    (local.set $length (i32.const 1))
+    (if (i32.lt_s (local.get $value) (i32.const 0))
+      (then
+        (local.set $magnitude (i64.sub (i64.const 0) (local.get $magnitude)))
+        (local.set $length (i32.const 2))))
+    (local.set $rest (local.get $magnitude))
+    (block $counted
+      (loop $count
+        (br_if $counted (i64.lt_u (local.get $rest) (i64.const 10)))
+        (local.set $rest (i64.div_u (local.get $rest) (i64.const 10)))
+        (local.set $length (i32.add (local.get $length) (i32.const 1)))
+        (br $count)))
+    (local.set $s (call $mpl_string (local.get $length)))
+    (i32.store8 (i32.add (local.get $s) (i32.const 4)) (i32.const 45))
+    (loop $digit
+      (local.set $length (i32.sub (local.get $length) (i32.const 1)))
+      (i32.store8 (i32.add (i32.add (local.get $s) (i32.const 4)) (local.get $length))
+        (i32.add (i32.const 48) (i32.wrap_i64 (i64.rem_u (local.get $magnitude) (i64.const 10)))))
+      (local.set $magnitude (i64.div_u (local.get $magnitude) (i64.const 10)))
+      (br_if $digit (i64.ne (local.get $magnitude) (i64.const 0))))
+    (local.get $s))
+
+  ;; Statements
+
+  (func $mpl_print (param $s i32)
+    (call $host_print (i32.add (local.get $s) (i32.const 4)) (i32.load (local.get $s))))
+
+  (func $mpl_print_int (param $value i32)
+    (call $mpl_print (call $mpl_int_to_string (local.get $value))))
+
+  (func $mpl_print_bool (param $value i32)
+    (call $mpl_print (select {"true"} {"false"} (local.get $value))))
+
+  (func $mpl_assert (param $condition i32) (param $message i32)
+    (if (i32.eqz (local.get $condition))
+      (then (call $mpl_print (local.get $message)))))
+
+  ;; One line of input including its line break, empty at the end of input.
+  (func $mpl_read_line (result i32)
+    (local $capacity i32)
+    (local $s i32)
+    (local $length i32)
+    (local.set $capacity (i32.const 64))
+    (loop $retry
+      (local.set $s (call $mpl_string (local.get $capacity)))
+      (local.set $length
+        (call $host_read (i32.add (local.get $s) (i32.const 4)) (local.get $capacity)))
+      (if (i32.gt_u (local.get $length) (local.get $capacity))
+        (then
+          (global.set $heap (local.get $s))
+          (local.set $capacity (local.get $length))
+          (br $retry))))
+    (i32.store (local.get $s) (local.get $length))
+    ;; Give back the unused part of the buffer.
+    (global.set $heap
+      (i32.and (i32.add (i32.add (local.get $s) (local.get $length)) (i32.const 7))
+        (i32.const -4)))
+    (local.get $s))
+
+  (func $mpl_is_space (param $byte i32) (result i32)
+    (i32.or (i32.eq (local.get $byte) (i32.const 32))
+      (i32.lt_u (i32.sub (local.get $byte) (i32.const 9)) (i32.const 5))))
+
+  (func $mpl_trim (param $s i32) (result i32)
+    (local $start i32)
+    (local $end i32)
+    (local $trimmed i32)
+    (local.set $start (i32.add (local.get $s) (i32.const 4)))
+    (local.set $end (i32.add (local.get $start) (i32.load (local.get $s))))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $start) (local.get $end)))
+        (br_if $done (i32.eqz (call $mpl_is_space (i32.load8_u (local.get $start)))))
+        (local.set $start (i32.add (local.get $start) (i32.const 1)))
+        (br $next)))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $start) (local.get $end)))
+        (br_if $done
+          (i32.eqz (call $mpl_is_space (i32.load8_u (i32.sub (local.get $end) (i32.const 1))))))
+        (local.set $end (i32.sub (local.get $end) (i32.const 1)))
+        (br $next)))
+    (local.set $trimmed (call $mpl_string (i32.sub (local.get $end) (local.get $start))))
+    (call $mpl_copy
+      (i32.add (local.get $trimmed) (i32.const 4))
+      (local.get $start)
+      (i32.sub (local.get $end) (local.get $start)))
+    (local.get $trimmed))
+
+  ;; Parses an optionally signed decimal integer that fits in i64 and sets
+  ;; `$parsed` to whether it did.
+  (func $mpl_parse_int (param $s i32) (result i64)
+    (local $i i32)
+    (local $length i32)
+    (local $negative i32)
+    (local $digit i32)
+    (local $result i64)
+    (global.set $parsed (i32.const 0))
+    (local.set $length (i32.load (local.get $s)))
+    (if (i32.gt_u (local.get $length) (i32.const 0))
+      (then
+        (local.set $digit (i32.load8_u (i32.add (local.get $s) (i32.const 4))))
+        (if (i32.or (i32.eq (local.get $digit) (i32.const 43))
+              (i32.eq (local.get $digit) (i32.const 45)))
+          (then
+            (local.set $negative (i32.eq (local.get $digit) (i32.const 45)))
+            (local.set $i (i32.const 1))))))
+    (if (i32.eq (local.get $i) (local.get $length))
+      (then (return (i64.const 0))))
+    ;; Accumulate negatively so that the minimum fits.
+    (loop $next
+      (local.set $digit
+        (i32.sub
+          (i32.load8_u (i32.add (i32.add (local.get $s) (i32.const 4)) (local.get $i)))
+          (i32.const 48)))
+      (if (i32.gt_u (local.get $digit) (i32.const 9))
+        (then (return (i64.const 0))))
+      (if (i64.lt_s (local.get $result)
+            (i64.div_s
+              (i64.add (i64.const -9223372036854775808) (i64.extend_i32_u (local.get $digit)))
+              (i64.const 10)))
+        (then (return (i64.const 0))))
+      (local.set $result
+        (i64.sub (i64.mul (local.get $result) (i64.const 10)) (i64.extend_i32_u (local.get $digit))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br_if $next (i32.lt_u (local.get $i) (local.get $length))))
+    (if (i32.eqz (local.get $negative))
+      (then
+        (if (i64.eq (local.get $result) (i64.const -9223372036854775808))
+          (then (return (i64.const 0))))
+        (local.set $result (i64.sub (i64.const 0) (local.get $result)))))
+    (global.set $parsed (i32.const 1))
+    (local.get $result))
+
+  (func $mpl_read_string (result i32)
+    (call $mpl_checked_string (call $mpl_read_line)))
+
+  (func $mpl_read_int (result i32)
+    (local $value i64)
+    (local.set $value (call $mpl_parse_int (call $mpl_trim (call $mpl_read_line))))
+    (if (i32.eqz (global.get $parsed))
+      (then (call $mpl_fail {"Mismatched types"} {"{code:MismatchedTypes}"})))
+    (call $mpl_checked (local.get $value)))
+
+  ;; Built-in functions. Positions and lengths count characters.
+
+  (func $mpl_length (param $s i32) (result i32)
+    (local $i i32)
+    (local $count i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $i) (i32.load (local.get $s))))
+        (if (i32.ne
+              (i32.and
+                (i32.load8_u (i32.add (i32.add (local.get $s) (i32.const 4)) (local.get $i)))
+                (i32.const 192))
+              (i32.const 128))
+          (then (local.set $count (i32.add (local.get $count) (i32.const 1)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $count))
+
+  ;; Byte offset of character `index`, or the length at the end.
+  (func $mpl_char_offset (param $s i32) (param $index i32) (result i32)
+    (local $i i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $i) (i32.load (local.get $s))))
+        (if (i32.ne
+              (i32.and
+                (i32.load8_u (i32.add (i32.add (local.get $s) (i32.const 4)) (local.get $i)))
+                (i32.const 192))
+              (i32.const 128))
+          (then
+            (if (i32.eqz (local.get $index))
+              (then (return (local.get $i))))
+            (local.set $index (i32.sub (local.get $index) (i32.const 1)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $i))
+
+  (func $mpl_substr (param $s i32) (param $start i32) (param $length i32) (result i32)
+    (local $size i32)
+    (local $from i32)
+    (local $to i32)
+    (local $substring i32)
+    (local.set $size (call $mpl_length (local.get $s)))
+    (if (i32.gt_u (local.get $start) (local.get $size))
+      (then
+        (call $mpl_fail
+          (call $mpl_join
+            (call $mpl_join
+              (call $mpl_join
+                {"Invalid argument to substr: start "}
+                (call $mpl_int_to_string (local.get $start)))
+              {" is outside a string of length "})
+            (call $mpl_int_to_string (local.get $size)))
+          {"{code:InvalidArgument}"})))
+    (if (i32.gt_u (local.get $length) (i32.sub (local.get $size) (local.get $start)))
+      (then
+        (call $mpl_fail
+          (call $mpl_join
+            (call $mpl_join
+              (call $mpl_join
+                (call $mpl_join
+                  (call $mpl_join
+                    {"Invalid argument to substr: length "}
+                    (call $mpl_int_to_string (local.get $length)))
+                  {" from "})
+                (call $mpl_int_to_string (local.get $start)))
+              {" is outside a string of length "})
+            (call $mpl_int_to_string (local.get $size)))
+          {"{code:InvalidArgument}"})))
+    (local.set $from (call $mpl_char_offset (local.get $s) (local.get $start)))
+    (local.set $to
+      (call $mpl_char_offset (local.get $s) (i32.add (local.get $start) (local.get $length))))
+    (local.set $substring (call $mpl_string (i32.sub (local.get $to) (local.get $from))))
+    (call $mpl_copy
+      (i32.add (local.get $substring) (i32.const 4))
+      (i32.add (i32.add (local.get $s) (i32.const 4)) (local.get $from))
+      (i32.sub (local.get $to) (local.get $from)))
+    (call $mpl_checked_string (local.get $substring)))
+
+  (func $mpl_to_string (param $value i32) (result i32)
+    (call $mpl_checked_string (call $mpl_int_to_string (local.get $value))))
+
+  ;; The string as a quoted literal, like the interpreter's messages.
+  (func $mpl_quote (param $s i32) (result i32)
+    (local $quoted i32)
+    (local $i i32)
+    (local $out i32)
+    (local $byte i32)
+    (local.set $quoted (call $mpl_string (i32.add (i32.mul (i32.load (local.get $s)) (i32.const 6)) (i32.const 2))))
+    (local.set $out (i32.add (local.get $quoted) (i32.const 4)))
+    (i32.store8 (local.get $out) (i32.const 34))
+    (local.set $out (i32.add (local.get $out) (i32.const 1)))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $i) (i32.load (local.get $s))))
+        (local.set $byte (i32.load8_u (i32.add (i32.add (local.get $s) (i32.const 4)) (local.get $i))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (block $escaped
+          (block $plain
+            (if (i32.or (i32.eq (local.get $byte) (i32.const 34)) (i32.eq (local.get $byte) (i32.const 92)))
+              (then (br $escaped)))
+            (br_if $plain (i32.and (i32.ge_u (local.get $byte) (i32.const 32)) (i32.ne (local.get $byte) (i32.const 127))))
+            (i32.store8 (local.get $out) (i32.const 92))
+            (local.set $out (i32.add (local.get $out) (i32.const 1)))
+            (if (i32.eq (local.get $byte) (i32.const 10)) (then (local.set $byte (i32.const 110)) (br $plain)))
+            (if (i32.eq (local.get $byte) (i32.const 13)) (then (local.set $byte (i32.const 114)) (br $plain)))
+            (if (i32.eq (local.get $byte) (i32.const 9)) (then (local.set $byte (i32.const 116)) (br $plain)))
+            (if (i32.eqz (local.get $byte)) (then (local.set $byte (i32.const 48)) (br $plain)))
+            ;; \u{hex}
+            (i32.store8 (local.get $out) (i32.const 117))
+            (i32.store8 (i32.add (local.get $out) (i32.const 1)) (i32.const 123))
+            (local.set $out (i32.add (local.get $out) (i32.const 2)))
+            (if (i32.ge_u (local.get $byte) (i32.const 16))
+              (then
+                (i32.store8 (local.get $out) (call $mpl_hex_digit (i32.shr_u (local.get $byte) (i32.const 4))))
+                (local.set $out (i32.add (local.get $out) (i32.const 1)))))
+            (i32.store8 (local.get $out) (call $mpl_hex_digit (i32.and (local.get $byte) (i32.const 15))))
+            (local.set $out (i32.add (local.get $out) (i32.const 1)))
+            (local.set $byte (i32.const 125)))
+          ;; $plain: write the byte itself.
+          (i32.store8 (local.get $out) (local.get $byte))
+          (local.set $out (i32.add (local.get $out) (i32.const 1)))
+          (br $next))
+        ;; $escaped: a backslash before the byte.
+        (i32.store8 (local.get $out) (i32.const 92))
+        (i32.store8 (i32.add (local.get $out) (i32.const 1)) (local.get $byte))
+        (local.set $out (i32.add (local.get $out) (i32.const 2)))
+        (br $next)))
+    (i32.store8 (local.get $out) (i32.const 34))
+    (local.set $out (i32.add (local.get $out) (i32.const 1)))
+    (i32.store (local.get $quoted)
+      (i32.sub (local.get $out) (i32.add (local.get $quoted) (i32.const 4))))
+    (local.get $quoted))
+
+  (func $mpl_hex_digit (param $value i32) (result i32)
+    (i32.add (local.get $value)
+      (select (i32.const 48) (i32.const 87) (i32.lt_u (local.get $value) (i32.const 10)))))
+
+  (func $mpl_to_int (param $s i32) (result i32)
+    (local $value i64)
+    (local.set $value (call $mpl_parse_int (call $mpl_trim (local.get $s))))
+    (if (i32.eqz (global.get $parsed))
+      (then
+        (call $mpl_fail
+          (call $mpl_join
+            (call $mpl_join {"Invalid argument to to_int: "} (call $mpl_quote (local.get $s)))
+            {" is not an integer"})
+          {"{code:InvalidArgument}"})))
+    (call $mpl_checked (local.get $value)))
+
+  ;; Case conversion covers ASCII letters only.
+  (func $mpl_convert_case (param $s i32) (param $from i32) (result i32)
+    (local $converted i32)
+    (local $i i32)
+    (local $byte i32)
+    (local.set $converted (call $mpl_string (i32.load (local.get $s))))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $i) (i32.load (local.get $s))))
+        (local.set $byte (i32.load8_u (i32.add (i32.add (local.get $s) (i32.const 4)) (local.get $i))))
+        (if (i32.lt_u (i32.sub (local.get $byte) (local.get $from)) (i32.const 26))
+          (then (local.set $byte (i32.xor (local.get $byte) (i32.const 32)))))
+        (i32.store8 (i32.add (i32.add (local.get $converted) (i32.const 4)) (local.get $i))
+          (local.get $byte))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (call $mpl_checked_string (local.get $converted)))
+
+  (func $mpl_upper (param $s i32) (result i32)
+    (call $mpl_convert_case (local.get $s) (i32.const 97)))
+
+  (func $mpl_lower (param $s i32) (result i32)
+    (call $mpl_convert_case (local.get $s) (i32.const 65)))
+
+  (func $mpl_contains (param $s i32) (param $part i32) (result i32)
+    (local $i i32)
+    (local $j i32)
+    (block $done
+      (loop $next
+        (br_if $done
+          (i32.gt_u (i32.add (local.get $i) (i32.load (local.get $part))) (i32.load (local.get $s))))
+        (local.set $j (i32.const 0))
+        (block $mismatch
+          (loop $compare
+            (if (i32.eq (local.get $j) (i32.load (local.get $part)))
+              (then (return (i32.const 1))))
+            (br_if $mismatch
+              (i32.ne
+                (i32.load8_u
+                  (i32.add (i32.add (local.get $s) (i32.const 4)) (i32.add (local.get $i) (local.get $j))))
+                (i32.load8_u (i32.add (i32.add (local.get $part) (i32.const 4)) (local.get $j)))))
+            (local.set $j (i32.add (local.get $j) (i32.const 1)))
+            (br $compare)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.const 0))
+
+  ;; Arrays. Reading an array variable copies it, so assignments and calls
+  ;; never share elements.
+
+  (func $mpl_array_new (param $size i32) (param $fill i32) (result i32)
+    (local $a i32)
+    (local $i i32)
+    (local.set $a (call $mpl_alloc (i32.add (i32.mul (local.get $size) (i32.const 4)) (i32.const 4))))
+    (i32.store (local.get $a) (local.get $size))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $i) (local.get $size)))
+        (i32.store
+          (i32.add (i32.add (local.get $a) (i32.const 4)) (i32.mul (local.get $i) (i32.const 4)))
+          (local.get $fill))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $a))
+
+  (func $mpl_array_copy (param $a i32) (result i32)
+    (local $copy i32)
+    (local $bytes i32)
+    (local.set $bytes (i32.add (i32.mul (i32.load (local.get $a)) (i32.const 4)) (i32.const 4)))
+    (local.set $copy (call $mpl_alloc (local.get $bytes)))
+    (call $mpl_copy (local.get $copy) (local.get $a) (local.get $bytes))
+    (local.get $copy))
+
+  ;; Address of element `index`.
+  (func $mpl_element (param $a i32) (param $index i32) (result i32)
+    (if (i32.ge_u (local.get $index) (i32.load (local.get $a)))
+      (then
+        (call $mpl_fail
+          (call $mpl_join
+            (call $mpl_join
+              (call $mpl_join {"Index "} (call $mpl_int_to_string (local.get $index)))
+              {" is out of bounds for an array of size "})
+            (call $mpl_int_to_string (i32.load (local.get $a))))
+          {"{code:IndexOutOfBounds}"})))
+    (i32.add (i32.add (local.get $a) (i32.const 4)) (i32.mul (local.get $index) (i32.const 4))))
+
+  (func $mpl_array_get (param $a i32) (param $index i32) (result i32)
+    (i32.load (call $mpl_element (local.get $a) (local.get $index))))
+
+  (func $mpl_array_set (param $a i32) (param $index i32) (param $value i32)
+    (i32.store (call $mpl_element (local.get $a) (local.get $index)) (local.get $value)))
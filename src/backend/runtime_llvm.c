@@ -0,0 +1,171 @@
+/*
+ * Runtime of Mini-PL programs compiled to LLVM IR: the runtime of the C
+ * backend with functions that the IR calls. The IR defines the integer
+ * range and string limit as minipl_int_min, minipl_int_max and
+ * minipl_string_limit.
+ *
+ * Integers are int64_t and booleans int. Strings and arrays are pointers
+ * to an mpl_string or minipl_array, which the IR passes around without
+ * looking inside, except that string literals are constant mpl_strings.
+ * Array elements are int64_t: integers, booleans as 0 or 1, and strings as
+ * their pointer.
+ */
+
+#include <stdint.h>
+
+extern const int64_t minipl_int_min;
+extern const int64_t minipl_int_max;
+extern const uint64_t minipl_string_limit;
+
+#define MPL_INT_MIN minipl_int_min
+#define MPL_INT_MAX minipl_int_max
+#define MPL_STRING_LIMIT minipl_string_limit
+
+#include "runtime.c"
+
+typedef struct {
+    size_t size;
+    int64_t *items;
+} minipl_array;
+
+static mpl_string *minipl_box(mpl_string s) {
+    mpl_string *boxed = mpl_alloc(sizeof *boxed);
+    *boxed = s;
+    return boxed;
+}
+
+void minipl_at(int line, int column) {
+    mpl_at(line, column);
+}
+
+/* The position of the call, which minipl_leave restores. */
+int64_t minipl_enter(void) {
+    mpl_span caller = mpl_enter();
+    return (int64_t)caller.line << 32 | (uint32_t)caller.column;
+}
+
+void minipl_leave(int64_t caller) {
+    mpl_span span;
+    span.line = (int)(caller >> 32);
+    span.column = (int)(uint32_t)caller;
+    mpl_leave(span);
+}
+
+void minipl_check_assigned(int assigned, const char *name) {
+    mpl_check_assigned(assigned != 0, name);
+}
+
+MPL_NORETURN void minipl_missing_return(const char *name) {
+    mpl_fail("{code:MissingReturn}", "Function %s ended without returning a value", name);
+}
+
+int64_t minipl_add(int64_t a, int64_t b) {
+    return mpl_add(a, b);
+}
+
+int64_t minipl_sub(int64_t a, int64_t b) {
+    return mpl_sub(a, b);
+}
+
+int64_t minipl_mul(int64_t a, int64_t b) {
+    return mpl_mul(a, b);
+}
+
+int64_t minipl_div(int64_t a, int64_t b) {
+    return mpl_div(a, b);
+}
+
+mpl_string *minipl_concat(const mpl_string *a, const mpl_string *b) {
+    return minipl_box(mpl_concat(*a, *b));
+}
+
+int minipl_compare(const mpl_string *a, const mpl_string *b) {
+    return mpl_compare(*a, *b);
+}
+
+int minipl_equals(const mpl_string *a, const mpl_string *b) {
+    return mpl_equals(*a, *b);
+}
+
+void minipl_print_int(int64_t value) {
+    mpl_print_int(value);
+}
+
+void minipl_print_bool(int value) {
+    mpl_print_bool(value != 0);
+}
+
+void minipl_print_string(const mpl_string *s) {
+    mpl_print_string(*s);
+}
+
+void minipl_assert(int condition, const mpl_string *message) {
+    mpl_assert(condition != 0, *message);
+}
+
+mpl_string *minipl_read_string(void) {
+    return minipl_box(mpl_read_string());
+}
+
+int64_t minipl_read_int(void) {
+    return mpl_read_int();
+}
+
+int64_t minipl_length(const mpl_string *s) {
+    return mpl_length(*s);
+}
+
+mpl_string *minipl_substr(const mpl_string *s, int64_t start, int64_t len) {
+    return minipl_box(mpl_substr(*s, start, len));
+}
+
+mpl_string *minipl_to_string(int64_t value) {
+    return minipl_box(mpl_to_string(value));
+}
+
+int64_t minipl_to_int(const mpl_string *s) {
+    return mpl_to_int(*s);
+}
+
+mpl_string *minipl_upper(const mpl_string *s) {
+    return minipl_box(mpl_upper(*s));
+}
+
+mpl_string *minipl_lower(const mpl_string *s) {
+    return minipl_box(mpl_lower(*s));
+}
+
+int minipl_contains(const mpl_string *s, const mpl_string *part) {
+    return mpl_contains(*s, *part);
+}
+
+minipl_array *minipl_array_new(int64_t size, int64_t fill) {
+    minipl_array *a = mpl_alloc(sizeof *a);
+    size_t i;
+    a->size = (size_t)size;
+    a->items = mpl_alloc(a->size * sizeof(int64_t));
+    for (i = 0; i < a->size; i++) {
+        a->items[i] = fill;
+    }
+    return a;
+}
+
+minipl_array *minipl_array_copy(const minipl_array *a) {
+    minipl_array *copy = mpl_alloc(sizeof *copy);
+    copy->size = a->size;
+    copy->items = mpl_alloc(a->size * sizeof(int64_t));
+    memcpy(copy->items, a->items, a->size * sizeof(int64_t));
+    return copy;
+}
+
+int64_t minipl_array_size(const minipl_array *a) {
+    return (int64_t)a->size;
+}
+
+int64_t minipl_array_get(const minipl_array *a, int64_t index) {
+    return a->items[mpl_position(index, a->size)];
+}
+
+void minipl_array_set(minipl_array *a, int64_t index, int64_t value) {
+    a->items[mpl_position(index, a->size)] = value;
+}
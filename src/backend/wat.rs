@@ -0,0 +1,587 @@
+//! Translation of a checked program into a WebAssembly module in the text
+//! format, to run programs in a browser.
+//!
+//! Integers are `i32` and behave like an evaluator with
+//! `IntegerWidth::I32`. Strings and arrays live in linear memory and every
+//! value, including those, is a single `i32`. The module imports `print`,
+//! `read` and `fail` from `env`, as documented in `runtime.wat`, and exports
+//! its `memory` and a `main` function that runs the program. When `main`
+//! traps after a call to `fail`, the program stopped with that error.
+
+use crate::ast::{
+    BinaryOperator, Expression, ExpressionKind, Program, Routine, Statement, StatementKind,
+    UnaryOperator,
+};
+use crate::backend::{mangle, with_error_codes};
+use crate::builtins::{self, SIZE};
+use crate::checker::SpannedCheckError;
+use crate::explain::Coded;
+use crate::utils::{CheckError, EvalError, Span, Type};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+const RUNTIME: &str = include_str!("runtime.wat");
+
+const INDENT: &str = "  ";
+
+/// Address of the first string literal. Address 0 is left unused.
+const DATA_START: u32 = 16;
+
+pub struct WatBackend {
+    string_limit: Option<usize>,
+}
+
+impl Default for WatBackend {
+    fn default() -> Self {
+        WatBackend::new()
+    }
+}
+
+impl WatBackend {
+    pub fn new() -> Self {
+        WatBackend { string_limit: None }
+    }
+
+    /// Makes strings longer than `limit` bytes an error.
+    pub fn with_string_limit(mut self, limit: usize) -> Self {
+        self.string_limit = Some(limit);
+        self
+    }
+
+    /// WebAssembly text of `program`, which must have passed the checker
+    /// with `i32` integers. Calls to host functions, which only exist in the
+    /// interpreter, are errors.
+    pub fn emit_program(&self, program: &Program) -> Result<String, SpannedCheckError> {
+        let mut generator = Generator {
+            next_literal: DATA_START,
+            ..Generator::default()
+        };
+        let routines: Vec<&Routine> = program
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Routine(routine) => Some(routine),
+                _ => None,
+            })
+            .collect();
+        for routine in &routines {
+            let parameters = routine
+                .parameters
+                .iter()
+                .map(|param| param.type_def.clone())
+                .collect();
+            let signature = (parameters, routine.return_type.clone());
+            generator.routines.insert(routine.name.clone(), signature);
+        }
+
+        let string_limit = match self.string_limit {
+            Some(limit) => u32::try_from(limit).unwrap_or(u32::MAX),
+            None => u32::MAX,
+        };
+        let runtime =
+            with_error_codes(RUNTIME).replace("{string_limit}", &string_limit.to_string());
+        let runtime = generator.intern_placeholders(&runtime);
+        let mut functions = String::new();
+        for routine in &routines {
+            functions.push('\n');
+            functions.push_str(&generator.emit_routine(routine)?);
+        }
+        functions.push('\n');
+        functions.push_str(&generator.emit_main(&program.statements)?);
+
+        let heap = generator.next_literal;
+        let pages = heap.div_ceil(0x10000).max(1);
+        let runtime = runtime
+            .replace("{heap}", &heap.to_string())
+            .replace("{pages}", &pages.to_string());
+        let mut output = String::from(";; Compiled from Mini-PL.\n(module\n");
+        output.push_str(&runtime);
+        output.push_str(&functions);
+        output.push('\n');
+        for (s, address) in &generator.literals {
+            output.push_str(&format!(
+                "{}(data (i32.const {}) {})\n",
+                INDENT,
+                address,
+                data_string(s)
+            ));
+        }
+        output.push_str(")\n");
+        Ok(output)
+    }
+}
+
+/// A variable in scope. Variables declared without a value have a flag
+/// `$a_<name>` that records whether they were assigned.
+struct Variable {
+    type_def: Type,
+    flagged: bool,
+}
+
+type Signature = (Vec<Type>, Option<Type>);
+
+#[derive(Default)]
+struct Generator {
+    output: String,
+    depth: usize,
+    /// Locals of the function being emitted, other than its parameters.
+    locals: Vec<String>,
+    labels: usize,
+    scopes: Vec<HashMap<String, Variable>>,
+    routines: HashMap<String, Signature>,
+    /// String literals in the order they were placed, with their address.
+    literals: Vec<(String, u32)>,
+    next_literal: u32,
+}
+
+type GenResult<T> = Result<T, SpannedCheckError>;
+
+impl Generator {
+    fn line(&mut self, text: &str) {
+        self.output.push_str(&INDENT.repeat(self.depth));
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    /// Address of the literal `s`, placing it in memory on first use.
+    fn literal(&mut self, s: &str) -> String {
+        if let Some((_, address)) = self.literals.iter().find(|(literal, _)| literal == s) {
+            return format!("(i32.const {})", address);
+        }
+        let address = self.next_literal;
+        self.literals.push((s.to_string(), address));
+        self.next_literal += (4 + s.len() as u32 + 3) & !3;
+        format!("(i32.const {})", address)
+    }
+
+    /// Replaces the `{"text"}` placeholders of the runtime by the address
+    /// of `text`.
+    fn intern_placeholders(&mut self, template: &str) -> String {
+        let mut output = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{\"") {
+            let end = match rest[start..].find("\"}") {
+                Some(end) => start + end,
+                None => break,
+            };
+            output.push_str(&rest[..start]);
+            output.push_str(&self.literal(&rest[start + 2..end]));
+            rest = &rest[end + 2..];
+        }
+        output.push_str(rest);
+        output
+    }
+
+    /// Declares the `i32` local `name` if needed and returns it as `$name`.
+    fn local(&mut self, name: String) -> String {
+        if !self.locals.contains(&name) {
+            self.locals.push(name.clone());
+        }
+        format!("${}", name)
+    }
+
+    fn temporary(&mut self) -> String {
+        self.labels += 1;
+        self.local(format!("t{}", self.labels))
+    }
+
+    /// The body emitted since the last call, with the declarations of its
+    /// locals.
+    fn take_body(&mut self) -> String {
+        let mut body = String::new();
+        for local in std::mem::take(&mut self.locals) {
+            body.push_str(&format!("{0}{0}(local ${1} i32)\n", INDENT, local));
+        }
+        body.push_str(&std::mem::take(&mut self.output));
+        body
+    }
+
+    fn emit_routine(&mut self, routine: &Routine) -> GenResult<String> {
+        let mut scope = HashMap::new();
+        let mut header = format!("{}(func {}", INDENT, function(&routine.name));
+        for param in &routine.parameters {
+            header.push_str(&format!(" (param {} i32)", variable(&param.name)));
+            let variable = Variable {
+                type_def: param.type_def.clone(),
+                flagged: false,
+            };
+            scope.insert(param.name.clone(), variable);
+        }
+        if routine.return_type.is_some() {
+            header.push_str(" (result i32)");
+        }
+        self.scopes = vec![scope];
+        self.depth = 2;
+        let line = self.local("caller_line".to_string());
+        let column = self.local("caller_column".to_string());
+        self.line(&format!("(local.set {} (global.get $line))", line));
+        self.line(&format!("(local.set {} (global.get $column))", column));
+        self.line("(call $mpl_enter)");
+        self.emit_block(&routine.body)?;
+        self.line("(call $mpl_leave (local.get $caller_line) (local.get $caller_column))");
+        if routine.return_type.is_some() {
+            let err = EvalError::MissingReturn(routine.name.clone());
+            let message = self.literal(&err.message());
+            let code = self.literal(err.code());
+            self.line(&format!("(call $mpl_fail {} {})", message, code));
+            self.line("(unreachable)");
+        }
+        Ok(format!("{}\n{}{})\n", header, self.take_body(), INDENT))
+    }
+
+    fn emit_main(&mut self, statements: &[Statement]) -> GenResult<String> {
+        self.scopes = vec![HashMap::new()];
+        self.depth = 2;
+        self.emit_block(statements)?;
+        Ok(format!(
+            "{}(func $main (export \"main\")\n{}{})\n",
+            INDENT,
+            self.take_body(),
+            INDENT
+        ))
+    }
+
+    fn emit_block(&mut self, statements: &[Statement]) -> GenResult<()> {
+        for statement in statements {
+            self.emit_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    fn emit_statement(&mut self, statement: &Statement) -> GenResult<()> {
+        let span = statement.span;
+        if let StatementKind::Routine(_) = statement.kind {
+            return Ok(());
+        }
+        self.line(&format!(
+            "(call $mpl_at (i32.const {}) (i32.const {}))",
+            span.start.line, span.start.column
+        ));
+        match &statement.kind {
+            StatementKind::VarInitialization(id, type_def) => {
+                let name = self.local(mangle("v_", id));
+                match type_def {
+                    Type::Array(size, element) => {
+                        let fill = match element.as_ref() {
+                            Type::String => self.literal(""),
+                            _ => "(i32.const 0)".to_string(),
+                        };
+                        self.line(&format!(
+                            "(local.set {} (call $mpl_array_new (i32.const {}) {}))",
+                            name, size, fill
+                        ));
+                        self.declare(id, type_def, false);
+                    }
+                    _ => {
+                        let flag = self.local(mangle("a_", id));
+                        self.line(&format!("(local.set {} (i32.const 0))", name));
+                        self.line(&format!("(local.set {} (i32.const 0))", flag));
+                        self.declare(id, type_def, true);
+                    }
+                }
+            }
+            StatementKind::NewAssignment(id, type_def, exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                let name = self.local(mangle("v_", id));
+                self.line(&format!("(local.set {} {})", name, value));
+                self.declare(id, type_def, false);
+            }
+            StatementKind::Assignment(id, exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                self.assign(id, value, span)?;
+            }
+            StatementKind::IndexAssignment(id, index, exp) => {
+                let (index, _) = self.emit_expression(index)?;
+                let (value, _) = self.emit_expression(exp)?;
+                self.lookup(id, span)?;
+                self.line(&format!(
+                    "(call $mpl_array_set (local.get {}) {} {})",
+                    variable(id),
+                    index,
+                    value
+                ));
+            }
+            StatementKind::Print(exp) => {
+                let (value, type_def) = self.emit_expression(exp)?;
+                let function = match type_def {
+                    Type::Integer => "$mpl_print_int",
+                    Type::Boolean => "$mpl_print_bool",
+                    Type::String => "$mpl_print",
+                    Type::Array(..) => return Err(unsupported("print", type_def, exp.span)),
+                };
+                self.line(&format!("(call {} {})", function, value));
+            }
+            StatementKind::Assert(exp) => {
+                let (value, _) = self.emit_expression(exp)?;
+                let message = self.literal(&format!("Assertion failed: {}\n", exp));
+                self.line(&format!("(call $mpl_assert {} {})", value, message));
+            }
+            StatementKind::Read(id) => {
+                let type_def = self.lookup(id, span)?.type_def.clone();
+                let value = match type_def {
+                    Type::Integer => "(call $mpl_read_int)",
+                    Type::String => "(call $mpl_read_string)",
+                    _ => return Err(unsupported("read", type_def, span)),
+                };
+                self.assign(id, value.to_string(), span)?;
+            }
+            StatementKind::For(id, start, end, stmts) => {
+                let (start, _) = self.emit_expression(start)?;
+                let (end, _) = self.emit_expression(end)?;
+                let counter = self.temporary();
+                let last = self.temporary();
+                let label = self.labels;
+                self.line(&format!("(local.set {} {})", counter, start));
+                self.line(&format!("(local.set {} {})", last, end));
+                self.line(&format!("(block $exit{}", label));
+                self.depth += 1;
+                self.line(&format!(
+                    "(br_if $exit{} (i32.gt_s (local.get {}) (local.get {})))",
+                    label, counter, last
+                ));
+                self.line(&format!("(loop $loop{}", label));
+                self.depth += 1;
+                self.assign(id, format!("(local.get {})", counter), span)?;
+                self.scopes.push(HashMap::new());
+                self.emit_block(stmts)?;
+                self.scopes.pop();
+                // Stopping at the last value before incrementing cannot
+                // overflow.
+                self.line(&format!(
+                    "(br_if $exit{} (i32.eq (local.get {}) (local.get {})))",
+                    label, counter, last
+                ));
+                self.line(&format!(
+                    "(local.set {0} (i32.add (local.get {0}) (i32.const 1)))",
+                    counter
+                ));
+                self.line(&format!("(br $loop{})))", label));
+                self.depth -= 2;
+            }
+            StatementKind::Routine(_) => {}
+            StatementKind::Call(name, args) => match self.emit_call(name, args, span)? {
+                (call, Some(_)) => self.line(&format!("(drop {})", call)),
+                (call, None) => self.line(&call),
+            },
+            StatementKind::Return(exp) => {
+                let leave = "(call $mpl_leave (local.get $caller_line) (local.get $caller_column))";
+                match exp {
+                    Some(exp) => {
+                        let (value, _) = self.emit_expression(exp)?;
+                        let result = self.local("result".to_string());
+                        self.line(&format!("(local.set {} {})", result, value));
+                        self.line(leave);
+                        self.line(&format!("(return (local.get {}))", result));
+                    }
+                    None => {
+                        self.line(leave);
+                        self.line("(return)");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Instructions computing `exp`, with its type. WebAssembly evaluates
+    /// operands from left to right, like the evaluator.
+    fn emit_expression(&mut self, exp: &Expression) -> GenResult<(String, Type)> {
+        let span = exp.span;
+        match &exp.kind {
+            // Literals outside i32 fail like the evaluator's overflow check.
+            ExpressionKind::IntegerConstant(int) => match i32::try_from(*int) {
+                Ok(int) => Ok((format!("(i32.const {})", int), Type::Integer)),
+                Err(_) => Ok((
+                    format!("(call $mpl_checked (i64.const {}))", int),
+                    Type::Integer,
+                )),
+            },
+            ExpressionKind::StringValue(s) => Ok((self.literal(s), Type::String)),
+            ExpressionKind::Boolean(b) => Ok((format!("(i32.const {})", *b as i32), Type::Boolean)),
+            ExpressionKind::Identifier(id) => {
+                let variable = self.lookup(id, span)?;
+                let type_def = variable.type_def.clone();
+                let value = match (&type_def, variable.flagged) {
+                    (Type::Array(..), _) => {
+                        format!("(call $mpl_array_copy (local.get {}))", self::variable(id))
+                    }
+                    (_, true) => {
+                        let message = self.literal(&format!("Variable {} not initialized", id));
+                        format!(
+                            "(block (result i32) (call $mpl_check_assigned (local.get {}) {}) (local.get {}))",
+                            mangle("$a_", id),
+                            message,
+                            self::variable(id)
+                        )
+                    }
+                    (_, false) => format!("(local.get {})", self::variable(id)),
+                };
+                Ok((value, type_def))
+            }
+            ExpressionKind::Unary(UnaryOperator::Not, operand) => {
+                let (operand, _) = self.emit_expression(operand)?;
+                Ok((format!("(i32.eqz {})", operand), Type::Boolean))
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                let (a, type_def) = self.emit_expression(left)?;
+                let (b, _) = self.emit_expression(right)?;
+                let (instruction, result) = match (&type_def, op) {
+                    (Type::Integer, BinaryOperator::Plus) => ("call $mpl_add", Type::Integer),
+                    (Type::Integer, BinaryOperator::Minus) => ("call $mpl_sub", Type::Integer),
+                    (Type::Integer, BinaryOperator::Multiplication) => {
+                        ("call $mpl_mul", Type::Integer)
+                    }
+                    (Type::Integer, BinaryOperator::Division) => ("call $mpl_div", Type::Integer),
+                    (Type::Integer, BinaryOperator::Equals)
+                    | (Type::Boolean, BinaryOperator::Equals) => ("i32.eq", Type::Boolean),
+                    (Type::Integer, BinaryOperator::LessThan) => ("i32.lt_s", Type::Boolean),
+                    (Type::Integer, BinaryOperator::GreaterThan) => ("i32.gt_s", Type::Boolean),
+                    (Type::Boolean, BinaryOperator::And) => ("i32.and", Type::Boolean),
+                    // `false < true` for booleans, which are 0 and 1.
+                    (Type::Boolean, BinaryOperator::LessThan) => ("i32.lt_u", Type::Boolean),
+                    (Type::Boolean, BinaryOperator::GreaterThan) => ("i32.gt_u", Type::Boolean),
+                    (Type::String, BinaryOperator::Plus) => ("call $mpl_concat", Type::String),
+                    (Type::String, BinaryOperator::Equals) => ("call $mpl_equals", Type::Boolean),
+                    (Type::String, BinaryOperator::LessThan) => {
+                        let compare = format!("(call $mpl_compare {} {})", a, b);
+                        let value = format!("(i32.lt_s {} (i32.const 0))", compare);
+                        return Ok((value, Type::Boolean));
+                    }
+                    (Type::String, BinaryOperator::GreaterThan) => {
+                        let compare = format!("(call $mpl_compare {} {})", a, b);
+                        let value = format!("(i32.gt_s {} (i32.const 0))", compare);
+                        return Ok((value, Type::Boolean));
+                    }
+                    _ => return Err(unsupported(&op.to_string(), type_def, span)),
+                };
+                Ok((format!("({} {} {})", instruction, a, b), result))
+            }
+            ExpressionKind::Call(name, args) => match self.emit_call(name, args, span)? {
+                (call, Some(type_def)) => Ok((call, type_def)),
+                (_, None) => Err((CheckError::NoReturnValue(name.clone()), span)),
+            },
+            ExpressionKind::Index(id, index) => {
+                let (index, _) = self.emit_expression(index)?;
+                let element = match &self.lookup(id, span)?.type_def {
+                    Type::Array(_, element) => element.as_ref().clone(),
+                    type_def => return Err(unsupported("[]", type_def.clone(), span)),
+                };
+                let value = format!(
+                    "(call $mpl_array_get (local.get {}) {})",
+                    variable(id),
+                    index
+                );
+                Ok((value, element))
+            }
+        }
+    }
+
+    fn emit_call(
+        &mut self,
+        name: &str,
+        args: &[Expression],
+        span: Span,
+    ) -> GenResult<(String, Option<Type>)> {
+        if let Some(builtin) = builtins::find(name) {
+            // The size of a variable is known from its type, without copying it.
+            if let (SIZE, [array]) = (name, args) {
+                if let ExpressionKind::Identifier(id) = &array.kind {
+                    if let Type::Array(size, _) = self.lookup(id, array.span)?.type_def {
+                        return Ok((format!("(i32.const {})", size), Some(Type::Integer)));
+                    }
+                }
+            }
+            let mut values = Vec::new();
+            for arg in args {
+                values.push(self.emit_expression(arg)?.0);
+            }
+            let call = match (builtin.name, values.as_slice()) {
+                (SIZE, [array]) => format!("(i32.load {})", array),
+                (name, values) => format!("(call $mpl_{} {})", name, values.join(" ")),
+            };
+            return Ok((call, Some(builtin.return_type.clone())));
+        }
+        let return_type = match self.routines.get(name) {
+            Some((_, return_type)) => return_type.clone(),
+            None => return Err((CheckError::UndeclaredRoutine(name.to_string()), span)),
+        };
+        let mut call = format!("(call {}", function(name));
+        for arg in args {
+            call.push(' ');
+            call.push_str(&self.emit_expression(arg)?.0);
+        }
+        call.push(')');
+        Ok((call, return_type))
+    }
+
+    fn declare(&mut self, id: &str, type_def: &Type, flagged: bool) {
+        let variable = Variable {
+            type_def: type_def.clone(),
+            flagged,
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(id.to_string(), variable);
+        }
+    }
+
+    fn lookup(&self, id: &str, span: Span) -> GenResult<&Variable> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(id)) {
+            Some(variable) => Ok(variable),
+            None => Err((CheckError::UndeclaredVariable(id.to_string()), span)),
+        }
+    }
+
+    fn assign(&mut self, id: &str, value: String, span: Span) -> GenResult<()> {
+        let flagged = self.lookup(id, span)?.flagged;
+        self.line(&format!("(local.set {} {})", variable(id), value));
+        if flagged {
+            self.line(&format!("(local.set {} (i32.const 1))", mangle("$a_", id)));
+        }
+        Ok(())
+    }
+}
+
+fn unsupported(op: &str, type_def: Type, span: Span) -> SpannedCheckError {
+    (
+        CheckError::UnsupportedOperation(op.to_string(), type_def),
+        span,
+    )
+}
+
+fn variable(id: &str) -> String {
+    mangle("$v_", id)
+}
+
+fn function(name: &str) -> String {
+    mangle("$f_", name)
+}
+
+/// Contents of a string in memory, its length as a little-endian `i32`
+/// followed by its bytes, as a WebAssembly string.
+fn data_string(s: &str) -> String {
+    let mut data = String::from("\"");
+    let length = (s.len() as u32).to_le_bytes();
+    for byte in length.iter().chain(s.as_bytes()) {
+        match byte {
+            b'"' | b'\\' => {
+                data.push('\\');
+                data.push(*byte as char);
+            }
+            b' '..=b'~' => data.push(*byte as char),
+            byte => data.push_str(&format!("\\{:02x}", byte)),
+        }
+    }
+    data.push('"');
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::wat::data_string;
+
+    #[test]
+    fn escape_data() {
+        assert_eq!(data_string("a\"\n"), "\"\\03\\00\\00\\00a\\\"\\0a\"");
+        assert_eq!(data_string(""), "\"\\00\\00\\00\\00\"");
+    }
+}
@@ -0,0 +1,15 @@
+use minipl_interpreter::lsp;
+use std::io;
+use std::process;
+
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    match lsp::run(stdin.lock(), stdout.lock()) {
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            eprintln!("minipl-lsp: {}", err);
+            process::exit(1);
+        }
+    }
+}
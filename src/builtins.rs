@@ -0,0 +1,179 @@
+//! Functions available to every program without a declaration. Their names
+//! are reserved: a procedure or function may not redeclare them.
+
+use crate::utils::{EvalError, Type, Value};
+use std::fmt;
+
+pub const SIZE: &str = "size";
+
+/// Type of a parameter of a built-in function.
+#[derive(Debug, PartialEq)]
+pub enum Parameter {
+    Of(Type),
+    /// An array of any size and element type.
+    AnyArray,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub parameters: &'static [Parameter],
+    pub return_type: Type,
+}
+
+impl fmt::Display for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parameters: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|param| match param {
+                Parameter::Of(type_def) => type_def.to_string(),
+                Parameter::AnyArray => "array".to_string(),
+            })
+            .collect();
+        write!(
+            f,
+            "{}({}) : {}",
+            self.name,
+            parameters.join(", "),
+            self.return_type
+        )
+    }
+}
+
+const STRING: Parameter = Parameter::Of(Type::String);
+const INTEGER: Parameter = Parameter::Of(Type::Integer);
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: SIZE,
+        parameters: &[Parameter::AnyArray],
+        return_type: Type::Integer,
+    },
+    Builtin {
+        name: "length",
+        parameters: &[STRING],
+        return_type: Type::Integer,
+    },
+    Builtin {
+        name: "substr",
+        parameters: &[STRING, INTEGER, INTEGER],
+        return_type: Type::String,
+    },
+    Builtin {
+        name: "to_string",
+        parameters: &[INTEGER],
+        return_type: Type::String,
+    },
+    Builtin {
+        name: "to_int",
+        parameters: &[STRING],
+        return_type: Type::Integer,
+    },
+    Builtin {
+        name: "upper",
+        parameters: &[STRING],
+        return_type: Type::String,
+    },
+    Builtin {
+        name: "lower",
+        parameters: &[STRING],
+        return_type: Type::String,
+    },
+    Builtin {
+        name: "contains",
+        parameters: &[STRING, STRING],
+        return_type: Type::Boolean,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// Applies the built-in function `name` to arguments of the types it
+/// declares. Lengths and positions count characters, not bytes, and
+/// `substr` takes a zero-based start. The caller checks the result against
+/// the integer width and string limit.
+pub fn call(name: &str, args: Vec<Value>) -> Result<Value, EvalError> {
+    let invalid = |message: String| Err(EvalError::InvalidArgument(name.to_string(), message));
+    match (name, args.as_slice()) {
+        (SIZE, [Value::Array(values)]) => Ok(Value::Integer(values.len() as i64)),
+        ("length", [Value::String(s)]) => Ok(Value::Integer(s.chars().count() as i64)),
+        ("substr", [Value::String(s), Value::Integer(start), Value::Integer(len)]) => {
+            let length = s.chars().count() as i64;
+            if *start < 0 || *start > length {
+                return invalid(format!(
+                    "start {} is outside a string of length {}",
+                    start, length
+                ));
+            }
+            if *len < 0 || *len > length - start {
+                return invalid(format!(
+                    "length {} from {} is outside a string of length {}",
+                    len, start, length
+                ));
+            }
+            let substring = s.chars().skip(*start as usize).take(*len as usize);
+            Ok(Value::String(substring.collect()))
+        }
+        ("to_string", [Value::Integer(int)]) => Ok(Value::String(int.to_string())),
+        ("to_int", [Value::String(s)]) => match s.trim().parse() {
+            Ok(int) => Ok(Value::Integer(int)),
+            Err(_) => invalid(format!("{:?} is not an integer", s)),
+        },
+        ("upper", [Value::String(s)]) => Ok(Value::String(s.to_uppercase())),
+        ("lower", [Value::String(s)]) => Ok(Value::String(s.to_lowercase())),
+        ("contains", [Value::String(s), Value::String(part)]) => {
+            Ok(Value::Bool(s.contains(part.as_str())))
+        }
+        _ => Err(EvalError::MismatchedTypes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtins::call;
+    use crate::utils::{EvalError, Value};
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn call_builtins() {
+        let substr = |start, len| call("substr", vec![string("häll"), start, len]);
+        assert_eq!(
+            substr(Value::Integer(1), Value::Integer(2)).ok(),
+            Some(string("äl"))
+        );
+        assert_eq!(
+            substr(Value::Integer(4), Value::Integer(0)).ok(),
+            Some(string(""))
+        );
+        assert!(matches!(
+            substr(Value::Integer(3), Value::Integer(2)),
+            Err(EvalError::InvalidArgument(..))
+        ));
+        assert!(matches!(
+            substr(Value::Integer(-1), Value::Integer(0)),
+            Err(EvalError::InvalidArgument(..))
+        ));
+        assert_eq!(
+            call("length", vec![string("häll")]).ok(),
+            Some(Value::Integer(4))
+        );
+        assert_eq!(
+            call("to_int", vec![string(" -12\n")]).ok(),
+            Some(Value::Integer(-12))
+        );
+        assert!(matches!(
+            call("to_int", vec![string("12a")]),
+            Err(EvalError::InvalidArgument(..))
+        ));
+        assert_eq!(
+            call("contains", vec![string("minipl"), string("pl")]).ok(),
+            Some(Value::Bool(true))
+        );
+    }
+}
@@ -0,0 +1,705 @@
+use crate::ast::{
+    BinaryOperator, Expression, ExpressionKind, Program, Routine, Statement, StatementKind,
+    UnaryOperator,
+};
+use crate::builtins::{self, Builtin, Parameter, BUILTINS};
+use crate::diagnostic::{Diagnostic, Label, ToDiagnostic};
+use crate::suggest::{did_you_mean, suggest};
+use crate::utils::{CheckError, Reported, Span, Type};
+use std::collections::{HashMap, HashSet};
+
+pub type SpannedCheckError = (CheckError, Span);
+
+/// A variable introduced by a `var` statement.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Declaration {
+    pub name: String,
+    pub type_def: Type,
+    pub span: Span,
+    /// The `for` statement or routine whose block declares the variable, or
+    /// `None` at the top level.
+    pub scope: Option<Span>,
+}
+
+/// Static type checker run between parsing and evaluation. It reports every
+/// error it finds instead of stopping at the first one.
+///
+/// Variables declared in a `for` body are only visible in that body, and a
+/// declaration may not shadow a variable of an enclosing block. Procedures
+/// and functions are declared at the top level and see only their parameters
+/// and their own locals.
+pub struct Checker {
+    scopes: Vec<HashMap<String, Type>>,
+    /// Variables whose block has ended, to tell use-after-scope apart from
+    /// undeclared variables.
+    out_of_scope: HashSet<String>,
+    /// Parameter types and return type of every declared routine and host
+    /// function.
+    routines: HashMap<String, (Vec<Type>, Option<Type>)>,
+    /// Name and return type of the routine whose body is being checked.
+    current_routine: Option<(String, Option<Type>)>,
+    /// The `for` statement or routine whose block is being checked.
+    block: Option<Span>,
+    declarations: Vec<Declaration>,
+    errors: Vec<Reported<CheckError>>,
+}
+
+impl Default for Checker {
+    fn default() -> Self {
+        Checker::new()
+    }
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        Checker {
+            scopes: vec![HashMap::new()],
+            out_of_scope: HashSet::new(),
+            routines: HashMap::new(),
+            current_routine: None,
+            block: None,
+            declarations: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Declares a function provided by the host, as registered with
+    /// `Evaluator::with_function`. The program may call it but not redeclare
+    /// it.
+    pub fn with_function(mut self, name: &str, parameters: Vec<Type>, return_type: Type) -> Self {
+        let signature = (parameters, Some(return_type));
+        self.routines.insert(name.to_string(), signature);
+        self
+    }
+
+    /// Declares a global variable defined by the host with
+    /// `Evaluator::define_variable`.
+    pub fn with_variable(mut self, name: &str, type_def: Type) -> Self {
+        self.scopes[0].insert(name.to_string(), type_def);
+        self
+    }
+
+    /// The type errors, each with a help note such as a suggestion for a
+    /// misspelled variable.
+    pub fn get_errors(&self) -> &[Reported<CheckError>] {
+        &self.errors
+    }
+
+    /// The errors of `get_errors` with their help notes and labels.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.errors
+            .iter()
+            .map(ToDiagnostic::to_diagnostic)
+            .collect()
+    }
+
+    pub fn declarations(&self) -> &[Declaration] {
+        &self.declarations
+    }
+
+    pub fn check_program(&mut self, program: &Program) {
+        for statement in &program.statements {
+            self.check_statement(statement);
+        }
+    }
+
+    fn check_statement(&mut self, statement: &Statement) {
+        let span = statement.span;
+        match &statement.kind {
+            StatementKind::VarInitialization(id, type_def) => self.declare(id, type_def, span),
+            StatementKind::NewAssignment(id, type_def, exp) => {
+                self.expect_type(exp, type_def);
+                self.declare(id, type_def, span);
+            }
+            StatementKind::Assignment(id, exp) => {
+                if let Some(type_def) = self.lookup(id, span) {
+                    self.expect_type(exp, &type_def);
+                }
+            }
+            StatementKind::IndexAssignment(id, index, exp) => {
+                if let Some(element) = self.check_index(id, index, span) {
+                    self.expect_type(exp, &element);
+                }
+            }
+            StatementKind::Print(exp) => {
+                if let Some(type_def @ Type::Array(..)) = self.infer_expression(exp) {
+                    let err = CheckError::UnsupportedOperation("print".to_string(), type_def);
+                    self.error(err, exp.span);
+                }
+            }
+            StatementKind::Assert(exp) => self.expect_type(exp, &Type::Boolean),
+            StatementKind::Read(id) => {
+                if let Some(type_def @ (Type::Boolean | Type::Array(..))) = self.lookup(id, span) {
+                    let err = CheckError::UnsupportedOperation("read".to_string(), type_def);
+                    self.error(err, span);
+                }
+            }
+            StatementKind::For(id, start, end, stmts) => {
+                if let Some(type_def) = self.lookup(id, span) {
+                    if type_def != Type::Integer {
+                        self.error(CheckError::MismatchedTypes(Type::Integer, type_def), span);
+                    }
+                }
+                self.expect_type(start, &Type::Integer);
+                self.expect_type(end, &Type::Integer);
+                self.scopes.push(HashMap::new());
+                let outer_block = self.block.replace(span);
+                for stmt in stmts {
+                    self.check_statement(stmt);
+                }
+                self.block = outer_block;
+                self.end_scope();
+            }
+            StatementKind::Routine(routine) => self.check_routine(routine, span),
+            StatementKind::Call(name, args) => {
+                self.check_call(name, args, span);
+            }
+            StatementKind::Return(exp) => match (self.current_routine.clone(), exp) {
+                (None, _) => self.error(CheckError::ReturnOutsideRoutine, span),
+                (Some((_, Some(type_def))), Some(exp)) => self.expect_type(exp, &type_def),
+                (Some((name, Some(type_def))), None) => {
+                    self.error(CheckError::MissingReturnValue(name, type_def), span)
+                }
+                (Some((name, None)), Some(_)) => {
+                    self.error(CheckError::UnexpectedReturnValue(name), span)
+                }
+                (Some((_, None)), None) => {}
+            },
+        }
+    }
+
+    fn check_routine(&mut self, routine: &Routine, span: Span) {
+        let name = &routine.name;
+        if self.current_routine.is_some() || self.scopes.len() > 1 {
+            self.error(CheckError::NestedRoutine(name.clone()), span);
+            return;
+        }
+        if self.routines.contains_key(name) || builtins::find(name).is_some() {
+            self.error(CheckError::RoutineAlreadyDeclared(name.clone()), span);
+            return;
+        }
+        // Registered before the body is checked so that it can recurse.
+        let parameter_types = routine.parameters.iter().map(|p| p.type_def.clone());
+        let signature = (parameter_types.collect(), routine.return_type.clone());
+        self.routines.insert(name.clone(), signature);
+
+        // The body sees its parameters and locals only, as in the evaluator.
+        self.current_routine = Some((name.clone(), routine.return_type.clone()));
+        let outer_scopes = std::mem::replace(&mut self.scopes, vec![HashMap::new()]);
+        self.block = Some(span);
+        for param in &routine.parameters {
+            self.declare(&param.name, &param.type_def, param.span);
+        }
+        for stmt in &routine.body {
+            self.check_statement(stmt);
+        }
+        self.end_scope();
+        self.scopes = outer_scopes;
+        self.block = None;
+        self.current_routine = None;
+    }
+
+    /// Checks the arguments of a call and returns the routine's return type,
+    /// or `None` if the routine is not declared.
+    fn check_call(&mut self, name: &str, args: &[Expression], span: Span) -> Option<Option<Type>> {
+        if let Some(builtin) = builtins::find(name) {
+            self.check_builtin_call(builtin, args, span);
+            return Some(Some(builtin.return_type.clone()));
+        }
+        let (parameters, return_type) = match self.routines.get(name) {
+            Some(signature) => signature.clone(),
+            None => {
+                let routines = self.routines.keys().map(String::as_str);
+                let builtins = BUILTINS.iter().map(|builtin| builtin.name);
+                let help = help(name, routines.chain(builtins));
+                let err = CheckError::UndeclaredRoutine(name.to_string());
+                self.error_with_help(err, span, help);
+                return None;
+            }
+        };
+        if args.len() != parameters.len() {
+            let err = CheckError::ArgumentCount(name.to_string(), parameters.len(), args.len());
+            self.error(err, span);
+        }
+        for (arg, type_def) in args.iter().zip(&parameters) {
+            self.expect_type(arg, type_def);
+        }
+        Some(return_type)
+    }
+
+    fn check_builtin_call(&mut self, builtin: &Builtin, args: &[Expression], span: Span) {
+        let name = builtin.name.to_string();
+        if args.len() != builtin.parameters.len() {
+            let err = CheckError::ArgumentCount(name.clone(), builtin.parameters.len(), args.len());
+            self.error(err, span);
+        }
+        for (arg, param) in args.iter().zip(builtin.parameters) {
+            match param {
+                Parameter::Of(type_def) => self.expect_type(arg, type_def),
+                Parameter::AnyArray => match self.infer_expression(arg) {
+                    Some(Type::Array(..)) | None => {}
+                    Some(type_def) => {
+                        let err = CheckError::UnsupportedOperation(name.clone(), type_def);
+                        self.error(err, arg.span);
+                    }
+                },
+            }
+        }
+    }
+
+    /// Checks `id[index]` and returns the element type of the array `id`.
+    fn check_index(&mut self, id: &str, index: &Expression, span: Span) -> Option<Type> {
+        let type_def = self.lookup(id, span);
+        self.expect_type(index, &Type::Integer);
+        match type_def? {
+            Type::Array(_, element) => Some(*element),
+            type_def => {
+                let err = CheckError::UnsupportedOperation("[]".to_string(), type_def);
+                self.error(err, span);
+                None
+            }
+        }
+    }
+
+    fn end_scope(&mut self) {
+        if let Some(scope) = self.scopes.pop() {
+            self.out_of_scope.extend(scope.into_keys());
+        }
+    }
+
+    /// Type of `exp`, or `None` if it contains an error that was already
+    /// reported.
+    pub fn infer_expression(&mut self, exp: &Expression) -> Option<Type> {
+        match &exp.kind {
+            ExpressionKind::IntegerConstant(_) => Some(Type::Integer),
+            ExpressionKind::StringValue(_) => Some(Type::String),
+            ExpressionKind::Boolean(_) => Some(Type::Boolean),
+            ExpressionKind::Identifier(id) => self.lookup(id, exp.span),
+            ExpressionKind::Index(id, index) => self.check_index(id, index, exp.span),
+            ExpressionKind::Call(name, args) => match self.check_call(name, args, exp.span)? {
+                Some(type_def) => Some(type_def),
+                None => {
+                    self.error(CheckError::NoReturnValue(name.clone()), exp.span);
+                    None
+                }
+            },
+            ExpressionKind::Unary(op, operand) => {
+                let type_def = self.infer_expression(operand)?;
+                match (op, &type_def) {
+                    (UnaryOperator::Not, Type::Boolean) => Some(Type::Boolean),
+                    _ => {
+                        let err = CheckError::UnsupportedOperation(op.to_string(), type_def);
+                        self.error(err, exp.span);
+                        None
+                    }
+                }
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                let left_type = self.infer_expression(left);
+                let right_type = self.infer_expression(right);
+                let (left_type, right_type) = (left_type?, right_type?);
+                if left_type != right_type {
+                    let err = CheckError::MismatchedTypes(left_type, right_type);
+                    self.error(err, right.span);
+                    return None;
+                }
+                match binary_result_type(op, &left_type) {
+                    Some(type_def) => Some(type_def),
+                    None => {
+                        let err = CheckError::UnsupportedOperation(op.to_string(), left_type);
+                        self.error(err, exp.span);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    fn expect_type(&mut self, exp: &Expression, expected: &Type) {
+        if let Some(found) = self.infer_expression(exp) {
+            if &found != expected {
+                self.error(
+                    CheckError::MismatchedTypes(expected.clone(), found),
+                    exp.span,
+                );
+            }
+        }
+    }
+
+    fn declare(&mut self, id: &str, type_def: &Type, span: Span) {
+        if self.scopes.iter().any(|scope| scope.contains_key(id)) {
+            let label = self.declaration_label(id, "is first declared here");
+            let err = CheckError::VariableAlreadyDeclared(id.to_string());
+            self.error_with_label(err, span, label);
+            return;
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(id.to_string(), type_def.clone());
+        }
+        self.declarations.push(Declaration {
+            name: id.to_string(),
+            type_def: type_def.clone(),
+            span,
+            scope: self.block,
+        });
+    }
+
+    fn lookup(&mut self, id: &str, span: Span) -> Option<Type> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(id)) {
+            Some(type_def) => Some(type_def.clone()),
+            None if self.out_of_scope.contains(id) => {
+                let what = "is declared here, in a block that has ended";
+                let label = self.declaration_label(id, what);
+                let err = CheckError::VariableOutOfScope(id.to_string());
+                self.error_with_label(err, span, label);
+                None
+            }
+            None => {
+                let variables = self.scopes.iter().flat_map(HashMap::keys);
+                let help = help(id, variables.map(String::as_str));
+                let err = CheckError::UndeclaredVariable(id.to_string());
+                self.error_with_help(err, span, help);
+                None
+            }
+        }
+    }
+
+    /// A label pointing at the latest declaration of `id`, if the program
+    /// declares it rather than the host.
+    fn declaration_label(&self, id: &str, what: &str) -> Option<Label> {
+        let declaration = self
+            .declarations
+            .iter()
+            .rev()
+            .find(|decl| decl.name == id)?;
+        Some(Label {
+            span: declaration.span,
+            message: format!("`{}` {}", id, what),
+        })
+    }
+
+    fn error(&mut self, err: CheckError, span: Span) {
+        self.errors.push(Reported::new(err, span));
+    }
+
+    fn error_with_help(&mut self, err: CheckError, span: Span, help: Option<String>) {
+        let error = Reported::new(err, span);
+        self.errors.push(Reported { help, ..error });
+    }
+
+    fn error_with_label(&mut self, err: CheckError, span: Span, label: Option<Label>) {
+        let error = Reported::new(err, span);
+        let labels = label.into_iter().collect();
+        self.errors.push(Reported { labels, ..error });
+    }
+}
+
+/// A help note suggesting the one of `candidates` that `name` is likely a
+/// misspelling of.
+fn help<'a, I: IntoIterator<Item = &'a str>>(name: &str, candidates: I) -> Option<String> {
+    suggest(name, candidates).map(|suggestion| did_you_mean(name, suggestion))
+}
+
+/// Result type of `op` applied to two operands of `operand` type, matching
+/// the operations the evaluator supports.
+pub fn binary_result_type(op: &BinaryOperator, operand: &Type) -> Option<Type> {
+    match (operand, op) {
+        (Type::Array(..), _) => None,
+        (Type::Integer, BinaryOperator::Plus)
+        | (Type::Integer, BinaryOperator::Minus)
+        | (Type::Integer, BinaryOperator::Multiplication)
+        | (Type::Integer, BinaryOperator::Division) => Some(Type::Integer),
+        (Type::String, BinaryOperator::Plus) => Some(Type::String),
+        (Type::Boolean, BinaryOperator::And) => Some(Type::Boolean),
+        (_, BinaryOperator::Equals)
+        | (_, BinaryOperator::LessThan)
+        | (_, BinaryOperator::GreaterThan) => Some(Type::Boolean),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checker::Checker;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::utils::{CheckError, Type};
+
+    fn check(source: &str) -> Checker {
+        let lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(parser.get_errors().is_empty());
+        let mut checker = Checker::new();
+        checker.check_program(&program);
+        checker
+    }
+
+    fn errors(source: &str) -> Vec<CheckError> {
+        let checker = check(source);
+        checker
+            .get_errors()
+            .iter()
+            .map(|reported| reported.error.0.clone())
+            .collect()
+    }
+
+    #[test]
+    fn accept_valid_program() {
+        let source = r#"
+            var X : int := 1 + (2 * 6);
+            assert (12 > X);
+            var n : int := 0;
+            read n;
+            var x : int;
+            for x in 0..n do
+                print "Hello" + " World!";
+            end for;
+            var s : string;
+            read s;
+            var b : bool := !(s = "yes") & true;
+        "#;
+        assert_eq!(errors(source), vec![]);
+    }
+
+    #[test]
+    fn report_type_errors() {
+        let source = r#"
+            var x : int := "a";
+            var s : string := "b" - "c";
+            var b : bool;
+            read b;
+            print y;
+            assert (1 + 1);
+            for s in 0..true do
+            end for;
+            var x : int;
+            print !1;
+        "#;
+        assert_eq!(
+            errors(source),
+            vec![
+                CheckError::MismatchedTypes(Type::Integer, Type::String),
+                CheckError::UnsupportedOperation("-".to_string(), Type::String),
+                CheckError::UnsupportedOperation("read".to_string(), Type::Boolean),
+                CheckError::UndeclaredVariable("y".to_string()),
+                CheckError::MismatchedTypes(Type::Boolean, Type::Integer),
+                CheckError::MismatchedTypes(Type::Integer, Type::String),
+                CheckError::MismatchedTypes(Type::Integer, Type::Boolean),
+                CheckError::VariableAlreadyDeclared("x".to_string()),
+                CheckError::UnsupportedOperation("!".to_string(), Type::Integer),
+            ]
+        );
+    }
+
+    #[test]
+    fn scope_loop_bodies() {
+        let source = r#"
+            var i : int;
+            var total : int := 0;
+            for i in 1..3 do
+                var square : int := i * i;
+                total := total + square;
+            end for;
+            for i in 1..3 do
+                var square : string := "again";
+                var total : int;
+            end for;
+            print square;
+            print missing;
+        "#;
+        assert_eq!(
+            errors(source),
+            vec![
+                CheckError::VariableAlreadyDeclared("total".to_string()),
+                CheckError::VariableOutOfScope("square".to_string()),
+                CheckError::UndeclaredVariable("missing".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_routines() {
+        let source = r#"
+            function fact(n : int) : int
+                var i : int;
+                for i in 1..n - 1 do
+                    return n * fact(n - 1);
+                end for;
+                return 1;
+            end function;
+            procedure show(s : string)
+                print s;
+                return 1;
+            end procedure;
+            function empty() : bool
+                return;
+            end function;
+            var x : int := fact("3");
+            x := fact(1, 2);
+            show("a", "b");
+            x := show("a");
+            missing();
+            return;
+            procedure show()
+            end procedure;
+            for x in 1..2 do
+                procedure inner()
+                end procedure;
+            end for;
+            print n;
+        "#;
+        assert_eq!(
+            errors(source),
+            vec![
+                CheckError::UnexpectedReturnValue("show".to_string()),
+                CheckError::MissingReturnValue("empty".to_string(), Type::Boolean),
+                CheckError::MismatchedTypes(Type::Integer, Type::String),
+                CheckError::ArgumentCount("fact".to_string(), 1, 2),
+                CheckError::ArgumentCount("show".to_string(), 1, 2),
+                CheckError::NoReturnValue("show".to_string()),
+                CheckError::UndeclaredRoutine("missing".to_string()),
+                CheckError::ReturnOutsideRoutine,
+                CheckError::RoutineAlreadyDeclared("show".to_string()),
+                CheckError::NestedRoutine("inner".to_string()),
+                CheckError::VariableOutOfScope("n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_arrays() {
+        let source = r#"
+            var a : array[3] of int;
+            var b : array[3] of int := a;
+            var s : array[3] of string := a;
+            var n : int := size(a) + a[0];
+            a[1] := "x";
+            a["y"] := 1;
+            n := n[0];
+            print a;
+            assert (a = b);
+            n := size(n);
+            read a;
+        "#;
+        let int_array = Type::Array(3, Box::new(Type::Integer));
+        let unsupported = |op: &str, type_def: &Type| {
+            CheckError::UnsupportedOperation(op.to_string(), type_def.clone())
+        };
+        assert_eq!(
+            errors(source),
+            vec![
+                CheckError::MismatchedTypes(
+                    Type::Array(3, Box::new(Type::String)),
+                    int_array.clone()
+                ),
+                CheckError::MismatchedTypes(Type::Integer, Type::String),
+                CheckError::MismatchedTypes(Type::Integer, Type::String),
+                unsupported("[]", &Type::Integer),
+                unsupported("print", &int_array),
+                unsupported("=", &int_array),
+                unsupported("size", &Type::Integer),
+                unsupported("read", &int_array),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_builtin_calls() {
+        let source = r#"
+            var s : string := upper(substr("mini-pl", 0, 4)) + to_string(length("pl"));
+            var n : int := to_int(s);
+            var b : bool := contains(s, 1);
+            n := length("a", "b");
+            s := to_string("1");
+            function length(s : string) : int
+                return 0;
+            end function;
+        "#;
+        assert_eq!(
+            errors(source),
+            vec![
+                CheckError::MismatchedTypes(Type::String, Type::Integer),
+                CheckError::ArgumentCount("length".to_string(), 1, 2),
+                CheckError::MismatchedTypes(Type::Integer, Type::String),
+                CheckError::RoutineAlreadyDeclared("length".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn suggest_misspelled_names() {
+        let source = "var count : int := 1;\nprint counter;\nprint lenght(\"a\");\n\
+                      print total;\nprocedure show(n : int)\n    print cout;\nend procedure;";
+        let checker = check(source);
+        let help: Vec<Option<&str>> = checker
+            .get_errors()
+            .iter()
+            .map(|r| r.help.as_deref())
+            .collect();
+        assert_eq!(
+            help,
+            vec![
+                Some("did you mean `count` instead of `counter`?"),
+                Some("did you mean `length` instead of `lenght`?"),
+                None,
+                // `count` is not in scope in the procedure.
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn label_earlier_declarations() {
+        let source = "var i : int;\nfor i in 1..2 do\n    var square : int := i;\n    \
+                      var i : int;\nend for;\nprint square;";
+        let labels: Vec<Vec<(usize, String)>> = check(source)
+            .diagnostics()
+            .into_iter()
+            .map(|d| {
+                let labels = d.labels.into_iter();
+                labels.map(|l| (l.span.start.line, l.message)).collect()
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                vec![(1, "`i` is first declared here".to_string())],
+                vec![(
+                    3,
+                    "`square` is declared here, in a block that has ended".to_string()
+                )],
+            ]
+        );
+    }
+
+    #[test]
+    fn record_declarations() {
+        let checker = check(
+            "var a : int;\nvar b : string := \"\";\nfor a in 1..2 do\n    var c : int;\nend for;\n\
+             procedure p(n : int)\nend procedure;",
+        );
+        let names: Vec<(&str, &Type, usize, Option<usize>)> = checker
+            .declarations()
+            .iter()
+            .map(|decl| {
+                let scope = decl.scope.map(|scope| scope.start.line);
+                (
+                    decl.name.as_str(),
+                    &decl.type_def,
+                    decl.span.start.line,
+                    scope,
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("a", &Type::Integer, 1, None),
+                ("b", &Type::String, 2, None),
+                ("c", &Type::Integer, 4, Some(3)),
+                ("n", &Type::Integer, 6, Some(6)),
+            ]
+        );
+    }
+}
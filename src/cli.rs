@@ -0,0 +1,232 @@
+use crate::diagnostic::Style;
+use crate::lexer::Lexer;
+use crate::lint::{Level, Lint};
+use crate::token::Token;
+use crate::tracer::TraceFormat;
+use crate::utils::{IntegerWidth, Type, Value};
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Emit {
+    Tokens,
+    Ast,
+    Ir,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Command {
+    Run,
+    Debug,
+    /// Print the program translated to C.
+    EmitC,
+    /// Print the program translated to WebAssembly text.
+    EmitWat,
+    /// Print the program translated to Python.
+    EmitPy,
+    /// Print the program translated to JavaScript.
+    EmitJs,
+    /// Print the program translated to LLVM IR.
+    EmitLlvm,
+    /// Report lints instead of running the program.
+    Lint,
+    /// Print the explanation of an error code.
+    Explain,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub command: Command,
+    /// The program, or the code to explain for `explain`.
+    pub file_path: String,
+    pub format: Format,
+    /// How errors and warnings are rendered with `--format=text`.
+    pub diagnostics: Style,
+    pub emit: Option<Emit>,
+    pub integer_width: IntegerWidth,
+    /// File of debugger commands, read from stdin when missing.
+    pub debug_commands: Option<String>,
+    pub trace: bool,
+    /// File the trace is written to, stderr when missing.
+    pub trace_file: Option<String>,
+    pub trace_format: TraceFormat,
+    pub max_steps: Option<u64>,
+    pub max_output: Option<u64>,
+    pub max_string: Option<usize>,
+    pub timeout: Option<Duration>,
+    /// Variables defined with `--set` before the program runs.
+    pub variables: Vec<(String, Type, Value)>,
+    pub dump_variables: bool,
+    /// Run the constant-folding pass before the program.
+    pub optimize: bool,
+    pub remove_true_asserts: bool,
+    /// Print the C runtime of `emit-llvm` instead of a program.
+    pub llvm_runtime: bool,
+    /// Levels of lints given with `--allow`, `--warn` and `--deny`.
+    pub lint_levels: Vec<(Lint, Level)>,
+}
+
+pub fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        command: Command::Run,
+        file_path: String::new(),
+        format: Format::Text,
+        diagnostics: Style::Plain,
+        emit: None,
+        integer_width: IntegerWidth::default(),
+        debug_commands: None,
+        trace: false,
+        trace_file: None,
+        trace_format: TraceFormat::Human,
+        max_steps: None,
+        max_output: None,
+        max_string: None,
+        timeout: None,
+        variables: Vec::new(),
+        dump_variables: false,
+        optimize: false,
+        remove_true_asserts: false,
+        llvm_runtime: false,
+        lint_levels: Vec::new(),
+    };
+    let mut args = args;
+    if let Some((first, rest)) = args.split_first() {
+        match first.as_str() {
+            "debug" => options.command = Command::Debug,
+            "emit-c" => options.command = Command::EmitC,
+            "emit-wat" => options.command = Command::EmitWat,
+            "emit-py" => options.command = Command::EmitPy,
+            "emit-js" => options.command = Command::EmitJs,
+            "emit-llvm" => options.command = Command::EmitLlvm,
+            "lint" => options.command = Command::Lint,
+            "explain" => options.command = Command::Explain,
+            _ => {}
+        }
+        if options.command != Command::Run {
+            args = rest;
+        }
+    }
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format=text" => options.format = Format::Text,
+            "--format=json" => options.format = Format::Json,
+            "--diagnostics=plain" => options.diagnostics = Style::Plain,
+            "--diagnostics=rich" => options.diagnostics = Style::Rich,
+            "--emit=tokens" => options.emit = Some(Emit::Tokens),
+            "--emit=ast" => options.emit = Some(Emit::Ast),
+            "--emit=ir" => options.emit = Some(Emit::Ir),
+            "--int-width=i32" => options.integer_width = IntegerWidth::I32,
+            "--int-width=i64" => options.integer_width = IntegerWidth::I64,
+            "--trace" => options.trace = true,
+            "--dump-vars" => options.dump_variables = true,
+            "--optimize" => options.optimize = true,
+            "--runtime" if options.command == Command::EmitLlvm => options.llvm_runtime = true,
+            "--remove-true-asserts" => {
+                options.optimize = true;
+                options.remove_true_asserts = true;
+            }
+            "--set" => match args.next() {
+                Some(text) => options.variables.push(setting(text)?),
+                None => return Err("Missing name=value after --set".to_string()),
+            },
+            "--trace-format=human" => options.trace_format = TraceFormat::Human,
+            "--trace-format=json" => options.trace_format = TraceFormat::JsonLines,
+            flag if flag.starts_with("--trace-file=") => {
+                options.trace = true;
+                options.trace_file = flag.strip_prefix("--trace-file=").map(String::from)
+            }
+            flag if flag.starts_with("--max-steps=") => {
+                options.max_steps = Some(value(flag)?);
+            }
+            flag if flag.starts_with("--max-output=") => {
+                options.max_output = Some(value(flag)?);
+            }
+            flag if flag.starts_with("--max-string=") => {
+                options.max_string = Some(value(flag)?);
+            }
+            flag if flag.starts_with("--timeout=") => {
+                let seconds: f64 = value(flag)?;
+                match Duration::try_from_secs_f64(seconds) {
+                    Ok(timeout) => options.timeout = Some(timeout),
+                    Err(_) => return Err(format!("Invalid value in {}", flag)),
+                }
+            }
+            flag if options.command == Command::Lint && flag.starts_with("--allow=") => {
+                options.lint_levels.push((lint(flag)?, Level::Allow));
+            }
+            flag if options.command == Command::Lint && flag.starts_with("--warn=") => {
+                options.lint_levels.push((lint(flag)?, Level::Warn));
+            }
+            flag if options.command == Command::Lint && flag.starts_with("--deny=") => {
+                options.lint_levels.push((lint(flag)?, Level::Deny));
+            }
+            flag if flag.starts_with("--commands=") => {
+                options.debug_commands = flag.strip_prefix("--commands=").map(String::from)
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+            path => options.file_path = path.to_string(),
+        }
+    }
+    if options.integer_width != IntegerWidth::I32 {
+        match options.command {
+            Command::EmitWat => return Err("emit-wat only supports --int-width=i32".to_string()),
+            Command::EmitJs => return Err("emit-js only supports --int-width=i32".to_string()),
+            _ => {}
+        }
+    }
+    Ok(options)
+}
+
+/// Parses the value of a `--name=value` option.
+fn value<T: FromStr>(flag: &str) -> Result<T, String> {
+    let (_, text) = flag.split_once('=').unwrap_or((flag, ""));
+    text.parse()
+        .map_err(|_| format!("Invalid value in {}", flag))
+}
+
+/// Parses the lint name or code of `--allow=`, `--warn=` or `--deny=`.
+fn lint(flag: &str) -> Result<Lint, String> {
+    let (_, name) = flag.split_once('=').unwrap_or((flag, ""));
+    Lint::find(name).ok_or_else(|| format!("Unknown lint {}", name))
+}
+
+/// Parses the `name=value` or `name:type=value` of `--set`. Without a type,
+/// the value is an `int` if it is a number, a `bool` if it is `true` or
+/// `false` and a `string` otherwise.
+fn setting(text: &str) -> Result<(String, Type, Value), String> {
+    let invalid = || format!("Invalid --set {}, expected name=value", text);
+    let (name, val) = text.split_once('=').ok_or_else(invalid)?;
+    let (name, type_name) = match name.split_once(':') {
+        Some((name, type_name)) => (name, Some(type_name)),
+        None => (name, None),
+    };
+    // The name must lex as a single identifier, which rules out keywords.
+    let mut lexer = Lexer::new(name.to_string());
+    if lexer.get_next_token() != Token::Identifier(name.to_string())
+        || lexer.get_next_token() != Token::EOF
+    {
+        return Err(invalid());
+    }
+    let type_def = match type_name {
+        Some("int") => Type::Integer,
+        Some("string") => Type::String,
+        Some("bool") => Type::Boolean,
+        Some(_) => return Err(invalid()),
+        None if val.parse::<i64>().is_ok() => Type::Integer,
+        None if val == "true" || val == "false" => Type::Boolean,
+        None => Type::String,
+    };
+    let val = match type_def {
+        Type::Integer => Value::Integer(val.parse().map_err(|_| invalid())?),
+        Type::Boolean => Value::Bool(val.parse().map_err(|_| invalid())?),
+        _ => Value::String(val.to_string()),
+    };
+    Ok((name.to_string(), type_def, val))
+}
@@ -0,0 +1,364 @@
+//! Interactive step debugger built on the evaluator's `Hook`.
+//!
+//! The debugger stops before the first statement and then reads one command
+//! per line:
+//!
+//! ```text
+//! break [LINE]      set a breakpoint, or list them without LINE (b)
+//! delete LINE       remove a breakpoint (d)
+//! step              run the next statement, entering loop bodies (s)
+//! next              run the next statement, stepping over loops and calls (n)
+//! continue          run until the next breakpoint (c)
+//! print NAME        show a variable (p)
+//! set NAME VALUE    change a variable
+//! vars              show all variables
+//! where             show the current statement, loops and calls (w)
+//! quit              stop the program (q)
+//! help              list the commands (h)
+//! ```
+//!
+//! Breakpoints stop before every statement that starts on their line. When
+//! the commands run out the program runs to the end without stopping.
+
+use crate::ast::Statement;
+use crate::evaluator::{EvalResult, Evaluator, Hook};
+use crate::formatter::{format_header, format_variable};
+use crate::lexer::Lexer;
+use crate::token::Token;
+use crate::utils::{EvalError, Type, Value};
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+
+const HELP: &str = "\
+break [LINE]      set a breakpoint, or list them without LINE (b)
+delete LINE       remove a breakpoint (d)
+step              run the next statement, entering loop bodies (s)
+next              run the next statement, stepping over loops and calls (n)
+continue          run until the next breakpoint (c)
+print NAME        show a variable (p)
+set NAME VALUE    change a variable
+vars              show all variables
+where             show the current statement, loops and calls (w)
+quit              stop the program (q)
+help              list the commands (h)
+";
+
+enum Mode {
+    /// Stop before the next statement.
+    Step,
+    /// Stop before the next statement nested in at most this many calls
+    /// and, within the innermost call, loops.
+    Next((usize, usize)),
+    /// Stop at breakpoints only.
+    Continue,
+    /// The commands ran out, never stop again.
+    Detached,
+}
+
+pub struct Debugger {
+    commands: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    breakpoints: BTreeSet<usize>,
+    mode: Mode,
+}
+
+impl Debugger {
+    pub fn new(commands: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        Debugger {
+            commands,
+            output,
+            breakpoints: BTreeSet::new(),
+            mode: Mode::Step,
+        }
+    }
+
+    fn should_stop(&self, evaluator: &Evaluator, statement: &Statement) -> bool {
+        if let Mode::Detached = self.mode {
+            return false;
+        }
+        if self.breakpoints.contains(&statement.span.start.line) {
+            return true;
+        }
+        match self.mode {
+            Mode::Step => true,
+            Mode::Next(depth) => nesting(evaluator) <= depth,
+            Mode::Continue | Mode::Detached => false,
+        }
+    }
+
+    /// Reads and runs commands until one of them resumes the program.
+    fn prompt(&mut self, evaluator: &mut Evaluator, statement: &Statement) -> EvalResult<()> {
+        self.write(&format!(
+            "Stopped at {}: {}\n",
+            statement.span,
+            format_header(statement)
+        ))?;
+        loop {
+            self.write("(minipl) ")?;
+            let mut line = String::new();
+            match self.commands.read_line(&mut line) {
+                Ok(0) => {
+                    self.mode = Mode::Detached;
+                    return self.write("\n");
+                }
+                Ok(_) => {}
+                Err(err) => return Err(EvalError::IOError(err.to_string())),
+            }
+            let mut words = line.trim().splitn(2, char::is_whitespace);
+            let command = words.next().unwrap_or("");
+            let argument = words.next().unwrap_or("").trim();
+            match command {
+                "" => {}
+                "step" | "s" => {
+                    self.mode = Mode::Step;
+                    return Ok(());
+                }
+                "next" | "n" => {
+                    self.mode = Mode::Next(nesting(evaluator));
+                    return Ok(());
+                }
+                "continue" | "c" => {
+                    self.mode = Mode::Continue;
+                    return Ok(());
+                }
+                "quit" | "q" => return Err(EvalError::Interrupted),
+                "break" | "b" => self.set_breakpoint(argument)?,
+                "delete" | "d" => self.delete_breakpoint(argument)?,
+                "print" | "p" => self.print_variable(evaluator, argument)?,
+                "set" => self.set_variable(evaluator, argument)?,
+                "vars" => {
+                    for (id, type_def, val) in evaluator.variables() {
+                        self.write(&describe(id, type_def, val))?;
+                    }
+                }
+                "where" | "w" => self.print_location(evaluator, statement)?,
+                "help" | "h" => self.write(HELP)?,
+                _ => self.write(&format!(
+                    "Unknown command {}, type help for a list of commands\n",
+                    command
+                ))?,
+            }
+        }
+    }
+
+    fn set_breakpoint(&mut self, argument: &str) -> EvalResult<()> {
+        if argument.is_empty() {
+            let lines: Vec<String> = self.breakpoints.iter().map(|l| l.to_string()).collect();
+            return if lines.is_empty() {
+                self.write("No breakpoints\n")
+            } else {
+                self.write(&format!("Breakpoints at lines {}\n", lines.join(", ")))
+            };
+        }
+        match argument.parse::<usize>() {
+            Ok(line) => {
+                self.breakpoints.insert(line);
+                self.write(&format!("Breakpoint at line {}\n", line))
+            }
+            Err(_) => self.write(&format!("Invalid line number {}\n", argument)),
+        }
+    }
+
+    fn delete_breakpoint(&mut self, argument: &str) -> EvalResult<()> {
+        match argument.parse::<usize>() {
+            Ok(line) if self.breakpoints.remove(&line) => {
+                self.write(&format!("Deleted breakpoint at line {}\n", line))
+            }
+            Ok(line) => self.write(&format!("No breakpoint at line {}\n", line)),
+            Err(_) => self.write(&format!("Invalid line number {}\n", argument)),
+        }
+    }
+
+    fn print_variable(&mut self, evaluator: &Evaluator, id: &str) -> EvalResult<()> {
+        let found = evaluator
+            .variables()
+            .into_iter()
+            .find(|(name, _, _)| *name == id)
+            .map(|(id, type_def, val)| describe(id, type_def, val));
+        match found {
+            Some(description) => self.write(&description),
+            None => self.write(&format!("No variable {}\n", id)),
+        }
+    }
+
+    fn set_variable(&mut self, evaluator: &mut Evaluator, argument: &str) -> EvalResult<()> {
+        let mut words = argument.splitn(2, char::is_whitespace);
+        let id = words.next().unwrap_or("");
+        let text = words.next().unwrap_or("").trim();
+        let type_def = evaluator
+            .variables()
+            .into_iter()
+            .find(|(name, _, _)| *name == id)
+            .map(|(_, type_def, _)| type_def.clone());
+        let type_def = match type_def {
+            Some(type_def) => type_def,
+            None => return self.write(&format!("No variable {}\n", id)),
+        };
+        let val = match parse_value(&type_def, text) {
+            Some(val) => val,
+            None => return self.write(&format!("Invalid {} value {}\n", type_def, text)),
+        };
+        match evaluator.set_variable(id, val) {
+            Ok(()) => self.print_variable(evaluator, id),
+            Err(err) => self.write(&format!("{}\n", err.message())),
+        }
+    }
+
+    fn print_location(&mut self, evaluator: &Evaluator, statement: &Statement) -> EvalResult<()> {
+        self.write(&format!(
+            "At {}: {}\n",
+            statement.span,
+            format_header(statement)
+        ))?;
+        for frame in evaluator.loops().iter().rev() {
+            self.write(&format!(
+                "  in for {} at {}, iteration {}, {} = {} of {}\n",
+                frame.variable, frame.span, frame.iteration, frame.variable, frame.value, frame.end
+            ))?;
+        }
+        for call in evaluator.calls().iter().rev() {
+            self.write(&format!("  in {} called at {}\n", call.name, call.span))?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, text: &str) -> EvalResult<()> {
+        let io_error = |err: std::io::Error| EvalError::IOError(err.to_string());
+        self.output.write_all(text.as_bytes()).map_err(io_error)?;
+        self.output.flush().map_err(io_error)
+    }
+}
+
+impl Hook for Debugger {
+    fn before_statement(
+        &mut self,
+        evaluator: &mut Evaluator,
+        statement: &Statement,
+    ) -> EvalResult<()> {
+        if self.should_stop(evaluator, statement) {
+            self.prompt(evaluator, statement)?;
+        }
+        Ok(())
+    }
+}
+
+fn nesting(evaluator: &Evaluator) -> (usize, usize) {
+    (evaluator.calls().len(), evaluator.loops().len())
+}
+
+fn describe(id: &str, type_def: &Type, val: Option<&Value>) -> String {
+    format!("{}\n", format_variable(id, type_def, val))
+}
+
+/// Parses the value of a `set` command. Strings are written as Mini-PL
+/// string literals.
+fn parse_value(type_def: &Type, text: &str) -> Option<Value> {
+    match type_def {
+        Type::Integer => text.parse().ok().map(Value::Integer),
+        Type::Boolean => match text {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Type::String => {
+            let tokens = Lexer::new(text.to_string()).get_all_tokens();
+            match tokens.as_slice() {
+                [(Token::StringValue(string), _), (Token::EOF, _)] => {
+                    Some(Value::String(string.clone()))
+                }
+                _ => None,
+            }
+        }
+        Type::Array(..) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debugger::Debugger;
+    use crate::evaluator::Evaluator;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use std::cell::RefCell;
+    use std::io::{self, Write};
+    use std::rc::Rc;
+
+    /// Output shared between the test and the debugger that owns it.
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn debug(source: &str, commands: &str) -> (String, String) {
+        let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+        let (transcript, output) = (Shared::default(), Shared::default());
+        let debugger = Debugger::new(
+            Box::new(io::Cursor::new(commands.to_string())),
+            Box::new(transcript.clone()),
+        );
+        let mut evaluator = Evaluator::new(program)
+            .with_io(Box::new(io::empty()), Box::new(output.clone()))
+            .with_hook(Box::new(debugger));
+        let _ = evaluator.evaluate_program();
+        let text = |shared: Shared| String::from_utf8(shared.0.borrow().clone()).unwrap();
+        (text(transcript), text(output))
+    }
+
+    #[test]
+    fn stop_at_breakpoints() {
+        let source = "var i : int;\nfor i in 1..3 do\n    print i;\nend for;\nprint 0;";
+        let (transcript, output) = debug(source, "b 3\nc\nc\nd 3\nc\n");
+        assert_eq!(
+            transcript,
+            "Stopped at 1:1: var i : int;\n\
+             (minipl) Breakpoint at line 3\n\
+             (minipl) Stopped at 3:5: print i;\n\
+             (minipl) Stopped at 3:5: print i;\n\
+             (minipl) Deleted breakpoint at line 3\n\
+             (minipl) "
+        );
+        assert_eq!(output, "1230");
+    }
+
+    #[test]
+    fn step_over_loops() {
+        let source = "var i : int;\nfor i in 1..3 do\n    print i;\nend for;\nprint 0;";
+        let (transcript, _) = debug(source, "n\nn\nn\n");
+        assert_eq!(
+            transcript,
+            "Stopped at 1:1: var i : int;\n\
+             (minipl) Stopped at 2:1: for i in 1..3 do\n\
+             (minipl) Stopped at 5:1: print 0;\n\
+             (minipl) "
+        );
+    }
+
+    #[test]
+    fn inspect_and_modify_variables() {
+        let source =
+            "var i : int;\nvar s : string := \"a\";\nfor i in 1..3 do\n    print s;\nend for;";
+        let commands = "b 4\nc\nwhere\nset s \"b\\n\"\nset s 1\nset i 3\nvars\nq\n";
+        let (transcript, output) = debug(source, commands);
+        assert_eq!(
+            transcript,
+            "Stopped at 1:1: var i : int;\n\
+             (minipl) Breakpoint at line 4\n\
+             (minipl) Stopped at 4:5: print s;\n\
+             (minipl) At 4:5: print s;\n  in for i at 3:1, iteration 1, i = 1 of 3\n\
+             (minipl) s : string = \"b\\n\"\n\
+             (minipl) Invalid string value 1\n\
+             (minipl) i : int = 3\n\
+             (minipl) i : int = 3\ns : string = \"b\\n\"\n\
+             (minipl) "
+        );
+        assert_eq!(output, "");
+    }
+}
@@ -0,0 +1,645 @@
+//! Errors and warnings of every stage in one shape, collected into a
+//! `Report` and rendered for the command line.
+//!
+//! The lexer, parser, checker and evaluator produce `Diagnostic`s from their
+//! own errors: a severity, a stable code, a message and the span the
+//! problem is at, with labelled secondary spans for related places, notes
+//! and a help note. The plain rendering is one line per diagnostic:
+//!
+//! ```text
+//! 4:5: Error: Variable i is already declared [E0104]
+//!     1:1: `i` is first declared here
+//! Found 1 error
+//! ```
+//!
+//! The rich rendering quotes the lines of the program the spans are on:
+//!
+//! ```text
+//! error[E0104]: Variable i is already declared
+//!  --> sample.mini:4:5
+//!   |
+//! 1 | var i : int;
+//!   | ------------ `i` is first declared here
+//! ...
+//! 4 |     var i : int;
+//!   |     ^^^^^^^^^^^^
+//!
+//! Found 1 error
+//! ```
+
+use crate::explain::Coded;
+use crate::json::{Json, ToJson};
+use crate::lint::LintWarning;
+use crate::utils::{CheckError, EvalError, LexError, ParseError, Reported, Span, Warning};
+use std::fmt;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A place related to a diagnostic, such as the earlier declaration of a
+/// variable that is declared twice.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    /// Where the problem is.
+    pub span: Span,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    /// A suggestion for fixing the problem.
+    pub help: Option<String>,
+    /// The fields of the error in the schema of `src/json.rs`, in order.
+    /// Those the diagnostic has a field for are `None` and filled in by
+    /// `to_json`, the others, such as the name of the error, are kept.
+    origin: Vec<(String, Option<Json>)>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: String, span: Span) -> Self {
+        Diagnostic {
+            severity,
+            code,
+            message,
+            span,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+            origin: Vec::new(),
+        }
+    }
+
+    pub fn with_label(mut self, span: Span, message: String) -> Self {
+        self.labels.push(Label { span, message });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Self {
+        self.help = Some(help);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// The JSON of the field `name` of the schema, from the diagnostic's
+    /// own fields.
+    fn field(&self, name: &str) -> Json {
+        match name {
+            "kind" => Json::from("Diagnostic"),
+            "severity" => Json::from(self.severity.to_string()),
+            "code" => Json::from(self.code),
+            "message" => Json::from(self.message.as_str()),
+            "span" => self.span.to_json(),
+            _ => Json::Null,
+        }
+    }
+}
+
+/// The JSON object of the error the diagnostic was made from, with
+/// `labels`, `notes` and `help` fields when it has them. Diagnostics made
+/// with `Diagnostic::new` are `{"kind": "Diagnostic", "severity", "code",
+/// "message", "span"}`.
+impl ToJson for Diagnostic {
+    fn to_json(&self) -> Json {
+        let mut fields: Vec<(String, Json)> = if self.origin.is_empty() {
+            let fields = ["kind", "severity", "code", "message", "span"];
+            fields
+                .iter()
+                .map(|name| (name.to_string(), self.field(name)))
+                .collect()
+        } else {
+            self.origin
+                .iter()
+                .map(|(name, value)| {
+                    let value = value.clone().unwrap_or_else(|| self.field(name));
+                    (name.clone(), value)
+                })
+                .collect()
+        };
+        if !self.labels.is_empty() {
+            let labels = self.labels.iter().map(|label| {
+                Json::object(vec![
+                    ("span", label.span.to_json()),
+                    ("message", Json::from(label.message.as_str())),
+                ])
+            });
+            fields.push(("labels".to_string(), Json::Array(labels.collect())));
+        }
+        if !self.notes.is_empty() {
+            let notes = self.notes.iter().map(|note| Json::from(note.as_str()));
+            fields.push(("notes".to_string(), Json::Array(notes.collect())));
+        }
+        if let Some(help) = &self.help {
+            fields.push(("help".to_string(), Json::from(help.as_str())));
+        }
+        Json::Object(fields)
+    }
+}
+
+/// Errors and warnings that become diagnostics.
+pub trait ToDiagnostic {
+    fn to_diagnostic(&self) -> Diagnostic;
+}
+
+/// The diagnostic of `error`, an error or warning with its span.
+fn from_error<E: Coded>(error: &(E, Span), severity: Severity, message: String) -> Diagnostic
+where
+    (E, Span): ToJson,
+{
+    let (err, span) = error;
+    let origin = match error.to_json() {
+        Json::Object(fields) => fields
+            .into_iter()
+            .map(|(name, value)| match name.as_str() {
+                "severity" | "code" | "message" | "span" => (name, None),
+                _ => (name, Some(value)),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Diagnostic {
+        origin,
+        ..Diagnostic::new(severity, err.code(), message, *span)
+    }
+}
+
+impl ToDiagnostic for (LexError, Span) {
+    fn to_diagnostic(&self) -> Diagnostic {
+        from_error(self, Severity::Error, self.0.to_string())
+    }
+}
+
+impl ToDiagnostic for (ParseError, Span) {
+    fn to_diagnostic(&self) -> Diagnostic {
+        from_error(self, Severity::Error, self.0.to_string())
+    }
+}
+
+impl ToDiagnostic for (CheckError, Span) {
+    fn to_diagnostic(&self) -> Diagnostic {
+        from_error(self, Severity::Error, self.0.to_string())
+    }
+}
+
+impl ToDiagnostic for (EvalError, Span) {
+    fn to_diagnostic(&self) -> Diagnostic {
+        from_error(self, Severity::Error, self.0.message())
+    }
+}
+
+impl ToDiagnostic for (Warning, Span) {
+    fn to_diagnostic(&self) -> Diagnostic {
+        from_error(self, Severity::Warning, self.0.message())
+    }
+}
+
+/// An error of the parser or checker, with its help note and labels.
+impl<E> ToDiagnostic for Reported<E>
+where
+    (E, Span): ToDiagnostic,
+{
+    fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            labels: self.labels.clone(),
+            help: self.help.clone(),
+            ..self.error.to_diagnostic()
+        }
+    }
+}
+
+/// Lints raised to `Deny` are errors. The message names the lint.
+impl ToDiagnostic for (LintWarning, Span) {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let (warning, _) = self;
+        let severity = if warning.is_error() {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        let message = format!("{} ({})", warning.message, warning.lint.name());
+        from_error(self, severity, message)
+    }
+}
+
+/// The diagnostics of one run, in the order they were found.
+#[derive(Debug, Default)]
+pub struct Report {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Report::default()
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.is_error()).count()
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.diagnostics.len() - self.error_count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    /// The number of errors and warnings, as in `Found 2 errors and 1
+    /// warning`, or `None` for an empty report.
+    pub fn summary(&self) -> Option<String> {
+        let count = |n: usize, noun: &str| match n {
+            1 => format!("1 {}", noun),
+            n => format!("{} {}s", n, noun),
+        };
+        let counts = match (self.error_count(), self.warning_count()) {
+            (0, 0) => return None,
+            (errors, 0) => count(errors, "error"),
+            (0, warnings) => count(warnings, "warning"),
+            (errors, warnings) => {
+                format!(
+                    "{} and {}",
+                    count(errors, "error"),
+                    count(warnings, "warning")
+                )
+            }
+        };
+        Some(format!("Found {}", counts))
+    }
+}
+
+impl Extend<Diagnostic> for Report {
+    fn extend<I: IntoIterator<Item = Diagnostic>>(&mut self, diagnostics: I) {
+        self.diagnostics.extend(diagnostics);
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Style {
+    /// One line per diagnostic, followed by its labels and notes.
+    Plain,
+    /// The lines of the program the diagnostic is about, with the spans
+    /// underlined.
+    Rich,
+}
+
+/// Renders diagnostics about one program.
+pub struct Renderer<'a> {
+    lines: Vec<&'a str>,
+    style: Style,
+    path: Option<&'a str>,
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(source: &'a str, style: Style) -> Self {
+        Renderer {
+            lines: source.lines().collect(),
+            style,
+            path: None,
+            color: false,
+        }
+    }
+
+    /// Names the file of the program in rich diagnostics.
+    pub fn with_path(mut self, path: &'a str) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// Highlights rich diagnostics with ANSI colors.
+    pub fn with_color(mut self) -> Self {
+        self.color = true;
+        self
+    }
+
+    /// The diagnostics of the report followed by its summary.
+    pub fn render_report(&self, report: &Report) -> String {
+        let mut output: String = report
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| self.render(diagnostic))
+            .collect();
+        output.push_str(&self.render_summary(report));
+        output
+    }
+
+    /// The line of `Report::summary`, empty for an empty report.
+    pub fn render_summary(&self, report: &Report) -> String {
+        match report.summary() {
+            Some(summary) => format!("{}\n", self.paint(BOLD, &summary)),
+            None => String::new(),
+        }
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        match self.style {
+            Style::Plain => render_plain(diagnostic),
+            Style::Rich => self.render_rich(diagnostic),
+        }
+    }
+
+    fn render_rich(&self, diagnostic: &Diagnostic) -> String {
+        let color = match diagnostic.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+        };
+        let header = format!("{}[{}]", diagnostic.severity, diagnostic.code);
+        let mut output = format!(
+            "{}{}\n",
+            self.paint(color, &header),
+            self.paint(BOLD, &format!(": {}", diagnostic.message))
+        );
+
+        let mut marks: Vec<(Span, char, &str, &str)> = vec![(diagnostic.span, '^', "", color)];
+        for label in &diagnostic.labels {
+            marks.push((label.span, '-', &label.message, BLUE));
+        }
+        marks.retain(|(span, ..)| self.line(span.start.line).is_some());
+        marks.sort_by_key(|(span, ..)| (span.start.line, span.start.column));
+        let last_line = marks.iter().map(|(span, ..)| span.start.line).max();
+        let width = last_line.unwrap_or_default().to_string().len();
+        let gutter = |text: &str| {
+            let bar = self.paint(BLUE, &format!("{:width$} |", "", width = width));
+            format!("{}{}\n", bar, text).trim_end().to_string() + "\n"
+        };
+
+        if self.line(diagnostic.span.start.line).is_some() {
+            let location = match self.path {
+                Some(path) => format!("{}:{}", path, diagnostic.span.start),
+                None => diagnostic.span.start.to_string(),
+            };
+            let arrow = format!("{:width$}--> ", "", width = width);
+            output.push_str(&format!("{}{}\n", self.paint(BLUE, &arrow), location));
+        }
+        let mut previous_line = None;
+        for (span, mark, message, mark_color) in &marks {
+            let number = span.start.line;
+            let text = self.line(number).unwrap_or_default();
+            if previous_line.is_none() {
+                output.push_str(&gutter(""));
+            }
+            if previous_line.is_some_and(|previous| number > previous + 1) {
+                output.push_str(&self.paint(BLUE, "...\n"));
+            }
+            if previous_line != Some(number) {
+                let number = self.paint(BLUE, &format!("{:>width$} |", number, width = width));
+                output.push_str(format!("{} {}", number, text).trim_end());
+                output.push('\n');
+            }
+            previous_line = Some(number);
+
+            let column = span.start.column.max(1);
+            let indent: String = text
+                .chars()
+                .take(column - 1)
+                .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+                .collect();
+            let end = if span.end.line == number {
+                span.end.column
+            } else {
+                text.chars().count() + 1
+            };
+            let underline = mark.to_string().repeat(end.saturating_sub(column).max(1));
+            let underline = match message.is_empty() {
+                true => underline,
+                false => format!("{} {}", underline, message),
+            };
+            output.push_str(&gutter(&format!(
+                " {}{}",
+                indent,
+                self.paint(mark_color, &underline)
+            )));
+        }
+
+        let notes = diagnostic.notes.iter().map(|note| ("note", note));
+        let notes: Vec<_> = notes
+            .chain(diagnostic.help.iter().map(|help| ("help", help)))
+            .collect();
+        if !notes.is_empty() && !marks.is_empty() {
+            output.push_str(&gutter(""));
+        }
+        for (kind, note) in notes {
+            let equals = self.paint(BLUE, &format!("{:width$} =", "", width = width));
+            output.push_str(&format!(
+                "{} {}: {}\n",
+                equals,
+                self.paint(BOLD, kind),
+                note
+            ));
+        }
+        output.push('\n');
+        output
+    }
+
+    /// The text of the 1-based line `number`, if the program has it.
+    fn line(&self, number: usize) -> Option<&'a str> {
+        number
+            .checked_sub(1)
+            .and_then(|index| self.lines.get(index))
+            .copied()
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn render_plain(diagnostic: &Diagnostic) -> String {
+    let severity = match diagnostic.severity {
+        Severity::Error => "Error",
+        Severity::Warning => "Warning",
+    };
+    // Problems that are not in the program, such as with a `--set`
+    // option, have no location.
+    let location = if diagnostic.span.start.line == 0 {
+        String::new()
+    } else {
+        format!("{}: ", diagnostic.span)
+    };
+    let mut output = format!(
+        "{}{}: {} [{}]\n",
+        location, severity, diagnostic.message, diagnostic.code
+    );
+    for label in &diagnostic.labels {
+        output.push_str(&format!("    {}: {}\n", label.span, label.message));
+    }
+    for note in &diagnostic.notes {
+        output.push_str(&format!("    note: {}\n", note));
+    }
+    if let Some(help) = &diagnostic.help {
+        output.push_str(&format!("    help: {}\n", help));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{Diagnostic, Renderer, Report, Severity, Style, ToDiagnostic};
+    use crate::json::ToJson;
+    use crate::utils::{CheckError, EvalError, Position, Span, Warning};
+
+    fn span(line: usize, start: usize, end: usize) -> Span {
+        Span::new(Position::new(line, start), Position::new(line, end))
+    }
+
+    fn redeclared() -> Diagnostic {
+        let err = (
+            CheckError::VariableAlreadyDeclared("i".to_string()),
+            span(3, 5, 17),
+        );
+        err.to_diagnostic()
+            .with_label(span(1, 1, 13), "`i` is first declared here".to_string())
+            .with_help("rename it".to_string())
+    }
+
+    const SOURCE: &str = "var i : int;\nfor i in 1..2 do\n    var i : int;\nend for;";
+
+    #[test]
+    fn count_errors_and_warnings() {
+        let mut report = Report::new();
+        assert_eq!(report.summary(), None);
+        report.push(redeclared());
+        assert_eq!(report.summary(), Some("Found 1 error".to_string()));
+        let warning = (Warning::Failure(EvalError::DivisionByZero), span(2, 1, 2));
+        report.extend(vec![warning.to_diagnostic(), warning.to_diagnostic()]);
+        assert_eq!(report.warning_count(), 2);
+        assert!(report.has_errors());
+        assert_eq!(
+            report.summary(),
+            Some("Found 1 error and 2 warnings".to_string())
+        );
+    }
+
+    #[test]
+    fn render_plain() {
+        let mut report = Report::new();
+        report.push(redeclared());
+        let runtime = (EvalError::DivisionByZero, span(4, 1, 9)).to_diagnostic();
+        report.push(runtime.with_note("1 more call is in progress".to_string()));
+        assert_eq!(
+            Renderer::new(SOURCE, Style::Plain).render_report(&report),
+            "3:5: Error: Variable i is already declared [E0104]\n    \
+             1:1: `i` is first declared here\n    help: rename it\n\
+             4:1: Error: Division by zero [E0207]\n    note: 1 more call is in progress\n\
+             Found 2 errors\n"
+        );
+    }
+
+    #[test]
+    fn render_rich() {
+        let renderer = Renderer::new(SOURCE, Style::Rich).with_path("loop.mini");
+        assert_eq!(
+            renderer.render(&redeclared()),
+            "error[E0104]: Variable i is already declared\n \
+             --> loop.mini:3:5\n  \
+             |\n\
+             1 | var i : int;\n  \
+             | ------------ `i` is first declared here\n\
+             ...\n\
+             3 |     var i : int;\n  \
+             |     ^^^^^^^^^^^^\n  \
+             |\n  \
+             = help: rename it\n\n"
+        );
+
+        // Tabs stay tabs so that the underline lines up, and spans past the
+        // end of the program are left out.
+        let source = "for i in 1..2 do\n\tprint i / 0;\nend for;";
+        let warning = Diagnostic::new(
+            Severity::Warning,
+            "E0207",
+            "Division by zero".to_string(),
+            span(2, 8, 13),
+        )
+        .with_label(span(9, 1, 2), "nowhere".to_string());
+        assert_eq!(
+            Renderer::new(source, Style::Rich).render(&warning),
+            "warning[E0207]: Division by zero\n --> 2:8\n  |\n2 | \tprint i / 0;\n  | \t      ^^^^^\n\n"
+        );
+
+        let colored = Renderer::new(SOURCE, Style::Rich).with_color();
+        assert!(colored
+            .render(&redeclared())
+            .starts_with("\x1b[1;31merror[E0104]\x1b[0m"));
+    }
+
+    #[test]
+    fn serialize_diagnostics() {
+        let json = redeclared().to_json();
+        assert_eq!(
+            json.get("kind").and_then(|k| k.as_str()),
+            Some("CheckError")
+        );
+        assert_eq!(json.get("help").and_then(|h| h.as_str()), Some("rename it"));
+        let labels = json.get("labels").and_then(|l| l.as_array()).unwrap();
+        assert_eq!(
+            labels[0].get("message").and_then(|m| m.as_str()),
+            Some("`i` is first declared here")
+        );
+
+        // Fields changed after the diagnostic is made are serialized too.
+        let mut edited = redeclared();
+        edited.message = "Variable i is declared twice".to_string();
+        edited.help = None;
+        let json = edited.to_json();
+        assert_eq!(
+            json.get("message").and_then(|m| m.as_str()),
+            Some("Variable i is declared twice")
+        );
+        assert_eq!(
+            json.get("error").and_then(|e| e.as_str()),
+            Some("VariableAlreadyDeclared")
+        );
+        assert_eq!(json.get("help"), None);
+
+        let diagnostic =
+            Diagnostic::new(Severity::Warning, "W0001", "x".to_string(), span(1, 1, 2));
+        assert_eq!(
+            diagnostic.to_json().to_string(),
+            r#"{"kind":"Diagnostic","severity":"warning","code":"W0001","message":"x","span":{"start":{"line":1,"column":1},"end":{"line":1,"column":2}}}"#
+        );
+    }
+}
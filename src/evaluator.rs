@@ -1,42 +1,461 @@
-use crate::ast::{BinaryOperator, Expression, Program, Statement, UnaryOperator};
-use crate::utils::{EvalError, Type, Value};
+use crate::ast::{
+    BinaryOperator, Expression, ExpressionKind, Program, Routine, Statement, StatementKind,
+    UnaryOperator,
+};
+use crate::builtins::{self, SIZE};
+use crate::diagnostic::{Diagnostic, ToDiagnostic};
+use crate::utils::{EvalError, IntegerWidth, Span, Type, Value};
 use std::collections::HashMap;
-use std::io::{stdout, stdin, Write};
+use std::convert::TryFrom;
+use std::io::{stdin, stdout, BufRead, BufReader, Write};
+use std::time::Instant;
 
-type EvalResult<T> = Result<T, EvalError>;
-type GlobalVar = (Type, Option<Value>);
+pub type EvalResult<T> = Result<T, EvalError>;
+type Variable = (Type, Option<Value>);
+
+/// Variables declared in one block, the outermost being the whole program.
+type Scope = HashMap<String, Variable>;
+
+/// Deepest nesting of procedure and function calls, so that runaway
+/// recursion fails cleanly instead of overflowing the stack.
+pub(crate) const MAX_CALL_DEPTH: usize = 200;
+
+/// Calls in progress labelled on the diagnostic of a runtime error.
+const LABELLED_CALLS: usize = 3;
+
+/// Callbacks invoked while a program runs, used by the debugger and the
+/// tracer. An error returned from a callback stops evaluation with that
+/// error.
+pub trait Hook {
+    /// Called before `statement` is executed. For `for` statements this is
+    /// once before the loop starts, not per iteration.
+    fn before_statement(
+        &mut self,
+        _evaluator: &mut Evaluator,
+        _statement: &Statement,
+    ) -> EvalResult<()> {
+        Ok(())
+    }
+
+    /// Called after a declaration, assignment or `read` has stored `new` in
+    /// `id`. `old` is `None` if the variable had no value.
+    fn after_assignment(
+        &mut self,
+        _evaluator: &mut Evaluator,
+        _id: &str,
+        _old: Option<&Value>,
+        _new: &Value,
+    ) -> EvalResult<()> {
+        Ok(())
+    }
+
+    /// Called at the start of every iteration of a `for` loop, after the
+    /// loop variable has been set to `frame.value`.
+    fn before_iteration(
+        &mut self,
+        _evaluator: &mut Evaluator,
+        _frame: &LoopFrame,
+    ) -> EvalResult<()> {
+        Ok(())
+    }
+}
+
+/// State of a `for` loop that is currently executing.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LoopFrame {
+    pub variable: String,
+    pub value: i64,
+    pub end: i64,
+    /// Number of the current iteration, starting from 1.
+    pub iteration: u64,
+    pub span: Span,
+}
+
+type NativeFunction = Box<dyn FnMut(&[Value]) -> EvalResult<Value>>;
+
+/// A Rust function registered with `Evaluator::with_function`.
+struct HostFunction {
+    parameters: Vec<Type>,
+    return_type: Type,
+    function: NativeFunction,
+}
+
+/// A procedure or function call that is currently executing.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CallFrame {
+    pub name: String,
+    /// Span of the statement making the call.
+    pub span: Span,
+}
 
 pub struct Evaluator {
-    global_scope: HashMap<String, GlobalVar>,
+    scopes: Vec<Scope>,
+    routines: HashMap<String, Routine>,
+    functions: HashMap<String, HostFunction>,
+    calls: Vec<CallFrame>,
+    /// Set by `return` until the enclosing call has unwound.
+    returning: bool,
+    return_value: Option<Value>,
     program: Program,
+    current_span: Span,
+    /// Span of the statement that failed and the calls in progress there,
+    /// kept once the calls have unwound.
+    failure: Option<(Span, Vec<CallFrame>)>,
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    steps: u64,
+    step_limit: Option<u64>,
+    output_bytes: u64,
+    output_limit: Option<u64>,
+    string_limit: Option<usize>,
+    deadline: Option<Instant>,
+    integer_width: IntegerWidth,
+    hooks: Vec<Box<dyn Hook>>,
+    loops: Vec<LoopFrame>,
 }
 
 impl Evaluator {
     pub fn new(program: Program) -> Self {
         Evaluator {
-            global_scope: HashMap::new(),
+            scopes: vec![Scope::new()],
+            routines: HashMap::new(),
+            functions: HashMap::new(),
+            calls: Vec::new(),
+            returning: false,
+            return_value: None,
             program,
+            current_span: Span::default(),
+            failure: None,
+            input: Box::new(BufReader::new(stdin())),
+            output: Box::new(stdout()),
+            steps: 0,
+            step_limit: None,
+            output_bytes: 0,
+            output_limit: None,
+            string_limit: None,
+            deadline: None,
+            integer_width: IntegerWidth::default(),
+            hooks: Vec::new(),
+            loops: Vec::new(),
+        }
+    }
+
+    /// Replaces stdin and stdout as the source for `read` and the sink for
+    /// `print` and assertion messages.
+    pub fn with_io(mut self, input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        self.input = input;
+        self.output = output;
+        self
+    }
+
+    /// Fails with `EvalError::StepLimitExceeded` once more than `limit`
+    /// statements and loop iterations have been executed.
+    pub fn with_step_limit(mut self, limit: u64) -> Self {
+        self.step_limit = Some(limit);
+        self
+    }
+
+    /// Fails with `EvalError::OutputLimitExceeded` instead of writing output
+    /// that would take the total past `limit` bytes.
+    pub fn with_output_limit(mut self, limit: u64) -> Self {
+        self.output_limit = Some(limit);
+        self
+    }
+
+    /// Fails with `EvalError::StringLimitExceeded` when concatenation or
+    /// `read` produces a string longer than `limit` bytes.
+    pub fn with_string_limit(mut self, limit: usize) -> Self {
+        self.string_limit = Some(limit);
+        self
+    }
+
+    /// Fails with `EvalError::DeadlineExceeded` at the first statement or
+    /// loop iteration started after `deadline`. A blocking `read` is not
+    /// interrupted.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Fails with `EvalError::IntegerOverflow` when arithmetic or `read`
+    /// produces an integer that does not fit in `width`.
+    pub fn with_integer_width(mut self, width: IntegerWidth) -> Self {
+        self.integer_width = width;
+        self
+    }
+
+    /// Makes `function` callable from the program as `name`. Arguments are
+    /// checked against `parameters` before the call and the result against
+    /// `return_type` after it; an error returned by `function` stops the
+    /// program. Declare the same signature with `Checker::with_function` so
+    /// that calls are type checked before the program runs.
+    pub fn with_function<F>(
+        mut self,
+        name: &str,
+        parameters: Vec<Type>,
+        return_type: Type,
+        function: F,
+    ) -> Self
+    where
+        F: FnMut(&[Value]) -> EvalResult<Value> + 'static,
+    {
+        let function = HostFunction {
+            parameters,
+            return_type,
+            function: Box::new(function),
+        };
+        self.functions.insert(name.to_string(), function);
+        self
+    }
+
+    /// Adds a hook. Hooks are called in the order they were added.
+    pub fn with_hook(mut self, hook: Box<dyn Hook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Span of the statement being executed, or of the one that failed once
+    /// `evaluate_program` has returned an error.
+    pub fn current_span(&self) -> Span {
+        match &self.failure {
+            Some((span, _)) => *span,
+            None => self.current_span,
+        }
+    }
+
+    /// The diagnostic of an error returned by `evaluate_program`, at the
+    /// statement that failed and labelled with the calls it failed in,
+    /// innermost first.
+    pub fn diagnostic(&self, err: EvalError) -> Diagnostic {
+        let calls = match &self.failure {
+            Some((_, calls)) => calls,
+            None => &self.calls,
+        };
+        let mut diagnostic = (err, self.current_span()).to_diagnostic();
+        for call in calls.iter().rev().take(LABELLED_CALLS) {
+            diagnostic =
+                diagnostic.with_label(call.span, format!("`{}` is called here", call.name));
+        }
+        if calls.len() > LABELLED_CALLS {
+            let more = calls.len() - LABELLED_CALLS;
+            diagnostic = diagnostic.with_note(format!("{} more calls are in progress", more));
         }
+        diagnostic
+    }
+
+    /// Variables in scope sorted by name, with `None` for variables that
+    /// have not been assigned yet.
+    pub fn variables(&self) -> Vec<(&str, &Type, Option<&Value>)> {
+        let mut variables: Vec<_> = self
+            .scopes
+            .iter()
+            .flatten()
+            .map(|(id, (type_def, val))| (id.as_str(), type_def, val.as_ref()))
+            .collect();
+        variables.sort_by_key(|(id, _, _)| *id);
+        variables
+    }
+
+    /// Value of the variable `id` in scope, which after `evaluate_program`
+    /// are the global variables. `None` if it is not declared or has not
+    /// been assigned.
+    pub fn variable(&self, id: &str) -> Option<&Value> {
+        self.lookup(id).and_then(|(_, val)| val.as_ref())
+    }
+
+    /// Declares a global variable before the program runs, as if by
+    /// `var id : type_def := val;`. Declare it to the checker as well with
+    /// `Checker::with_variable`.
+    pub fn define_variable(&mut self, id: &str, type_def: Type, val: Value) -> EvalResult<()> {
+        self.check_type_conformance(&type_def, &val)?;
+        let val = match val {
+            Value::Integer(int) => self.checked(Some(int))?,
+            Value::String(string) => self.checked_string(string)?,
+            val => val,
+        };
+        if self.scope_of(id).is_some() {
+            return Err(EvalError::VariableAlreadyInitialized(id.to_string()));
+        }
+        self.scopes[0].insert(id.to_string(), (type_def, Some(val)));
+        Ok(())
+    }
+
+    /// Overwrites a declared variable, checking the value against its type.
+    pub fn set_variable(&mut self, id: &str, val: Value) -> EvalResult<()> {
+        let (type_def, _) = self.find_assigned_variable(&id.to_string())?;
+        self.check_type_conformance(&type_def, &val)?;
+        let val = match val {
+            Value::Integer(int) => self.checked(Some(int))?,
+            Value::String(string) => self.checked_string(string)?,
+            val => val,
+        };
+        let scope = self.scope_of(id).unwrap_or(0);
+        self.scopes[scope].insert(id.to_string(), (type_def, Some(val)));
+        Ok(())
+    }
+
+    /// Enclosing `for` loops of the current statement within the innermost
+    /// call, outermost first.
+    pub fn loops(&self) -> &[LoopFrame] {
+        &self.loops
+    }
+
+    /// Procedure and function calls in progress, outermost first.
+    pub fn calls(&self) -> &[CallFrame] {
+        &self.calls
     }
 
     pub fn evaluate_program(&mut self) -> EvalResult<()> {
-        for statement in self.program.statements.clone() {
-            self.evaluate_statement(statement)?;
+        self.failure = None;
+        let statements = self.program.statements.clone();
+        self.evaluate_block(&statements)
+    }
+
+    /// Runs `statements` in order, stopping early at a `return`.
+    fn evaluate_block(&mut self, statements: &[Statement]) -> EvalResult<()> {
+        for statement in statements {
+            self.evaluate_statement(statement.clone())?;
+            if self.returning {
+                break;
+            }
         }
         Ok(())
     }
 
     fn evaluate_statement(&mut self, statement: Statement) -> EvalResult<()> {
-        match statement {
-            Statement::NewAssignment(id, type_def, exp) => {
+        self.current_span = statement.span;
+        self.step()?;
+        self.run_hooks(|hook, evaluator| hook.before_statement(evaluator, &statement))?;
+        match statement.kind {
+            StatementKind::NewAssignment(id, type_def, exp) => {
                 self.evaluate_new_assignment(id, type_def, exp)
             }
-            Statement::VarInitialization(id, type_def) => self.evaluate_var_init(id, type_def),
-            Statement::Assignment(id, exp) => self.evaluate_assignment(id, exp),
-            Statement::Print(exp) => self.evaluate_print(exp),
-            Statement::Assert(exp) => self.evaluate_assert(exp),
-            Statement::Read(id) => self.evaluate_read(id),
-            Statement::For(id, start, end, stmts) => self.evaluate_for(id, start, end, stmts),
+            StatementKind::VarInitialization(id, type_def) => self.evaluate_var_init(id, type_def),
+            StatementKind::Assignment(id, exp) => self.evaluate_assignment(id, exp),
+            StatementKind::IndexAssignment(id, index, exp) => {
+                self.evaluate_index_assignment(id, index, exp)
+            }
+            StatementKind::Print(exp) => self.evaluate_print(exp),
+            StatementKind::Assert(exp) => self.evaluate_assert(exp),
+            StatementKind::Read(id) => self.evaluate_read(id),
+            StatementKind::For(id, start, end, stmts) => self.evaluate_for(id, start, end, stmts),
+            StatementKind::Routine(routine) => {
+                self.routines.insert(routine.name.clone(), routine);
+                Ok(())
+            }
+            StatementKind::Call(name, args) => self.call(name, args).map(|_| ()),
+            StatementKind::Return(exp) => {
+                self.return_value = match exp {
+                    Some(exp) => Some(self.evaluate_expression(exp)?),
+                    None => None,
+                };
+                self.returning = true;
+                Ok(())
+            }
+        }
+    }
+
+    /// Calls a procedure or function, returning the function's value.
+    fn call(&mut self, name: String, args: Vec<Expression>) -> EvalResult<Option<Value>> {
+        if builtins::find(&name).is_some() {
+            return self.call_builtin(&name, args).map(Some);
+        }
+        if self.functions.contains_key(&name) {
+            return self.call_host_function(&name, args).map(Some);
+        }
+        let routine = match self.routines.get(&name) {
+            Some(routine) => routine.clone(),
+            None => return Err(EvalError::UndeclaredRoutine(name)),
+        };
+        if routine.parameters.len() != args.len() {
+            return Err(EvalError::MismatchedTypes);
+        }
+        let mut values = Vec::new();
+        for (arg, param) in args.into_iter().zip(&routine.parameters) {
+            let val = self.evaluate_expression(arg)?;
+            self.check_type_conformance(&param.type_def, &val)?;
+            values.push(val);
+        }
+        if self.calls.len() >= MAX_CALL_DEPTH {
+            return Err(EvalError::CallDepthExceeded(MAX_CALL_DEPTH));
+        }
+
+        let span = self.current_span;
+        self.calls.push(CallFrame { name, span });
+        // The body sees its parameters and locals only, not the caller's
+        // variables.
+        let caller_scopes = std::mem::replace(&mut self.scopes, vec![Scope::new()]);
+        let caller_loops = std::mem::take(&mut self.loops);
+        let result = self.run_routine(&routine, values);
+        if result.is_err() && self.failure.is_none() {
+            self.failure = Some((self.current_span, self.calls.clone()));
+        }
+        // The caller's state comes back even if the body failed.
+        self.returning = false;
+        let value = self.return_value.take();
+        self.scopes = caller_scopes;
+        self.loops = caller_loops;
+        self.calls.pop();
+        self.current_span = span;
+        result?;
+        match (&routine.return_type, value) {
+            (Some(_), None) => Err(EvalError::MissingReturn(routine.name)),
+            (_, value) => Ok(value),
+        }
+    }
+
+    fn run_routine(&mut self, routine: &Routine, values: Vec<Value>) -> EvalResult<()> {
+        for (param, val) in routine.parameters.iter().zip(values) {
+            self.add_new_variable(param.name.clone(), param.type_def.clone(), val)?;
+        }
+        self.evaluate_block(&routine.body)
+    }
+
+    fn call_builtin(&mut self, name: &str, args: Vec<Expression>) -> EvalResult<Value> {
+        // The size of a variable is known from its type, without copying it.
+        if let (SIZE, [array]) = (name, args.as_slice()) {
+            if let ExpressionKind::Identifier(id) = &array.kind {
+                if let Some((Type::Array(size, _), _)) = self.lookup(id) {
+                    return Ok(Value::Integer(*size as i64));
+                }
+            }
+        }
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.evaluate_expression(arg)?);
+        }
+        match builtins::call(name, values)? {
+            Value::Integer(int) => self.checked(Some(int)),
+            Value::String(string) => self.checked_string(string),
+            val => Ok(val),
+        }
+    }
+
+    fn call_host_function(&mut self, name: &str, args: Vec<Expression>) -> EvalResult<Value> {
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.evaluate_expression(arg)?);
+        }
+        let function = match self.functions.get(name) {
+            Some(function) => function,
+            None => return Err(EvalError::UndeclaredRoutine(name.to_string())),
+        };
+        if values.len() != function.parameters.len() {
+            return Err(EvalError::MismatchedTypes);
+        }
+        for (param, val) in function.parameters.iter().zip(&values) {
+            self.check_type_conformance(param, val)?;
+        }
+        let return_type = function.return_type.clone();
+        let val = match self.functions.get_mut(name) {
+            Some(function) => (function.function)(&values)?,
+            None => return Err(EvalError::UndeclaredRoutine(name.to_string())),
+        };
+        self.check_type_conformance(&return_type, &val)?;
+        match val {
+            Value::Integer(int) => self.checked(Some(int)),
+            Value::String(string) => self.checked_string(string),
+            val => Ok(val),
         }
     }
 
@@ -48,12 +467,12 @@ impl Evaluator {
     ) -> EvalResult<()> {
         let val = self.evaluate_expression(exp)?;
         self.check_type_conformance(&type_def, &val)?;
-        self.add_new_variable(identifier, (type_def, Some(val)))?;
-        Ok(())
+        self.add_new_variable(identifier, type_def, val)
     }
 
     fn evaluate_var_init(&mut self, id: String, type_def: Type) -> EvalResult<()> {
-        self.global_scope.insert(id, (type_def, None));
+        let val = type_def.default_value();
+        self.innermost_scope().insert(id, (type_def, val));
         Ok(())
     }
 
@@ -61,15 +480,40 @@ impl Evaluator {
         let (type_def, _) = self.find_assigned_variable(&identifier)?;
         let val = self.evaluate_expression(exp)?;
         self.check_type_conformance(&type_def, &val)?;
-        self.global_scope.insert(identifier, (type_def, Some(val)));
-        Ok(())
+        self.assign(identifier, type_def, val)
+    }
+
+    fn evaluate_index_assignment(
+        &mut self,
+        id: String,
+        index: Expression,
+        exp: Expression,
+    ) -> EvalResult<()> {
+        let index = self.evaluate_integer(index)?;
+        let val = self.evaluate_expression(exp)?;
+        let element = match self.lookup(&id) {
+            Some((Type::Array(_, element), _)) => element.as_ref().clone(),
+            Some(_) => return Err(EvalError::MismatchedTypes),
+            None => return Err(EvalError::VariableNotInitialized(id)),
+        };
+        self.check_type_conformance(&element, &val)?;
+        let scope = self.scope_of(&id).unwrap_or(0);
+        let old = match self.scopes[scope].get_mut(&id) {
+            Some((_, Some(Value::Array(values)))) => {
+                let position = element_position(index, values.len())?;
+                std::mem::replace(&mut values[position], val.clone())
+            }
+            _ => return Err(EvalError::VariableNotInitialized(id)),
+        };
+        let element = format!("{}[{}]", id, index);
+        self.run_hooks(|hook, evaluator| {
+            hook.after_assignment(evaluator, &element, Some(&old), &val)
+        })
     }
 
     fn evaluate_print(&mut self, exp: Expression) -> EvalResult<()> {
         let val = self.evaluate_expression(exp)?;
-        print!("{}", val);
-        stdout().flush().unwrap();
-        Ok(())
+        self.write_output(&val.to_string())
     }
 
     fn evaluate_read(&mut self, id: String) -> EvalResult<()> {
@@ -77,15 +521,15 @@ impl Evaluator {
         let input = self.get_input()?;
         match type_def {
             Type::String => {
-                let val = Value::String(input);
-                self.global_scope.insert(id, (type_def, Some(val)));
+                let val = self.checked_string(input)?;
+                self.assign(id, type_def, val)?;
             }
             Type::Integer => {
-                let parsed = input.trim().parse::<i32>();
+                let parsed = input.trim().parse::<i64>();
                 match parsed {
                     Ok(int) => {
-                        let val = Value::Integer(int);
-                        self.global_scope.insert(id, (type_def, Some(val)));
+                        let val = self.checked(Some(int))?;
+                        self.assign(id, type_def, val)?;
                     }
                     Err(_) => return Err(EvalError::MismatchedTypes),
                 }
@@ -99,11 +543,10 @@ impl Evaluator {
         let val = self.evaluate_expression(exp.clone())?;
         match val {
             Value::Bool(boolean) if !boolean => {
-                println!("Assertion failed: {}", &exp);
-                Ok(())
+                self.write_output(&format!("Assertion failed: {}\n", &exp))
             }
             Value::Bool(_) => Ok(()),
-            _ => return Err(EvalError::MismatchedTypes),
+            _ => Err(EvalError::MismatchedTypes),
         }
     }
 
@@ -112,7 +555,7 @@ impl Evaluator {
         id: String,
         exp1: Expression,
         exp2: Expression,
-        stmts: Vec<Box<Statement>>,
+        stmts: Vec<Statement>,
     ) -> EvalResult<()> {
         let start = match self.evaluate_expression(exp1) {
             Ok(Value::Integer(int)) => int,
@@ -126,25 +569,78 @@ impl Evaluator {
         if type_def != Type::Integer {
             return Err(EvalError::MismatchedTypes);
         }
+        self.loops.push(LoopFrame {
+            variable: id.clone(),
+            value: start,
+            end,
+            iteration: 0,
+            span: self.current_span,
+        });
+        let result = self.evaluate_iterations(&id, start, end, &stmts);
+        self.loops.pop();
+        result
+    }
+
+    /// Runs the body of the innermost loop for `id` from `start` to `end`.
+    fn evaluate_iterations(
+        &mut self,
+        id: &str,
+        start: i64,
+        end: i64,
+        stmts: &[Statement],
+    ) -> EvalResult<()> {
         for i in start..=end {
+            self.step()?;
             let loop_val = Value::Integer(i);
-            self.global_scope
-                .insert(id.clone(), (Type::Integer, Some(loop_val)));
-            for stmt in stmts.clone() {
-                self.evaluate_statement(*stmt)?;
+            let scope = self.scope_of(id).unwrap_or(0);
+            self.scopes[scope].insert(id.to_string(), (Type::Integer, Some(loop_val)));
+            if let Some(frame) = self.loops.last_mut() {
+                frame.value = i;
+                frame.iteration += 1;
+                let frame = frame.clone();
+                self.run_hooks(|hook, evaluator| hook.before_iteration(evaluator, &frame))?;
+            }
+            // Each iteration runs the body in a fresh block.
+            self.scopes.push(Scope::new());
+            let result = self.evaluate_block(stmts);
+            self.scopes.pop();
+            result?;
+            if self.returning {
+                break;
             }
         }
         Ok(())
     }
 
+    /// Value of an expression of constants, used by the optimiser to fold it
+    /// exactly as a run would.
+    pub(crate) fn evaluate_constant(&mut self, exp: Expression) -> EvalResult<Value> {
+        self.evaluate_expression(exp)
+    }
+
     fn evaluate_expression(&mut self, exp: Expression) -> EvalResult<Value> {
-        match exp {
-            Expression::IntegerConstant(val) => Ok(Value::Integer(val)),
-            Expression::StringValue(string) => Ok(Value::String(string.clone())),
-            Expression::Boolean(boolean) => Ok(Value::Bool(boolean)),
-            Expression::Binary(exp1, op, exp2) => self.evaluate_binary(exp1, op, exp2),
-            Expression::Unary(op, exp) => self.evaluate_unary(op, *exp),
-            Expression::Identifier(id) => {
+        match exp.kind {
+            ExpressionKind::IntegerConstant(val) => Ok(Value::Integer(val)),
+            ExpressionKind::StringValue(string) => Ok(Value::String(string)),
+            ExpressionKind::Boolean(boolean) => Ok(Value::Bool(boolean)),
+            ExpressionKind::Binary(exp1, op, exp2) => self.evaluate_binary(*exp1, op, *exp2),
+            ExpressionKind::Unary(op, exp) => self.evaluate_unary(op, *exp),
+            ExpressionKind::Call(name, args) => match self.call(name.clone(), args)? {
+                Some(val) => Ok(val),
+                None => Err(EvalError::MissingReturn(name)),
+            },
+            ExpressionKind::Index(id, index) => {
+                let index = self.evaluate_integer(*index)?;
+                match self.lookup(&id) {
+                    Some((_, Some(Value::Array(values)))) => {
+                        let position = element_position(index, values.len())?;
+                        Ok(values[position].clone())
+                    }
+                    Some(_) => Err(EvalError::MismatchedTypes),
+                    None => Err(EvalError::VariableNotInitialized(id)),
+                }
+            }
+            ExpressionKind::Identifier(id) => {
                 let (_, opt) = self.find_assigned_variable(&id)?;
                 match opt {
                     Some(val) => Ok(val.clone()),
@@ -154,20 +650,28 @@ impl Evaluator {
         }
     }
 
+    fn evaluate_integer(&mut self, exp: Expression) -> EvalResult<i64> {
+        match self.evaluate_expression(exp)? {
+            Value::Integer(int) => Ok(int),
+            _ => Err(EvalError::MismatchedTypes),
+        }
+    }
+
     fn evaluate_binary(
         &mut self,
-        left: Box<Expression>,
+        left: Expression,
         op: BinaryOperator,
-        right: Box<Expression>,
+        right: Expression,
     ) -> EvalResult<Value> {
-        let left = self.evaluate_expression(*left)?;
-        let right = self.evaluate_expression(*right)?;
+        let left = self.evaluate_expression(left)?;
+        let right = self.evaluate_expression(right)?;
         match (left, right) {
             (Value::Integer(val1), Value::Integer(val2)) => match op {
-                BinaryOperator::Plus => Ok(Value::Integer(val1 + val2)),
-                BinaryOperator::Minus => Ok(Value::Integer(val1 - val2)),
-                BinaryOperator::Multiplication => Ok(Value::Integer(val1 * val2)),
-                BinaryOperator::Division => Ok(Value::Integer(val1 / val2)),
+                BinaryOperator::Plus => self.checked(val1.checked_add(val2)),
+                BinaryOperator::Minus => self.checked(val1.checked_sub(val2)),
+                BinaryOperator::Multiplication => self.checked(val1.checked_mul(val2)),
+                BinaryOperator::Division if val2 == 0 => Err(EvalError::DivisionByZero),
+                BinaryOperator::Division => self.checked(val1.checked_div(val2)),
                 BinaryOperator::Equals => Ok(Value::Bool(val1 == val2)),
                 BinaryOperator::LessThan => Ok(Value::Bool(val1 < val2)),
                 BinaryOperator::GreaterThan => Ok(Value::Bool(val1 > val2)),
@@ -176,12 +680,12 @@ impl Evaluator {
             (Value::Bool(bool1), Value::Bool(bool2)) => match op {
                 BinaryOperator::And => Ok(Value::Bool(bool1 && bool2)),
                 BinaryOperator::Equals => Ok(Value::Bool(bool1 == bool2)),
-                BinaryOperator::LessThan => Ok(Value::Bool(bool1 < bool2)),
-                BinaryOperator::GreaterThan => Ok(Value::Bool(bool1 > bool2)),
+                BinaryOperator::LessThan => Ok(Value::Bool(!bool1 & bool2)),
+                BinaryOperator::GreaterThan => Ok(Value::Bool(bool1 & !bool2)),
                 _ => Err(EvalError::UnsupportedOperation),
             },
             (Value::String(str1), Value::String(str2)) => match op {
-                BinaryOperator::Plus => Ok(Value::String(str1 + &str2)),
+                BinaryOperator::Plus => self.checked_string(str1 + &str2),
                 BinaryOperator::Equals => Ok(Value::Bool(str1 == str2)),
                 BinaryOperator::LessThan => Ok(Value::Bool(str1 < str2)),
                 BinaryOperator::GreaterThan => Ok(Value::Bool(str1 > str2)),
@@ -201,32 +705,112 @@ impl Evaluator {
         }
     }
 
-    fn find_assigned_variable(&mut self, id: &String) -> EvalResult<GlobalVar> {
-        match self.global_scope.get(id) {
+    fn find_assigned_variable(&mut self, id: &String) -> EvalResult<Variable> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(id)) {
             Some((type_def, val)) => Ok((type_def.clone(), val.clone())),
             None => Err(EvalError::VariableNotInitialized(id.clone())),
         }
     }
 
-    fn add_new_variable(&mut self, id: String, var: GlobalVar) -> EvalResult<()> {
-        let (type_def, val) = var;
-        match self.global_scope.get(&id) {
+    /// Type and value of `id` without copying them.
+    fn lookup(&self, id: &str) -> Option<&Variable> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(id))
+    }
+
+    /// Index of the innermost scope declaring `id`.
+    fn scope_of(&self, id: &str) -> Option<usize> {
+        self.scopes.iter().rposition(|scope| scope.contains_key(id))
+    }
+
+    fn innermost_scope(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("the global scope is never popped")
+    }
+
+    /// Declares `id` in the innermost scope. Shadowing a variable of an
+    /// enclosing scope is an error, like redeclaring one in the same scope.
+    fn add_new_variable(&mut self, id: String, type_def: Type, val: Value) -> EvalResult<()> {
+        match self.scope_of(&id) {
             Some(_) => Err(EvalError::VariableAlreadyInitialized(id)),
-            None => {
-                self.global_scope.insert(id, (type_def, val));
-                Ok(())
-            }
+            None => self.assign(id, type_def, val),
+        }
+    }
+
+    /// Stores `val` in `id`, declaring it in the innermost scope if it is not
+    /// in scope yet, and reports the write to the hooks.
+    fn assign(&mut self, id: String, type_def: Type, val: Value) -> EvalResult<()> {
+        let scope = self.scope_of(&id).unwrap_or(self.scopes.len() - 1);
+        let old = self.scopes[scope]
+            .insert(id.clone(), (type_def, Some(val.clone())))
+            .and_then(|(_, old)| old);
+        self.run_hooks(|hook, evaluator| hook.after_assignment(evaluator, &id, old.as_ref(), &val))
+    }
+
+    /// Calls `call` on every hook, stopping at the first error.
+    fn run_hooks<F>(&mut self, mut call: F) -> EvalResult<()>
+    where
+        F: FnMut(&mut dyn Hook, &mut Evaluator) -> EvalResult<()>,
+    {
+        if self.hooks.is_empty() {
+            return Ok(());
         }
+        let mut hooks = std::mem::take(&mut self.hooks);
+        let result = hooks
+            .iter_mut()
+            .try_for_each(|hook| call(hook.as_mut(), self));
+        self.hooks = hooks;
+        result
     }
 
     fn get_input(&mut self) -> EvalResult<String> {
         let mut input = String::new();
-        match stdin().read_line(&mut input) {
+        match self.input.read_line(&mut input) {
             Ok(_) => Ok(input),
             Err(err) => Err(EvalError::IOError(err.to_string())),
         }
     }
 
+    fn write_output(&mut self, text: &str) -> EvalResult<()> {
+        let bytes = self.output_bytes + text.len() as u64;
+        match self.output_limit {
+            Some(limit) if bytes > limit => return Err(EvalError::OutputLimitExceeded(limit)),
+            _ => self.output_bytes = bytes,
+        }
+        let io_error = |err: std::io::Error| EvalError::IOError(err.to_string());
+        self.output.write_all(text.as_bytes()).map_err(io_error)?;
+        self.output.flush().map_err(io_error)
+    }
+
+    fn step(&mut self) -> EvalResult<()> {
+        self.steps += 1;
+        if let Some(limit) = self.step_limit {
+            if self.steps > limit {
+                return Err(EvalError::StepLimitExceeded(limit));
+            }
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() > deadline => Err(EvalError::DeadlineExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    /// Wraps an arithmetic result, failing if it overflowed `i64` or does not
+    /// fit in the configured integer width.
+    fn checked(&self, result: Option<i64>) -> EvalResult<Value> {
+        match result {
+            Some(int) if self.integer_width.contains(int) => Ok(Value::Integer(int)),
+            _ => Err(EvalError::IntegerOverflow),
+        }
+    }
+
+    fn checked_string(&self, string: String) -> EvalResult<Value> {
+        match self.string_limit {
+            Some(limit) if string.len() > limit => Err(EvalError::StringLimitExceeded(limit)),
+            _ => Ok(Value::String(string)),
+        }
+    }
+
     fn check_type_conformance(&self, type_def: &Type, val: &Value) -> EvalResult<()> {
         match val {
             Value::Bool(_) => match type_def {
@@ -241,6 +825,203 @@ impl Evaluator {
                 Type::String => Ok(()),
                 _ => Err(EvalError::MismatchedTypes),
             },
+            Value::Array(values) => match type_def {
+                Type::Array(size, element) if *size == values.len() => values
+                    .iter()
+                    .try_for_each(|val| self.check_type_conformance(element, val)),
+                _ => Err(EvalError::MismatchedTypes),
+            },
         }
     }
 }
+
+/// Position of `index` in an array of `size` elements.
+pub(crate) fn element_position(index: i64, size: usize) -> EvalResult<usize> {
+    match usize::try_from(index) {
+        Ok(position) if position < size => Ok(position),
+        _ => Err(EvalError::IndexOutOfBounds(index, size)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluator::{Evaluator, MAX_CALL_DEPTH};
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::utils::{EvalError, Type, Value};
+    use std::io;
+    use std::time::Instant;
+
+    fn evaluator(source: &str) -> Evaluator {
+        let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+        Evaluator::new(program).with_io(Box::new(io::empty()), Box::new(io::sink()))
+    }
+
+    const ENDLESS: &str = "var i : int;\nvar s : string := \"ab\";\n\
+                           for i in 0..2000000000 do\n    print s;\n    s := s + s;\nend for;";
+
+    #[test]
+    fn scope_loop_bodies() {
+        let source = "var i : int;\nvar total : int := 0;\nfor i in 1..3 do\n    \
+                      var square : int := i * i;\n    total := total + square;\nend for;";
+        let mut evaluator = evaluator(source);
+        assert!(evaluator.evaluate_program().is_ok());
+        let names: Vec<&str> = evaluator.variables().iter().map(|v| v.0).collect();
+        assert_eq!(names, vec!["i", "total"]);
+    }
+
+    #[test]
+    fn unwind_failed_calls_and_loops() {
+        let source = "var total : int := 0;\nprocedure fail(n : int)\n    \
+                      var i : int;\n    \
+                      for i in 1..2 do\n        \
+                          total := 1 / n;\n    \
+                      end for;\n\
+                      end procedure;\nvar j : int;\nfor j in 1..2 do\n    \
+                          fail(0);\n\
+                      end for;";
+        let mut evaluator = evaluator(source);
+        let err = evaluator.evaluate_program().unwrap_err();
+        assert!(evaluator.calls().is_empty());
+        assert!(evaluator.loops().is_empty());
+        let names: Vec<&str> = evaluator.variables().iter().map(|v| v.0).collect();
+        assert_eq!(names, vec!["j", "total"]);
+        assert_eq!(evaluator.current_span().start.line, 5);
+        let diagnostic = evaluator.diagnostic(err);
+        let labels: Vec<usize> = diagnostic
+            .labels
+            .iter()
+            .map(|l| l.span.start.line)
+            .collect();
+        assert_eq!(labels, vec![10]);
+    }
+
+    #[test]
+    fn call_routines() {
+        let source = "function sum(n : int) : int\n    var i : int;\n    \
+                      for i in 1..n - 1 do\n        return n + sum(n - 1);\n    end for;\n    \
+                      return n;\nend function;\nvar total : int := sum(10);";
+        let mut evaluator = evaluator(source);
+        assert!(evaluator.evaluate_program().is_ok());
+        let total = evaluator
+            .variables()
+            .iter()
+            .find(|v| v.0 == "total")
+            .unwrap()
+            .2;
+        assert_eq!(total, Some(&Value::Integer(55)));
+        assert!(evaluator.calls().is_empty());
+    }
+
+    #[test]
+    fn limit_call_depth() {
+        let source = "procedure forever()\n    forever();\nend procedure;\nforever();";
+        let result = evaluator(source).evaluate_program();
+        assert!(matches!(
+            result,
+            Err(EvalError::CallDepthExceeded(MAX_CALL_DEPTH))
+        ));
+    }
+
+    #[test]
+    fn label_calls_in_progress() {
+        let source = "function half(n : int) : int\n    return 1 / n;\nend function;\n\
+                      procedure show(n : int)\n    print half(n);\nend procedure;\nshow(0);";
+        let mut calls = evaluator(source);
+        let err = calls.evaluate_program().unwrap_err();
+        let diagnostic = calls.diagnostic(err);
+        assert_eq!((diagnostic.code, diagnostic.span.start.line), ("E0207", 2));
+        let labels: Vec<(usize, &str)> = diagnostic
+            .labels
+            .iter()
+            .map(|label| (label.span.start.line, label.message.as_str()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![(5, "`half` is called here"), (7, "`show` is called here")]
+        );
+
+        let source = "procedure forever()\n    forever();\nend procedure;\nforever();";
+        let mut calls = evaluator(source);
+        let err = calls.evaluate_program().unwrap_err();
+        let diagnostic = calls.diagnostic(err);
+        assert_eq!(diagnostic.labels.len(), 3);
+        assert_eq!(diagnostic.notes, vec!["197 more calls are in progress"]);
+    }
+
+    #[test]
+    fn index_arrays() {
+        let source = "var a : array[3] of int;\nvar i : int;\nfor i in a do\n    \
+                      a[i] := i * 2;\nend for;\nvar last : int := a[2];";
+        let mut evaluator = evaluator(source);
+        assert!(evaluator.evaluate_program().is_ok());
+        let values: Vec<_> = evaluator.variables().iter().map(|v| v.2.cloned()).collect();
+        let array = [0, 2, 4].iter().map(|int| Value::Integer(*int)).collect();
+        assert_eq!(
+            values,
+            vec![
+                Some(Value::Array(array)),
+                Some(Value::Integer(2)),
+                Some(Value::Integer(4))
+            ]
+        );
+    }
+
+    #[test]
+    fn check_array_bounds() {
+        for index in ["3", "0 - 1"] {
+            let source = format!("var a : array[3] of int;\nprint a[{}];", index);
+            let result = evaluator(&source).evaluate_program();
+            assert!(matches!(result, Err(EvalError::IndexOutOfBounds(_, 3))));
+        }
+    }
+
+    #[test]
+    fn enforce_step_limit() {
+        let result = evaluator(ENDLESS).with_step_limit(3).evaluate_program();
+        assert!(matches!(result, Err(EvalError::StepLimitExceeded(3))));
+    }
+
+    #[test]
+    fn enforce_output_limit() {
+        let result = evaluator(ENDLESS).with_output_limit(7).evaluate_program();
+        assert!(matches!(result, Err(EvalError::OutputLimitExceeded(7))));
+    }
+
+    #[test]
+    fn enforce_string_limit() {
+        let result = evaluator(ENDLESS).with_string_limit(100).evaluate_program();
+        assert!(matches!(result, Err(EvalError::StringLimitExceeded(100))));
+    }
+
+    #[test]
+    fn limit_strings_set_by_the_host() {
+        let mut evaluator = evaluator("var s : string := \"a\";").with_string_limit(3);
+        assert!(evaluator.evaluate_program().is_ok());
+        let long = Value::String("abcd".to_string());
+        assert!(matches!(
+            evaluator.set_variable("s", long.clone()),
+            Err(EvalError::StringLimitExceeded(3))
+        ));
+        assert!(matches!(
+            evaluator.define_variable("t", Type::String, long),
+            Err(EvalError::StringLimitExceeded(3))
+        ));
+        assert!(evaluator
+            .set_variable("s", Value::String("abc".to_string()))
+            .is_ok());
+        assert_eq!(
+            evaluator.variable("s"),
+            Some(&Value::String("abc".to_string()))
+        );
+    }
+
+    #[test]
+    fn enforce_deadline() {
+        let source = "var i : int;\nfor i in 0..2000000000 do\nend for;";
+        let result = evaluator(source)
+            .with_deadline(Instant::now())
+            .evaluate_program();
+        assert!(matches!(result, Err(EvalError::DeadlineExceeded)));
+    }
+}
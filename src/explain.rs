@@ -0,0 +1,706 @@
+//! Stable codes of every error and warning, and the long-form explanations
+//! printed by `minipl-interpreter explain <code>`.
+//!
+//! Codes never change meaning once released and are never reused: parse
+//! errors are `E00xx`, type errors `E01xx`, runtime errors `E02xx`, lexical
+//! errors `E03xx` and lints and other warnings `W00xx`. A new variant gets
+//! the next free code of its group and an entry in `EXPLANATIONS`.
+
+use crate::lint::LintWarning;
+use crate::utils::{CheckError, EvalError, LexError, ParseError, Warning};
+use std::fmt;
+
+/// Errors and warnings with a code in `EXPLANATIONS`.
+pub trait Coded {
+    fn code(&self) -> &'static str;
+}
+
+impl Coded for ParseError {
+    fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken(_) => "E0001",
+            ParseError::ExpectedColon(_) => "E0002",
+            ParseError::ExpectedTypeDefinition(_) => "E0003",
+            ParseError::ExpectedAssignment(_) => "E0004",
+            ParseError::ExpectedIdentifier(_) => "E0005",
+            ParseError::ExpectedOperand(_) => "E0006",
+            ParseError::ExpectedSemiColon(_) => "E0007",
+            ParseError::ExpectedClosingBracket(_) => "E0008",
+            ParseError::ExpectedLeftBracket(_) => "E0009",
+            ParseError::ExpectedIn(_) => "E0010",
+            ParseError::ExpectedDo(_) => "E0011",
+            ParseError::ExpectedRange(_) => "E0012",
+            ParseError::ExpectedFor(_) => "E0013",
+            ParseError::ExpectedProcedure(_) => "E0014",
+            ParseError::ExpectedFunction(_) => "E0015",
+            ParseError::ExpectedOf(_) => "E0016",
+            ParseError::ExpectedLeftSquareBracket(_) => "E0017",
+            ParseError::ExpectedClosingSquareBracket(_) => "E0018",
+            ParseError::InvalidArraySize(_) => "E0019",
+            ParseError::IntegerOutOfRange(_) => "E0020",
+            ParseError::NestingTooDeep(_) => "E0021",
+        }
+    }
+}
+
+impl Coded for CheckError {
+    fn code(&self) -> &'static str {
+        match self {
+            CheckError::MismatchedTypes(..) => "E0101",
+            CheckError::UnsupportedOperation(..) => "E0102",
+            CheckError::UndeclaredVariable(_) => "E0103",
+            CheckError::VariableAlreadyDeclared(_) => "E0104",
+            CheckError::VariableOutOfScope(_) => "E0105",
+            CheckError::UndeclaredRoutine(_) => "E0106",
+            CheckError::RoutineAlreadyDeclared(_) => "E0107",
+            CheckError::NestedRoutine(_) => "E0108",
+            CheckError::ArgumentCount(..) => "E0109",
+            CheckError::NoReturnValue(_) => "E0110",
+            CheckError::ReturnOutsideRoutine => "E0111",
+            CheckError::MissingReturnValue(..) => "E0112",
+            CheckError::UnexpectedReturnValue(_) => "E0113",
+        }
+    }
+}
+
+impl Coded for EvalError {
+    fn code(&self) -> &'static str {
+        match self {
+            EvalError::MismatchedTypes => "E0201",
+            EvalError::UnsupportedOperation => "E0202",
+            EvalError::VariableNotInitialized(_) => "E0203",
+            EvalError::VariableAlreadyInitialized(_) => "E0204",
+            EvalError::SyntaxError => "E0205",
+            EvalError::IOError(_) => "E0206",
+            EvalError::DivisionByZero => "E0207",
+            EvalError::IntegerOverflow => "E0208",
+            EvalError::StepLimitExceeded(_) => "E0209",
+            EvalError::OutputLimitExceeded(_) => "E0210",
+            EvalError::StringLimitExceeded(_) => "E0211",
+            EvalError::DeadlineExceeded => "E0212",
+            EvalError::UndeclaredRoutine(_) => "E0213",
+            EvalError::MissingReturn(_) => "E0214",
+            EvalError::CallDepthExceeded(_) => "E0215",
+            EvalError::IndexOutOfBounds(..) => "E0216",
+            EvalError::InvalidArgument(..) => "E0217",
+            EvalError::Interrupted => "E0218",
+        }
+    }
+}
+
+impl Coded for LexError {
+    fn code(&self) -> &'static str {
+        match self {
+            LexError::IllegalCharacter(_) => "E0301",
+            LexError::UnterminatedString => "E0302",
+            LexError::InvalidEscape(_) => "E0303",
+        }
+    }
+}
+
+/// A failing constant expression has the code of its runtime error.
+impl Coded for Warning {
+    fn code(&self) -> &'static str {
+        match self {
+            Warning::Failure(err) => err.code(),
+            Warning::UninitializedRead(_) => "W0006",
+        }
+    }
+}
+
+impl Coded for LintWarning {
+    fn code(&self) -> &'static str {
+        self.lint.code()
+    }
+}
+
+pub struct Explanation {
+    pub code: &'static str,
+    /// One line summary.
+    pub title: &'static str,
+    pub text: &'static str,
+    /// A program that fails with the error or raises the warning.
+    pub example: &'static str,
+}
+
+/// Prints the explanation as `explain` does, with the example indented.
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {}\n\n{}\n\nFor example:\n",
+            self.code, self.title, self.text
+        )?;
+        for line in self.example.lines() {
+            writeln!(f, "    {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// The explanation of `code`, ignoring case.
+pub fn find(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS
+        .iter()
+        .find(|explanation| explanation.code.eq_ignore_ascii_case(code))
+}
+
+pub const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "E0001",
+        title: "Unexpected token",
+        text: "A statement starts with a token that cannot start a statement, or an \
+               expression continues with a token that cannot follow it. Statements \
+               start with a keyword such as `var`, `print` or `for`, or with the \
+               name of a variable or a procedure. A misspelled keyword is read as a \
+               variable name, so the error is often reported at the token after it. \
+               A `;` missing between two statements, or a keyword missing after an \
+               expression, is reported at the token that follows as well.",
+        example: "pritn \"hello\";",
+    },
+    Explanation {
+        code: "E0002",
+        title: "Expected a colon",
+        text: "A variable declaration or a parameter names its type after a colon, \
+               as in `var x : int;`.",
+        example: "var x int;",
+    },
+    Explanation {
+        code: "E0003",
+        title: "Expected a type",
+        text: "The type of a variable, parameter or function result must be `int`, \
+               `string`, `bool` or `array[n] of` one of those.",
+        example: "var x : integer;",
+    },
+    Explanation {
+        code: "E0004",
+        title: "Expected an assignment",
+        text: "A statement starting with a variable name assigns to it with `:=`. \
+               Equality is tested with `=` in expressions only.",
+        example: "var x : int;\nx = 1;",
+    },
+    Explanation {
+        code: "E0005",
+        title: "Expected an identifier",
+        text: "A variable, parameter, procedure or function name is missing. Names \
+               start with a letter, followed by letters, digits and underscores, and \
+               cannot be keywords.",
+        example: "var for : int;",
+    },
+    Explanation {
+        code: "E0006",
+        title: "Expected an operand",
+        text: "An expression is missing a value where one is required: a literal, a \
+               variable, a call or an expression in parentheses. The size of an array \
+               type must be an integer literal.",
+        example: "var x : int := 1 + ;",
+    },
+    Explanation {
+        code: "E0007",
+        title: "Expected a semicolon",
+        text: "Every statement ends with `;`, including `end for;`, `end procedure;` \
+               and `end function;`. An expression holds at most one binary operator \
+               unless the operands are parenthesized, so `1 + 2 * 3` ends after \
+               `1 + 2` and the `*` is found where `;` should be; write \
+               `1 + (2 * 3)`.",
+        example: "print 1 + 2 * 3;",
+    },
+    Explanation {
+        code: "E0008",
+        title: "Expected a closing parenthesis",
+        text: "A parenthesized expression, an argument list or a parameter list is \
+               not closed with `)`. Inside parentheses too, an expression holds at \
+               most one binary operator.",
+        example: "print (1 + 2;",
+    },
+    Explanation {
+        code: "E0009",
+        title: "Expected an opening parenthesis",
+        text: "A procedure or function declaration lists its parameters in \
+               parentheses after its name, even when there are none.",
+        example: "procedure greet\n    print \"hello\";\nend procedure;",
+    },
+    Explanation {
+        code: "E0010",
+        title: "Expected `in`",
+        text: "A `for` loop names its variable and then the range or array it runs \
+               over after `in`.",
+        example: "var i : int;\nfor i 1..3 do\n    print i;\nend for;",
+    },
+    Explanation {
+        code: "E0011",
+        title: "Expected `do`",
+        text: "The range or array of a `for` loop is followed by `do` and the body. \
+               When `do` is missing at the end of the line, the first token of the \
+               body is usually reported as unexpected instead.",
+        example: "var i : int;\nfor i in 1..3;\n    print i;\nend for;",
+    },
+    Explanation {
+        code: "E0012",
+        title: "Expected `..`",
+        text: "A `for` loop over integers gives the first and the last value \
+               separated by `..`. Both ends are included.",
+        example: "var i : int;\nfor i in 1, 3 do\n    print i;\nend for;",
+    },
+    Explanation {
+        code: "E0013",
+        title: "Expected `for`",
+        text: "The body of a `for` loop ends with `end for;`.",
+        example: "var i : int;\nfor i in 1..3 do\n    print i;\nend;",
+    },
+    Explanation {
+        code: "E0014",
+        title: "Expected `procedure`",
+        text: "The body of a procedure ends with `end procedure;`.",
+        example: "procedure greet()\n    print \"hello\";\nend function;",
+    },
+    Explanation {
+        code: "E0015",
+        title: "Expected `function`",
+        text: "The body of a function, a routine with a return type, ends with \
+               `end function;`.",
+        example: "function one() : int\n    return 1;\nend procedure;",
+    },
+    Explanation {
+        code: "E0016",
+        title: "Expected `of`",
+        text: "An array type gives its element type after `of`, as in \
+               `array[3] of int`.",
+        example: "var a : array[3] int;",
+    },
+    Explanation {
+        code: "E0017",
+        title: "Expected an opening bracket",
+        text: "An array type gives its size in square brackets after `array`.",
+        example: "var a : array 3 of int;",
+    },
+    Explanation {
+        code: "E0018",
+        title: "Expected a closing bracket",
+        text: "The size of an array type or the index of an element is not closed \
+               with `]`.",
+        example: "var a : array[3] of int;\nprint a[1;",
+    },
+    Explanation {
+        code: "E0019",
+        title: "Invalid array size",
+        text: "Arrays hold at least 1 and at most 1048576 elements.",
+        example: "var a : array[0] of int;",
+    },
+    Explanation {
+        code: "E0020",
+        title: "Integer literal out of range",
+        text: "An integer literal does not fit in the `int` type. Integers are 32 \
+               bits wide unless the program is run with `--int-width=i64`.",
+        example: "print 3000000000;",
+    },
+    Explanation {
+        code: "E0021",
+        title: "Nesting too deep",
+        text: "Expressions and `for` loops nest at most 100 levels deep.",
+        example: "print ((((((((((((((((((((((((((((((((((((((((((((((((((\
+                  ((((((((((((((((((((((((((((((((((((((((((((((((((((1\
+                  ))))))))))))))))))))))))))))))))))))))))))))))))))\
+                  ))))))))))))))))))))))))))))))))))))))))))))))))));",
+    },
+    Explanation {
+        code: "E0101",
+        title: "Mismatched types",
+        text: "A value has a different type than its place requires: the declared \
+               type of a variable, the type of a parameter or function result, \
+               the other operand of a binary operator, or `bool` for assertions. \
+               Values are never converted implicitly; use `to_string` and `to_int`.",
+        example: "var x : int := \"1\";",
+    },
+    Explanation {
+        code: "E0102",
+        title: "Unsupported operation",
+        text: "An operator or statement does not apply to values of this type. \
+               Only integers support `-`, `*` and `/`, `&` and `!` need `bool` \
+               operands, and arrays cannot be printed, read or compared.",
+        example: "print \"a\" - \"b\";",
+    },
+    Explanation {
+        code: "E0103",
+        title: "Undeclared variable",
+        text: "A variable is used without being declared with `var` first. \
+               Procedures and functions see only their parameters and their own \
+               variables, not those of the main program.",
+        example: "var count : int := 1;\nprint counter;",
+    },
+    Explanation {
+        code: "E0104",
+        title: "Variable already declared",
+        text: "A variable is declared again while an earlier declaration of the same \
+               name is in scope. There is no shadowing, not even in a `for` body.",
+        example: "var x : int;\nvar x : string;",
+    },
+    Explanation {
+        code: "E0105",
+        title: "Variable out of scope",
+        text: "A variable is used after the `for` body declaring it has ended. \
+               Variables declared in a body are local to one iteration; declare the \
+               variable before the loop to keep its value.",
+        example: "var i : int;\nfor i in 1..3 do\n    var last : int := i;\nend for;\n\
+                  print last;",
+    },
+    Explanation {
+        code: "E0106",
+        title: "Undeclared procedure or function",
+        text: "A call names neither a built-in function nor a procedure or function \
+               declared before the call.",
+        example: "greet(\"Ada\");",
+    },
+    Explanation {
+        code: "E0107",
+        title: "Procedure or function already declared",
+        text: "Procedures and functions share one namespace with the built-in \
+               functions, and each name can be declared once.",
+        example: "procedure length()\nend procedure;",
+    },
+    Explanation {
+        code: "E0108",
+        title: "Nested procedure or function",
+        text: "Procedures and functions are declared at the top level of the \
+               program, not inside loops or other routines.",
+        example: "procedure outer()\n    procedure inner()\n    end procedure;\n\
+                  end procedure;",
+    },
+    Explanation {
+        code: "E0109",
+        title: "Wrong number of arguments",
+        text: "A call passes a different number of arguments than the procedure or \
+               function declares parameters.",
+        example: "print length(\"a\", \"b\");",
+    },
+    Explanation {
+        code: "E0110",
+        title: "Procedure used as a value",
+        text: "A procedure is called inside an expression, but only functions return \
+               a value. Call the procedure as a statement or give it a return type.",
+        example: "procedure greet()\n    print \"hello\";\nend procedure;\n\
+                  print greet();",
+    },
+    Explanation {
+        code: "E0111",
+        title: "Return outside of a procedure or function",
+        text: "`return` leaves a procedure or function and cannot be used in the \
+               main program.",
+        example: "return;",
+    },
+    Explanation {
+        code: "E0112",
+        title: "Missing return value",
+        text: "A function returns with `return;` or its body never returns a value \
+               of its return type.",
+        example: "function one() : int\n    return;\nend function;",
+    },
+    Explanation {
+        code: "E0113",
+        title: "Unexpected return value",
+        text: "A procedure has no return type and leaves with `return;` only.",
+        example: "procedure one()\n    return 1;\nend procedure;",
+    },
+    Explanation {
+        code: "E0201",
+        title: "Mismatched types at runtime",
+        text: "A value has the wrong type while the program runs. The type checker \
+               rules this out for the program itself, so it happens when `read` gets \
+               input that is not an integer for an `int` variable, or when a host \
+               function returns a value of the wrong type.",
+        example: "var x : int;\nread x; // with the input \"ten\"",
+    },
+    Explanation {
+        code: "E0202",
+        title: "Unsupported operation at runtime",
+        text: "An operator is applied to values it does not support while the \
+               program runs. The type checker rules this out; it can only happen for \
+               programs that were not checked.",
+        example: "print \"a\" - \"b\";",
+    },
+    Explanation {
+        code: "E0203",
+        title: "Variable not initialized",
+        text: "A variable declared without a value is read before anything is \
+               assigned to it, for example after a `for` loop that ran zero times. \
+               This is also reported as a warning before the program runs when some \
+               path through the program reads it unassigned.",
+        example: "var x : int;\nprint x;",
+    },
+    Explanation {
+        code: "E0204",
+        title: "Variable already initialized",
+        text: "The host defines a variable, with `--set` or \
+               `Evaluator::define_variable`, that it has already defined. When the \
+               program declares a variable of the same name, the type checker \
+               reports E0104 instead.",
+        example: "print x; // run with --set x=1 --set x=2",
+    },
+    Explanation {
+        code: "E0205",
+        title: "Syntax error",
+        text: "The program could not be parsed, so it did not run. The parse errors \
+               themselves are reported with their own codes.",
+        example: "print 1",
+    },
+    Explanation {
+        code: "E0206",
+        title: "Input or output error",
+        text: "Reading input or writing output failed, for example because the \
+               output was closed.",
+        example: "print \"hello\"; // with the output closed",
+    },
+    Explanation {
+        code: "E0207",
+        title: "Division by zero",
+        text: "An integer is divided by zero.",
+        example: "var x : int := 0;\nprint 10 / x;",
+    },
+    Explanation {
+        code: "E0208",
+        title: "Integer overflow",
+        text: "Arithmetic, `read` or `to_int` produces an integer that does not fit \
+               in the `int` type, 32 bits unless the program is run with \
+               `--int-width=i64`. Results never wrap around.",
+        example: "var x : int := 2147483647;\nprint x + 1;",
+    },
+    Explanation {
+        code: "E0209",
+        title: "Step limit exceeded",
+        text: "The program ran more statements and loop iterations than \
+               `--max-steps` allows.",
+        example: "var i : int;\nfor i in 1..1000000 do\nend for; // run with --max-steps=10",
+    },
+    Explanation {
+        code: "E0210",
+        title: "Output limit exceeded",
+        text: "The program printed more bytes in total than `--max-output` allows.",
+        example: "print \"hello\"; // run with --max-output=3",
+    },
+    Explanation {
+        code: "E0211",
+        title: "String limit exceeded",
+        text: "Concatenation or `read` produces a string longer than `--max-string` \
+               allows.",
+        example: "print \"abc\" + \"def\"; // run with --max-string=4",
+    },
+    Explanation {
+        code: "E0212",
+        title: "Time limit exceeded",
+        text: "The program ran longer than `--timeout` allows.",
+        example: "var i : int;\nfor i in 1..2000000000 do\nend for; // run with --timeout=1",
+    },
+    Explanation {
+        code: "E0213",
+        title: "Undeclared procedure or function at runtime",
+        text: "A call names a procedure or function that does not exist while the \
+               program runs. The type checker rules this out; it can only happen for \
+               programs that were not checked.",
+        example: "greet();",
+    },
+    Explanation {
+        code: "E0214",
+        title: "Function ended without a value",
+        text: "A function reached the end of its body without a `return`. Every path \
+               through a function must return a value, including when a loop \
+               holding the `return` runs zero times.",
+        example: "function first(n : int) : int\n    var i : int;\n    for i in 1..n do\n        \
+                  return i;\n    end for;\nend function;\nprint first(0);",
+    },
+    Explanation {
+        code: "E0215",
+        title: "Calls nested too deep",
+        text: "Procedure and function calls nest more than 200 deep, usually through \
+               recursion that never stops.",
+        example: "function down(n : int) : int\n    return down(n - 1);\nend function;\n\
+                  print down(1);",
+    },
+    Explanation {
+        code: "E0216",
+        title: "Index out of bounds",
+        text: "An array is indexed with a negative number or a number not below its \
+               size. Indices start at 0.",
+        example: "var a : array[3] of int;\nprint a[3];",
+    },
+    Explanation {
+        code: "E0217",
+        title: "Invalid argument",
+        text: "A built-in or host function got an argument it cannot handle, such as \
+               a position outside the string for `substr` or text that is not an \
+               integer for `to_int`.",
+        example: "print to_int(\"ten\");",
+    },
+    Explanation {
+        code: "E0218",
+        title: "Execution interrupted",
+        text: "The program was stopped from outside, by the `quit` command of the \
+               debugger.",
+        example: "print \"hello\"; // run with debug and the command quit",
+    },
+    Explanation {
+        code: "E0301",
+        title: "Illegal character",
+        text: "The program contains a character that does not start any token, such \
+               as `$`, `%` or a single `.`. Dots only appear in the `..` of a range.",
+        example: "print 10 % 3;",
+    },
+    Explanation {
+        code: "E0302",
+        title: "Unterminated string",
+        text: "A string literal has no closing `\"` before the end of the program. \
+               Write a quote inside a string as `\\\"`.",
+        example: "print \"hello;",
+    },
+    Explanation {
+        code: "E0303",
+        title: "Invalid escape sequence",
+        text: "A backslash in a string literal is followed by a character other than \
+               `\"`, `\\`, `n` or `t`. Write a backslash itself as `\\\\`.",
+        example: "print \"C:\\data\";",
+    },
+    Explanation {
+        code: "W0001",
+        title: "Unused variable",
+        text: "A variable is declared and never assigned or read. Remove it, or \
+               allow the lint with `--allow=unused-variable` or a \
+               `// lint: allow(unused-variable)` comment.",
+        example: "var unused : int;",
+    },
+    Explanation {
+        code: "W0002",
+        title: "Variable assigned but never read",
+        text: "A variable is assigned, by `:=`, `read` or an element assignment, but \
+               its value is never read, so the assignments have no effect apart from \
+               consuming input.",
+        example: "var x : int;\nx := 1;",
+    },
+    Explanation {
+        code: "W0003",
+        title: "Constant assertion",
+        text: "The condition of an assertion does not depend on any variable, so it \
+               always holds, and the assertion checks nothing, or it always fails.",
+        example: "assert (2 > 1);",
+    },
+    Explanation {
+        code: "W0004",
+        title: "Empty loop body",
+        text: "A `for` loop has no statements. It only sets its variable to the last \
+               value of the range; assign that value directly instead.",
+        example: "var i : int;\nfor i in 1..10 do\nend for;",
+    },
+    Explanation {
+        code: "W0005",
+        title: "Empty range",
+        text: "The start of a `for` range is a constant greater than its constant end, \
+               so the body never runs. Ranges always count up; swap the ends and \
+               count down in the body to run over them backwards.",
+        example: "var i : int;\nfor i in 10..1 do\n    print i;\nend for;",
+    },
+    Explanation {
+        code: "W0006",
+        title: "Variable may be read before it is assigned",
+        text: "A variable declared without a value is read on some path through the \
+               program before any assignment, for example after a `for` loop that \
+               may run zero times. The program still runs and fails with E0203 if \
+               it takes that path.",
+        example: "var x : int;\nvar i : int;\nfor i in 1..0 do\n    x := i;\nend for;\nprint x;",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::explain::{find, Coded, EXPLANATIONS};
+    use crate::lint::LINTS;
+    use crate::token::Token;
+    use crate::utils::{CheckError, EvalError, LexError, ParseError, Type, Warning};
+
+    #[test]
+    fn explain_every_code() {
+        let t = || Token::EOF;
+        let s = String::new;
+        let parse_errors = vec![
+            ParseError::UnexpectedToken(t()),
+            ParseError::ExpectedColon(t()),
+            ParseError::ExpectedTypeDefinition(t()),
+            ParseError::ExpectedAssignment(t()),
+            ParseError::ExpectedIdentifier(t()),
+            ParseError::ExpectedOperand(t()),
+            ParseError::ExpectedSemiColon(t()),
+            ParseError::ExpectedClosingBracket(t()),
+            ParseError::ExpectedLeftBracket(t()),
+            ParseError::ExpectedIn(t()),
+            ParseError::ExpectedDo(t()),
+            ParseError::ExpectedRange(t()),
+            ParseError::ExpectedFor(t()),
+            ParseError::ExpectedProcedure(t()),
+            ParseError::ExpectedFunction(t()),
+            ParseError::ExpectedOf(t()),
+            ParseError::ExpectedLeftSquareBracket(t()),
+            ParseError::ExpectedClosingSquareBracket(t()),
+            ParseError::InvalidArraySize(s()),
+            ParseError::IntegerOutOfRange(s()),
+            ParseError::NestingTooDeep(t()),
+        ];
+        let check_errors = vec![
+            CheckError::MismatchedTypes(Type::Integer, Type::Integer),
+            CheckError::UnsupportedOperation(s(), Type::Integer),
+            CheckError::UndeclaredVariable(s()),
+            CheckError::VariableAlreadyDeclared(s()),
+            CheckError::VariableOutOfScope(s()),
+            CheckError::UndeclaredRoutine(s()),
+            CheckError::RoutineAlreadyDeclared(s()),
+            CheckError::NestedRoutine(s()),
+            CheckError::ArgumentCount(s(), 0, 0),
+            CheckError::NoReturnValue(s()),
+            CheckError::ReturnOutsideRoutine,
+            CheckError::MissingReturnValue(s(), Type::Integer),
+            CheckError::UnexpectedReturnValue(s()),
+        ];
+        let eval_errors = vec![
+            EvalError::MismatchedTypes,
+            EvalError::UnsupportedOperation,
+            EvalError::VariableNotInitialized(s()),
+            EvalError::VariableAlreadyInitialized(s()),
+            EvalError::SyntaxError,
+            EvalError::IOError(s()),
+            EvalError::DivisionByZero,
+            EvalError::IntegerOverflow,
+            EvalError::StepLimitExceeded(0),
+            EvalError::OutputLimitExceeded(0),
+            EvalError::StringLimitExceeded(0),
+            EvalError::DeadlineExceeded,
+            EvalError::UndeclaredRoutine(s()),
+            EvalError::MissingReturn(s()),
+            EvalError::CallDepthExceeded(0),
+            EvalError::IndexOutOfBounds(0, 0),
+            EvalError::InvalidArgument(s(), s()),
+            EvalError::Interrupted,
+        ];
+        let lex_errors = [
+            LexError::IllegalCharacter(' '),
+            LexError::UnterminatedString,
+            LexError::InvalidEscape(' '),
+        ];
+        let codes: Vec<&str> = parse_errors
+            .iter()
+            .map(Coded::code)
+            .chain(check_errors.iter().map(Coded::code))
+            .chain(eval_errors.iter().map(Coded::code))
+            .chain(lex_errors.iter().map(Coded::code))
+            .chain(LINTS.iter().map(|lint| lint.code()))
+            .chain([Warning::UninitializedRead(s()).code()])
+            .collect();
+        let registered: Vec<&str> = EXPLANATIONS.iter().map(|entry| entry.code).collect();
+        assert_eq!(codes, registered);
+    }
+
+    #[test]
+    fn print_explanations() {
+        assert!(find("W0009").is_none());
+        assert_eq!(
+            find("e0207").unwrap().to_string(),
+            "E0207: Division by zero\n\nAn integer is divided by zero.\n\nFor example:\n\n    \
+             var x : int := 0;\n    print 10 / x;\n"
+        );
+    }
+}
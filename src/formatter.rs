@@ -0,0 +1,194 @@
+use crate::ast::{Expression, ExpressionKind, Program, Statement, StatementKind};
+use crate::utils::{Type, Value};
+
+const INDENT: &str = "    ";
+
+/// Renders a program back to Mini-PL source in canonical layout: one
+/// statement per line, four-space indentation inside `for` bodies and only
+/// the brackets the grammar requires.
+pub fn format_program(program: &Program) -> String {
+    let mut output = String::new();
+    for statement in &program.statements {
+        format_statement(statement, 0, &mut output);
+    }
+    output
+}
+
+pub fn format_statement(statement: &Statement, depth: usize, output: &mut String) {
+    let indent = INDENT.repeat(depth);
+    output.push_str(&indent);
+    match &statement.kind {
+        StatementKind::VarInitialization(id, type_def) => {
+            output.push_str(&format!("var {} : {};", id, type_def))
+        }
+        StatementKind::NewAssignment(id, type_def, exp) => output.push_str(&format!(
+            "var {} : {} := {};",
+            id,
+            type_def,
+            format_expression(exp)
+        )),
+        StatementKind::Assignment(id, exp) => {
+            output.push_str(&format!("{} := {};", id, format_expression(exp)))
+        }
+        StatementKind::IndexAssignment(id, index, exp) => output.push_str(&format!(
+            "{}[{}] := {};",
+            id,
+            format_expression(index),
+            format_expression(exp)
+        )),
+        StatementKind::Print(exp) => output.push_str(&format!("print {};", format_expression(exp))),
+        StatementKind::Assert(exp) => {
+            output.push_str(&format!("assert ({});", format_expression(exp)))
+        }
+        StatementKind::Read(id) => output.push_str(&format!("read {};", id)),
+        StatementKind::For(id, start, end, stmts) => {
+            output.push_str(&format!(
+                "for {} in {}..{} do\n",
+                id,
+                format_expression(start),
+                format_expression(end)
+            ));
+            for stmt in stmts {
+                format_statement(stmt, depth + 1, output);
+            }
+            output.push_str(&indent);
+            output.push_str("end for;");
+        }
+        StatementKind::Routine(routine) => {
+            let parameters: Vec<String> = routine
+                .parameters
+                .iter()
+                .map(|param| format!("{} : {}", param.name, param.type_def))
+                .collect();
+            output.push_str(&format!(
+                "{} {}({})",
+                routine.keyword(),
+                routine.name,
+                parameters.join(", ")
+            ));
+            if let Some(type_def) = &routine.return_type {
+                output.push_str(&format!(" : {}", type_def));
+            }
+            output.push('\n');
+            for stmt in &routine.body {
+                format_statement(stmt, depth + 1, output);
+            }
+            output.push_str(&indent);
+            output.push_str(&format!("end {};", routine.keyword()));
+        }
+        StatementKind::Call(name, args) => {
+            output.push_str(&format!("{}({});", name, format_arguments(args)))
+        }
+        StatementKind::Return(Some(exp)) => {
+            output.push_str(&format!("return {};", format_expression(exp)))
+        }
+        StatementKind::Return(None) => output.push_str("return;"),
+    }
+    output.push('\n');
+}
+
+/// First line of a statement in canonical layout, which is the whole
+/// statement except for the body of a `for` loop.
+pub fn format_header(statement: &Statement) -> String {
+    let mut output = String::new();
+    format_statement(statement, 0, &mut output);
+    output.lines().next().unwrap_or("").to_string()
+}
+
+/// A variable and its value as `name : type = value`, with strings quoted
+/// so that whitespace is visible.
+pub fn format_variable(id: &str, type_def: &Type, val: Option<&Value>) -> String {
+    match val {
+        Some(Value::String(string)) => format!("{} : {} = {:?}", id, type_def, string),
+        Some(val) => format!("{} : {} = {}", id, type_def, val),
+        None => format!("{} : {} is unassigned", id, type_def),
+    }
+}
+
+pub fn format_expression(exp: &Expression) -> String {
+    match &exp.kind {
+        ExpressionKind::Unary(op, operand) => format!("{}{}", op, format_operand(operand)),
+        ExpressionKind::Binary(left, op, right) => {
+            format!("{} {} {}", format_operand(left), op, format_operand(right))
+        }
+        _ => format_operand(exp),
+    }
+}
+
+fn format_operand(exp: &Expression) -> String {
+    match &exp.kind {
+        ExpressionKind::Identifier(id) => id.clone(),
+        // Mini-PL has no negative literals, but the optimiser can produce them.
+        ExpressionKind::IntegerConstant(int) if *int < 0 => {
+            format!("(0 - {})", int.unsigned_abs())
+        }
+        ExpressionKind::IntegerConstant(int) => int.to_string(),
+        ExpressionKind::StringValue(s) => format!("\"{}\"", escape_string(s)),
+        ExpressionKind::Boolean(b) => b.to_string(),
+        ExpressionKind::Call(name, args) => format!("{}({})", name, format_arguments(args)),
+        ExpressionKind::Index(id, index) => format!("{}[{}]", id, format_expression(index)),
+        ExpressionKind::Unary(..) | ExpressionKind::Binary(..) => {
+            format!("({})", format_expression(exp))
+        }
+    }
+}
+
+fn format_arguments(args: &[Expression]) -> String {
+    let args: Vec<String> = args.iter().map(format_expression).collect();
+    args.join(", ")
+}
+
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::new();
+    for ch in s.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formatter::format_program;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn format(source: &str) -> String {
+        let lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(parser.get_errors().is_empty());
+        format_program(&program)
+    }
+
+    #[test]
+    fn format_layout() {
+        let source = r#"var X:int:=1+(2*6);  assert(12>X);
+            var x : int; for x in 0..X-1 do print "a\"b\n";
+        print !(x = 1); x:=!(!true); end for;
+        read x;"#;
+        let expected = r#"var X : int := 1 + (2 * 6);
+assert (12 > X);
+var x : int;
+for x in 0..X - 1 do
+    print "a\"b\n";
+    print !(x = 1);
+    x := !(!true);
+end for;
+read x;
+"#;
+        assert_eq!(format(source), expected);
+    }
+
+    #[test]
+    fn format_is_idempotent() {
+        let source = std::fs::read_to_string("test.mini").unwrap();
+        let once = format(&source);
+        assert_eq!(format(&once), once);
+    }
+}
@@ -0,0 +1,54 @@
+//! Entry points shared by the cargo-fuzz targets in `fuzz/` and the
+//! self-contained mutation test in `tests/fuzz.rs`. Each one accepts
+//! arbitrary bytes and must return without panicking or hanging.
+
+use crate::checker::Checker;
+use crate::evaluator::Evaluator;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::token::Token;
+use std::io;
+
+/// Statements and loop iterations a fuzzed program may execute.
+pub const FUEL: u64 = 10_000;
+
+/// Longest string a fuzzed program may build, so that repeated doubling
+/// cannot exhaust memory.
+pub const MAX_STRING: usize = 1 << 16;
+
+fn source(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).into_owned()
+}
+
+pub fn lex(data: &[u8]) {
+    let mut lexer = Lexer::new(source(data));
+    let tokens = lexer.get_all_tokens();
+    assert_eq!(tokens.last().map(|(token, _)| token), Some(&Token::EOF));
+}
+
+pub fn parse(data: &[u8]) {
+    let mut parser = Parser::new(Lexer::new(source(data)));
+    let program = parser.parse_program();
+    let mut checker = Checker::new();
+    checker.check_program(&program);
+}
+
+/// Runs well-typed programs with `data` doubling as the input for `read`.
+pub fn evaluate(data: &[u8]) {
+    let mut parser = Parser::new(Lexer::new(source(data)));
+    let program = parser.parse_program();
+    if !parser.get_errors().is_empty() {
+        return;
+    }
+    let mut checker = Checker::new();
+    checker.check_program(&program);
+    if !checker.get_errors().is_empty() {
+        return;
+    }
+    let input = Box::new(io::Cursor::new(data.to_vec()));
+    let mut evaluator = Evaluator::new(program)
+        .with_io(input, Box::new(io::sink()))
+        .with_step_limit(FUEL)
+        .with_string_limit(MAX_STRING);
+    let _ = evaluator.evaluate_program();
+}
@@ -0,0 +1,149 @@
+//! Static analyses of lowered programs.
+
+use crate::ir::{Function, Instruction, InstructionKind, Module, Var};
+use crate::utils::{SpannedWarning, Type, Warning};
+
+/// Reads of variables declared without a value that may happen before the
+/// variable is assigned, along some path through the program: the
+/// variable is only assigned in the body of a loop that may run zero
+/// times, or later, or nowhere. The evaluator stops with an error if it
+/// takes that path. Sorted by position.
+pub fn uninitialized_reads(module: &Module) -> Vec<SpannedWarning> {
+    let mut warnings = Vec::new();
+    for function in module.functions.iter().chain([&module.main]) {
+        function_uninitialized_reads(function, &mut warnings);
+    }
+    warnings.sort_by_key(|(_, span)| (span.start.line, span.start.column));
+    warnings
+}
+
+/// Forward data flow of the variables that are assigned on every path
+/// reaching each block, starting from all of them outside the entry block
+/// and narrowing until nothing changes.
+fn function_uninitialized_reads(function: &Function, warnings: &mut Vec<SpannedWarning>) {
+    let size = function.locals.len();
+    let mut tracked = vec![false; size];
+    for block in &function.blocks {
+        for instruction in &block.instructions {
+            if let InstructionKind::Declare(var) = instruction.kind {
+                tracked[var.0] = !matches!(function.locals[var.0].type_def, Type::Array(..));
+            }
+        }
+    }
+    if !tracked.contains(&true) {
+        return;
+    }
+
+    let predecessors = function.predecessors();
+    let mut entry_states = vec![vec![true; size]; function.blocks.len()];
+    entry_states[0] = vec![false; size];
+    let mut exit_states = entry_states.clone();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (id, block) in function.blocks.iter().enumerate() {
+            if id != 0 {
+                let mut assigned = vec![true; size];
+                for pred in &predecessors[id] {
+                    for (var, value) in assigned.iter_mut().enumerate() {
+                        *value &= exit_states[*pred][var];
+                    }
+                }
+                entry_states[id] = assigned;
+            }
+            let mut assigned = entry_states[id].clone();
+            for instruction in &block.instructions {
+                transfer(instruction, &mut assigned);
+            }
+            if assigned != exit_states[id] {
+                exit_states[id] = assigned;
+                changed = true;
+            }
+        }
+    }
+
+    let mut reported = Vec::new();
+    for (id, block) in function.blocks.iter().enumerate() {
+        let mut assigned = entry_states[id].clone();
+        let uses = block
+            .instructions
+            .iter()
+            .map(|instruction| (instruction.uses(), instruction.span, Some(instruction)))
+            .chain([(block.terminator.uses(), block.terminator.span, None)]);
+        for (vars, span, instruction) in uses {
+            for Var(var) in vars {
+                if tracked[var] && !assigned[var] && !reported.contains(&(var, span)) {
+                    reported.push((var, span));
+                    let name = function.locals[var].name.clone().unwrap_or_default();
+                    let warning = Warning::UninitializedRead(name);
+                    warnings.push((warning, span));
+                }
+            }
+            if let Some(instruction) = instruction {
+                transfer(instruction, &mut assigned);
+            }
+        }
+    }
+}
+
+fn transfer(instruction: &Instruction, assigned: &mut [bool]) {
+    match (&instruction.kind, instruction.definition()) {
+        (InstructionKind::Declare(var), _) => assigned[var.0] = false,
+        (_, Some(var)) => assigned[var.0] = true,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::analysis::uninitialized_reads;
+    use crate::ir::lower::Lowering;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Line and message of every warning.
+    fn warnings(source: &str) -> Vec<(usize, String)> {
+        let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+        let module = Lowering::new().lower_program(&program).unwrap();
+        uninitialized_reads(&module)
+            .iter()
+            .map(|(warning, span)| (span.start.line, warning.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn find_reads_before_assignment() {
+        let source = "var x : int;\nprint x;\nx := 1;\nprint x;";
+        assert_eq!(
+            warnings(source),
+            vec![(
+                2,
+                "Warning: Variable x may be read before it is assigned".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn follow_loops() {
+        // The loop may run zero times, leaving `i` and `last` unassigned.
+        let source = "var i : int;\nvar last : int;\nvar n : int := 0;\nfor i in 1..n do\n    \
+                      last := i;\nend for;\nprint i;\nprint last;";
+        let lines: Vec<usize> = warnings(source).iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![7, 8]);
+
+        // A variable declared in the body is unassigned again in every
+        // iteration.
+        let source = "var i : int;\nfor i in 1..2 do\n    var x : int;\n    print x;\n    \
+                      x := i;\nend for;";
+        let lines: Vec<usize> = warnings(source).iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![4]);
+    }
+
+    #[test]
+    fn accept_assigned_variables() {
+        let source = "function f(n : int) : int\n    var x : int;\n    read x;\n    \
+                      return x + n;\nend function;\nvar a : array[2] of int;\nvar s : string;\n\
+                      s := \"a\";\nprint a[0];\nprint s;\nprint f(1);";
+        assert!(warnings(source).is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+//! Interpreter of lowered programs, which runs them exactly as the
+//! `Evaluator` runs the syntax tree, so that each can check the other.
+//! It has no step, output or time limits and no hooks.
+
+use crate::ast::{BinaryOperator, UnaryOperator};
+use crate::builtins;
+use crate::evaluator::{element_position, EvalResult, MAX_CALL_DEPTH};
+use crate::ir::{Function, InstructionKind, Module, Operand, TerminatorKind, Var};
+use crate::utils::{EvalError, IntegerWidth, Span, Type, Value};
+use std::io::{stdin, stdout, BufRead, BufReader, Write};
+use std::rc::Rc;
+
+pub struct Interpreter {
+    module: Rc<Module>,
+    /// Values of the variables of `main` defined before it runs.
+    globals: Vec<Option<Value>>,
+    depth: usize,
+    current_span: Span,
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    string_limit: Option<usize>,
+    integer_width: IntegerWidth,
+}
+
+/// Values of the locals of a function being run.
+struct Frame<'a> {
+    function: &'a Function,
+    values: Vec<Option<Value>>,
+}
+
+impl<'a> Frame<'a> {
+    fn read(&self, operand: &Operand) -> EvalResult<Value> {
+        match operand {
+            Operand::Constant(val) => Ok(val.clone()),
+            Operand::Var(var) => match &self.values[var.0] {
+                Some(val) => Ok(val.clone()),
+                None => Err(EvalError::VariableNotInitialized(self.name(*var))),
+            },
+        }
+    }
+
+    fn write(&mut self, var: Var, val: Value) {
+        self.values[var.0] = Some(val);
+    }
+
+    fn name(&self, var: Var) -> String {
+        self.function.locals[var.0].name.clone().unwrap_or_default()
+    }
+
+    fn type_of(&self, var: Var) -> &Type {
+        &self.function.locals[var.0].type_def
+    }
+}
+
+impl Interpreter {
+    pub fn new(module: Module) -> Self {
+        let globals = vec![None; module.main.locals.len()];
+        Interpreter {
+            module: Rc::new(module),
+            globals,
+            depth: 0,
+            current_span: Span::default(),
+            input: Box::new(BufReader::new(stdin())),
+            output: Box::new(stdout()),
+            string_limit: None,
+            integer_width: IntegerWidth::default(),
+        }
+    }
+
+    /// Replaces stdin and stdout as the source for `read` and the sink for
+    /// `print` and assertion messages.
+    pub fn with_io(mut self, input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        self.input = input;
+        self.output = output;
+        self
+    }
+
+    /// Fails with `EvalError::StringLimitExceeded` when concatenation or
+    /// `read` produces a string longer than `limit` bytes.
+    pub fn with_string_limit(mut self, limit: usize) -> Self {
+        self.string_limit = Some(limit);
+        self
+    }
+
+    /// Fails with `EvalError::IntegerOverflow` when arithmetic or `read`
+    /// produces an integer that does not fit in `width`.
+    pub fn with_integer_width(mut self, width: IntegerWidth) -> Self {
+        self.integer_width = width;
+        self
+    }
+
+    /// Gives the global variable `id`, declared with
+    /// `Lowering::with_variable`, its value before the program runs.
+    pub fn define_variable(&mut self, id: &str, val: Value) -> EvalResult<()> {
+        let locals = &self.module.main.locals;
+        match locals
+            .iter()
+            .position(|local| local.name.as_deref() == Some(id))
+        {
+            Some(index) => {
+                self.globals[index] = Some(val);
+                Ok(())
+            }
+            None => Err(EvalError::VariableNotInitialized(id.to_string())),
+        }
+    }
+
+    /// Span of the statement being run, or of the one that failed once
+    /// `run_program` has returned an error.
+    pub fn current_span(&self) -> Span {
+        self.current_span
+    }
+
+    pub fn run_program(&mut self) -> EvalResult<()> {
+        let module = Rc::clone(&self.module);
+        let values = std::mem::take(&mut self.globals);
+        self.run(&module.main, values).map(|_| ())
+    }
+
+    /// Runs `function` from its entry block, returning its value.
+    fn run(
+        &mut self,
+        function: &Function,
+        values: Vec<Option<Value>>,
+    ) -> EvalResult<Option<Value>> {
+        let mut frame = Frame { function, values };
+        let mut block = &function.blocks[0];
+        loop {
+            for instruction in &block.instructions {
+                self.current_span = instruction.span;
+                self.execute(&mut frame, &instruction.kind)?;
+            }
+            self.current_span = block.terminator.span;
+            let next = match &block.terminator.kind {
+                TerminatorKind::Jump(target) => *target,
+                TerminatorKind::Branch(condition, then, otherwise) => {
+                    match frame.read(condition)? {
+                        Value::Bool(true) => *then,
+                        Value::Bool(false) => *otherwise,
+                        _ => return Err(EvalError::MismatchedTypes),
+                    }
+                }
+                TerminatorKind::Return(value) => {
+                    return value.as_ref().map(|value| frame.read(value)).transpose();
+                }
+            };
+            block = &function.blocks[next];
+        }
+    }
+
+    fn execute(&mut self, frame: &mut Frame, instruction: &InstructionKind) -> EvalResult<()> {
+        match instruction {
+            InstructionKind::Declare(var) => {
+                frame.values[var.0] = frame.type_of(*var).default_value();
+            }
+            InstructionKind::Copy(dest, source) => {
+                let val = frame.read(source)?;
+                frame.write(*dest, val);
+            }
+            InstructionKind::Unary(dest, UnaryOperator::Not, operand) => {
+                match frame.read(operand)? {
+                    Value::Bool(boolean) => frame.write(*dest, Value::Bool(!boolean)),
+                    _ => return Err(EvalError::MismatchedTypes),
+                }
+            }
+            InstructionKind::Binary(dest, left, op, right) => {
+                let left = frame.read(left)?;
+                let right = frame.read(right)?;
+                let val = self.binary(left, op, right)?;
+                frame.write(*dest, val);
+            }
+            InstructionKind::Load(dest, array, index) => {
+                let index = integer(frame.read(index)?)?;
+                match &frame.values[array.0] {
+                    Some(Value::Array(values)) => {
+                        let val = values[element_position(index, values.len())?].clone();
+                        frame.write(*dest, val);
+                    }
+                    _ => return Err(EvalError::MismatchedTypes),
+                }
+            }
+            InstructionKind::Store(array, index, value) => {
+                let index = integer(frame.read(index)?)?;
+                let val = frame.read(value)?;
+                match &mut frame.values[array.0] {
+                    Some(Value::Array(values)) => {
+                        let position = element_position(index, values.len())?;
+                        values[position] = val;
+                    }
+                    _ => return Err(EvalError::MismatchedTypes),
+                }
+            }
+            InstructionKind::Call(dest, name, args) => {
+                let mut values = Vec::new();
+                for arg in args {
+                    values.push(frame.read(arg)?);
+                }
+                if let Some(val) = self.call(name, values)? {
+                    if let Some(dest) = dest {
+                        frame.write(*dest, val);
+                    }
+                }
+            }
+            InstructionKind::Print(value) => {
+                let text = frame.read(value)?.to_string();
+                self.write_output(&text)?;
+            }
+            InstructionKind::Assert(condition, text) => match frame.read(condition)? {
+                Value::Bool(false) => {
+                    self.write_output(&format!("Assertion failed: {}\n", text))?
+                }
+                Value::Bool(true) => {}
+                _ => return Err(EvalError::MismatchedTypes),
+            },
+            InstructionKind::Read(var) => {
+                let input = self.get_input()?;
+                let val = match frame.type_of(*var) {
+                    Type::String => self.checked_string(input)?,
+                    Type::Integer => match input.trim().parse::<i64>() {
+                        Ok(int) => self.checked(Some(int))?,
+                        Err(_) => return Err(EvalError::MismatchedTypes),
+                    },
+                    _ => return Err(EvalError::MismatchedTypes),
+                };
+                frame.write(*var, val);
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls a procedure or function with its arguments, returning the
+    /// function's value.
+    fn call(&mut self, name: &str, args: Vec<Value>) -> EvalResult<Option<Value>> {
+        if builtins::find(name).is_some() {
+            let val = match builtins::call(name, args)? {
+                Value::Integer(int) => self.checked(Some(int))?,
+                Value::String(string) => self.checked_string(string)?,
+                val => val,
+            };
+            return Ok(Some(val));
+        }
+        let module = Rc::clone(&self.module);
+        let function = match module.function(name) {
+            Some(function) => function,
+            None => return Err(EvalError::UndeclaredRoutine(name.to_string())),
+        };
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(EvalError::CallDepthExceeded(MAX_CALL_DEPTH));
+        }
+        let span = self.current_span;
+        let mut values = vec![None; function.locals.len()];
+        for (param, val) in function.parameters.iter().zip(args) {
+            values[param.0] = Some(val);
+        }
+        self.depth += 1;
+        let value = self.run(function, values)?;
+        self.depth -= 1;
+        self.current_span = span;
+        match (&function.return_type, value) {
+            (Some(_), None) => Err(EvalError::MissingReturn(name.to_string())),
+            (_, value) => Ok(value),
+        }
+    }
+
+    fn binary(&self, left: Value, op: &BinaryOperator, right: Value) -> EvalResult<Value> {
+        match (left, right) {
+            (Value::Integer(val1), Value::Integer(val2)) => match op {
+                BinaryOperator::Plus => self.checked(val1.checked_add(val2)),
+                BinaryOperator::Minus => self.checked(val1.checked_sub(val2)),
+                BinaryOperator::Multiplication => self.checked(val1.checked_mul(val2)),
+                BinaryOperator::Division if val2 == 0 => Err(EvalError::DivisionByZero),
+                BinaryOperator::Division => self.checked(val1.checked_div(val2)),
+                BinaryOperator::Equals => Ok(Value::Bool(val1 == val2)),
+                BinaryOperator::LessThan => Ok(Value::Bool(val1 < val2)),
+                BinaryOperator::GreaterThan => Ok(Value::Bool(val1 > val2)),
+                _ => Err(EvalError::UnsupportedOperation),
+            },
+            (Value::Bool(bool1), Value::Bool(bool2)) => match op {
+                BinaryOperator::And => Ok(Value::Bool(bool1 && bool2)),
+                BinaryOperator::Equals => Ok(Value::Bool(bool1 == bool2)),
+                BinaryOperator::LessThan => Ok(Value::Bool(!bool1 & bool2)),
+                BinaryOperator::GreaterThan => Ok(Value::Bool(bool1 & !bool2)),
+                _ => Err(EvalError::UnsupportedOperation),
+            },
+            (Value::String(str1), Value::String(str2)) => match op {
+                BinaryOperator::Plus => self.checked_string(str1 + &str2),
+                BinaryOperator::Equals => Ok(Value::Bool(str1 == str2)),
+                BinaryOperator::LessThan => Ok(Value::Bool(str1 < str2)),
+                BinaryOperator::GreaterThan => Ok(Value::Bool(str1 > str2)),
+                _ => Err(EvalError::UnsupportedOperation),
+            },
+            _ => Err(EvalError::MismatchedTypes),
+        }
+    }
+
+    fn get_input(&mut self) -> EvalResult<String> {
+        let mut input = String::new();
+        match self.input.read_line(&mut input) {
+            Ok(_) => Ok(input),
+            Err(err) => Err(EvalError::IOError(err.to_string())),
+        }
+    }
+
+    fn write_output(&mut self, text: &str) -> EvalResult<()> {
+        let io_error = |err: std::io::Error| EvalError::IOError(err.to_string());
+        self.output.write_all(text.as_bytes()).map_err(io_error)?;
+        self.output.flush().map_err(io_error)
+    }
+
+    fn checked(&self, result: Option<i64>) -> EvalResult<Value> {
+        match result {
+            Some(int) if self.integer_width.contains(int) => Ok(Value::Integer(int)),
+            _ => Err(EvalError::IntegerOverflow),
+        }
+    }
+
+    fn checked_string(&self, string: String) -> EvalResult<Value> {
+        match self.string_limit {
+            Some(limit) if string.len() > limit => Err(EvalError::StringLimitExceeded(limit)),
+            _ => Ok(Value::String(string)),
+        }
+    }
+}
+
+fn integer(val: Value) -> EvalResult<i64> {
+    match val {
+        Value::Integer(int) => Ok(int),
+        _ => Err(EvalError::MismatchedTypes),
+    }
+}
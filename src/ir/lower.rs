@@ -0,0 +1,554 @@
+//! Lowering of a checked program into the intermediate representation.
+
+use crate::ast::{
+    BinaryOperator, Expression, ExpressionKind, Program, Routine, Statement, StatementKind,
+};
+use crate::builtins::{self, SIZE};
+use crate::checker::{binary_result_type, SpannedCheckError};
+use crate::ir::{
+    Block, BlockId, Function, Instruction, InstructionKind, Local, Module, Operand, Terminator,
+    TerminatorKind, Var, MAIN,
+};
+use crate::utils::{CheckError, IntegerWidth, Span, Type, Value};
+use std::collections::{HashMap, HashSet};
+
+type Signature = (Vec<Type>, Option<Type>);
+
+pub struct Lowering {
+    variables: Vec<(String, Type)>,
+}
+
+impl Default for Lowering {
+    fn default() -> Self {
+        Lowering::new()
+    }
+}
+
+impl Lowering {
+    pub fn new() -> Self {
+        Lowering {
+            variables: Vec::new(),
+        }
+    }
+
+    /// Declares a global variable defined by the host, which
+    /// `Interpreter::define_variable` gives its value.
+    pub fn with_variable(mut self, name: &str, type_def: Type) -> Self {
+        self.variables.push((name.to_string(), type_def));
+        self
+    }
+
+    /// The module of `program`, which must have passed the checker. Calls
+    /// to host functions, which the module cannot hold, are errors.
+    pub fn lower_program(&self, program: &Program) -> Result<Module, SpannedCheckError> {
+        let mut routines = HashMap::new();
+        for statement in &program.statements {
+            if let StatementKind::Routine(routine) = &statement.kind {
+                let parameters = routine
+                    .parameters
+                    .iter()
+                    .map(|param| param.type_def.clone())
+                    .collect();
+                let signature = (parameters, routine.return_type.clone());
+                routines.insert(routine.name.clone(), signature);
+            }
+        }
+
+        let mut functions = Vec::new();
+        for statement in &program.statements {
+            if let StatementKind::Routine(routine) = &statement.kind {
+                functions.push(lower_routine(routine, &routines, statement.span)?);
+            }
+        }
+        let mut builder = Builder::new(&routines);
+        for (name, type_def) in &self.variables {
+            builder.declare(name, type_def);
+        }
+        builder.lower_block(&program.statements)?;
+        let main = builder.finish(MAIN, Vec::new(), None);
+        Ok(Module { functions, main })
+    }
+}
+
+fn lower_routine(
+    routine: &Routine,
+    routines: &HashMap<String, Signature>,
+    span: Span,
+) -> Result<Function, SpannedCheckError> {
+    let mut builder = Builder::new(routines);
+    builder.span = span;
+    let parameters = routine
+        .parameters
+        .iter()
+        .map(|param| builder.declare(&param.name, &param.type_def))
+        .collect();
+    builder.lower_block(&routine.body)?;
+    Ok(builder.finish(&routine.name, parameters, routine.return_type.clone()))
+}
+
+/// A block whose terminator is still missing while its function is built.
+#[derive(Default)]
+struct OpenBlock {
+    instructions: Vec<Instruction>,
+    terminator: Option<Terminator>,
+}
+
+type LowerResult<T> = Result<T, SpannedCheckError>;
+
+/// Builder of one function, adding instructions to the current block.
+struct Builder<'a> {
+    routines: &'a HashMap<String, Signature>,
+    locals: Vec<Local>,
+    scopes: Vec<HashMap<String, Var>>,
+    /// Variables declared without a value, which may be unassigned when
+    /// read.
+    unassigned: HashSet<Var>,
+    blocks: Vec<OpenBlock>,
+    current: BlockId,
+    /// Span of the statement being lowered.
+    span: Span,
+}
+
+impl<'a> Builder<'a> {
+    fn new(routines: &'a HashMap<String, Signature>) -> Self {
+        Builder {
+            routines,
+            locals: Vec::new(),
+            scopes: vec![HashMap::new()],
+            unassigned: HashSet::new(),
+            blocks: vec![OpenBlock::default()],
+            current: 0,
+            span: Span::default(),
+        }
+    }
+
+    /// The function built so far, returning no value at its end and without
+    /// the blocks that cannot be reached.
+    fn finish(mut self, name: &str, parameters: Vec<Var>, return_type: Option<Type>) -> Function {
+        self.terminate(TerminatorKind::Return(None));
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut pending = vec![0];
+        while let Some(id) = pending.pop() {
+            if !reachable[id] {
+                reachable[id] = true;
+                if let Some(terminator) = &self.blocks[id].terminator {
+                    pending.extend(terminator.successors());
+                }
+            }
+        }
+        let mut renumbered = Vec::new();
+        let mut count = 0;
+        for &reached in &reachable {
+            renumbered.push(count);
+            if reached {
+                count += 1;
+            }
+        }
+        let blocks = self
+            .blocks
+            .into_iter()
+            .zip(reachable)
+            .filter(|(_, reached)| *reached)
+            .map(|(block, _)| {
+                let mut terminator = block.terminator.expect("every block is terminated");
+                terminator.kind = match terminator.kind {
+                    TerminatorKind::Jump(target) => TerminatorKind::Jump(renumbered[target]),
+                    TerminatorKind::Branch(condition, then, otherwise) => {
+                        TerminatorKind::Branch(condition, renumbered[then], renumbered[otherwise])
+                    }
+                    kind => kind,
+                };
+                Block {
+                    instructions: block.instructions,
+                    terminator,
+                }
+            })
+            .collect();
+        Function {
+            name: name.to_string(),
+            parameters,
+            return_type,
+            locals: self.locals,
+            blocks,
+        }
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(OpenBlock::default());
+        self.blocks.len() - 1
+    }
+
+    fn emit(&mut self, kind: InstructionKind) {
+        let instruction = Instruction {
+            kind,
+            span: self.span,
+        };
+        self.blocks[self.current].instructions.push(instruction);
+    }
+
+    /// Ends the current block, unless it already ended.
+    fn terminate(&mut self, kind: TerminatorKind) {
+        let block = &mut self.blocks[self.current];
+        if block.terminator.is_none() {
+            block.terminator = Some(Terminator {
+                kind,
+                span: self.span,
+            });
+        }
+    }
+
+    fn temporary(&mut self, type_def: Type) -> Var {
+        self.locals.push(Local {
+            name: None,
+            type_def,
+        });
+        Var(self.locals.len() - 1)
+    }
+
+    fn declare(&mut self, id: &str, type_def: &Type) -> Var {
+        self.locals.push(Local {
+            name: Some(id.to_string()),
+            type_def: type_def.clone(),
+        });
+        let var = Var(self.locals.len() - 1);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(id.to_string(), var);
+        }
+        var
+    }
+
+    fn lookup(&self, id: &str) -> LowerResult<(Var, Type)> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(id)) {
+            Some(var) => Ok((*var, self.locals[var.0].type_def.clone())),
+            None => Err((CheckError::UndeclaredVariable(id.to_string()), self.span)),
+        }
+    }
+
+    fn lower_block(&mut self, statements: &[Statement]) -> LowerResult<()> {
+        for statement in statements {
+            self.span = statement.span;
+            self.lower_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    fn lower_statement(&mut self, statement: &Statement) -> LowerResult<()> {
+        match &statement.kind {
+            StatementKind::VarInitialization(id, type_def) => {
+                let var = self.declare(id, type_def);
+                if !matches!(type_def, Type::Array(..)) {
+                    self.unassigned.insert(var);
+                }
+                self.emit(InstructionKind::Declare(var));
+            }
+            StatementKind::NewAssignment(id, type_def, exp) => {
+                let (value, _) = self.lower_expression(exp)?;
+                let var = self.declare(id, type_def);
+                self.emit(InstructionKind::Copy(var, value));
+            }
+            StatementKind::Assignment(id, exp) => {
+                let (var, _) = self.lookup(id)?;
+                let (value, _) = self.lower_expression(exp)?;
+                self.emit(InstructionKind::Copy(var, value));
+            }
+            StatementKind::IndexAssignment(id, index, exp) => {
+                let (index, _) = self.lower_expression(index)?;
+                let (value, _) = self.lower_expression(exp)?;
+                let (array, _) = self.lookup(id)?;
+                self.emit(InstructionKind::Store(array, index, value));
+            }
+            StatementKind::Print(exp) => {
+                let (value, _) = self.lower_expression(exp)?;
+                self.emit(InstructionKind::Print(value));
+            }
+            StatementKind::Assert(exp) => {
+                let (value, _) = self.lower_expression(exp)?;
+                self.emit(InstructionKind::Assert(value, exp.to_string()));
+            }
+            StatementKind::Read(id) => {
+                let (var, _) = self.lookup(id)?;
+                self.emit(InstructionKind::Read(var));
+            }
+            StatementKind::For(id, start, end, stmts) => self.lower_for(id, start, end, stmts)?,
+            // Lowered on their own by `lower_program`.
+            StatementKind::Routine(_) => {}
+            StatementKind::Call(name, args) => {
+                self.lower_call(name, args)?;
+            }
+            StatementKind::Return(exp) => {
+                let value = match exp {
+                    Some(exp) => Some(self.lower_expression(exp)?.0),
+                    None => None,
+                };
+                self.terminate(TerminatorKind::Return(value));
+                // Statements after the `return` go into a block that is
+                // never reached.
+                self.current = self.new_block();
+            }
+        }
+        Ok(())
+    }
+
+    /// Lowers a loop into a header testing the counter, the body, a block
+    /// stepping the counter and the block after the loop. The body ends the
+    /// loop when the counter reaches the end, so that it never steps past
+    /// the largest integer.
+    fn lower_for(
+        &mut self,
+        id: &str,
+        start: &Expression,
+        end: &Expression,
+        stmts: &[Statement],
+    ) -> LowerResult<()> {
+        let span = self.span;
+        let (start, _) = self.lower_expression(start)?;
+        let (end, _) = self.lower_expression(end)?;
+        // The end is evaluated once, before the body can change it.
+        let end = match end {
+            Operand::Var(var) if self.locals[var.0].name.is_some() => {
+                let last = self.temporary(Type::Integer);
+                self.emit(InstructionKind::Copy(last, Operand::Var(var)));
+                Operand::Var(last)
+            }
+            end => end,
+        };
+        let (var, _) = self.lookup(id)?;
+        // A loop over constants that runs at least once skips the first
+        // test, so that the graph has no path around its body.
+        let runs = match (&start, &end) {
+            (Operand::Constant(Value::Integer(start)), Operand::Constant(Value::Integer(end))) => {
+                start <= end
+            }
+            _ => false,
+        };
+        let counter = self.temporary(Type::Integer);
+        self.emit(InstructionKind::Copy(counter, start));
+
+        let header = self.new_block();
+        let body = self.new_block();
+        let next = self.new_block();
+        let exit = self.new_block();
+        self.terminate(TerminatorKind::Jump(if runs { body } else { header }));
+
+        self.current = header;
+        let done = self.temporary(Type::Boolean);
+        self.emit(InstructionKind::Binary(
+            done,
+            Operand::Var(counter),
+            BinaryOperator::GreaterThan,
+            end.clone(),
+        ));
+        self.terminate(TerminatorKind::Branch(Operand::Var(done), exit, body));
+
+        self.current = body;
+        self.emit(InstructionKind::Copy(var, Operand::Var(counter)));
+        self.scopes.push(HashMap::new());
+        self.lower_block(stmts)?;
+        self.scopes.pop();
+        self.span = span;
+        let more = self.temporary(Type::Boolean);
+        self.emit(InstructionKind::Binary(
+            more,
+            Operand::Var(counter),
+            BinaryOperator::LessThan,
+            end,
+        ));
+        self.terminate(TerminatorKind::Branch(Operand::Var(more), next, exit));
+
+        self.current = next;
+        let one = Operand::Constant(Value::Integer(1));
+        self.emit(InstructionKind::Binary(
+            counter,
+            Operand::Var(counter),
+            BinaryOperator::Plus,
+            one,
+        ));
+        self.terminate(TerminatorKind::Jump(header));
+
+        self.current = exit;
+        Ok(())
+    }
+
+    /// The operand holding the value of `exp`, and its type.
+    fn lower_expression(&mut self, exp: &Expression) -> LowerResult<(Operand, Type)> {
+        match &exp.kind {
+            ExpressionKind::IntegerConstant(int) => {
+                Ok((Operand::Constant(Value::Integer(*int)), Type::Integer))
+            }
+            ExpressionKind::StringValue(s) => {
+                Ok((Operand::Constant(Value::String(s.clone())), Type::String))
+            }
+            ExpressionKind::Boolean(b) => Ok((Operand::Constant(Value::Bool(*b)), Type::Boolean)),
+            ExpressionKind::Identifier(id) => {
+                let (var, type_def) = self.lookup(id)?;
+                if !self.unassigned.contains(&var) {
+                    return Ok((Operand::Var(var), type_def));
+                }
+                // Read now, so that an unassigned variable fails before the
+                // rest of the expression runs.
+                let value = self.temporary(type_def.clone());
+                self.emit(InstructionKind::Copy(value, Operand::Var(var)));
+                Ok((Operand::Var(value), type_def))
+            }
+            ExpressionKind::Unary(op, operand) => {
+                let (operand, _) = self.lower_expression(operand)?;
+                let value = self.temporary(Type::Boolean);
+                self.emit(InstructionKind::Unary(value, op.clone(), operand));
+                Ok((Operand::Var(value), Type::Boolean))
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                let (left, type_def) = self.lower_expression(left)?;
+                let (right, _) = self.lower_expression(right)?;
+                if let Some(int) = fold(&left, op, &right) {
+                    return Ok((Operand::Constant(Value::Integer(int)), Type::Integer));
+                }
+                let result = match binary_result_type(op, &type_def) {
+                    Some(result) => result,
+                    None => {
+                        let err = CheckError::UnsupportedOperation(op.to_string(), type_def);
+                        return Err((err, exp.span));
+                    }
+                };
+                let value = self.temporary(result.clone());
+                self.emit(InstructionKind::Binary(value, left, op.clone(), right));
+                Ok((Operand::Var(value), result))
+            }
+            ExpressionKind::Call(name, args) => match self.lower_call(name, args)? {
+                Some(result) => Ok(result),
+                None => Err((CheckError::NoReturnValue(name.clone()), exp.span)),
+            },
+            ExpressionKind::Index(id, index) => {
+                let (index, _) = self.lower_expression(index)?;
+                let (array, type_def) = self.lookup(id)?;
+                let element = match type_def {
+                    Type::Array(_, element) => *element,
+                    type_def => {
+                        let err = CheckError::UnsupportedOperation("[]".to_string(), type_def);
+                        return Err((err, exp.span));
+                    }
+                };
+                let value = self.temporary(element.clone());
+                self.emit(InstructionKind::Load(value, array, index));
+                Ok((Operand::Var(value), element))
+            }
+        }
+    }
+
+    /// Lowers a call, returning the result of a function.
+    fn lower_call(
+        &mut self,
+        name: &str,
+        args: &[Expression],
+    ) -> LowerResult<Option<(Operand, Type)>> {
+        // The size of a variable is known from its type.
+        if let (SIZE, [array]) = (name, args) {
+            if let ExpressionKind::Identifier(id) = &array.kind {
+                if let (_, Type::Array(size, _)) = self.lookup(id)? {
+                    let size = Operand::Constant(Value::Integer(size as i64));
+                    return Ok(Some((size, Type::Integer)));
+                }
+            }
+        }
+        let return_type = match (builtins::find(name), self.routines.get(name)) {
+            (Some(builtin), _) => Some(builtin.return_type.clone()),
+            (None, Some((_, return_type))) => return_type.clone(),
+            (None, None) => {
+                let err = CheckError::UndeclaredRoutine(name.to_string());
+                return Err((err, self.span));
+            }
+        };
+        let mut operands = Vec::new();
+        for arg in args {
+            operands.push(self.lower_expression(arg)?.0);
+        }
+        let dest = return_type.clone().map(|type_def| self.temporary(type_def));
+        self.emit(InstructionKind::Call(dest, name.to_string(), operands));
+        Ok(dest
+            .zip(return_type)
+            .map(|(dest, type_def)| (Operand::Var(dest), type_def)))
+    }
+}
+
+/// Integer arithmetic on constants, done while lowering when the result
+/// fits in every integer width, so that `size(a) - 1` is a constant.
+fn fold(left: &Operand, op: &BinaryOperator, right: &Operand) -> Option<i64> {
+    let (left, right) = match (left, right) {
+        (Operand::Constant(Value::Integer(left)), Operand::Constant(Value::Integer(right))) => {
+            (*left, *right)
+        }
+        _ => return None,
+    };
+    let result = match op {
+        BinaryOperator::Plus => left.checked_add(right),
+        BinaryOperator::Minus => left.checked_sub(right),
+        BinaryOperator::Multiplication => left.checked_mul(right),
+        _ => None,
+    }?;
+    IntegerWidth::I32.contains(result).then_some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::lower::Lowering;
+    use crate::ir::{InstructionKind, TerminatorKind};
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    #[test]
+    fn lower_loops_into_blocks() {
+        let source = "var i : int;\nvar n : int := 3;\nvar total : int := 0;\n\
+                      for i in 1..n do\n    total := total + i;\nend for;\nprint total;";
+        let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+        let module = Lowering::new().lower_program(&program).unwrap();
+        let successors: Vec<Vec<usize>> = module
+            .main
+            .blocks
+            .iter()
+            .map(|block| block.terminator.successors())
+            .collect();
+        // Entry, header, body, step and exit.
+        assert_eq!(
+            successors,
+            vec![vec![1], vec![4, 2], vec![3, 4], vec![1], vec![]]
+        );
+        assert_eq!(module.main.predecessors()[1], vec![0, 3]);
+        let exit = &module.main.blocks[4];
+        assert!(matches!(
+            exit.instructions[0].kind,
+            InstructionKind::Print(_)
+        ));
+        assert_eq!(exit.terminator.kind, TerminatorKind::Return(None));
+    }
+
+    #[test]
+    fn enter_constant_loops() {
+        let source = "var a : array[3] of int;\nvar i : int;\nfor i in a do\n    \
+                      print a[i];\nend for;";
+        let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+        let module = Lowering::new().lower_program(&program).unwrap();
+        // `size(a) - 1` is folded, and the loop runs at least once.
+        assert_eq!(
+            module.main.blocks[0].terminator.kind,
+            TerminatorKind::Jump(2)
+        );
+        assert_eq!(module.main.predecessors()[1], vec![3]);
+    }
+
+    #[test]
+    fn drop_unreachable_blocks() {
+        let source = "function f(n : int) : int\n    var i : int;\n    \
+                      for i in 1..n do\n        return i;\n        print i;\n    end for;\n    \
+                      return 0;\nend function;\nprint f(2);";
+        let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+        let module = Lowering::new().lower_program(&program).unwrap();
+        let function = module.function("f").unwrap();
+        // The body returns before it could print or step the counter.
+        assert_eq!(function.blocks.len(), 4);
+        let printed = function
+            .blocks
+            .iter()
+            .flat_map(|block| &block.instructions)
+            .any(|instruction| matches!(instruction.kind, InstructionKind::Print(_)));
+        assert!(!printed);
+    }
+}
@@ -0,0 +1,351 @@
+//! Intermediate representation between the checked syntax tree and the
+//! analyses and backends that would otherwise each walk it on their own.
+//!
+//! Every procedure and function, and the statements outside them, become a
+//! `Function` of basic blocks. A block is a list of three-address
+//! instructions, each writing at most one variable from operands that are
+//! variables or constants, and ends in a terminator that jumps, branches or
+//! returns. The blocks and their terminators form the control-flow graph: a
+//! `for` loop becomes a header block testing the counter, the body, a block
+//! stepping the counter and an exit block. Intermediate results live in
+//! temporaries, which are variables without a name.
+//!
+//! `Lowering` builds a `Module` from a program and `Interpreter` runs it.
+//! `analysis` holds the static analyses that work on modules.
+
+pub mod analysis;
+pub mod interpreter;
+pub mod lower;
+
+use crate::ast::{BinaryOperator, UnaryOperator};
+use crate::utils::{Span, Type, Value};
+use std::fmt;
+
+/// Name of the function holding the statements outside of procedures and
+/// functions. It cannot clash with a Mini-PL identifier.
+pub const MAIN: &str = "<main>";
+
+/// Index of a block in `Function::blocks`. The entry block is 0.
+pub type BlockId = usize;
+
+/// A variable or temporary, indexing `Function::locals`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Var(pub usize);
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Local {
+    /// Name in the source, or `None` for a temporary.
+    pub name: Option<String>,
+    pub type_def: Type,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Operand {
+    Var(Var),
+    Constant(Value),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Instruction {
+    pub kind: InstructionKind,
+    /// Span of the statement the instruction was lowered from.
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum InstructionKind {
+    /// Starts the lifetime of a variable declared without a value: arrays
+    /// are filled with their default and other variables are unassigned.
+    Declare(Var),
+    /// `dest := source`, copying arrays.
+    Copy(Var, Operand),
+    Unary(Var, UnaryOperator, Operand),
+    Binary(Var, Operand, BinaryOperator, Operand),
+    /// `dest := array[index]`
+    Load(Var, Var, Operand),
+    /// `array[index] := value`
+    Store(Var, Operand, Operand),
+    /// A call of a procedure, function or built-in function, storing the
+    /// result of functions.
+    Call(Option<Var>, String, Vec<Operand>),
+    Print(Operand),
+    /// Prints `Assertion failed: ` and the text of the condition when the
+    /// condition is false.
+    Assert(Operand, String),
+    Read(Var),
+}
+
+impl Instruction {
+    /// The variable the instruction writes, if any. `Store` changes its
+    /// array without replacing it.
+    pub fn definition(&self) -> Option<Var> {
+        match &self.kind {
+            InstructionKind::Declare(dest)
+            | InstructionKind::Copy(dest, _)
+            | InstructionKind::Unary(dest, _, _)
+            | InstructionKind::Binary(dest, _, _, _)
+            | InstructionKind::Load(dest, _, _)
+            | InstructionKind::Read(dest) => Some(*dest),
+            InstructionKind::Call(dest, _, _) => *dest,
+            _ => None,
+        }
+    }
+
+    /// The variables the instruction reads, in the order it reads them.
+    pub fn uses(&self) -> Vec<Var> {
+        let operands: Vec<&Operand> = match &self.kind {
+            InstructionKind::Declare(_) | InstructionKind::Read(_) => Vec::new(),
+            InstructionKind::Copy(_, source) | InstructionKind::Unary(_, _, source) => {
+                vec![source]
+            }
+            InstructionKind::Binary(_, left, _, right) => vec![left, right],
+            InstructionKind::Load(_, array, index) => {
+                return variables(&[index]).into_iter().chain([*array]).collect();
+            }
+            InstructionKind::Store(array, index, value) => {
+                return variables(&[index, value])
+                    .into_iter()
+                    .chain([*array])
+                    .collect();
+            }
+            InstructionKind::Call(_, _, args) => args.iter().collect(),
+            InstructionKind::Print(value) | InstructionKind::Assert(value, _) => vec![value],
+        };
+        variables(&operands)
+    }
+}
+
+fn variables(operands: &[&Operand]) -> Vec<Var> {
+    operands
+        .iter()
+        .filter_map(|operand| match operand {
+            Operand::Var(var) => Some(*var),
+            Operand::Constant(_) => None,
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Terminator {
+    pub kind: TerminatorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TerminatorKind {
+    Jump(BlockId),
+    /// Continues with the first block if the condition is true, with the
+    /// second otherwise.
+    Branch(Operand, BlockId, BlockId),
+    /// Ends the function. A function returning no value fails.
+    Return(Option<Operand>),
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockId> {
+        match &self.kind {
+            TerminatorKind::Jump(target) => vec![*target],
+            TerminatorKind::Branch(_, then, otherwise) => vec![*then, *otherwise],
+            TerminatorKind::Return(_) => Vec::new(),
+        }
+    }
+
+    pub fn uses(&self) -> Vec<Var> {
+        match &self.kind {
+            TerminatorKind::Branch(condition, _, _) => variables(&[condition]),
+            TerminatorKind::Return(Some(value)) => variables(&[value]),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Block {
+    pub instructions: Vec<Instruction>,
+    pub terminator: Terminator,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Function {
+    pub name: String,
+    pub parameters: Vec<Var>,
+    pub return_type: Option<Type>,
+    pub locals: Vec<Local>,
+    /// Blocks reachable from the entry block, which comes first.
+    pub blocks: Vec<Block>,
+}
+
+impl Function {
+    /// Blocks that continue with each block, in the order of `blocks`.
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut predecessors = vec![Vec::new(); self.blocks.len()];
+        for (id, block) in self.blocks.iter().enumerate() {
+            for successor in block.terminator.successors() {
+                predecessors[successor].push(id);
+            }
+        }
+        predecessors
+    }
+
+    /// Names of the locals in the text format: variables by their name,
+    /// numbered from the second variable of the same name on, and
+    /// temporaries as `%0`, `%1` and so on.
+    pub fn labels(&self) -> Vec<String> {
+        let mut temporaries = 0;
+        let mut labels: Vec<String> = Vec::new();
+        for (index, local) in self.locals.iter().enumerate() {
+            let label = match &local.name {
+                Some(name) => {
+                    let earlier = self.locals[..index]
+                        .iter()
+                        .filter(|other| other.name.as_ref() == Some(name))
+                        .count();
+                    match earlier {
+                        0 => name.clone(),
+                        _ => format!("{}.{}", name, earlier + 1),
+                    }
+                }
+                None => {
+                    temporaries += 1;
+                    format!("%{}", temporaries - 1)
+                }
+            };
+            labels.push(label);
+        }
+        labels
+    }
+
+    fn parameter_list(&self, labels: &[String]) -> String {
+        self.parameters
+            .iter()
+            .map(|param| format!("{} : {}", labels[param.0], self.locals[param.0].type_def))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+/// A lowered program. `main` holds the statements outside of procedures
+/// and functions.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Module {
+    pub functions: Vec<Function>,
+    pub main: Function,
+}
+
+impl Module {
+    /// The procedure or function `name`.
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for function in &self.functions {
+            writeln!(f, "{}", function)?;
+        }
+        write!(f, "{}", self.main)
+    }
+}
+
+/// The text format of `--emit=ir`, for example
+///
+/// ```text
+/// function double(n : int) : int
+/// b0:
+///     %0 := n * 2
+///     return %0
+/// end function
+/// ```
+///
+/// Blocks after the entry block list their predecessors.
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let labels = self.labels();
+        let label = |var: &Var| labels[var.0].as_str();
+        let operand = |operand: &Operand| match operand {
+            Operand::Var(var) => label(var).to_string(),
+            Operand::Constant(Value::String(s)) => format!("{:?}", s),
+            Operand::Constant(val) => val.to_string(),
+        };
+        let keyword = match (&self.return_type, self.name.as_str()) {
+            (Some(return_type), name) => {
+                let parameters = self.parameter_list(&labels);
+                writeln!(f, "function {}({}) : {}", name, parameters, return_type)?;
+                "function"
+            }
+            (None, MAIN) => {
+                writeln!(f, "program")?;
+                "program"
+            }
+            (None, name) => {
+                writeln!(f, "procedure {}({})", name, self.parameter_list(&labels))?;
+                "procedure"
+            }
+        };
+        let predecessors = self.predecessors();
+        for (id, block) in self.blocks.iter().enumerate() {
+            match id {
+                0 => writeln!(f, "b0:")?,
+                _ => {
+                    let from: Vec<String> = predecessors[id]
+                        .iter()
+                        .map(|pred| format!("b{}", pred))
+                        .collect();
+                    writeln!(f, "b{}: ; from {}", id, from.join(", "))?
+                }
+            }
+            for instruction in &block.instructions {
+                let text = match &instruction.kind {
+                    InstructionKind::Declare(var) => {
+                        format!("declare {} : {}", label(var), self.locals[var.0].type_def)
+                    }
+                    InstructionKind::Copy(dest, source) => {
+                        format!("{} := {}", label(dest), operand(source))
+                    }
+                    InstructionKind::Unary(dest, op, value) => {
+                        format!("{} := {}{}", label(dest), op, operand(value))
+                    }
+                    InstructionKind::Binary(dest, left, op, right) => format!(
+                        "{} := {} {} {}",
+                        label(dest),
+                        operand(left),
+                        op,
+                        operand(right)
+                    ),
+                    InstructionKind::Load(dest, array, index) => {
+                        format!("{} := {}[{}]", label(dest), label(array), operand(index))
+                    }
+                    InstructionKind::Store(array, index, value) => {
+                        format!("{}[{}] := {}", label(array), operand(index), operand(value))
+                    }
+                    InstructionKind::Call(dest, name, args) => {
+                        let args: Vec<String> = args.iter().map(operand).collect();
+                        match dest {
+                            Some(dest) => {
+                                format!("{} := call {}({})", label(dest), name, args.join(", "))
+                            }
+                            None => format!("call {}({})", name, args.join(", ")),
+                        }
+                    }
+                    InstructionKind::Print(value) => format!("print {}", operand(value)),
+                    InstructionKind::Assert(value, text) => {
+                        format!("assert {}, {:?}", operand(value), text)
+                    }
+                    InstructionKind::Read(var) => format!("read {}", label(var)),
+                };
+                writeln!(f, "    {}", text)?;
+            }
+            let text = match &block.terminator.kind {
+                TerminatorKind::Jump(target) => format!("jump b{}", target),
+                TerminatorKind::Branch(condition, then, otherwise) => {
+                    format!("branch {}, b{}, b{}", operand(condition), then, otherwise)
+                }
+                TerminatorKind::Return(Some(value)) => format!("return {}", operand(value)),
+                TerminatorKind::Return(None) => "return".to_string(),
+            };
+            writeln!(f, "    {}", text)?;
+        }
+        writeln!(f, "end {}", keyword)
+    }
+}
@@ -0,0 +1,863 @@
+//! Minimal JSON values and the machine-readable schema of the interpreter's
+//! data structures.
+//!
+//! Every node is an object with a `kind` field naming its variant, and every
+//! token, statement and expression carries a `span`:
+//!
+//! ```text
+//! Position   {"line": 1, "column": 1}
+//! Span       {"start": Position, "end": Position}
+//! Token      {"kind": "Identifier", "value": "x", "span": Span}
+//! Program    {"statements": [Statement]}
+//! Statement  {"kind": "For", "span": Span, "identifier": "x",
+//!             "start": Expression, "end": Expression, "body": [Statement]}
+//!            {"kind": "Routine", "span": Span, "name": "f",
+//!             "parameters": [{"name": "n", "type": "int", "span": Span}],
+//!             "returnType": "int" or null, "body": [Statement]}
+//! Expression {"kind": "Binary", "span": Span, "operator": "+",
+//!             "left": Expression, "right": Expression}
+//! LexError   {"kind": "LexError", "error": "IllegalCharacter", "code": "E0301",
+//!             "message": "Illegal character '$'", "span": Span}
+//! ParseError {"kind": "ParseError", "error": "ExpectedSemiColon", "code": "E0007",
+//!             "message": "Expected ; got )", "token": Token, "span": Span}
+//! CheckError {"kind": "CheckError", "error": "UndeclaredVariable", "code": "E0103",
+//!             "message": "Variable y is not declared", "span": Span}
+//! EvalError  {"kind": "EvalError", "error": "MismatchedTypes", "code": "E0201",
+//!             "message": "Mismatched types", "span": Span}
+//! Warning    {"kind": "Warning", "error": "DivisionByZero", "code": "E0207",
+//!             "message": "Division by zero", "span": Span}, with the
+//!             runtime error of a constant expression or "UninitializedRead"
+//! Lint       {"kind": "Lint", "code": "W0001", "lint": "unused-variable",
+//!             "severity": "warning" or "error",
+//!             "message": "Variable x is never used", "span": Span}
+//! Value      3, true, "text" or [Value]
+//! ```
+//!
+//! `code` is the stable code of the error, see `src/explain.rs`. `value` is
+//! only present on tokens that carry a lexeme (identifiers, integer and
+//! string literals). Errors, warnings and lints may also carry `labels`
+//! (`[{"span": Span, "message": "..."}]`, further places involved),
+//! `notes` (`["..."]`) and `help` (a suggested fix); each field is omitted
+//! when empty. Field names are part of the public interface: new
+//! fields may be added, existing ones are not renamed.
+
+use crate::ast::{Expression, ExpressionKind, Parameter, Program, Statement, StatementKind};
+use crate::explain::Coded;
+use crate::lint::LintWarning;
+use crate::token::Token;
+use crate::utils::{
+    CheckError, EvalError, LexError, ParseError, Position, Span, Type, Value, Warning,
+};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object(fields: Vec<(&str, Json)>) -> Self {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, val)| (key.to_string(), val))
+                .collect(),
+        )
+    }
+
+    pub fn array<T: ToJson>(items: &[T]) -> Self {
+        Json::Array(items.iter().map(ToJson::to_json).collect())
+    }
+
+    pub fn parse(source: &str) -> Result<Json, String> {
+        let mut reader = Reader {
+            chars: source.chars().collect(),
+            position: 0,
+        };
+        let value = reader.read_value()?;
+        reader.skip_whitespace();
+        match reader.peek() {
+            None => Ok(value),
+            Some(ch) => Err(format!("Unexpected character {} after JSON value", ch)),
+        }
+    }
+
+    /// Field of an object, or `None` for missing fields and non-objects.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+struct Reader {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Reader {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek();
+        self.position += 1;
+        ch
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(ch) = self.peek() {
+            if !ch.is_whitespace() {
+                break;
+            }
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(ch) if ch == expected => Ok(()),
+            Some(ch) => Err(format!("Expected {} got {}", expected, ch)),
+            None => Err(format!("Expected {} got end of input", expected)),
+        }
+    }
+
+    fn expect_word(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for ch in word.chars() {
+            self.expect(ch)?;
+        }
+        Ok(value)
+    }
+
+    fn read_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.expect_word("null", Json::Null),
+            Some('t') => self.expect_word("true", Json::Bool(true)),
+            Some('f') => self.expect_word("false", Json::Bool(false)),
+            Some('"') => Ok(Json::String(self.read_string()?)),
+            Some('[') => self.read_array(),
+            Some('{') => self.read_object(),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => self.read_number(),
+            Some(ch) => Err(format!("Unexpected character {}", ch)),
+            None => Err("Unexpected end of input".to_string()),
+        }
+    }
+
+    fn read_number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while let Some(ch) = self.peek() {
+            if !(ch.is_ascii_digit() || "+-.eE".contains(ch)) {
+                break;
+            }
+            self.position += 1;
+        }
+        let lexeme: String = self.chars[start..self.position].iter().collect();
+        match lexeme.parse::<i64>() {
+            Ok(n) => Ok(Json::Number(n)),
+            // Fractions are not part of any message we read; keep the integer part.
+            Err(_) => match lexeme.parse::<f64>() {
+                Ok(n) => Ok(Json::Number(n as i64)),
+                Err(_) => Err(format!("Invalid number {}", lexeme)),
+            },
+        }
+    }
+
+    fn read_hex(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.next().and_then(|ch| ch.to_digit(16));
+            match digit {
+                Some(d) => code = code * 16 + d,
+                None => return Err("Invalid unicode escape".to_string()),
+            }
+        }
+        Ok(code)
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.next() {
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('/') => s.push('/'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('u') => {
+                        let mut code = self.read_hex()?;
+                        if (0xd800..0xdc00).contains(&code) {
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            let low = self.read_hex()?;
+                            code = 0x10000
+                                + ((code - 0xd800) << 10)
+                                + (low.wrapping_sub(0xdc00) & 0x3ff);
+                        }
+                        s.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    _ => return Err("Invalid escape sequence".to_string()),
+                },
+                Some(ch) => s.push(ch),
+                None => return Err("Unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn read_array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.read_value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(items)),
+                _ => return Err("Expected , or ] in array".to_string()),
+            }
+        }
+    }
+
+    fn read_object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.read_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.read_value()?));
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(fields)),
+                _ => return Err("Expected , or } in object".to_string()),
+            }
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(n: i64) -> Self {
+        Json::Number(n)
+    }
+}
+
+impl From<i32> for Json {
+    fn from(n: i32) -> Self {
+        Json::Number(n as i64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(n as i64)
+    }
+}
+
+fn write_escaped(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for ch in s.chars() {
+        match ch {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            ch if (ch as u32) < 0x20 => write!(f, "\\u{:04x}", ch as u32)?,
+            ch => write!(f, "{}", ch)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_escaped(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, val)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_escaped(f, key)?;
+                    write!(f, ":{}", val)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+impl ToJson for Position {
+    fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("line", self.line.into()),
+            ("column", self.column.into()),
+        ])
+    }
+}
+
+impl ToJson for Span {
+    fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("start", self.start.to_json()),
+            ("end", self.end.to_json()),
+        ])
+    }
+}
+
+impl ToJson for Type {
+    fn to_json(&self) -> Json {
+        Json::from(self.to_string())
+    }
+}
+
+impl ToJson for Value {
+    fn to_json(&self) -> Json {
+        match self {
+            Value::Bool(b) => Json::from(*b),
+            Value::String(s) => Json::from(s.as_str()),
+            Value::Integer(int) => Json::from(*int),
+            Value::Array(values) => Json::array(values),
+        }
+    }
+}
+
+fn token_fields(token: &Token) -> Vec<(&'static str, Json)> {
+    let mut fields = vec![("kind", Json::from(token_name(token)))];
+    match token {
+        Token::Identifier(lexeme) | Token::IntegerConstant(lexeme) | Token::StringValue(lexeme) => {
+            fields.push(("value", Json::from(lexeme.as_str())))
+        }
+        _ => {}
+    }
+    fields
+}
+
+impl ToJson for Token {
+    fn to_json(&self) -> Json {
+        Json::object(token_fields(self))
+    }
+}
+
+impl ToJson for (Token, Span) {
+    fn to_json(&self) -> Json {
+        let (token, span) = self;
+        let mut fields = token_fields(token);
+        fields.push(("span", span.to_json()));
+        Json::object(fields)
+    }
+}
+
+impl ToJson for Program {
+    fn to_json(&self) -> Json {
+        Json::object(vec![("statements", Json::array(&self.statements))])
+    }
+}
+
+impl ToJson for Statement {
+    fn to_json(&self) -> Json {
+        let id = |id: &String| Json::from(id.as_str());
+        let mut fields = vec![
+            ("kind", Json::from(statement_name(&self.kind))),
+            ("span", self.span.to_json()),
+        ];
+        match &self.kind {
+            StatementKind::VarInitialization(identifier, type_def) => {
+                fields.push(("identifier", id(identifier)));
+                fields.push(("type", type_def.to_json()));
+            }
+            StatementKind::NewAssignment(identifier, type_def, exp) => {
+                fields.push(("identifier", id(identifier)));
+                fields.push(("type", type_def.to_json()));
+                fields.push(("value", exp.to_json()));
+            }
+            StatementKind::Assignment(identifier, exp) => {
+                fields.push(("identifier", id(identifier)));
+                fields.push(("value", exp.to_json()));
+            }
+            StatementKind::IndexAssignment(identifier, index, exp) => {
+                fields.push(("identifier", id(identifier)));
+                fields.push(("index", index.to_json()));
+                fields.push(("value", exp.to_json()));
+            }
+            StatementKind::Print(exp) => fields.push(("value", exp.to_json())),
+            StatementKind::Assert(exp) => fields.push(("condition", exp.to_json())),
+            StatementKind::Read(identifier) => fields.push(("identifier", id(identifier))),
+            StatementKind::For(identifier, start, end, stmts) => {
+                fields.push(("identifier", id(identifier)));
+                fields.push(("start", start.to_json()));
+                fields.push(("end", end.to_json()));
+                fields.push(("body", Json::array(stmts)));
+            }
+            StatementKind::Routine(routine) => {
+                fields.push(("name", id(&routine.name)));
+                fields.push(("parameters", Json::array(&routine.parameters)));
+                let return_type = routine.return_type.as_ref();
+                fields.push((
+                    "returnType",
+                    return_type.map_or(Json::Null, ToJson::to_json),
+                ));
+                fields.push(("body", Json::array(&routine.body)));
+            }
+            StatementKind::Call(name, args) => {
+                fields.push(("name", id(name)));
+                fields.push(("arguments", Json::array(args)));
+            }
+            StatementKind::Return(exp) => {
+                fields.push(("value", exp.as_ref().map_or(Json::Null, ToJson::to_json)))
+            }
+        }
+        Json::object(fields)
+    }
+}
+
+impl ToJson for Parameter {
+    fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("name", Json::from(self.name.as_str())),
+            ("type", self.type_def.to_json()),
+            ("span", self.span.to_json()),
+        ])
+    }
+}
+
+impl ToJson for Expression {
+    fn to_json(&self) -> Json {
+        let mut fields = vec![
+            ("kind", Json::from(expression_name(&self.kind))),
+            ("span", self.span.to_json()),
+        ];
+        match &self.kind {
+            ExpressionKind::Identifier(id) => fields.push(("name", Json::from(id.as_str()))),
+            ExpressionKind::IntegerConstant(int) => fields.push(("value", Json::from(*int))),
+            ExpressionKind::StringValue(s) => fields.push(("value", Json::from(s.as_str()))),
+            ExpressionKind::Boolean(b) => fields.push(("value", Json::from(*b))),
+            ExpressionKind::Unary(op, exp) => {
+                fields.push(("operator", Json::from(op.to_string())));
+                fields.push(("operand", exp.to_json()));
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                fields.push(("operator", Json::from(op.to_string())));
+                fields.push(("left", left.to_json()));
+                fields.push(("right", right.to_json()));
+            }
+            ExpressionKind::Call(name, args) => {
+                fields.push(("name", Json::from(name.as_str())));
+                fields.push(("arguments", Json::array(args)));
+            }
+            ExpressionKind::Index(name, index) => {
+                fields.push(("name", Json::from(name.as_str())));
+                fields.push(("index", index.to_json()));
+            }
+        }
+        Json::object(fields)
+    }
+}
+
+impl ToJson for (LexError, Span) {
+    fn to_json(&self) -> Json {
+        let (err, span) = self;
+        let name = match err {
+            LexError::IllegalCharacter(_) => "IllegalCharacter",
+            LexError::UnterminatedString => "UnterminatedString",
+            LexError::InvalidEscape(_) => "InvalidEscape",
+        };
+        Json::object(vec![
+            ("kind", Json::from("LexError")),
+            ("error", Json::from(name)),
+            ("code", Json::from(err.code())),
+            ("message", Json::from(err.to_string())),
+            ("span", span.to_json()),
+        ])
+    }
+}
+
+impl ToJson for (ParseError, Span) {
+    fn to_json(&self) -> Json {
+        let (err, span) = self;
+        let mut fields = vec![
+            ("kind", Json::from("ParseError")),
+            ("error", Json::from(parse_error_name(err))),
+            ("code", Json::from(err.code())),
+            ("message", Json::from(err.to_string())),
+        ];
+        if let Some(token) = err.token() {
+            fields.push(("token", token.to_json()));
+        }
+        fields.push(("span", span.to_json()));
+        Json::object(fields)
+    }
+}
+
+impl ToJson for (CheckError, Span) {
+    fn to_json(&self) -> Json {
+        let (err, span) = self;
+        Json::object(vec![
+            ("kind", Json::from("CheckError")),
+            ("error", Json::from(check_error_name(err))),
+            ("code", Json::from(err.code())),
+            ("message", Json::from(err.to_string())),
+            ("span", span.to_json()),
+        ])
+    }
+}
+
+impl ToJson for (Warning, Span) {
+    fn to_json(&self) -> Json {
+        let (warning, span) = self;
+        let name = match warning {
+            Warning::Failure(err) => eval_error_name(err),
+            Warning::UninitializedRead(_) => "UninitializedRead",
+        };
+        Json::object(vec![
+            ("kind", Json::from("Warning")),
+            ("error", Json::from(name)),
+            ("code", Json::from(warning.code())),
+            ("message", Json::from(warning.message())),
+            ("span", span.to_json()),
+        ])
+    }
+}
+
+impl ToJson for (LintWarning, Span) {
+    fn to_json(&self) -> Json {
+        let (warning, span) = self;
+        let severity = if warning.is_error() {
+            "error"
+        } else {
+            "warning"
+        };
+        Json::object(vec![
+            ("kind", Json::from("Lint")),
+            ("code", Json::from(warning.lint.code())),
+            ("lint", Json::from(warning.lint.name())),
+            ("severity", Json::from(severity)),
+            ("message", Json::from(warning.message.as_str())),
+            ("span", span.to_json()),
+        ])
+    }
+}
+
+impl ToJson for (EvalError, Span) {
+    fn to_json(&self) -> Json {
+        let (err, span) = self;
+        Json::object(vec![
+            ("kind", Json::from("EvalError")),
+            ("error", Json::from(eval_error_name(err))),
+            ("code", Json::from(err.code())),
+            ("message", Json::from(err.message())),
+            ("span", span.to_json()),
+        ])
+    }
+}
+
+fn token_name(token: &Token) -> &'static str {
+    match token {
+        Token::Identifier(_) => "Identifier",
+        Token::IntegerConstant(_) => "IntegerConstant",
+        Token::StringValue(_) => "StringValue",
+        Token::Plus => "Plus",
+        Token::Minus => "Minus",
+        Token::Multiplication => "Multiplication",
+        Token::Division => "Division",
+        Token::Equals => "Equals",
+        Token::LessThan => "LessThan",
+        Token::GreaterThan => "GreaterThan",
+        Token::Assign => "Assign",
+        Token::Var => "Var",
+        Token::Print => "Print",
+        Token::Colon => "Colon",
+        Token::True => "True",
+        Token::False => "False",
+        Token::Read => "Read",
+        Token::And => "And",
+        Token::Not => "Not",
+        Token::For => "For",
+        Token::In => "In",
+        Token::Do => "Do",
+        Token::End => "End",
+        Token::Range => "Range",
+        Token::Assert => "Assert",
+        Token::Procedure => "Procedure",
+        Token::Function => "Function",
+        Token::Return => "Return",
+        Token::Array => "Array",
+        Token::Of => "Of",
+        Token::LeftBracket => "LeftBracket",
+        Token::RightBracket => "RightBracket",
+        Token::LeftSquareBracket => "LeftSquareBracket",
+        Token::RightSquareBracket => "RightSquareBracket",
+        Token::SemiColon => "SemiColon",
+        Token::Comma => "Comma",
+        Token::IntegerType => "IntegerType",
+        Token::StringType => "StringType",
+        Token::BooleanType => "BooleanType",
+        Token::EOF => "EOF",
+        Token::Illegal => "Illegal",
+    }
+}
+
+fn statement_name(kind: &StatementKind) -> &'static str {
+    match kind {
+        StatementKind::VarInitialization(..) => "VarInitialization",
+        StatementKind::NewAssignment(..) => "NewAssignment",
+        StatementKind::Assignment(..) => "Assignment",
+        StatementKind::IndexAssignment(..) => "IndexAssignment",
+        StatementKind::Print(_) => "Print",
+        StatementKind::Assert(_) => "Assert",
+        StatementKind::Read(_) => "Read",
+        StatementKind::For(..) => "For",
+        StatementKind::Routine(_) => "Routine",
+        StatementKind::Call(..) => "Call",
+        StatementKind::Return(_) => "Return",
+    }
+}
+
+fn expression_name(kind: &ExpressionKind) -> &'static str {
+    match kind {
+        ExpressionKind::Identifier(_) => "Identifier",
+        ExpressionKind::IntegerConstant(_) => "IntegerConstant",
+        ExpressionKind::StringValue(_) => "StringValue",
+        ExpressionKind::Boolean(_) => "Boolean",
+        ExpressionKind::Unary(..) => "Unary",
+        ExpressionKind::Binary(..) => "Binary",
+        ExpressionKind::Call(..) => "Call",
+        ExpressionKind::Index(..) => "Index",
+    }
+}
+
+fn parse_error_name(err: &ParseError) -> &'static str {
+    match err {
+        ParseError::UnexpectedToken(_) => "UnexpectedToken",
+        ParseError::ExpectedColon(_) => "ExpectedColon",
+        ParseError::ExpectedTypeDefinition(_) => "ExpectedTypeDefinition",
+        ParseError::ExpectedAssignment(_) => "ExpectedAssignment",
+        ParseError::ExpectedIdentifier(_) => "ExpectedIdentifier",
+        ParseError::ExpectedOperand(_) => "ExpectedOperand",
+        ParseError::ExpectedSemiColon(_) => "ExpectedSemiColon",
+        ParseError::ExpectedClosingBracket(_) => "ExpectedClosingBracket",
+        ParseError::ExpectedLeftBracket(_) => "ExpectedLeftBracket",
+        ParseError::ExpectedIn(_) => "ExpectedIn",
+        ParseError::ExpectedDo(_) => "ExpectedDo",
+        ParseError::ExpectedRange(_) => "ExpectedRange",
+        ParseError::ExpectedFor(_) => "ExpectedFor",
+        ParseError::ExpectedProcedure(_) => "ExpectedProcedure",
+        ParseError::ExpectedFunction(_) => "ExpectedFunction",
+        ParseError::ExpectedOf(_) => "ExpectedOf",
+        ParseError::ExpectedLeftSquareBracket(_) => "ExpectedLeftSquareBracket",
+        ParseError::ExpectedClosingSquareBracket(_) => "ExpectedClosingSquareBracket",
+        ParseError::InvalidArraySize(_) => "InvalidArraySize",
+        ParseError::IntegerOutOfRange(_) => "IntegerOutOfRange",
+        ParseError::NestingTooDeep(_) => "NestingTooDeep",
+    }
+}
+
+fn check_error_name(err: &CheckError) -> &'static str {
+    match err {
+        CheckError::MismatchedTypes(..) => "MismatchedTypes",
+        CheckError::UnsupportedOperation(..) => "UnsupportedOperation",
+        CheckError::UndeclaredVariable(_) => "UndeclaredVariable",
+        CheckError::VariableAlreadyDeclared(_) => "VariableAlreadyDeclared",
+        CheckError::VariableOutOfScope(_) => "VariableOutOfScope",
+        CheckError::UndeclaredRoutine(_) => "UndeclaredRoutine",
+        CheckError::RoutineAlreadyDeclared(_) => "RoutineAlreadyDeclared",
+        CheckError::NestedRoutine(_) => "NestedRoutine",
+        CheckError::ArgumentCount(..) => "ArgumentCount",
+        CheckError::NoReturnValue(_) => "NoReturnValue",
+        CheckError::ReturnOutsideRoutine => "ReturnOutsideRoutine",
+        CheckError::MissingReturnValue(..) => "MissingReturnValue",
+        CheckError::UnexpectedReturnValue(_) => "UnexpectedReturnValue",
+    }
+}
+
+fn eval_error_name(err: &EvalError) -> &'static str {
+    match err {
+        EvalError::MismatchedTypes => "MismatchedTypes",
+        EvalError::UnsupportedOperation => "UnsupportedOperation",
+        EvalError::VariableNotInitialized(_) => "VariableNotInitialized",
+        EvalError::VariableAlreadyInitialized(_) => "VariableAlreadyInitialized",
+        EvalError::SyntaxError => "SyntaxError",
+        EvalError::IOError(_) => "IOError",
+        EvalError::DivisionByZero => "DivisionByZero",
+        EvalError::IntegerOverflow => "IntegerOverflow",
+        EvalError::StepLimitExceeded(_) => "StepLimitExceeded",
+        EvalError::OutputLimitExceeded(_) => "OutputLimitExceeded",
+        EvalError::StringLimitExceeded(_) => "StringLimitExceeded",
+        EvalError::DeadlineExceeded => "DeadlineExceeded",
+        EvalError::UndeclaredRoutine(_) => "UndeclaredRoutine",
+        EvalError::MissingReturn(_) => "MissingReturn",
+        EvalError::CallDepthExceeded(_) => "CallDepthExceeded",
+        EvalError::IndexOutOfBounds(..) => "IndexOutOfBounds",
+        EvalError::InvalidArgument(..) => "InvalidArgument",
+        EvalError::Interrupted => "Interrupted",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{Json, ToJson};
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::token::Token;
+    use crate::utils::{EvalError, ParseError, Position, Span};
+
+    #[test]
+    fn escape_strings() {
+        let json = Json::object(vec![("s", Json::from("a\"b\\c\nd\u{1}"))]);
+        assert_eq!(json.to_string(), r#"{"s":"a\"b\\c\nd\u0001"}"#);
+    }
+
+    #[test]
+    fn parse_values() {
+        let source = r#" {"id": 1, "params": {"text": "a\"\n\u00e4\ud83d\ude00", "list": [true, null, -2.5]}} "#;
+        let json = Json::parse(source).unwrap();
+        assert_eq!(json.get("id").and_then(Json::as_i64), Some(1));
+        let params = json.get("params").unwrap();
+        assert_eq!(
+            params.get("text").and_then(Json::as_str),
+            Some("a\"\n\u{e4}\u{1f600}")
+        );
+        assert_eq!(
+            params.get("list"),
+            Some(&Json::Array(vec![
+                Json::Bool(true),
+                Json::Null,
+                Json::Number(-2)
+            ]))
+        );
+        assert!(Json::parse("{\"a\": }").is_err());
+        assert!(Json::parse("[1, 2] 3").is_err());
+    }
+
+    #[test]
+    fn serialize_tokens() {
+        let mut lexer = Lexer::new("var x".to_string());
+        let var = lexer.get_next_spanned_token();
+        let x = lexer.get_next_spanned_token();
+        assert_eq!(
+            var.to_json().to_string(),
+            r#"{"kind":"Var","span":{"start":{"line":1,"column":1},"end":{"line":1,"column":4}}}"#
+        );
+        assert_eq!(
+            x.to_json().to_string(),
+            r#"{"kind":"Identifier","value":"x","span":{"start":{"line":1,"column":5},"end":{"line":1,"column":6}}}"#
+        );
+    }
+
+    #[test]
+    fn serialize_program() {
+        let lexer = Lexer::new("print !x;".to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        let span = |c1, c2| {
+            format!(
+                r#"{{"start":{{"line":1,"column":{}}},"end":{{"line":1,"column":{}}}}}"#,
+                c1, c2
+            )
+        };
+        let expected = format!(
+            r#"{{"statements":[{{"kind":"Print","span":{},"value":{{"kind":"Unary","span":{},"operator":"!","operand":{{"kind":"Identifier","span":{},"name":"x"}}}}}}]}}"#,
+            span(1, 10),
+            span(7, 9),
+            span(8, 9)
+        );
+        assert_eq!(program.to_json().to_string(), expected);
+    }
+
+    #[test]
+    fn serialize_errors() {
+        let span = Span::new(Position::new(2, 3), Position::new(2, 4));
+        let parse_err = (ParseError::ExpectedSemiColon(Token::RightBracket), span);
+        assert_eq!(
+            parse_err.to_json().to_string(),
+            r#"{"kind":"ParseError","error":"ExpectedSemiColon","code":"E0007","message":"Expected ; got )","token":{"kind":"RightBracket"},"span":{"start":{"line":2,"column":3},"end":{"line":2,"column":4}}}"#
+        );
+        let eval_err = (EvalError::VariableNotInitialized("x".to_string()), span);
+        assert_eq!(
+            eval_err.to_json().to_string(),
+            r#"{"kind":"EvalError","error":"VariableNotInitialized","code":"E0203","message":"Variable x not initialized","span":{"start":{"line":2,"column":3},"end":{"line":2,"column":4}}}"#
+        );
+    }
+}
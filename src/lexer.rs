@@ -1,45 +1,58 @@
 use crate::token::{get_id_or_key_token, Token};
+use crate::utils::{LexError, Position, Span};
 use regex::Regex;
 
+pub type SpannedToken = (Token, Span);
+pub type SpannedLexError = (LexError, Span);
+
 pub struct Lexer {
     position: usize,
-    source: String,
+    source: Vec<char>,
     current_char: Option<char>,
+    location: Position,
+    id_pattern: Regex,
+    comments: Vec<(String, Span)>,
+    errors: Vec<SpannedLexError>,
 }
 
 impl Lexer {
     pub fn new(source: String) -> Self {
-        let src = source.trim();
+        let src: Vec<char> = source.chars().collect();
         Lexer {
-            source: src.to_string(),
+            current_char: src.first().copied(),
+            source: src,
             position: 0,
-            current_char: src.chars().next(),
+            location: Position::new(1, 1),
+            id_pattern: Regex::new(r"^[a-zA-Z0-9_]$").unwrap(),
+            comments: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
     fn advance(&mut self) {
-        self.position += 1;
-        if self.source.len() > self.position {
-            self.current_char = Some(self.source.as_bytes()[self.position] as char);
-        } else {
-            self.current_char = None;
+        match self.current_char {
+            Some('\n') => {
+                self.location.line += 1;
+                self.location.column = 1;
+            }
+            Some(_) => self.location.column += 1,
+            None => {}
         }
+        self.position += 1;
+        self.current_char = self.source.get(self.position).copied();
     }
 
     fn peek(&self) -> Option<char> {
-        let pos = self.position + 1;
-        if self.source.len() > pos {
-            Some(self.source.as_bytes()[pos] as char)
-        } else {
-            None
-        }
+        self.source.get(self.position + 1).copied()
     }
 
     fn read_identifier(&mut self) -> String {
         let mut lexeme = String::new();
-        let id_pattern = Regex::new(r"[a-zA-Z0-9_]+").unwrap();
-        let is_match = |ch: char| id_pattern.is_match(&ch.to_string());
-        while self.peek() != None && is_match(self.peek().unwrap()) {
+        let mut buffer = [0; 4];
+        while let Some(next) = self.peek() {
+            if !self.id_pattern.is_match(next.encode_utf8(&mut buffer)) {
+                break;
+            }
             lexeme.push(self.current_char.unwrap());
             self.advance();
         }
@@ -49,7 +62,7 @@ impl Lexer {
 
     fn read_integer(&mut self) -> String {
         let mut lexeme = String::new();
-        while self.peek() != None && self.peek().unwrap().is_numeric() {
+        while self.peek().is_some() && self.peek().unwrap().is_numeric() {
             lexeme.push(self.current_char.unwrap());
             self.advance();
         }
@@ -57,9 +70,13 @@ impl Lexer {
         lexeme
     }
 
-    fn read_string(&mut self) -> String {
+    /// Reads a string literal up to its closing quote. Fails for
+    /// unterminated literals and unknown escapes, after skipping past the
+    /// rest of the literal.
+    fn read_string(&mut self) -> Result<String, LexError> {
         let mut lexeme = String::new();
-        while self.current_char != None && self.current_char.unwrap() != '"' {
+        let mut invalid_escape = None;
+        while self.current_char.is_some() && self.current_char.unwrap() != '"' {
             let current_char = self.current_char.unwrap();
             if current_char == '\\' {
                 match self.peek() {
@@ -67,7 +84,10 @@ impl Lexer {
                     Some('\\') => lexeme.push('\\'),
                     Some('n') => lexeme.push('\n'),
                     Some('t') => lexeme.push('\t'),
-                    _ => panic!("Unknown character escape!"),
+                    Some(ch) => {
+                        invalid_escape.get_or_insert(ch);
+                    }
+                    None => {}
                 }
                 self.advance();
             } else {
@@ -75,17 +95,72 @@ impl Lexer {
             }
             self.advance();
         }
-        lexeme
+        match (self.current_char, invalid_escape) {
+            (None, _) => Err(LexError::UnterminatedString),
+            (_, Some(ch)) => Err(LexError::InvalidEscape(ch)),
+            _ => Ok(lexeme),
+        }
     }
 
+    /// Skips whitespace and comments, which run from `//` to the end of the
+    /// line.
     fn skip_whitespace(&mut self) {
-        while self.current_char != None && self.current_char.unwrap().is_whitespace() {
+        loop {
+            match self.current_char {
+                Some(ch) if ch.is_whitespace() => self.advance(),
+                Some('/') if self.peek() == Some('/') => self.skip_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        let start = self.location;
+        self.advance();
+        self.advance();
+        let mut text = String::new();
+        while let Some(ch) = self.current_char {
+            if ch == '\n' {
+                break;
+            }
+            text.push(ch);
             self.advance();
         }
+        self.comments.push((text, Span::new(start, self.location)));
+    }
+
+    /// The text after `//` of the comments skipped so far, with their spans.
+    pub fn comments(&self) -> &[(String, Span)] {
+        &self.comments
+    }
+
+    /// Why each `Token::Illegal` lexed so far is illegal.
+    pub fn get_errors(&self) -> &[SpannedLexError] {
+        &self.errors
     }
 
     pub fn get_next_token(&mut self) -> Token {
+        let (token, _) = self.get_next_spanned_token();
+        token
+    }
+
+    /// Lexes the rest of the source. The last token is always `Token::EOF`.
+    pub fn get_all_tokens(&mut self) -> Vec<SpannedToken> {
+        let mut tokens = Vec::new();
+        loop {
+            let (token, span) = self.get_next_spanned_token();
+            let done = token == Token::EOF;
+            tokens.push((token, span));
+            if done {
+                return tokens;
+            }
+        }
+    }
+
+    pub fn get_next_spanned_token(&mut self) -> SpannedToken {
         self.skip_whitespace();
+        let start = self.location;
+        let mut error = None;
 
         let token = match self.current_char {
             Some('+') => Token::Plus,
@@ -93,10 +168,13 @@ impl Lexer {
             Some('*') => Token::Multiplication,
             Some('/') => Token::Division,
             Some(';') => Token::SemiColon,
+            Some(',') => Token::Comma,
             Some('&') => Token::And,
             Some('!') => Token::Not,
             Some('(') => Token::LeftBracket,
             Some(')') => Token::RightBracket,
+            Some('[') => Token::LeftSquareBracket,
+            Some(']') => Token::RightSquareBracket,
             Some('=') => Token::Equals,
             Some('>') => Token::GreaterThan,
             Some('<') => Token::LessThan,
@@ -105,6 +183,7 @@ impl Lexer {
                     self.advance();
                     Token::Range
                 } else {
+                    error = Some(LexError::IllegalCharacter('.'));
                     Token::Illegal
                 }
             }
@@ -118,7 +197,13 @@ impl Lexer {
             }
             Some('"') => {
                 self.advance();
-                Token::StringValue(self.read_string())
+                match self.read_string() {
+                    Ok(string) => Token::StringValue(string),
+                    Err(err) => {
+                        error = Some(err);
+                        Token::Illegal
+                    }
+                }
             }
             Some(ch) => {
                 if ch.is_alphabetic() {
@@ -128,6 +213,7 @@ impl Lexer {
                     let lexeme = self.read_integer();
                     Token::IntegerConstant(lexeme)
                 } else {
+                    error = Some(LexError::IllegalCharacter(ch));
                     Token::Illegal
                 }
             }
@@ -135,7 +221,11 @@ impl Lexer {
         };
 
         self.advance();
-        token
+        let span = Span::new(start, self.location);
+        if let Some(err) = error {
+            self.errors.push((err, span));
+        }
+        (token, span)
     }
 }
 
@@ -143,6 +233,7 @@ impl Lexer {
 mod tests {
     use crate::lexer::Lexer;
     use crate::token::Token;
+    use crate::utils::{LexError, Position, Span};
 
     #[test]
     fn lex_tokens() {
@@ -208,4 +299,84 @@ mod tests {
             assert_eq!(token, expected);
         }
     }
+
+    #[test]
+    fn track_spans() {
+        let source = "var x := \"a\\nb\";\n  x..10";
+        let mut lexer = Lexer::new(source.to_string());
+        let span = |l1, c1, l2, c2| Span::new(Position::new(l1, c1), Position::new(l2, c2));
+        let expected = vec![
+            (Token::Var, span(1, 1, 1, 4)),
+            (Token::Identifier("x".to_string()), span(1, 5, 1, 6)),
+            (Token::Assign, span(1, 7, 1, 9)),
+            (Token::StringValue("a\nb".to_string()), span(1, 10, 1, 16)),
+            (Token::SemiColon, span(1, 16, 1, 17)),
+            (Token::Identifier("x".to_string()), span(2, 3, 2, 4)),
+            (Token::Range, span(2, 4, 2, 6)),
+            (Token::IntegerConstant("10".to_string()), span(2, 6, 2, 8)),
+            (Token::EOF, span(2, 8, 2, 8)),
+        ];
+        for expected in expected {
+            assert_eq!(lexer.get_next_spanned_token(), expected);
+        }
+    }
+
+    #[test]
+    fn skip_comments() {
+        let source = "// lint: allow(x)\nprint 1 / 2; // half\n\"//\"//";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens: Vec<Token> = lexer
+            .get_all_tokens()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Print,
+                Token::IntegerConstant("1".to_string()),
+                Token::Division,
+                Token::IntegerConstant("2".to_string()),
+                Token::SemiColon,
+                Token::StringValue("//".to_string()),
+                Token::EOF,
+            ]
+        );
+        let span = |l1, c1, l2, c2| Span::new(Position::new(l1, c1), Position::new(l2, c2));
+        assert_eq!(
+            lexer.comments(),
+            &[
+                (" lint: allow(x)".to_string(), span(1, 1, 1, 18)),
+                (" half".to_string(), span(2, 14, 2, 21)),
+                (String::new(), span(3, 5, 3, 7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_invalid_strings() {
+        let mut lexer = Lexer::new(r#""a\qb" ; "ok" $ "open\"#.to_string());
+        assert_eq!(lexer.get_next_token(), Token::Illegal);
+        assert_eq!(lexer.get_next_token(), Token::SemiColon);
+        assert_eq!(lexer.get_next_token(), Token::StringValue("ok".to_string()));
+        assert_eq!(lexer.get_next_token(), Token::Illegal);
+        assert_eq!(lexer.get_next_token(), Token::Illegal);
+        assert_eq!(lexer.get_next_token(), Token::EOF);
+        let errors: Vec<LexError> = lexer
+            .get_errors()
+            .iter()
+            .map(|(err, _)| err)
+            .cloned()
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                LexError::InvalidEscape('q'),
+                LexError::IllegalCharacter('$'),
+                LexError::UnterminatedString,
+            ]
+        );
+        let span = |c1, c2| Span::new(Position::new(1, c1), Position::new(1, c2));
+        assert_eq!(lexer.get_errors()[1].1, span(15, 16));
+    }
 }
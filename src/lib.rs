@@ -0,0 +1,24 @@
+extern crate regex;
+
+pub mod ast;
+pub mod backend;
+pub mod builtins;
+pub mod checker;
+pub mod cli;
+pub mod debugger;
+pub mod diagnostic;
+pub mod evaluator;
+pub mod explain;
+pub mod formatter;
+pub mod fuzz;
+pub mod ir;
+pub mod json;
+pub mod lexer;
+pub mod lint;
+pub mod lsp;
+pub mod optimizer;
+pub mod parser;
+pub mod suggest;
+pub mod token;
+pub mod tracer;
+pub mod utils;
@@ -0,0 +1,480 @@
+//! Warnings about programs that are valid but probably not what was meant,
+//! such as a variable that is never read or an assertion that cannot fail.
+//!
+//! Every `Lint` has a stable code and name and starts at the `Warn` level.
+//! `Linter::with_level` changes the level of a lint: `Allow` turns it off
+//! and `Deny` reports it as an error. A comment such as
+//!
+//! ```text
+//! var unused : int; // lint: allow(unused-variable)
+//! ```
+//!
+//! suppresses the lints it lists, by name or code, on its own line and on
+//! the line after it, so that it can also stand above the statement.
+//!
+//! The linter runs on programs that passed the type checker. Constant
+//! expressions are folded by the `Optimizer` first, so `assert (2 > 1);`
+//! and `for i in 10..2 * 3 do` are recognised as well.
+
+use crate::ast::{Expression, ExpressionKind, Program, Statement, StatementKind};
+use crate::optimizer::Optimizer;
+use crate::utils::{IntegerWidth, Span};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Lint {
+    /// A variable that is declared and never assigned or read.
+    UnusedVariable,
+    /// A variable that is assigned but whose value is never read.
+    UnreadVariable,
+    /// An assertion whose condition is a constant.
+    ConstantAssert,
+    /// A `for` loop without statements.
+    EmptyLoop,
+    /// A `for` loop over a constant range that is empty.
+    EmptyRange,
+}
+
+pub const LINTS: [Lint; 5] = [
+    Lint::UnusedVariable,
+    Lint::UnreadVariable,
+    Lint::ConstantAssert,
+    Lint::EmptyLoop,
+    Lint::EmptyRange,
+];
+
+impl Lint {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "W0001",
+            Lint::UnreadVariable => "W0002",
+            Lint::ConstantAssert => "W0003",
+            Lint::EmptyLoop => "W0004",
+            Lint::EmptyRange => "W0005",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused-variable",
+            Lint::UnreadVariable => "unread-variable",
+            Lint::ConstantAssert => "constant-assert",
+            Lint::EmptyLoop => "empty-loop",
+            Lint::EmptyRange => "empty-range",
+        }
+    }
+
+    /// The lint with the name or code `text`.
+    pub fn find(text: &str) -> Option<Lint> {
+        LINTS
+            .iter()
+            .find(|lint| lint.name() == text || lint.code() == text)
+            .copied()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct LintWarning {
+    pub lint: Lint,
+    /// `Warn` or `Deny`; allowed lints are not reported.
+    pub level: Level,
+    pub message: String,
+}
+
+impl LintWarning {
+    pub fn is_error(&self) -> bool {
+        self.level == Level::Deny
+    }
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = if self.is_error() { "Error" } else { "Warning" };
+        write!(f, "{}: {} ({})", severity, self.message, self.lint.name())
+    }
+}
+
+pub type SpannedLintWarning = (LintWarning, Span);
+
+/// A variable declaration and how the program uses it.
+struct Variable {
+    name: String,
+    span: Span,
+    parameter: bool,
+    assigned: bool,
+    read: bool,
+}
+
+pub struct Linter {
+    levels: Vec<(Lint, Level)>,
+    integer_width: IntegerWidth,
+    /// Lints suppressed by comments, with the first of the two lines.
+    suppressions: Vec<(Lint, usize)>,
+    variables: Vec<Variable>,
+    /// Indices into `variables` of the variables in scope, innermost block
+    /// last.
+    scopes: Vec<Vec<usize>>,
+    warnings: Vec<SpannedLintWarning>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Linter::new()
+    }
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Linter {
+            levels: LINTS.iter().map(|lint| (*lint, Level::Warn)).collect(),
+            integer_width: IntegerWidth::default(),
+            suppressions: Vec::new(),
+            variables: Vec::new(),
+            scopes: vec![Vec::new()],
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn with_level(mut self, lint: Lint, level: Level) -> Self {
+        for (other, old) in self.levels.iter_mut() {
+            if *other == lint {
+                *old = level;
+            }
+        }
+        self
+    }
+
+    /// Folds constants as an evaluator with the same `width` would.
+    pub fn with_integer_width(mut self, width: IntegerWidth) -> Self {
+        self.integer_width = width;
+        self
+    }
+
+    /// Reads `// lint: allow(...)` suppressions from the comments of the
+    /// program, as collected by `Lexer::comments`.
+    pub fn with_comments(mut self, comments: &[(String, Span)]) -> Self {
+        for (text, span) in comments {
+            let names = text
+                .trim()
+                .strip_prefix("lint:")
+                .map(str::trim)
+                .and_then(|text| text.strip_prefix("allow("))
+                .and_then(|text| text.strip_suffix(')'));
+            for name in names.into_iter().flat_map(|names| names.split(',')) {
+                if let Some(lint) = Lint::find(name.trim()) {
+                    self.suppressions.push((lint, span.start.line));
+                }
+            }
+        }
+        self
+    }
+
+    /// The lints found in `program`, sorted by position.
+    pub fn lint_program(mut self, program: &Program) -> Vec<SpannedLintWarning> {
+        let mut optimizer = Optimizer::new().with_integer_width(self.integer_width);
+        let program = optimizer.optimize_program(Program {
+            statements: program.statements.clone(),
+        });
+        self.lint_block(&program.statements);
+        self.leave_scope();
+        self.warnings
+            .sort_by_key(|(_, span)| (span.start.line, span.start.column));
+        self.warnings
+    }
+
+    fn lint_block(&mut self, statements: &[Statement]) {
+        for stmt in statements {
+            self.lint_statement(stmt);
+        }
+    }
+
+    fn lint_statement(&mut self, statement: &Statement) {
+        let span = statement.span;
+        match &statement.kind {
+            StatementKind::VarInitialization(id, _) => self.declare(id, span, false),
+            StatementKind::NewAssignment(id, _, exp) => {
+                self.lint_expression(exp);
+                self.declare(id, span, false);
+            }
+            StatementKind::Assignment(id, exp) => {
+                self.lint_expression(exp);
+                self.assign(id);
+            }
+            StatementKind::IndexAssignment(id, index, exp) => {
+                self.lint_expression(index);
+                self.lint_expression(exp);
+                self.assign(id);
+            }
+            StatementKind::Read(id) => self.assign(id),
+            StatementKind::Print(exp) => self.lint_expression(exp),
+            StatementKind::Assert(exp) => {
+                self.lint_expression(exp);
+                match exp.kind {
+                    ExpressionKind::Boolean(true) => {
+                        self.report(Lint::ConstantAssert, span, "Assertion is always true")
+                    }
+                    ExpressionKind::Boolean(false) => {
+                        self.report(Lint::ConstantAssert, span, "Assertion always fails")
+                    }
+                    _ => {}
+                }
+            }
+            StatementKind::For(id, start, end, stmts) => {
+                self.lint_expression(start);
+                self.lint_expression(end);
+                // The loop both sets and depends on its variable, which
+                // cannot be left out, so it counts as used.
+                self.assign(id);
+                self.read(id);
+                if let (
+                    ExpressionKind::IntegerConstant(first),
+                    ExpressionKind::IntegerConstant(last),
+                ) = (&start.kind, &end.kind)
+                {
+                    if first > last {
+                        let message = format!(
+                            "Loop never runs, its start {} is greater than its end {}",
+                            first, last
+                        );
+                        self.report(Lint::EmptyRange, span, &message);
+                    }
+                }
+                if stmts.is_empty() {
+                    self.report(Lint::EmptyLoop, span, "Loop body is empty");
+                }
+                self.scopes.push(Vec::new());
+                self.lint_block(stmts);
+                self.leave_scope();
+            }
+            StatementKind::Routine(routine) => {
+                // A body sees its parameters and its own variables only.
+                let outer = std::mem::replace(&mut self.scopes, vec![Vec::new()]);
+                for param in &routine.parameters {
+                    self.declare(&param.name, param.span, true);
+                }
+                self.lint_block(&routine.body);
+                self.leave_scope();
+                self.scopes = outer;
+            }
+            StatementKind::Call(_, args) => {
+                for arg in args {
+                    self.lint_expression(arg);
+                }
+            }
+            StatementKind::Return(exp) => {
+                if let Some(exp) = exp {
+                    self.lint_expression(exp);
+                }
+            }
+        }
+    }
+
+    fn lint_expression(&mut self, exp: &Expression) {
+        match &exp.kind {
+            ExpressionKind::Identifier(id) => self.read(id),
+            ExpressionKind::Index(id, index) => {
+                self.lint_expression(index);
+                self.read(id);
+            }
+            ExpressionKind::Unary(_, operand) => self.lint_expression(operand),
+            ExpressionKind::Binary(left, _, right) => {
+                self.lint_expression(left);
+                self.lint_expression(right);
+            }
+            ExpressionKind::Call(_, args) => {
+                for arg in args {
+                    self.lint_expression(arg);
+                }
+            }
+            ExpressionKind::IntegerConstant(_)
+            | ExpressionKind::StringValue(_)
+            | ExpressionKind::Boolean(_) => {}
+        }
+    }
+
+    fn declare(&mut self, name: &str, span: Span, parameter: bool) {
+        self.variables.push(Variable {
+            name: name.to_string(),
+            span,
+            parameter,
+            assigned: false,
+            read: false,
+        });
+        let index = self.variables.len() - 1;
+        self.scopes.last_mut().unwrap().push(index);
+    }
+
+    /// The variable `name` refers to, or `None` for variables the host
+    /// declared.
+    fn lookup(&mut self, name: &str) -> Option<&mut Variable> {
+        let index = self
+            .scopes
+            .iter()
+            .rev()
+            .flatten()
+            .find(|index| self.variables[**index].name == name)
+            .copied()?;
+        Some(&mut self.variables[index])
+    }
+
+    fn assign(&mut self, name: &str) {
+        if let Some(var) = self.lookup(name) {
+            var.assigned = true;
+        }
+    }
+
+    fn read(&mut self, name: &str) {
+        if let Some(var) = self.lookup(name) {
+            var.read = true;
+        }
+    }
+
+    /// Reports the unused variables of the innermost scope and closes it.
+    fn leave_scope(&mut self) {
+        for index in self.scopes.pop().unwrap_or_default() {
+            let var = &self.variables[index];
+            let (lint, message) = match (var.parameter, var.assigned, var.read) {
+                (false, false, false) => (
+                    Lint::UnusedVariable,
+                    format!("Variable {} is never used", var.name),
+                ),
+                (false, true, false) => (
+                    Lint::UnreadVariable,
+                    format!("Variable {} is assigned but never read", var.name),
+                ),
+                _ => continue,
+            };
+            let span = var.span;
+            self.report(lint, span, &message);
+        }
+    }
+
+    fn report(&mut self, lint: Lint, span: Span, message: &str) {
+        let line = span.start.line;
+        let suppressed = self
+            .suppressions
+            .iter()
+            .any(|(other, first)| *other == lint && (*first == line || *first + 1 == line));
+        let level = self
+            .levels
+            .iter()
+            .find(|(other, _)| *other == lint)
+            .map_or(Level::Warn, |(_, level)| *level);
+        if suppressed || level == Level::Allow {
+            return;
+        }
+        let warning = LintWarning {
+            lint,
+            level,
+            message: message.to_string(),
+        };
+        self.warnings.push((warning, span));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::lint::{Level, Lint, Linter};
+    use crate::parser::Parser;
+
+    /// Line and text of every lint.
+    fn lint(source: &str, linter: Linter) -> Vec<(usize, String)> {
+        let mut lexer = Lexer::new(source.to_string());
+        lexer.get_all_tokens();
+        let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+        linter
+            .with_comments(lexer.comments())
+            .lint_program(&program)
+            .iter()
+            .map(|(warning, span)| (span.start.line, warning.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn find_unused_variables() {
+        let source = "var a : int;\nvar b : int := 1;\nvar c : int;\nc := 2;\nvar d : string;\n\
+                      read d;\nvar e : int;\nfor e in 1..b do\n    var f : int := e;\nend for;\n\
+                      procedure p(n : int)\n    var g : bool;\nend procedure;";
+        assert_eq!(
+            lint(source, Linter::new()),
+            vec![
+                (
+                    1,
+                    "Warning: Variable a is never used (unused-variable)".to_string()
+                ),
+                (
+                    3,
+                    "Warning: Variable c is assigned but never read (unread-variable)".to_string()
+                ),
+                (
+                    5,
+                    "Warning: Variable d is assigned but never read (unread-variable)".to_string()
+                ),
+                (
+                    9,
+                    "Warning: Variable f is never used (unused-variable)".to_string()
+                ),
+                (
+                    12,
+                    "Warning: Variable g is never used (unused-variable)".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_constant_asserts_and_loops() {
+        let source = "var i : int;\nassert (2 > 1);\nassert (\"a\" = \"b\");\nassert (i = 0);\n\
+                      for i in 10..2 * 3 do\n    print i;\nend for;\nfor i in 0..i do\nend for;";
+        let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+        let lints: Vec<(usize, Lint)> = Linter::new()
+            .lint_program(&program)
+            .iter()
+            .map(|(warning, span)| (span.start.line, warning.lint))
+            .collect();
+        assert_eq!(
+            lints,
+            vec![
+                (2, Lint::ConstantAssert),
+                (3, Lint::ConstantAssert),
+                (5, Lint::EmptyRange),
+                (8, Lint::EmptyLoop),
+            ]
+        );
+    }
+
+    #[test]
+    fn change_levels() {
+        let source = "var a : int;\nassert (true);";
+        let linter = Linter::new()
+            .with_level(Lint::UnusedVariable, Level::Deny)
+            .with_level(Lint::ConstantAssert, Level::Allow);
+        assert_eq!(
+            lint(source, linter),
+            vec![(
+                1,
+                "Error: Variable a is never used (unused-variable)".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn suppress_with_comments() {
+        let source = "var a : int; // lint: allow(unused-variable)\n\
+                      // lint: allow(W0003, empty-loop)\nassert (true);\nvar b : int;\n\
+                      // lint: allow(empty-loop)\n\nassert (false);";
+        let lines: Vec<usize> = lint(source, Linter::new())
+            .iter()
+            .map(|(line, _)| *line)
+            .collect();
+        assert_eq!(lines, vec![4, 7]);
+    }
+}
@@ -0,0 +1,753 @@
+//! Language Server Protocol support for Mini-PL documents.
+//!
+//! The server keeps the full text of every open document (full sync) and
+//! re-analyses it on each change. Messages use the standard
+//! `Content-Length` framing over any reader/writer pair, so the server can be
+//! driven by an editor over stdio or by a scripted client in tests.
+
+use crate::ast::{Program, StatementKind};
+use crate::builtins::BUILTINS;
+use crate::checker::{Checker, Declaration};
+use crate::diagnostic::{Diagnostic, Severity, ToDiagnostic};
+use crate::formatter::format_program;
+use crate::ir::analysis::uninitialized_reads;
+use crate::ir::lower::Lowering;
+use crate::json::Json;
+use crate::lexer::{Lexer, SpannedToken};
+use crate::parser::Parser;
+use crate::token::{Token, KEYWORDS};
+use crate::utils::{Position, Span};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{self, BufRead, Write};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+const SEVERITY_ERROR: i64 = 1;
+const SEVERITY_WARNING: i64 = 2;
+const COMPLETION_KIND_FUNCTION: i64 = 3;
+const COMPLETION_KIND_VARIABLE: i64 = 6;
+const COMPLETION_KIND_KEYWORD: i64 = 14;
+
+/// Reads one framed message. Returns `Ok(None)` once the input is closed.
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Json>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = match content_length {
+        Some(length) => length,
+        None => return Err(invalid_data("Missing Content-Length header".to_string())),
+    };
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    let body = String::from_utf8(body).map_err(|err| invalid_data(err.to_string()))?;
+    Json::parse(&body).map(Some).map_err(invalid_data)
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Serves requests until the client sends `exit` or closes the input, and
+/// returns the process exit code mandated by the protocol.
+pub fn run<R: BufRead, W: Write>(mut reader: R, mut writer: W) -> io::Result<i32> {
+    let mut server = Server::new();
+    while let Some(message) = read_message(&mut reader)? {
+        for outgoing in server.handle(&message) {
+            write_message(&mut writer, &outgoing)?;
+        }
+        if let Some(code) = server.exit_code {
+            return Ok(code);
+        }
+    }
+    Ok(if server.shutdown_requested { 0 } else { 1 })
+}
+
+/// Result of lexing, parsing and checking one document.
+struct Analysis {
+    tokens: Vec<SpannedToken>,
+    has_comments: bool,
+    program: Program,
+    /// Whether the lexer and parser found no errors.
+    parsed: bool,
+    checker: Checker,
+    /// Errors of the lexer and parser, or else of the checker, or else the
+    /// warnings of the static analyses.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Analysis {
+    fn new(text: &str) -> Self {
+        let mut lexer = Lexer::new(text.to_string());
+        let tokens = lexer.get_all_tokens();
+        let has_comments = !lexer.comments().is_empty();
+        let mut parser = Parser::new(Lexer::new(text.to_string()));
+        let program = parser.parse_program();
+        let mut diagnostics = parser.diagnostics();
+        let parsed = diagnostics.is_empty();
+        let mut checker = Checker::new();
+        checker.check_program(&program);
+        if parsed {
+            diagnostics = checker.diagnostics();
+        }
+        if diagnostics.is_empty() {
+            if let Ok(module) = Lowering::new().lower_program(&program) {
+                let warnings = uninitialized_reads(&module);
+                diagnostics = warnings.iter().map(ToDiagnostic::to_diagnostic).collect();
+            }
+        }
+        Analysis {
+            tokens,
+            has_comments,
+            program,
+            parsed,
+            checker,
+            diagnostics,
+        }
+    }
+
+    fn identifier_at(&self, pos: Position) -> Option<(&str, Span)> {
+        self.tokens.iter().find_map(|(token, span)| match token {
+            Token::Identifier(id) if contains(span, pos) => Some((id.as_str(), *span)),
+            _ => None,
+        })
+    }
+
+    /// The declarations whose block encloses `pos`, in the order they are
+    /// declared. Inside a routine only its parameters and locals are
+    /// visible, as in the checker.
+    fn visible_declarations(&self, pos: Position) -> impl Iterator<Item = &Declaration> {
+        let routine = self
+            .program
+            .statements
+            .iter()
+            .find(|stmt| {
+                matches!(stmt.kind, StatementKind::Routine(_)) && contains(&stmt.span, pos)
+            })
+            .map(|stmt| stmt.span);
+        self.checker.declarations().iter().filter(move |decl| {
+            let in_block = decl.scope.is_none_or(|scope| contains(&scope, pos));
+            let in_routine = routine.is_none_or(|routine| contains(&routine, decl.span.start));
+            in_block && in_routine
+        })
+    }
+
+    /// The innermost declaration of `name` visible at `pos`.
+    fn declaration(&self, name: &str, pos: Position) -> Option<&Declaration> {
+        self.visible_declarations(pos)
+            .filter(|decl| decl.name == name && before(decl.span.start, pos))
+            .last()
+    }
+}
+
+fn contains(span: &Span, pos: Position) -> bool {
+    before(span.start, pos) && before(pos, span.end)
+}
+
+fn before(a: Position, b: Position) -> bool {
+    (a.line, a.column) <= (b.line, b.column)
+}
+
+pub struct Server {
+    documents: HashMap<String, String>,
+    shutdown_requested: bool,
+    exit_code: Option<i32>,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server::new()
+    }
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server {
+            documents: HashMap::new(),
+            shutdown_requested: false,
+            exit_code: None,
+        }
+    }
+
+    /// Handles one incoming message and returns the messages to send back:
+    /// the response for requests plus any notifications it triggers.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let id = message.get("id").cloned();
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+        let result = match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Ok(Json::Null)
+            }
+            "exit" => {
+                self.exit_code = Some(if self.shutdown_requested { 0 } else { 1 });
+                return vec![];
+            }
+            "textDocument/didOpen" => {
+                let document = params.get("textDocument");
+                let uri = document
+                    .and_then(|doc| doc.get("uri"))
+                    .and_then(Json::as_str);
+                let text = document
+                    .and_then(|doc| doc.get("text"))
+                    .and_then(Json::as_str);
+                return match (uri, text) {
+                    (Some(uri), Some(text)) => self.update(uri, text.to_string()),
+                    _ => vec![],
+                };
+            }
+            "textDocument/didChange" => {
+                let uri = document_uri(&params);
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                return match (uri, text) {
+                    (Some(uri), Some(text)) => self.update(&uri, text.to_string()),
+                    _ => vec![],
+                };
+            }
+            "textDocument/didClose" => {
+                return match document_uri(&params) {
+                    Some(uri) => {
+                        self.documents.remove(&uri);
+                        vec![publish_diagnostics(&uri, vec![])]
+                    }
+                    None => vec![],
+                };
+            }
+            "textDocument/hover" => {
+                self.with_position(&params, |analysis, pos, _| hover(analysis, pos))
+            }
+            "textDocument/definition" => self.with_position(&params, definition),
+            "textDocument/completion" => {
+                self.with_position(&params, |analysis, pos, _| completion(analysis, pos))
+            }
+            "textDocument/formatting" => match self.document(&params) {
+                Some((_, text)) => Ok(formatting(text)),
+                None => Err((INVALID_PARAMS, "Unknown document".to_string())),
+            },
+            other => Err((METHOD_NOT_FOUND, format!("Unsupported method {}", other))),
+        };
+        match id {
+            Some(id) => vec![response(id, result)],
+            // Notifications never get a response, not even an error.
+            None => vec![],
+        }
+    }
+
+    fn update(&mut self, uri: &str, text: String) -> Vec<Json> {
+        let diagnostics = diagnostics(&Analysis::new(&text), uri);
+        self.documents.insert(uri.to_string(), text);
+        vec![publish_diagnostics(uri, diagnostics)]
+    }
+
+    fn document(&self, params: &Json) -> Option<(String, &String)> {
+        let uri = document_uri(params)?;
+        let text = self.documents.get(&uri)?;
+        Some((uri, text))
+    }
+
+    fn with_position<F>(&self, params: &Json, handler: F) -> Result<Json, (i64, String)>
+    where
+        F: Fn(&Analysis, Position, &str) -> Json,
+    {
+        let (uri, text) = match self.document(params) {
+            Some(doc) => doc,
+            None => return Err((INVALID_PARAMS, "Unknown document".to_string())),
+        };
+        match params.get("position").and_then(from_lsp_position) {
+            Some(pos) => Ok(handler(&Analysis::new(text), pos, &uri)),
+            None => Err((INVALID_PARAMS, "Missing or invalid position".to_string())),
+        }
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                ("textDocumentSync", 1_i64.into()),
+                ("hoverProvider", true.into()),
+                ("definitionProvider", true.into()),
+                ("completionProvider", Json::object(vec![])),
+                ("documentFormattingProvider", true.into()),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object(vec![("name", "minipl-lsp".into())]),
+        ),
+    ])
+}
+
+fn response(id: Json, result: Result<Json, (i64, String)>) -> Json {
+    match result {
+        Ok(result) => Json::object(vec![
+            ("jsonrpc", "2.0".into()),
+            ("id", id),
+            ("result", result),
+        ]),
+        Err((code, message)) => Json::object(vec![
+            ("jsonrpc", "2.0".into()),
+            ("id", id),
+            (
+                "error",
+                Json::object(vec![("code", code.into()), ("message", message.into())]),
+            ),
+        ]),
+    }
+}
+
+fn document_uri(params: &Json) -> Option<String> {
+    params
+        .get("textDocument")
+        .and_then(|doc| doc.get("uri"))
+        .and_then(Json::as_str)
+        .map(String::from)
+}
+
+/// Converts a zero-based LSP position, `None` if missing, negative or too large.
+fn from_lsp_position(pos: &Json) -> Option<Position> {
+    let index = |field| {
+        let value = pos.get(field).and_then(Json::as_i64)?;
+        usize::try_from(value).ok()?.checked_add(1)
+    };
+    Some(Position::new(index("line")?, index("character")?))
+}
+
+fn to_lsp_position(pos: Position) -> Json {
+    Json::object(vec![
+        ("line", pos.line.saturating_sub(1).into()),
+        ("character", pos.column.saturating_sub(1).into()),
+    ])
+}
+
+fn to_lsp_range(span: Span) -> Json {
+    Json::object(vec![
+        ("start", to_lsp_position(span.start)),
+        ("end", to_lsp_position(span.end)),
+    ])
+}
+
+/// The diagnostic in LSP form. Notes and help follow the message on lines
+/// of their own, and labels become related information.
+fn to_lsp_diagnostic(diagnostic: &Diagnostic, uri: &str) -> Json {
+    let severity = match diagnostic.severity {
+        Severity::Error => SEVERITY_ERROR,
+        Severity::Warning => SEVERITY_WARNING,
+    };
+    let mut message = diagnostic.message.clone();
+    for note in &diagnostic.notes {
+        message.push_str(&format!("\nnote: {}", note));
+    }
+    if let Some(help) = &diagnostic.help {
+        message.push_str(&format!("\nhelp: {}", help));
+    }
+    let mut fields = vec![
+        ("range", to_lsp_range(diagnostic.span)),
+        ("severity", severity.into()),
+        ("code", diagnostic.code.into()),
+        ("source", "minipl".into()),
+        ("message", message.into()),
+    ];
+    if !diagnostic.labels.is_empty() {
+        let related = diagnostic.labels.iter().map(|label| {
+            let location = Json::object(vec![
+                ("uri", uri.into()),
+                ("range", to_lsp_range(label.span)),
+            ]);
+            Json::object(vec![
+                ("location", location),
+                ("message", label.message.as_str().into()),
+            ])
+        });
+        fields.push(("relatedInformation", Json::Array(related.collect())));
+    }
+    Json::object(fields)
+}
+
+fn diagnostics(analysis: &Analysis, uri: &str) -> Vec<Json> {
+    analysis
+        .diagnostics
+        .iter()
+        .map(|diagnostic| to_lsp_diagnostic(diagnostic, uri))
+        .collect()
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        (
+            "params",
+            Json::object(vec![
+                ("uri", uri.into()),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        ),
+    ])
+}
+
+fn hover(analysis: &Analysis, pos: Position) -> Json {
+    let (name, span) = match analysis.identifier_at(pos) {
+        Some(found) => found,
+        None => return Json::Null,
+    };
+    match analysis.declaration(name, pos) {
+        Some(decl) => Json::object(vec![
+            (
+                "contents",
+                Json::object(vec![
+                    ("kind", "markdown".into()),
+                    (
+                        "value",
+                        format!("```minipl\nvar {} : {}\n```", decl.name, decl.type_def).into(),
+                    ),
+                ]),
+            ),
+            ("range", to_lsp_range(span)),
+        ]),
+        None => Json::Null,
+    }
+}
+
+fn definition(analysis: &Analysis, pos: Position, uri: &str) -> Json {
+    analysis
+        .identifier_at(pos)
+        .and_then(|(name, _)| analysis.declaration(name, pos))
+        .map(|decl| {
+            Json::object(vec![
+                ("uri", uri.into()),
+                ("range", to_lsp_range(decl.span)),
+            ])
+        })
+        .unwrap_or(Json::Null)
+}
+
+fn completion(analysis: &Analysis, pos: Position) -> Json {
+    let keywords = KEYWORDS.iter().map(|keyword| {
+        Json::object(vec![
+            ("label", (*keyword).into()),
+            ("kind", COMPLETION_KIND_KEYWORD.into()),
+        ])
+    });
+    let variables = analysis
+        .visible_declarations(pos)
+        .filter(|decl| before(decl.span.end, pos))
+        .map(|decl| {
+            Json::object(vec![
+                ("label", decl.name.as_str().into()),
+                ("kind", COMPLETION_KIND_VARIABLE.into()),
+                ("detail", decl.type_def.to_string().into()),
+            ])
+        });
+    let builtins = BUILTINS.iter().map(|builtin| {
+        Json::object(vec![
+            ("label", builtin.name.into()),
+            ("kind", COMPLETION_KIND_FUNCTION.into()),
+            ("detail", builtin.to_string().into()),
+        ])
+    });
+    Json::Array(variables.chain(builtins).chain(keywords).collect())
+}
+
+fn formatting(text: &str) -> Json {
+    let analysis = Analysis::new(text);
+    if !analysis.parsed || analysis.has_comments {
+        // The syntax tree keeps no comments, so formatting would drop them.
+        return Json::Null;
+    }
+    let formatted = format_program(&analysis.program);
+    if formatted == text {
+        return Json::Array(vec![]);
+    }
+    let lines: Vec<&str> = text.split('\n').collect();
+    let end = Position::new(lines.len(), lines[lines.len() - 1].chars().count() + 1);
+    let whole_document = Span::new(Position::new(1, 1), end);
+    Json::Array(vec![Json::object(vec![
+        ("range", to_lsp_range(whole_document)),
+        ("newText", formatted.into()),
+    ])])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::Json;
+    use crate::lsp::{read_message, write_message, Server};
+
+    const URI: &str = "file:///test.mini";
+
+    fn request(id: i64, method: &str, params: Json) -> Json {
+        Json::object(vec![
+            ("jsonrpc", "2.0".into()),
+            ("id", id.into()),
+            ("method", method.into()),
+            ("params", params),
+        ])
+    }
+
+    fn notification(method: &str, params: Json) -> Json {
+        Json::object(vec![
+            ("jsonrpc", "2.0".into()),
+            ("method", method.into()),
+            ("params", params),
+        ])
+    }
+
+    fn open(server: &mut Server, text: &str) -> Vec<Json> {
+        let document = Json::object(vec![
+            ("uri", URI.into()),
+            ("languageId", "minipl".into()),
+            ("version", 1_i64.into()),
+            ("text", text.into()),
+        ]);
+        let params = Json::object(vec![("textDocument", document)]);
+        server.handle(&notification("textDocument/didOpen", params))
+    }
+
+    fn at(line: i64, character: i64) -> Json {
+        Json::object(vec![
+            ("textDocument", Json::object(vec![("uri", URI.into())])),
+            (
+                "position",
+                Json::object(vec![("line", line.into()), ("character", character.into())]),
+            ),
+        ])
+    }
+
+    fn result(mut messages: Vec<Json>) -> Json {
+        assert_eq!(messages.len(), 1);
+        messages.remove(0).get("result").cloned().unwrap()
+    }
+
+    fn diagnostics(messages: &[Json]) -> Vec<String> {
+        let params = messages[0].get("params").unwrap();
+        params
+            .get("diagnostics")
+            .and_then(Json::as_array)
+            .unwrap()
+            .iter()
+            .map(|d| d.get("message").and_then(Json::as_str).unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn publish_diagnostics() {
+        let mut server = Server::new();
+        let messages = open(&mut server, "var x : int := \"a\";\nprint y;");
+        assert_eq!(
+            diagnostics(&messages),
+            vec![
+                "Mismatched types: expected int got string",
+                "Variable y is not declared"
+            ]
+        );
+
+        let change = Json::object(vec![
+            ("textDocument", Json::object(vec![("uri", URI.into())])),
+            (
+                "contentChanges",
+                Json::Array(vec![Json::object(vec![("text", "print 1);".into())])]),
+            ),
+        ]);
+        let messages = server.handle(&notification("textDocument/didChange", change));
+        assert_eq!(
+            diagnostics(&messages),
+            vec!["Expected ; got )", "Unexpected token: ;"]
+        );
+
+        let messages = open(&mut server, "var count : int;\nread counter;");
+        assert_eq!(
+            diagnostics(&messages),
+            vec!["Variable counter is not declared\nhelp: did you mean `count` instead of `counter`?"]
+        );
+    }
+
+    #[test]
+    fn publish_warnings() {
+        let mut server = Server::new();
+        let messages = open(&mut server, "var x : int;\nprint x;");
+        assert_eq!(
+            diagnostics(&messages),
+            vec!["Variable x may be read before it is assigned"]
+        );
+        let params = messages[0].get("params").unwrap();
+        let diagnostic = &params.get("diagnostics").and_then(Json::as_array).unwrap()[0];
+        assert_eq!(diagnostic.get("severity"), Some(&Json::from(2)));
+        assert_eq!(diagnostic.get("code"), Some(&Json::from("W0006")));
+    }
+
+    #[test]
+    fn hover_and_definition() {
+        let mut server = Server::new();
+        open(&mut server, "var count : int;\ncount := count + 1;");
+        let hover = result(server.handle(&request(1, "textDocument/hover", at(1, 11))));
+        assert_eq!(
+            hover.get("contents").and_then(|c| c.get("value")),
+            Some(&Json::from("```minipl\nvar count : int\n```"))
+        );
+        let definition = result(server.handle(&request(2, "textDocument/definition", at(1, 0))));
+        assert_eq!(
+            definition.get("range").map(Json::to_string),
+            Some(
+                r#"{"start":{"line":0,"character":0},"end":{"line":0,"character":16}}"#.to_string()
+            )
+        );
+        let nothing = result(server.handle(&request(3, "textDocument/hover", at(0, 0))));
+        assert_eq!(nothing, Json::Null);
+    }
+
+    #[test]
+    fn resolve_parameters_before_globals() {
+        let mut server = Server::new();
+        let text = "var n : string := \"a\";\nprocedure p(n : int)\n    print n;\nend procedure;\nprint n;";
+        open(&mut server, text);
+        let hover_type = |server: &mut Server, line, character| {
+            let hover =
+                result(server.handle(&request(1, "textDocument/hover", at(line, character))));
+            hover.get("contents").and_then(|c| c.get("value")).cloned()
+        };
+        let int = Some(Json::from("```minipl\nvar n : int\n```"));
+        let string = Some(Json::from("```minipl\nvar n : string\n```"));
+        assert_eq!(hover_type(&mut server, 1, 12), int);
+        assert_eq!(hover_type(&mut server, 2, 10), int);
+        assert_eq!(hover_type(&mut server, 4, 6), string);
+        let definition = result(server.handle(&request(2, "textDocument/definition", at(2, 10))));
+        assert_eq!(
+            definition.get("range").map(Json::to_string),
+            Some(
+                r#"{"start":{"line":1,"character":12},"end":{"line":1,"character":19}}"#
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn complete_keywords_and_variables() {
+        let mut server = Server::new();
+        open(&mut server, "var a : int;\n\nvar b : int;");
+        let items = result(server.handle(&request(1, "textDocument/completion", at(1, 0))));
+        let labels: Vec<&str> = items
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item.get("label").and_then(Json::as_str).unwrap())
+            .collect();
+        assert!(labels.contains(&"a"));
+        assert!(!labels.contains(&"b"));
+        assert!(labels.contains(&"print"));
+    }
+
+    #[test]
+    fn complete_variables_in_scope() {
+        let mut server = Server::new();
+        let text = "var i : int;\nfor i in 1..2 do\n    var sq : int := i * i;\n    \n\
+                    end for;\n\nprocedure p(n : string)\n    \nend procedure;\nvar n : int;\n";
+        open(&mut server, text);
+        let variables = |server: &mut Server, line, character| -> Vec<String> {
+            let items =
+                result(server.handle(&request(1, "textDocument/completion", at(line, character))));
+            let items = items.as_array().unwrap().iter();
+            items
+                .filter(|item| item.get("kind") == Some(&Json::from(6)))
+                .map(|item| {
+                    let label = item.get("label").and_then(Json::as_str).unwrap();
+                    let detail = item.get("detail").and_then(Json::as_str).unwrap();
+                    format!("{} : {}", label, detail)
+                })
+                .collect()
+        };
+        assert_eq!(variables(&mut server, 3, 4), vec!["i : int", "sq : int"]);
+        assert_eq!(variables(&mut server, 5, 0), vec!["i : int"]);
+        assert_eq!(variables(&mut server, 7, 4), vec!["n : string"]);
+        assert_eq!(variables(&mut server, 10, 0), vec!["i : int", "n : int"]);
+    }
+
+    #[test]
+    fn format_document() {
+        let mut server = Server::new();
+        open(&mut server, "var x:int;\nx:=1+2;");
+        let params = Json::object(vec![(
+            "textDocument",
+            Json::object(vec![("uri", URI.into())]),
+        )]);
+        let edits = result(server.handle(&request(1, "textDocument/formatting", params)));
+        let edit = &edits.as_array().unwrap()[0];
+        assert_eq!(
+            edit.get("newText").and_then(Json::as_str),
+            Some("var x : int;\nx := 1 + 2;\n")
+        );
+        assert_eq!(
+            edit.get("range").map(Json::to_string),
+            Some(
+                r#"{"start":{"line":0,"character":0},"end":{"line":1,"character":7}}"#.to_string()
+            )
+        );
+
+        open(&mut server, "var x:int; // counter");
+        let params = Json::object(vec![(
+            "textDocument",
+            Json::object(vec![("uri", URI.into())]),
+        )]);
+        let edits = result(server.handle(&request(2, "textDocument/formatting", params)));
+        assert_eq!(edits, Json::Null);
+    }
+
+    #[test]
+    fn reject_unknown_requests() {
+        let mut server = Server::new();
+        let messages = server.handle(&request(7, "workspace/symbol", Json::Null));
+        let error = messages[0].get("error").unwrap();
+        assert_eq!(error.get("code").and_then(Json::as_i64), Some(-32601));
+        assert!(server
+            .handle(&notification("$/cancelRequest", Json::Null))
+            .is_empty());
+    }
+
+    #[test]
+    fn reject_invalid_positions() {
+        let mut server = Server::new();
+        open(&mut server, "print 1;");
+        for (id, position) in vec![at(-1, 0), at(0, -5)].into_iter().enumerate() {
+            let messages = server.handle(&request(id as i64, "textDocument/hover", position));
+            let error = messages[0].get("error").unwrap();
+            assert_eq!(error.get("code").and_then(Json::as_i64), Some(-32602));
+        }
+    }
+
+    #[test]
+    fn frame_messages() {
+        let message = request(1, "shutdown", Json::Null);
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &message).unwrap();
+        let text = String::from_utf8(buffer.clone()).unwrap();
+        assert!(text.starts_with("Content-Length: 58\r\n\r\n"));
+        let mut reader = &buffer[..];
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+}
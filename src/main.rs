@@ -1,26 +1,50 @@
-extern crate regex;
-
-mod ast;
-mod evaluator;
-mod lexer;
-mod parser;
-mod token;
-mod utils;
-
-use evaluator::Evaluator;
-use lexer::Lexer;
-use parser::Parser;
+use minipl_interpreter::ast::Program;
+use minipl_interpreter::backend::c::CBackend;
+use minipl_interpreter::backend::javascript::JavaScriptBackend;
+use minipl_interpreter::backend::llvm::LlvmBackend;
+use minipl_interpreter::backend::python::PythonBackend;
+use minipl_interpreter::backend::wat::WatBackend;
+use minipl_interpreter::checker::Checker;
+use minipl_interpreter::cli::{self, Command, Emit, Format, Options};
+use minipl_interpreter::debugger::Debugger;
+use minipl_interpreter::diagnostic::{Diagnostic, Renderer, Report, Severity, Style, ToDiagnostic};
+use minipl_interpreter::evaluator::Evaluator;
+use minipl_interpreter::explain::{self, Coded, EXPLANATIONS};
+use minipl_interpreter::formatter::format_variable;
+use minipl_interpreter::ir::analysis::uninitialized_reads;
+use minipl_interpreter::ir::lower::Lowering;
+use minipl_interpreter::json::{Json, ToJson};
+use minipl_interpreter::lexer::Lexer;
+use minipl_interpreter::lint::Linter;
+use minipl_interpreter::optimizer::Optimizer;
+use minipl_interpreter::parser::Parser;
+use minipl_interpreter::tracer::Tracer;
+use minipl_interpreter::utils::Span;
 use std::env;
 use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
 use std::process;
-use utils::EvalError;
+use std::time::Instant;
+
+const JSON_SCHEMA_VERSION: i64 = 1;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let file_path = match args.get(1) {
-        Some(path) => path,
-        None => "",
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match cli::parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
     };
+    if options.command == Command::Explain {
+        process::exit(if explain(&options.file_path) { 0 } else { 1 });
+    }
+    if options.llvm_runtime {
+        print!("{}", LlvmBackend::runtime());
+        process::exit(0);
+    }
+    let file_path = &options.file_path;
     let file = match fs::read_to_string(file_path) {
         Ok(file) => file,
         _ => {
@@ -29,34 +53,434 @@ fn main() {
         }
     };
 
-    match interpret(file) {
-        Ok(_) => {
-            println!("\nSuccess!");
-            process::exit(0);
+    let mut reporter = Reporter::new(&file, &options);
+    let report = &mut reporter.report;
+    let ok = match (options.emit, options.command) {
+        (Some(Emit::Tokens), _) => emit_tokens(&file, options.format, report),
+        (Some(Emit::Ast), _) => emit_ast(&file, &options, report),
+        (Some(Emit::Ir), _) => emit_ir(&file, &options, report),
+        (None, Command::EmitC) => emit_c(&file, &options, report),
+        (None, Command::EmitWat) => emit_wat(&file, &options, report),
+        (None, Command::EmitLlvm) => emit_llvm(&file, &options, report),
+        (None, Command::Lint) => lint(&file, &options, report),
+        (None, Command::EmitPy | Command::EmitJs) => emit_script(&file, &options, report),
+        (None, _) => {
+            let ok = interpret(&file, &options, &mut reporter);
+            if ok && options.format == Format::Text {
+                println!("\nSuccess!");
+            }
+            ok
         }
+    };
+    reporter.finish();
+    process::exit(if ok { 0 } else { 1 });
+}
+
+/// Parses and type checks the program, adding the errors and warnings to
+/// `report`. `None` if the program has errors.
+fn check(file: &str, options: &Options, report: &mut Report) -> Option<Program> {
+    let lexer = Lexer::new(file.to_string());
+    let mut parser = Parser::new(lexer).with_integer_width(options.integer_width);
+
+    let program = parser.parse_program();
+    report.extend(parser.diagnostics());
+    if report.has_errors() {
+        return None;
+    }
+
+    let mut checker = Checker::new();
+    for (id, type_def, _) in &options.variables {
+        checker = checker.with_variable(id, type_def.clone());
+    }
+    checker.check_program(&program);
+    report.extend(checker.diagnostics());
+    if report.has_errors() {
+        return None;
+    }
+    let mut lowering = Lowering::new();
+    for (id, type_def, _) in &options.variables {
+        lowering = lowering.with_variable(id, type_def.clone());
+    }
+    if let Ok(module) = lowering.lower_program(&program) {
+        let warnings = uninitialized_reads(&module);
+        report.extend(warnings.iter().map(ToDiagnostic::to_diagnostic));
+    }
+    Some(optimize(program, options, report))
+}
+
+/// Runs the checked program. The diagnostics of the checks are written
+/// before it starts, and a runtime error ends it.
+fn interpret(file: &str, options: &Options, reporter: &mut Reporter) -> bool {
+    let program = match check(file, options, &mut reporter.report) {
+        Some(program) => program,
+        None => return false,
+    };
+    reporter.flush();
+    let mut evaluator = Evaluator::new(program).with_integer_width(options.integer_width);
+    if let Some(limit) = options.max_steps {
+        evaluator = evaluator.with_step_limit(limit);
+    }
+    if let Some(limit) = options.max_output {
+        evaluator = evaluator.with_output_limit(limit);
+    }
+    if let Some(limit) = options.max_string {
+        evaluator = evaluator.with_string_limit(limit);
+    }
+    if let Some(timeout) = options.timeout {
+        evaluator = evaluator.with_deadline(Instant::now() + timeout);
+    }
+    if options.trace {
+        evaluator = evaluator.with_hook(Box::new(tracer(options)));
+    }
+    if options.command == Command::Debug {
+        evaluator = evaluator.with_hook(Box::new(debugger(options)));
+    }
+    for (id, type_def, val) in &options.variables {
+        if let Err(err) = evaluator.define_variable(id, type_def.clone(), val.clone()) {
+            let message = format!("Cannot set {}: {}", id, err.message());
+            let diagnostic = Diagnostic::new(Severity::Error, err.code(), message, Span::default());
+            reporter.report.push(diagnostic);
+            return false;
+        }
+    }
+    let ok = match evaluator.evaluate_program() {
+        Ok(()) => true,
         Err(err) => {
-            eprintln!("\n{}", err);
-            process::exit(1);
+            reporter.report.push(evaluator.diagnostic(err));
+            false
+        }
+    };
+    // The output of the program need not end with a newline.
+    if options.format == Format::Text && !ok {
+        eprintln!();
+    }
+    reporter.flush();
+    if options.dump_variables {
+        dump_variables(&evaluator, options.format);
+    }
+    ok
+}
+
+/// Runs the optimiser if `--optimize` is given. Its warnings do not stop
+/// the program.
+fn optimize(program: Program, options: &Options, report: &mut Report) -> Program {
+    if !options.optimize {
+        return program;
+    }
+    let mut optimizer = Optimizer::new().with_integer_width(options.integer_width);
+    if let Some(limit) = options.max_string {
+        optimizer = optimizer.with_string_limit(limit);
+    }
+    if options.remove_true_asserts {
+        optimizer = optimizer.with_assert_removal();
+    }
+    let program = optimizer.optimize_program(program);
+    let warnings = optimizer.get_warnings();
+    report.extend(warnings.iter().map(ToDiagnostic::to_diagnostic));
+    program
+}
+
+/// Writes the variables as they were when the program ended to stderr.
+fn dump_variables(evaluator: &Evaluator, format: Format) {
+    let variables = evaluator.variables();
+    match format {
+        Format::Text => {
+            for (id, type_def, val) in variables {
+                eprintln!("{}", format_variable(id, type_def, val));
+            }
+        }
+        Format::Json => {
+            let variables: Vec<Json> = variables
+                .into_iter()
+                .map(|(id, type_def, val)| {
+                    Json::object(vec![
+                        ("name", Json::from(id)),
+                        ("type", type_def.to_json()),
+                        ("value", val.map_or(Json::Null, ToJson::to_json)),
+                    ])
+                })
+                .collect();
+            eprintln!(
+                "{}",
+                Json::object(vec![("variables", Json::Array(variables))])
+            );
         }
     }
 }
 
-fn interpret(file: String) -> Result<(), EvalError> {
-    let lexer = Lexer::new(file);
-    let mut parser = Parser::new(lexer);
+/// Debugger reading commands from the `--commands` file or stdin and
+/// writing to stderr, so that its transcript stays apart from the program's
+/// output.
+fn debugger(options: &Options) -> Debugger {
+    let commands: Box<dyn BufRead> = match &options.debug_commands {
+        Some(path) => match fs::File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(_) => {
+                eprintln!("File {} not found!", path);
+                process::exit(1);
+            }
+        },
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+    Debugger::new(commands, Box::new(io::stderr()))
+}
 
-    let program = parser.parse_program();
-    let syntax_errors = parser.get_errors();
-    match syntax_errors.is_empty() {
-        false => {
-            for err in syntax_errors {
-                eprintln!("{}", err);
+fn tracer(options: &Options) -> Tracer {
+    let output: Box<dyn Write> = match &options.trace_file {
+        Some(path) => match fs::File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(err) => {
+                eprintln!("Cannot create {}: {}", path, err);
+                process::exit(1);
             }
-            Err(EvalError::SyntaxError)
+        },
+        None => Box::new(io::stderr()),
+    };
+    Tracer::new(output, options.trace_format)
+}
+
+/// Writes diagnostics to stderr, in the `--diagnostics` style or as JSON
+/// lines, and collects them for the summary. Rich diagnostics are colored on
+/// terminals, unless `NO_COLOR` is set.
+struct Reporter<'a> {
+    report: Report,
+    renderer: Renderer<'a>,
+    format: Format,
+    /// Number of diagnostics in `report` written so far.
+    written: usize,
+}
+
+impl<'a> Reporter<'a> {
+    fn new(file: &'a str, options: &'a Options) -> Self {
+        let mut renderer = Renderer::new(file, options.diagnostics).with_path(&options.file_path);
+        let color = io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none();
+        if options.diagnostics == Style::Rich && color {
+            renderer = renderer.with_color();
         }
-        _ => {
-            let mut evaluator = Evaluator::new(program);
-            evaluator.evaluate_program()
+        Reporter {
+            report: Report::new(),
+            renderer,
+            format: options.format,
+            written: 0,
+        }
+    }
+
+    /// Writes the diagnostics added since the last call.
+    fn flush(&mut self) {
+        for diagnostic in &self.report.diagnostics()[self.written..] {
+            match self.format {
+                Format::Text => eprint!("{}", self.renderer.render(diagnostic)),
+                Format::Json => eprintln!("{}", diagnostic.to_json()),
+            }
+        }
+        self.written = self.report.diagnostics().len();
+    }
+
+    /// Writes the remaining diagnostics and the summary of all of them.
+    fn finish(mut self) {
+        self.flush();
+        if self.format == Format::Text {
+            eprint!("{}", self.renderer.render_summary(&self.report));
+        }
+    }
+}
+
+/// Prints the explanation of `code`, or a list of all codes when it is
+/// empty.
+fn explain(code: &str) -> bool {
+    if code.is_empty() {
+        for explanation in EXPLANATIONS {
+            println!("{}: {}", explanation.code, explanation.title);
+        }
+        return true;
+    }
+    match explain::find(code) {
+        Some(explanation) => {
+            print!("{}", explanation);
+            true
+        }
+        None => {
+            eprintln!("Unknown code {}", code);
+            false
+        }
+    }
+}
+
+fn emit_tokens(file: &str, format: Format, report: &mut Report) -> bool {
+    let mut lexer = Lexer::new(file.to_string());
+    let tokens = lexer.get_all_tokens();
+    match format {
+        Format::Text => {
+            for (token, span) in &tokens {
+                println!("{}\t{:?}", span, token);
+            }
+        }
+        Format::Json => {
+            let doc = Json::object(vec![
+                ("version", JSON_SCHEMA_VERSION.into()),
+                ("tokens", Json::array(&tokens)),
+            ]);
+            println!("{}", doc);
+        }
+    }
+    let errors = lexer.get_errors();
+    report.extend(errors.iter().map(ToDiagnostic::to_diagnostic));
+    errors.is_empty()
+}
+
+/// Prints the parsed program. With `--format=json` the errors are part of
+/// the document instead of the report.
+fn emit_ast(file: &str, options: &Options, report: &mut Report) -> bool {
+    let lexer = Lexer::new(file.to_string());
+    let mut parser = Parser::new(lexer).with_integer_width(options.integer_width);
+    let mut program = parser.parse_program();
+    let errors = parser.diagnostics();
+    if errors.is_empty() {
+        program = optimize(program, options, report);
+    }
+    let ok = errors.is_empty();
+    match options.format {
+        Format::Text => {
+            for stmt in &program.statements {
+                println!("{}\t{}", stmt.span, stmt);
+            }
+            report.extend(errors);
+        }
+        Format::Json => {
+            let doc = Json::object(vec![
+                ("version", JSON_SCHEMA_VERSION.into()),
+                ("program", program.to_json()),
+                ("errors", Json::array(&errors)),
+            ]);
+            println!("{}", doc);
+        }
+    }
+    ok
+}
+
+fn emit_ir(file: &str, options: &Options, report: &mut Report) -> bool {
+    let program = match check(file, options, report) {
+        Some(program) => program,
+        None => return false,
+    };
+    let mut lowering = Lowering::new();
+    for (id, type_def, _) in &options.variables {
+        lowering = lowering.with_variable(id, type_def.clone());
+    }
+    match lowering.lower_program(&program) {
+        Ok(module) => {
+            print!("{}", module);
+            true
+        }
+        Err(err) => {
+            report.push(err.to_diagnostic());
+            false
+        }
+    }
+}
+
+/// Reports the lints of a checked program. Fails on errors and on lints
+/// raised to `--deny`.
+fn lint(file: &str, options: &Options, report: &mut Report) -> bool {
+    let mut lexer = Lexer::new(file.to_string());
+    lexer.get_all_tokens();
+    let program = match check(file, options, report) {
+        Some(program) => program,
+        None => return false,
+    };
+    let mut linter = Linter::new()
+        .with_integer_width(options.integer_width)
+        .with_comments(lexer.comments());
+    for (lint, level) in &options.lint_levels {
+        linter = linter.with_level(*lint, *level);
+    }
+    let warnings = linter.lint_program(&program);
+    report.extend(warnings.iter().map(ToDiagnostic::to_diagnostic));
+    !report.has_errors()
+}
+
+fn emit_c(file: &str, options: &Options, report: &mut Report) -> bool {
+    let program = match check(file, options, report) {
+        Some(program) => program,
+        None => return false,
+    };
+    let mut backend = CBackend::new().with_integer_width(options.integer_width);
+    if let Some(limit) = options.max_string {
+        backend = backend.with_string_limit(limit);
+    }
+    match backend.emit_program(&program) {
+        Ok(source) => {
+            print!("{}", source);
+            true
+        }
+        Err(err) => {
+            report.push(err.to_diagnostic());
+            false
+        }
+    }
+}
+
+fn emit_wat(file: &str, options: &Options, report: &mut Report) -> bool {
+    let program = match check(file, options, report) {
+        Some(program) => program,
+        None => return false,
+    };
+    let mut backend = WatBackend::new();
+    if let Some(limit) = options.max_string {
+        backend = backend.with_string_limit(limit);
+    }
+    match backend.emit_program(&program) {
+        Ok(source) => {
+            print!("{}", source);
+            true
+        }
+        Err(err) => {
+            report.push(err.to_diagnostic());
+            false
+        }
+    }
+}
+
+fn emit_llvm(file: &str, options: &Options, report: &mut Report) -> bool {
+    let program = match check(file, options, report) {
+        Some(program) => program,
+        None => return false,
+    };
+    let mut backend = LlvmBackend::new().with_integer_width(options.integer_width);
+    if let Some(limit) = options.max_string {
+        backend = backend.with_string_limit(limit);
+    }
+    match backend.emit_program(&program) {
+        Ok(source) => {
+            print!("{}", source);
+            true
+        }
+        Err(err) => {
+            report.push(err.to_diagnostic());
+            false
+        }
+    }
+}
+
+fn emit_script(file: &str, options: &Options, report: &mut Report) -> bool {
+    let program = match check(file, options, report) {
+        Some(program) => program,
+        None => return false,
+    };
+    let source = match options.command {
+        Command::EmitPy => PythonBackend::new()
+            .with_integer_width(options.integer_width)
+            .emit_program(&program),
+        _ => JavaScriptBackend::new().emit_program(&program),
+    };
+    match source {
+        Ok(source) => {
+            print!("{}", source);
+            true
+        }
+        Err(err) => {
+            report.push(err.to_diagnostic());
+            false
         }
     }
 }
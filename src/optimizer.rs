@@ -0,0 +1,280 @@
+//! Optional pass between type checking and evaluation that simplifies the
+//! syntax tree without changing what the program does.
+//!
+//! Constant subexpressions, such as `1 + (2 * 6)` or `"a" + "b"`, are
+//! replaced by their value, `!(!x)` by `x` and `x & true` by `x`. Constants
+//! are computed by the evaluator itself, so folding follows the integer width,
+//! string limit and error rules of a real run. An expression that would fail,
+//! such as `1 / 0`, is reported as a warning and left for the evaluator, which
+//! fails on it only if it actually runs.
+
+use crate::ast::{
+    BinaryOperator, Expression, ExpressionKind, Program, Routine, Statement, StatementKind,
+    UnaryOperator,
+};
+use crate::evaluator::Evaluator;
+use crate::utils::{IntegerWidth, SpannedWarning, Value, Warning};
+use std::io;
+
+pub struct Optimizer {
+    evaluator: Evaluator,
+    remove_true_asserts: bool,
+    warnings: Vec<SpannedWarning>,
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Optimizer::new()
+    }
+}
+
+impl Optimizer {
+    pub fn new() -> Self {
+        let program = Program {
+            statements: Vec::new(),
+        };
+        let evaluator =
+            Evaluator::new(program).with_io(Box::new(io::empty()), Box::new(io::sink()));
+        Optimizer {
+            evaluator,
+            remove_true_asserts: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Folds integers as an evaluator with the same `width` would.
+    pub fn with_integer_width(mut self, width: IntegerWidth) -> Self {
+        self.evaluator = self.evaluator.with_integer_width(width);
+        self
+    }
+
+    /// Leaves concatenations longer than `limit` bytes to the evaluator.
+    pub fn with_string_limit(mut self, limit: usize) -> Self {
+        self.evaluator = self.evaluator.with_string_limit(limit);
+        self
+    }
+
+    /// Removes `assert` statements whose condition folds to `true`. They can
+    /// never fail, but they no longer show up in traces or the debugger.
+    pub fn with_assert_removal(mut self) -> Self {
+        self.remove_true_asserts = true;
+        self
+    }
+
+    pub fn get_warnings(&self) -> &[SpannedWarning] {
+        &self.warnings
+    }
+
+    pub fn optimize_program(&mut self, program: Program) -> Program {
+        Program {
+            statements: self.optimize_block(program.statements),
+        }
+    }
+
+    fn optimize_block(&mut self, statements: Vec<Statement>) -> Vec<Statement> {
+        statements
+            .into_iter()
+            .filter_map(|stmt| self.optimize_statement(stmt))
+            .collect()
+    }
+
+    /// The optimised statement, or `None` if it can be removed.
+    fn optimize_statement(&mut self, statement: Statement) -> Option<Statement> {
+        let kind = match statement.kind {
+            StatementKind::NewAssignment(id, type_def, exp) => {
+                StatementKind::NewAssignment(id, type_def, self.optimize_expression(exp))
+            }
+            StatementKind::Assignment(id, exp) => {
+                StatementKind::Assignment(id, self.optimize_expression(exp))
+            }
+            StatementKind::IndexAssignment(id, index, exp) => StatementKind::IndexAssignment(
+                id,
+                self.optimize_expression(index),
+                self.optimize_expression(exp),
+            ),
+            StatementKind::Print(exp) => StatementKind::Print(self.optimize_expression(exp)),
+            StatementKind::Assert(exp) => {
+                let exp = self.optimize_expression(exp);
+                if self.remove_true_asserts && exp.kind == ExpressionKind::Boolean(true) {
+                    return None;
+                }
+                StatementKind::Assert(exp)
+            }
+            StatementKind::For(id, start, end, stmts) => StatementKind::For(
+                id,
+                self.optimize_expression(start),
+                self.optimize_expression(end),
+                self.optimize_block(stmts),
+            ),
+            StatementKind::Routine(routine) => StatementKind::Routine(Routine {
+                body: self.optimize_block(routine.body),
+                ..routine
+            }),
+            StatementKind::Call(name, args) => StatementKind::Call(name, self.optimize_all(args)),
+            StatementKind::Return(exp) => {
+                StatementKind::Return(exp.map(|exp| self.optimize_expression(exp)))
+            }
+            kind @ (StatementKind::VarInitialization(..) | StatementKind::Read(_)) => kind,
+        };
+        Some(Statement::new(kind, statement.span))
+    }
+
+    fn optimize_all(&mut self, exps: Vec<Expression>) -> Vec<Expression> {
+        exps.into_iter()
+            .map(|exp| self.optimize_expression(exp))
+            .collect()
+    }
+
+    pub fn optimize_expression(&mut self, exp: Expression) -> Expression {
+        let span = exp.span;
+        let kind = match exp.kind {
+            ExpressionKind::Unary(op, operand) => {
+                let operand = self.optimize_expression(*operand);
+                match (op, operand.kind) {
+                    (UnaryOperator::Not, ExpressionKind::Unary(UnaryOperator::Not, inner)) => {
+                        return *inner;
+                    }
+                    (op, kind) => {
+                        let operand = Expression::new(kind, operand.span);
+                        ExpressionKind::Unary(op, Box::new(operand))
+                    }
+                }
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                let left = self.optimize_expression(*left);
+                let right = self.optimize_expression(*right);
+                let is_true = |exp: &Expression| exp.kind == ExpressionKind::Boolean(true);
+                match op {
+                    // Both sides are always evaluated, so dropping a `true`
+                    // operand cannot skip an error.
+                    BinaryOperator::And if is_true(&left) => return right,
+                    BinaryOperator::And if is_true(&right) => return left,
+                    op => ExpressionKind::Binary(Box::new(left), op, Box::new(right)),
+                }
+            }
+            ExpressionKind::Call(name, args) => ExpressionKind::Call(name, self.optimize_all(args)),
+            ExpressionKind::Index(id, index) => {
+                ExpressionKind::Index(id, Box::new(self.optimize_expression(*index)))
+            }
+            kind => return Expression::new(kind, span),
+        };
+        self.fold(Expression::new(kind, span))
+    }
+
+    /// Replaces an operator applied to constants by its value.
+    fn fold(&mut self, exp: Expression) -> Expression {
+        let operands_constant = match &exp.kind {
+            ExpressionKind::Unary(_, operand) => is_constant(operand),
+            ExpressionKind::Binary(left, _, right) => is_constant(left) && is_constant(right),
+            _ => false,
+        };
+        if !operands_constant {
+            return exp;
+        }
+        let kind = match self.evaluator.evaluate_constant(exp.clone()) {
+            Ok(Value::Integer(int)) => ExpressionKind::IntegerConstant(int),
+            Ok(Value::String(s)) => ExpressionKind::StringValue(s),
+            Ok(Value::Bool(b)) => ExpressionKind::Boolean(b),
+            Ok(Value::Array(_)) => return exp,
+            Err(err) => {
+                self.warnings.push((Warning::Failure(err), exp.span));
+                return exp;
+            }
+        };
+        Expression::new(kind, exp.span)
+    }
+}
+
+fn is_constant(exp: &Expression) -> bool {
+    matches!(
+        exp.kind,
+        ExpressionKind::IntegerConstant(_)
+            | ExpressionKind::StringValue(_)
+            | ExpressionKind::Boolean(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{Program, Statement, StatementKind};
+    use crate::formatter::format_program;
+    use crate::lexer::Lexer;
+    use crate::optimizer::Optimizer;
+    use crate::parser::Parser;
+    use crate::utils::{EvalError, IntegerWidth, Warning};
+
+    fn parse(source: &str) -> Program {
+        let mut parser = Parser::new(Lexer::new(source.to_string()));
+        let program = parser.parse_program();
+        assert!(parser.get_errors().is_empty());
+        program
+    }
+
+    fn optimize(optimizer: &mut Optimizer, source: &str) -> String {
+        format_program(&optimizer.optimize_program(parse(source)))
+    }
+
+    #[test]
+    fn fold_constants() {
+        let source = r#"
+            var X : int := 1 + (2 * 6);
+            var s : string := ("a" + "b") + s;
+            var b : bool := !(!(x < (2 + 3)));
+            var c : bool := (true & (!false)) & b;
+            var n : int := 0 - 5;
+            for i in 0..10 / 2 do
+                print (1 < 2) = (2 > 1);
+            end for;
+        "#;
+        let expected = r#"var X : int := 13;
+var s : string := "ab" + s;
+var b : bool := x < 5;
+var c : bool := b;
+var n : int := (0 - 5);
+for i in 0..5 do
+    print true;
+end for;
+"#;
+        assert_eq!(optimize(&mut Optimizer::new(), source), expected);
+    }
+
+    #[test]
+    fn keep_failing_expressions() {
+        let source = "print 1 / 0;\nprint 2147483647 + 1;";
+        let mut optimizer = Optimizer::new();
+        assert_eq!(
+            optimize(&mut optimizer, source),
+            "print 1 / 0;\nprint 2147483647 + 1;\n"
+        );
+        let warnings: Vec<_> = optimizer.get_warnings().iter().map(|w| &w.0).collect();
+        assert!(matches!(
+            warnings.as_slice(),
+            [
+                Warning::Failure(EvalError::DivisionByZero),
+                Warning::Failure(EvalError::IntegerOverflow)
+            ]
+        ));
+        let mut wide = Optimizer::new().with_integer_width(IntegerWidth::I64);
+        assert_eq!(
+            optimize(&mut wide, "print 2147483647 + 1;"),
+            "print 2147483648;\n"
+        );
+    }
+
+    #[test]
+    fn remove_true_asserts() {
+        let source = "assert (1 < 2);\nassert (x = 1);\nassert (2 < 1);";
+        let mut optimizer = Optimizer::new().with_assert_removal();
+        let program = optimizer.optimize_program(parse(source));
+        let conditions: Vec<String> = program
+            .statements
+            .iter()
+            .map(|stmt: &Statement| match &stmt.kind {
+                StatementKind::Assert(exp) => exp.to_string(),
+                _ => String::new(),
+            })
+            .collect();
+        assert_eq!(conditions, vec!["(x, =, 1)", "false"]);
+        assert_eq!(optimize(&mut Optimizer::new(), source).lines().count(), 3);
+    }
+}
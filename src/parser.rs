@@ -1,15 +1,35 @@
-use crate::ast::{BinaryOperator, Expression, Program, Statement, UnaryOperator};
+use crate::ast::{
+    BinaryOperator, Expression, ExpressionKind, Parameter, Program, Routine, Statement,
+    StatementKind, UnaryOperator,
+};
+use crate::builtins::SIZE;
+use crate::diagnostic::{Diagnostic, ToDiagnostic};
 use crate::lexer::Lexer;
-use crate::token::Token;
-use crate::utils::{ParseError, Type};
+use crate::suggest::{did_you_mean, suggest};
+use crate::token::{Token, KEYWORDS};
+use crate::utils::{IntegerWidth, ParseError, Reported, Span, Type};
 
 type ParseResult<T> = Result<T, ParseError>;
+pub type SpannedParseError = (ParseError, Span);
+
+/// Deepest nesting of brackets and `for` bodies accepted, so that hostile
+/// input cannot overflow the stack of the recursive descent.
+const MAX_NESTING: usize = 100;
+
+/// Largest array size accepted, so that a declaration cannot exhaust memory.
+pub const MAX_ARRAY_SIZE: usize = 1 << 20;
 
 pub struct Parser {
     lexer: Lexer,
     current_token: Token,
+    current_span: Span,
     peek_token: Token,
-    errors: Vec<ParseError>,
+    peek_span: Span,
+    errors: Vec<Reported<ParseError>>,
+    /// First token of the innermost statement being parsed.
+    statement_start: Token,
+    depth: usize,
+    integer_width: IntegerWidth,
 }
 
 impl Parser {
@@ -17,27 +37,62 @@ impl Parser {
         let mut parser = Parser {
             lexer,
             current_token: Token::EOF,
+            current_span: Span::default(),
             peek_token: Token::EOF,
+            peek_span: Span::default(),
             errors: Vec::new(),
+            statement_start: Token::EOF,
+            depth: 0,
+            integer_width: IntegerWidth::default(),
         };
         parser.next_token();
         parser.next_token();
         parser
     }
 
-    pub fn get_errors(&self) -> &[ParseError] {
+    /// Rejects integer literals that do not fit in `width`.
+    pub fn with_integer_width(mut self, width: IntegerWidth) -> Self {
+        self.integer_width = width;
+        self
+    }
+
+    /// The syntax errors, each with a help note such as a suggestion for a
+    /// misspelled keyword.
+    pub fn get_errors(&self) -> &[Reported<ParseError>] {
         &self.errors
     }
 
+    /// The errors of the lexer and of `get_errors` in source order, with
+    /// their help notes. A parse error about an illegal token is left out
+    /// for the lexer's error saying why it is illegal.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let lex_errors = self
+            .lexer
+            .get_errors()
+            .iter()
+            .map(ToDiagnostic::to_diagnostic);
+        let parse_errors = self
+            .errors
+            .iter()
+            .filter(|reported| reported.error.0.token() != Some(&Token::Illegal))
+            .map(ToDiagnostic::to_diagnostic);
+        let mut diagnostics: Vec<Diagnostic> = lex_errors.chain(parse_errors).collect();
+        diagnostics.sort_by_key(|d| (d.span.start.line, d.span.start.column));
+        diagnostics
+    }
+
     pub fn parse_program(&mut self) -> Program {
         let mut statements: Vec<Statement> = Vec::new();
         while self.current_token != Token::EOF {
+            self.depth = 0;
             match self.parse_statement() {
                 Ok(stmt) => {
                     statements.push(stmt);
                 }
                 Err(err) => {
-                    self.errors.push(err);
+                    let help = self.keyword_help(&err);
+                    let error = Reported::new(err, self.current_span);
+                    self.errors.push(Reported { help, ..error });
                 }
             };
             self.next_token();
@@ -45,31 +100,83 @@ impl Parser {
         Program { statements }
     }
 
+    /// Suggests a keyword for the identifier an error is about, as in
+    /// `var x : itn;`, or else for the identifier starting a statement that
+    /// is not an assignment, as in `pritn x;`.
+    fn keyword_help(&self, err: &ParseError) -> Option<String> {
+        let keywords = || KEYWORDS.iter().copied();
+        let about = match err.token() {
+            Some(Token::Identifier(name)) => suggest(name, keywords()).map(|kw| (name, kw)),
+            _ => None,
+        };
+        let start = match (&self.statement_start, err) {
+            (Token::Identifier(name), ParseError::ExpectedAssignment(_)) => {
+                suggest(name, keywords()).map(|kw| (name, kw))
+            }
+            _ => None,
+        };
+        about
+            .or(start)
+            .map(|(name, keyword)| did_you_mean(name, keyword))
+    }
+
     fn parse_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.current_span;
+        self.statement_start = self.current_token.clone();
+        self.enter()?;
+        let kind = self.parse_statement_kind()?;
+        self.depth -= 1;
+        Ok(Statement::new(kind, start.to(self.current_span)))
+    }
+
+    fn parse_statement_kind(&mut self) -> ParseResult<StatementKind> {
         match self.get_current_token() {
+            Token::Identifier(_) if self.peek_token == Token::LeftBracket => {
+                let (name, args) = self.parse_call()?;
+                self.next_token();
+                self.expect_current_token(Token::SemiColon, ParseError::ExpectedSemiColon)?;
+                Ok(StatementKind::Call(name, args))
+            }
+            Token::Identifier(_) if self.peek_token == Token::LeftSquareBracket => {
+                self.parse_index_assignment()
+            }
             Token::Identifier(_) => self.parse_assignment(),
             Token::Var => self.parse_new_assignment(),
             Token::For => self.parse_for(),
             Token::Assert => self.parse_assert(),
+            Token::Procedure => self.parse_routine(false),
+            Token::Function => self.parse_routine(true),
+            Token::Return => {
+                self.next_token();
+                if self.current_token == Token::SemiColon {
+                    return Ok(StatementKind::Return(None));
+                }
+                let exp = self.parse_expression()?;
+                self.next_token();
+                self.expect_current_token(Token::SemiColon, ParseError::ExpectedSemiColon)?;
+                Ok(StatementKind::Return(Some(exp)))
+            }
             Token::Print => {
                 self.next_token();
                 let exp = self.parse_expression()?;
                 self.next_token();
                 self.expect_current_token(Token::SemiColon, ParseError::ExpectedSemiColon)?;
-                Ok(Statement::Print(exp))
+                Ok(StatementKind::Print(exp))
             }
             Token::Read => {
                 self.next_token();
                 let identifier = self.parse_identifier()?;
                 self.next_token();
                 self.expect_current_token(Token::SemiColon, ParseError::ExpectedSemiColon)?;
-                Ok(Statement::Read(identifier))
+                Ok(StatementKind::Read(identifier))
             }
             other => Err(ParseError::UnexpectedToken(other)),
         }
     }
 
-    fn parse_for(&mut self) -> ParseResult<Statement> {
+    /// Parses `for i in start..end do`, or `for i in a do` over the indices
+    /// of an array, which is read as `for i in 0..size(a) - 1 do`.
+    fn parse_for(&mut self) -> ParseResult<StatementKind> {
         self.next_token();
         let identifier = self.parse_identifier()?;
         self.next_token();
@@ -77,17 +184,30 @@ impl Parser {
 
         let exp1 = self.parse_expression()?;
         self.next_token();
+        if self.current_token == Token::Do {
+            let (start, end) = array_indices(exp1);
+            return self.parse_for_body(identifier, start, end);
+        }
         self.expect_and_advance(Token::Range, ParseError::ExpectedRange)?;
 
         let exp2 = self.parse_expression()?;
         self.next_token();
 
+        self.parse_for_body(identifier, exp1, exp2)
+    }
+
+    fn parse_for_body(
+        &mut self,
+        identifier: String,
+        exp1: Expression,
+        exp2: Expression,
+    ) -> ParseResult<StatementKind> {
         self.expect_and_advance(Token::Do, ParseError::ExpectedDo)?;
 
-        let mut stmts: Vec<Box<Statement>> = Vec::new();
+        let mut stmts: Vec<Statement> = Vec::new();
         while self.current_token != Token::End {
             let stmt = self.parse_statement()?;
-            stmts.push(Box::new(stmt));
+            stmts.push(stmt);
             self.next_token();
         }
         self.next_token();
@@ -95,10 +215,88 @@ impl Parser {
         self.expect_and_advance(Token::For, ParseError::ExpectedFor)?;
         self.expect_current_token(Token::SemiColon, ParseError::ExpectedSemiColon)?;
 
-        Ok(Statement::For(identifier, exp1, exp2, stmts))
+        Ok(StatementKind::For(identifier, exp1, exp2, stmts))
+    }
+
+    /// Parses `procedure name(params) ... end procedure;` or, with a return
+    /// type after the parameters, the `function` equivalent.
+    fn parse_routine(&mut self, is_function: bool) -> ParseResult<StatementKind> {
+        self.next_token();
+        let name = self.parse_identifier()?;
+        self.next_token();
+        self.expect_and_advance(Token::LeftBracket, ParseError::ExpectedLeftBracket)?;
+
+        let mut parameters = Vec::new();
+        while self.current_token != Token::RightBracket {
+            let span = self.current_span;
+            let param = self.parse_identifier()?;
+            self.next_token();
+            self.expect_and_advance(Token::Colon, ParseError::ExpectedColon)?;
+            let type_def = self.parse_type()?;
+            parameters.push(Parameter {
+                name: param,
+                type_def,
+                span: span.to(self.current_span),
+            });
+            self.next_token();
+            if self.current_token == Token::Comma {
+                self.next_token();
+            } else {
+                self.expect_current_token(Token::RightBracket, ParseError::ExpectedClosingBracket)?;
+            }
+        }
+        self.next_token();
+
+        let return_type = if is_function {
+            self.expect_and_advance(Token::Colon, ParseError::ExpectedColon)?;
+            let type_def = self.parse_type()?;
+            self.next_token();
+            Some(type_def)
+        } else {
+            None
+        };
+
+        let mut body = Vec::new();
+        while self.current_token != Token::End {
+            body.push(self.parse_statement()?);
+            self.next_token();
+        }
+        self.next_token();
+        if is_function {
+            self.expect_and_advance(Token::Function, ParseError::ExpectedFunction)?;
+        } else {
+            self.expect_and_advance(Token::Procedure, ParseError::ExpectedProcedure)?;
+        }
+        self.expect_current_token(Token::SemiColon, ParseError::ExpectedSemiColon)?;
+
+        Ok(StatementKind::Routine(Routine {
+            name,
+            parameters,
+            return_type,
+            body,
+        }))
     }
 
-    fn parse_assignment(&mut self) -> ParseResult<Statement> {
+    /// Parses `name(arg, ...)`, leaving the closing bracket as the current
+    /// token.
+    fn parse_call(&mut self) -> ParseResult<(String, Vec<Expression>)> {
+        let name = self.parse_identifier()?;
+        self.next_token();
+        self.next_token();
+        let mut args = Vec::new();
+        while self.current_token != Token::RightBracket {
+            args.push(self.parse_expression()?);
+            self.next_token();
+            if self.current_token == Token::Comma {
+                self.next_token();
+            } else {
+                self.expect_current_token(Token::RightBracket, ParseError::ExpectedClosingBracket)?;
+            }
+        }
+        Ok((name, args))
+    }
+
+    fn parse_assignment(&mut self) -> ParseResult<StatementKind> {
         let identifier = self.parse_identifier()?;
         self.next_token();
 
@@ -108,36 +306,95 @@ impl Parser {
         self.next_token();
         self.expect_current_token(Token::SemiColon, ParseError::ExpectedSemiColon)?;
 
-        Ok(Statement::Assignment(identifier, exp))
+        Ok(StatementKind::Assignment(identifier, exp))
+    }
+
+    fn parse_index_assignment(&mut self) -> ParseResult<StatementKind> {
+        let identifier = self.parse_identifier()?;
+        self.next_token();
+        let index = self.parse_index()?;
+        self.next_token();
+        self.expect_and_advance(Token::Assign, ParseError::ExpectedAssignment)?;
+        let exp = self.parse_expression()?;
+        self.next_token();
+        self.expect_current_token(Token::SemiColon, ParseError::ExpectedSemiColon)?;
+        Ok(StatementKind::IndexAssignment(identifier, index, exp))
+    }
+
+    /// Parses `[index]`, leaving the closing bracket as the current token.
+    fn parse_index(&mut self) -> ParseResult<Expression> {
+        self.next_token();
+        let index = self.parse_expression()?;
+        self.next_token();
+        self.expect_current_token(
+            Token::RightSquareBracket,
+            ParseError::ExpectedClosingSquareBracket,
+        )?;
+        Ok(index)
     }
 
-    fn parse_new_assignment(&mut self) -> ParseResult<Statement> {
+    fn parse_new_assignment(&mut self) -> ParseResult<StatementKind> {
         self.next_token();
         let identifier = self.parse_identifier()?;
         self.next_token();
 
         self.expect_and_advance(Token::Colon, ParseError::ExpectedColon)?;
 
-        let type_def = match self.get_current_token() {
-            Token::BooleanType => Type::Boolean,
-            Token::IntegerType => Type::Integer,
-            Token::StringType => Type::String,
-            invalid => return Err(ParseError::ExpectedTypeDefinition(invalid)),
-        };
+        let type_def = self.parse_type()?;
         self.next_token();
 
         if self.current_token == Token::SemiColon {
-            return Ok(Statement::VarInitialization(identifier, type_def));
+            return Ok(StatementKind::VarInitialization(identifier, type_def));
         }
 
         self.expect_and_advance(Token::Assign, ParseError::ExpectedAssignment)?;
         let exp = self.parse_expression()?;
         self.next_token();
         self.expect_current_token(Token::SemiColon, ParseError::ExpectedSemiColon)?;
-        Ok(Statement::NewAssignment(identifier, type_def, exp))
+        Ok(StatementKind::NewAssignment(identifier, type_def, exp))
     }
 
-    fn parse_assert(&mut self) -> ParseResult<Statement> {
+    /// Parses a scalar type or `array[size] of` a scalar type.
+    fn parse_type(&mut self) -> ParseResult<Type> {
+        if self.current_token != Token::Array {
+            return self.parse_scalar_type();
+        }
+        self.next_token();
+        self.expect_and_advance(
+            Token::LeftSquareBracket,
+            ParseError::ExpectedLeftSquareBracket,
+        )?;
+        let size = match self.get_current_token() {
+            Token::IntegerConstant(size) => match size.parse::<usize>() {
+                Ok(n) if (1..=MAX_ARRAY_SIZE).contains(&n) => n,
+                _ => {
+                    let err = ParseError::InvalidArraySize(size);
+                    self.errors.push(Reported::new(err, self.current_span));
+                    1
+                }
+            },
+            invalid => return Err(ParseError::ExpectedOperand(invalid)),
+        };
+        self.next_token();
+        self.expect_and_advance(
+            Token::RightSquareBracket,
+            ParseError::ExpectedClosingSquareBracket,
+        )?;
+        self.expect_and_advance(Token::Of, ParseError::ExpectedOf)?;
+        let element = self.parse_scalar_type()?;
+        Ok(Type::Array(size, Box::new(element)))
+    }
+
+    fn parse_scalar_type(&mut self) -> ParseResult<Type> {
+        match self.get_current_token() {
+            Token::BooleanType => Ok(Type::Boolean),
+            Token::IntegerType => Ok(Type::Integer),
+            Token::StringType => Ok(Type::String),
+            invalid => Err(ParseError::ExpectedTypeDefinition(invalid)),
+        }
+    }
+
+    fn parse_assert(&mut self) -> ParseResult<StatementKind> {
         self.next_token();
         self.expect_and_advance(Token::LeftBracket, ParseError::ExpectedLeftBracket)?;
         let exp = self.parse_expression()?;
@@ -145,53 +402,75 @@ impl Parser {
         self.expect_current_token(Token::RightBracket, ParseError::ExpectedClosingBracket)?;
         self.next_token();
         self.expect_current_token(Token::SemiColon, ParseError::ExpectedSemiColon)?;
-        Ok(Statement::Assert(exp))
+        Ok(StatementKind::Assert(exp))
     }
 
     fn parse_expression(&mut self) -> ParseResult<Expression> {
-        match self.get_current_token() {
+        self.enter()?;
+        let exp = match self.get_current_token() {
             Token::Not => self.parse_unary(UnaryOperator::Not),
             _ => self.parse_binary(),
-        }
+        }?;
+        self.depth -= 1;
+        Ok(exp)
     }
 
     fn parse_binary(&mut self) -> ParseResult<Expression> {
         let left = self.parse_operand()?;
         if self.is_end_of_exp() {
-            return Ok(left)
+            return Ok(left);
         }
         self.next_token();
         let op = self.parse_op()?;
         self.next_token();
         let right = self.parse_operand()?;
-        let exp = Expression::Binary(Box::new(left), op, Box::new(right));
-        Ok(exp)
+        let span = left.span.to(right.span);
+        let exp = ExpressionKind::Binary(Box::new(left), op, Box::new(right));
+        Ok(Expression::new(exp, span))
     }
 
     fn parse_unary(&mut self, op: UnaryOperator) -> ParseResult<Expression> {
+        let start = self.current_span;
         self.next_token();
         let exp = self.parse_binary()?;
-        let exp = Expression::Unary(op, Box::new(exp));
-        Ok(exp)
+        let span = start.to(exp.span);
+        let exp = ExpressionKind::Unary(op, Box::new(exp));
+        Ok(Expression::new(exp, span))
     }
 
     fn parse_operand(&mut self) -> ParseResult<Expression> {
+        let start = self.current_span;
         let operand = match self.get_current_token() {
-            Token::Identifier(id) => Expression::Identifier(id),
-            Token::IntegerConstant(int) => Expression::IntegerConstant(int.parse::<i32>().unwrap()),
-            Token::StringValue(string) => Expression::StringValue(string),
-            Token::True => Expression::Boolean(true),
-            Token::False => Expression::Boolean(false),
+            Token::Identifier(_) if self.peek_token == Token::LeftBracket => {
+                let (name, args) = self.parse_call()?;
+                ExpressionKind::Call(name, args)
+            }
+            Token::Identifier(id) if self.peek_token == Token::LeftSquareBracket => {
+                self.next_token();
+                ExpressionKind::Index(id, Box::new(self.parse_index()?))
+            }
+            Token::Identifier(id) => ExpressionKind::Identifier(id),
+            Token::IntegerConstant(int) => match self.integer_width.parse(&int) {
+                Some(val) => ExpressionKind::IntegerConstant(val),
+                None => {
+                    let err = ParseError::IntegerOutOfRange(int);
+                    self.errors.push(Reported::new(err, self.current_span));
+                    ExpressionKind::IntegerConstant(0)
+                }
+            },
+            Token::StringValue(string) => ExpressionKind::StringValue(string),
+            Token::True => ExpressionKind::Boolean(true),
+            Token::False => ExpressionKind::Boolean(false),
             Token::LeftBracket => {
                 self.next_token();
                 let exp = self.parse_expression()?;
                 self.next_token();
                 self.expect_current_token(Token::RightBracket, ParseError::ExpectedClosingBracket)?;
-                exp
+                exp.kind
             }
             invalid => return Err(ParseError::ExpectedOperand(invalid)),
         };
-        Ok(operand)
+        Ok(Expression::new(operand, start.to(self.current_span)))
     }
 
     fn parse_op(&mut self) -> ParseResult<BinaryOperator> {
@@ -205,7 +484,7 @@ impl Parser {
             Token::GreaterThan => Ok(BinaryOperator::GreaterThan),
             Token::And => Ok(BinaryOperator::And),
             invalid => Err(ParseError::UnexpectedToken(invalid)),
-        }        
+        }
     }
 
     fn parse_identifier(&mut self) -> ParseResult<String> {
@@ -216,20 +495,33 @@ impl Parser {
     }
 
     fn is_end_of_exp(&self) -> bool {
-        match self.peek_token {
-            Token::SemiColon => true,
-            Token::RightBracket => true,
-            Token::Range => true,
-            Token::Do => true,
-            Token::End => true,
-            _ => false
+        matches!(
+            self.peek_token,
+            Token::SemiColon
+                | Token::RightBracket
+                | Token::RightSquareBracket
+                | Token::Range
+                | Token::Do
+                | Token::End
+                | Token::Comma
+        )
+    }
+
+    fn enter(&mut self) -> ParseResult<()> {
+        if self.depth >= MAX_NESTING {
+            return Err(ParseError::NestingTooDeep(self.get_current_token()));
         }
+        self.depth += 1;
+        Ok(())
     }
 
     fn next_token(&mut self) {
         let next = self.peek_token.clone();
         self.current_token = next;
-        self.peek_token = self.lexer.get_next_token();
+        self.current_span = self.peek_span;
+        let (token, span) = self.lexer.get_next_spanned_token();
+        self.peek_token = token;
+        self.peek_span = span;
     }
 
     fn get_current_token(&mut self) -> Token {
@@ -259,13 +551,41 @@ impl Parser {
     }
 }
 
+/// Range `0..size(array) - 1` of the indices of `array`.
+fn array_indices(array: Expression) -> (Expression, Expression) {
+    let span = array.span;
+    let exp = |kind| Expression::new(kind, span);
+    let size = exp(ExpressionKind::Call(SIZE.to_string(), vec![array]));
+    let last = ExpressionKind::Binary(
+        Box::new(size),
+        BinaryOperator::Minus,
+        Box::new(exp(ExpressionKind::IntegerConstant(1))),
+    );
+    (exp(ExpressionKind::IntegerConstant(0)), exp(last))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::ast::{BinaryOperator, Expression, Statement, UnaryOperator};
+    use crate::ast::{
+        BinaryOperator, Expression, ExpressionKind, Parameter, Routine, Statement, StatementKind,
+        UnaryOperator,
+    };
     use crate::lexer::Lexer;
     use crate::parser::Parser;
     use crate::token::Token;
-    use crate::utils::{ParseError, Type};
+    use crate::utils::{IntegerWidth, ParseError, Position, Span, Type};
+
+    fn stmt(kind: StatementKind) -> Statement {
+        Statement::new(kind, Span::default())
+    }
+
+    fn exp(kind: ExpressionKind) -> Expression {
+        Expression::new(kind, Span::default())
+    }
+
+    fn without_spans(stmts: &[Statement]) -> Vec<Statement> {
+        stmts.iter().map(Statement::without_spans).collect()
+    }
 
     #[test]
     fn parse_assignment() -> Result<(), ParseError> {
@@ -279,31 +599,34 @@ mod tests {
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let expected = vec![
-            Statement::NewAssignment(
+            stmt(StatementKind::NewAssignment(
                 "x".to_string(),
                 Type::Integer,
-                Expression::Binary(
-                    Box::new(Expression::IntegerConstant(1)),
+                exp(ExpressionKind::Binary(
+                    Box::new(exp(ExpressionKind::IntegerConstant(1))),
                     BinaryOperator::Plus,
-                    Box::new(Expression::IntegerConstant(2)),
-                ),
-            ),
-            Statement::Assignment(
+                    Box::new(exp(ExpressionKind::IntegerConstant(2))),
+                )),
+            )),
+            stmt(StatementKind::Assignment(
                 "x".to_string(),
-                Expression::Binary(
-                    Box::new(Expression::Identifier("x".to_string())),
+                exp(ExpressionKind::Binary(
+                    Box::new(exp(ExpressionKind::Identifier("x".to_string()))),
                     BinaryOperator::Minus,
-                    Box::new(Expression::IntegerConstant(1)),
-                ),
-            ),
-            Statement::NewAssignment(
+                    Box::new(exp(ExpressionKind::IntegerConstant(1))),
+                )),
+            )),
+            stmt(StatementKind::NewAssignment(
                 "yY_1".to_string(),
                 Type::String,
-                Expression::StringValue("hello".to_string()),
-            ),
-            Statement::VarInitialization("Zz2_".to_string(), Type::Boolean),
+                exp(ExpressionKind::StringValue("hello".to_string())),
+            )),
+            stmt(StatementKind::VarInitialization(
+                "Zz2_".to_string(),
+                Type::Boolean,
+            )),
         ];
-        assert_eq!(program.statements, expected);
+        assert_eq!(without_spans(&program.statements), expected);
         Ok(())
     }
 
@@ -321,36 +644,38 @@ mod tests {
 
         let program = parser.parse_program();
         let expected = vec![
-            Statement::Print(Expression::StringValue("hello".to_string())),
-            Statement::Print(Expression::Binary(
-                Box::new(Expression::IntegerConstant(1)),
+            stmt(StatementKind::Print(exp(ExpressionKind::StringValue(
+                "hello".to_string(),
+            )))),
+            stmt(StatementKind::Print(exp(ExpressionKind::Binary(
+                Box::new(exp(ExpressionKind::IntegerConstant(1))),
                 BinaryOperator::Plus,
-                Box::new(Expression::IntegerConstant(2)),
-            )),
-            Statement::Print(Expression::Unary(
+                Box::new(exp(ExpressionKind::IntegerConstant(2))),
+            )))),
+            stmt(StatementKind::Print(exp(ExpressionKind::Unary(
                 UnaryOperator::Not,
-                Box::new(Expression::Boolean(true)),
-            )),
-            Statement::Print(Expression::Binary(
-                Box::new(Expression::IntegerConstant(1)),
+                Box::new(exp(ExpressionKind::Boolean(true))),
+            )))),
+            stmt(StatementKind::Print(exp(ExpressionKind::Binary(
+                Box::new(exp(ExpressionKind::IntegerConstant(1))),
                 BinaryOperator::Plus,
-                Box::new(Expression::Binary(
-                    Box::new(Expression::IntegerConstant(2)),
+                Box::new(exp(ExpressionKind::Binary(
+                    Box::new(exp(ExpressionKind::IntegerConstant(2))),
                     BinaryOperator::Division,
-                    Box::new(Expression::Binary(
-                        Box::new(Expression::IntegerConstant(3)),
+                    Box::new(exp(ExpressionKind::Binary(
+                        Box::new(exp(ExpressionKind::IntegerConstant(3))),
                         BinaryOperator::Multiplication,
-                        Box::new(Expression::IntegerConstant(2)),
-                    )),
-                )),
-            )),
-            Statement::Print(Expression::Binary(
-                Box::new(Expression::IntegerConstant(1)),
+                        Box::new(exp(ExpressionKind::IntegerConstant(2))),
+                    ))),
+                ))),
+            )))),
+            stmt(StatementKind::Print(exp(ExpressionKind::Binary(
+                Box::new(exp(ExpressionKind::IntegerConstant(1))),
                 BinaryOperator::Equals,
-                Box::new(Expression::IntegerConstant(1)),
-            )),
+                Box::new(exp(ExpressionKind::IntegerConstant(1))),
+            )))),
         ];
-        assert_eq!(program.statements, expected);
+        assert_eq!(without_spans(&program.statements), expected);
         Ok(())
     }
 
@@ -365,22 +690,132 @@ mod tests {
         let lexer = Lexer::new(source.to_string());
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
-        let expected = vec![Statement::For(
+        let expected = vec![stmt(StatementKind::For(
             "x".to_string(),
-            Expression::IntegerConstant(1),
-            Expression::IntegerConstant(5),
+            exp(ExpressionKind::IntegerConstant(1)),
+            exp(ExpressionKind::IntegerConstant(5)),
             vec![
-                Box::new(Statement::Print(Expression::Identifier("x".to_string()))),
-                Box::new(Statement::Print(Expression::StringValue(
+                stmt(StatementKind::Print(exp(ExpressionKind::Identifier(
+                    "x".to_string(),
+                )))),
+                stmt(StatementKind::Print(exp(ExpressionKind::StringValue(
                     "hello".to_string(),
-                ))),
+                )))),
             ],
-        )];
+        ))];
         println!("{}", expected[0]);
-        assert_eq!(program.statements, expected);
+        assert_eq!(without_spans(&program.statements), expected);
         Ok(())
     }
 
+    #[test]
+    fn parse_routine() -> Result<(), ParseError> {
+        let source = r#"
+            function add(a : int, b : int) : int
+                return a + b;
+            end function;
+            procedure greet()
+                print add(1, 2);
+                return;
+            end procedure;
+            greet();
+        "#;
+        let lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(parser.get_errors().is_empty());
+        let parameter = |name: &str| Parameter {
+            name: name.to_string(),
+            type_def: Type::Integer,
+            span: Span::default(),
+        };
+        let expected = vec![
+            stmt(StatementKind::Routine(Routine {
+                name: "add".to_string(),
+                parameters: vec![parameter("a"), parameter("b")],
+                return_type: Some(Type::Integer),
+                body: vec![stmt(StatementKind::Return(Some(exp(
+                    ExpressionKind::Binary(
+                        Box::new(exp(ExpressionKind::Identifier("a".to_string()))),
+                        BinaryOperator::Plus,
+                        Box::new(exp(ExpressionKind::Identifier("b".to_string()))),
+                    ),
+                ))))],
+            })),
+            stmt(StatementKind::Routine(Routine {
+                name: "greet".to_string(),
+                parameters: vec![],
+                return_type: None,
+                body: vec![
+                    stmt(StatementKind::Print(exp(ExpressionKind::Call(
+                        "add".to_string(),
+                        vec![
+                            exp(ExpressionKind::IntegerConstant(1)),
+                            exp(ExpressionKind::IntegerConstant(2)),
+                        ],
+                    )))),
+                    stmt(StatementKind::Return(None)),
+                ],
+            })),
+            stmt(StatementKind::Call("greet".to_string(), vec![])),
+        ];
+        assert_eq!(without_spans(&program.statements), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_array() -> Result<(), ParseError> {
+        let source = r#"
+            var a : array[3] of bool;
+            a[0] := a[1];
+            for i in a do
+            end for;
+        "#;
+        let lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(parser.get_errors().is_empty());
+        let int = |int| exp(ExpressionKind::IntegerConstant(int));
+        let a = || exp(ExpressionKind::Identifier("a".to_string()));
+        let expected = vec![
+            stmt(StatementKind::VarInitialization(
+                "a".to_string(),
+                Type::Array(3, Box::new(Type::Boolean)),
+            )),
+            stmt(StatementKind::IndexAssignment(
+                "a".to_string(),
+                int(0),
+                exp(ExpressionKind::Index("a".to_string(), Box::new(int(1)))),
+            )),
+            stmt(StatementKind::For(
+                "i".to_string(),
+                int(0),
+                exp(ExpressionKind::Binary(
+                    Box::new(exp(ExpressionKind::Call("size".to_string(), vec![a()]))),
+                    BinaryOperator::Minus,
+                    Box::new(int(1)),
+                )),
+                vec![],
+            )),
+        ];
+        assert_eq!(without_spans(&program.statements), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn report_invalid_array_size() {
+        for size in ["0", "1048577"] {
+            let source = format!("var a : array[{}] of int;", size);
+            let mut parser = Parser::new(Lexer::new(source));
+            parser.parse_program();
+            assert_eq!(parser.get_errors().len(), 1);
+            assert_eq!(
+                parser.get_errors()[0].error.0,
+                ParseError::InvalidArraySize(size.to_string())
+            );
+        }
+    }
+
     #[test]
     fn report_error() {
         let source = "print 1);";
@@ -388,9 +823,138 @@ mod tests {
         let mut parser = Parser::new(lexer);
         parser.parse_program();
         let errors = parser.get_errors();
+        assert!(errors
+            .iter()
+            .any(|r| r.error.0 == ParseError::ExpectedSemiColon(Token::RightBracket)));
+    }
+
+    #[test]
+    fn suggest_keywords() {
+        let source = "pritn 1;\nvar x : itn;\nvar y : int := 1;\nfor y ni 1..2 do\nend for;\n\
+                      x := y;";
+        let mut parser = Parser::new(Lexer::new(source.to_string()));
+        parser.parse_program();
+        let help = |index: usize| parser.get_errors()[index].help.as_deref();
+        assert_eq!(help(0), Some("did you mean `print` instead of `pritn`?"));
+        assert_eq!(help(1), None);
+        assert_eq!(help(2), Some("did you mean `int` instead of `itn`?"));
+        let in_error = parser
+            .get_errors()
+            .iter()
+            .find(|r| matches!(r.error.0, ParseError::ExpectedIn(_)))
+            .unwrap();
         assert_eq!(
-            true,
-            errors.contains(&ParseError::ExpectedSemiColon(Token::RightBracket))
+            in_error.help.as_deref(),
+            Some("did you mean `in` instead of `ni`?")
+        );
+    }
+
+    #[test]
+    fn report_lexer_and_parser_errors() {
+        let source = "pritn 1;\nprint 10 % 3;\nprint \"a\\qb\";";
+        let mut parser = Parser::new(Lexer::new(source.to_string()));
+        parser.parse_program();
+        let diagnostics: Vec<(usize, &str, Option<String>)> = parser
+            .diagnostics()
+            .into_iter()
+            .map(|d| (d.span.start.line, d.code, d.help))
+            .collect();
+        let help = Some("did you mean `print` instead of `pritn`?".to_string());
+        assert_eq!(
+            diagnostics,
+            vec![
+                (1, "E0004", help),
+                (1, "E0001", None),
+                (2, "E0301", None),
+                (2, "E0001", None),
+                (2, "E0001", None),
+                (3, "E0303", None),
+                (3, "E0001", None),
+            ]
         );
     }
+
+    #[test]
+    fn record_spans() {
+        let source = "var x : int;\nprint 1 + (x * 2);\nfor x in 1..2 do\n  print x;\nend for;";
+        let lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        let span = |l1, c1, l2, c2| Span::new(Position::new(l1, c1), Position::new(l2, c2));
+
+        assert_eq!(program.statements[0].span, span(1, 1, 1, 13));
+        assert_eq!(program.statements[1].span, span(2, 1, 2, 19));
+        match &program.statements[1].kind {
+            StatementKind::Print(exp) => {
+                assert_eq!(exp.span, span(2, 7, 2, 18));
+                if let ExpressionKind::Binary(_, _, right) = &exp.kind {
+                    assert_eq!(right.span, span(2, 11, 2, 18));
+                }
+            }
+            other => panic!("Expected print statement, got {}", other),
+        }
+        assert_eq!(program.statements[2].span, span(3, 1, 5, 9));
+        if let StatementKind::For(_, _, _, stmts) = &program.statements[2].kind {
+            assert_eq!(stmts[0].span, span(4, 3, 4, 11));
+        }
+    }
+
+    #[test]
+    fn report_error_span() {
+        let source = "var x : int;\nprint 1);";
+        let lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+        let (err, span) = &parser.get_errors()[0].error;
+        assert_eq!(err, &ParseError::ExpectedSemiColon(Token::RightBracket));
+        assert_eq!(span.start, Position::new(2, 8));
+    }
+
+    #[test]
+    fn report_integer_out_of_range() {
+        let source = "var x : int := 2147483648;\nprint 2147483647;";
+        let lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert_eq!(parser.get_errors().len(), 1);
+        let (err, span) = &parser.get_errors()[0].error;
+        assert_eq!(
+            err,
+            &ParseError::IntegerOutOfRange("2147483648".to_string())
+        );
+        assert_eq!(*span, Span::new(Position::new(1, 16), Position::new(1, 26)));
+        assert_eq!(program.statements.len(), 2);
+    }
+
+    #[test]
+    fn accept_wide_integers() {
+        let source = "print 2147483648;\nprint 9223372036854775808;";
+        let lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(lexer).with_integer_width(IntegerWidth::I64);
+        let program = parser.parse_program();
+        assert_eq!(
+            program.statements[0].without_spans(),
+            stmt(StatementKind::Print(exp(ExpressionKind::IntegerConstant(
+                2147483648
+            ))))
+        );
+        assert_eq!(parser.get_errors().len(), 1);
+        assert_eq!(
+            parser.get_errors()[0].error.0,
+            ParseError::IntegerOutOfRange("9223372036854775808".to_string())
+        );
+    }
+
+    #[test]
+    fn limit_nesting() {
+        let source = format!("print {}1{};\nprint 1;", "(".repeat(500), ")".repeat(500));
+        let lexer = Lexer::new(source);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(matches!(
+            parser.get_errors()[0].error.0,
+            ParseError::NestingTooDeep(Token::LeftBracket)
+        ));
+        assert_eq!(program.statements.len(), 1);
+    }
 }
@@ -0,0 +1,91 @@
+//! "Did you mean" suggestions for misspelled keywords and names, attached to
+//! errors as help notes.
+
+/// Number of single-character insertions, deletions, substitutions and
+/// swaps of adjacent characters that turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between a[..i] and b[..j].
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// The candidate closest to `name`, if it is close enough to be a likely
+/// misspelling: one edit for names of up to five characters, and one more
+/// for every three characters after that. Of equally close candidates the
+/// one closest in length wins, as `int` over `in` for `itn`, and then the
+/// alphabetically first, so that the suggestion does not depend on the
+/// order of `candidates`. Single characters are never corrected.
+pub fn suggest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let length = name.chars().count();
+    if length < 2 {
+        return None;
+    }
+    let limit = length.max(3) / 3;
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| {
+            let length_difference = length.abs_diff(candidate.chars().count());
+            (edit_distance(name, candidate), length_difference, candidate)
+        })
+        .filter(|(distance, _, _)| *distance <= limit)
+        .min()
+        .map(|(_, _, candidate)| candidate)
+}
+
+/// The text of the help note suggesting `suggestion` for `name`.
+pub fn did_you_mean(name: &str, suggestion: &str) -> String {
+    format!("did you mean `{}` instead of `{}`?", suggestion, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::suggest::{edit_distance, suggest};
+    use crate::token::KEYWORDS;
+
+    #[test]
+    fn count_edits() {
+        assert_eq!(edit_distance("print", "print"), 0);
+        assert_eq!(edit_distance("pritn", "print"), 1);
+        assert_eq!(edit_distance("counter", "count"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn suggest_close_names() {
+        assert_eq!(suggest("pritn", KEYWORDS.iter().copied()), Some("print"));
+        assert_eq!(suggest("itn", KEYWORDS.iter().copied()), Some("int"));
+        assert_eq!(
+            suggest("fucntion", KEYWORDS.iter().copied()),
+            Some("function")
+        );
+        assert_eq!(suggest("counter", ["total", "count"]), Some("count"));
+        assert_eq!(suggest("x", ["y"]), None);
+        assert_eq!(suggest("total", KEYWORDS.iter().copied()), None);
+        assert_eq!(suggest("ab", ["ac", "ab", "aa"]), Some("aa"));
+    }
+}
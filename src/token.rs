@@ -1,5 +1,6 @@
 use std::fmt;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
     Identifier(String),
@@ -27,9 +28,17 @@ pub enum Token {
     End,
     Range,
     Assert,
+    Procedure,
+    Function,
+    Return,
+    Array,
+    Of,
     LeftBracket,
     RightBracket,
+    LeftSquareBracket,
+    RightSquareBracket,
     SemiColon,
+    Comma,
     IntegerType,
     StringType,
     BooleanType,
@@ -51,8 +60,11 @@ impl fmt::Display for Token {
             Token::Not => "!",
             Token::LeftBracket => "(",
             Token::RightBracket => ")",
+            Token::LeftSquareBracket => "[",
+            Token::RightSquareBracket => "]",
             Token::Colon => ":",
             Token::SemiColon => ";",
+            Token::Comma => ",",
             Token::Equals => "=",
             Token::LessThan => "<",
             Token::GreaterThan => ">",
@@ -65,6 +77,11 @@ impl fmt::Display for Token {
             Token::End => "end",
             Token::Range => "..",
             Token::Assert => "assert",
+            Token::Procedure => "procedure",
+            Token::Function => "function",
+            Token::Return => "return",
+            Token::Array => "array",
+            Token::Of => "of",
             Token::Read => "read",
             Token::True => "true",
             Token::False => "false",
@@ -78,6 +95,28 @@ impl fmt::Display for Token {
     }
 }
 
+/// Reserved words recognised by `get_id_or_key_token`.
+pub const KEYWORDS: &[&str] = &[
+    "for",
+    "in",
+    "do",
+    "end",
+    "true",
+    "false",
+    "var",
+    "print",
+    "bool",
+    "string",
+    "int",
+    "assert",
+    "read",
+    "procedure",
+    "function",
+    "return",
+    "array",
+    "of",
+];
+
 pub fn get_id_or_key_token(lexeme: &str) -> Token {
     match lexeme {
         "for" => Token::For,
@@ -93,6 +132,26 @@ pub fn get_id_or_key_token(lexeme: &str) -> Token {
         "int" => Token::IntegerType,
         "assert" => Token::Assert,
         "read" => Token::Read,
+        "procedure" => Token::Procedure,
+        "function" => Token::Function,
+        "return" => Token::Return,
+        "array" => Token::Array,
+        "of" => Token::Of,
         id => Token::Identifier(String::from(id)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::token::{get_id_or_key_token, Token, KEYWORDS};
+
+    #[test]
+    fn keywords_are_reserved() {
+        for keyword in KEYWORDS {
+            assert_ne!(
+                get_id_or_key_token(keyword),
+                Token::Identifier(keyword.to_string())
+            );
+        }
+    }
+}
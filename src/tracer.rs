@@ -0,0 +1,122 @@
+//! Execution trace written by the evaluator's `Hook`.
+//!
+//! Every executed statement, variable write and `for` iteration becomes one
+//! line, prefixed by the span of the statement or loop it belongs to:
+//!
+//! ```text
+//! 2:1 total := total + 1;
+//! 2:1   total: 0 -> 1
+//! 3:1 for i in 1..2 do
+//! 3:1   iteration 1: i = 1
+//! ```
+//!
+//! In JSON lines format each line is an object instead:
+//!
+//! ```text
+//! {"event": "statement", "span": Span, "statement": "total := total + 1;"}
+//! {"event": "assignment", "span": Span, "variable": "total",
+//!  "old": Value or null, "new": Value}
+//! {"event": "iteration", "span": Span, "variable": "i", "value": 1,
+//!  "iteration": 1}
+//! ```
+
+use crate::ast::Statement;
+use crate::evaluator::{EvalResult, Evaluator, Hook, LoopFrame};
+use crate::formatter::format_header;
+use crate::json::{Json, ToJson};
+use crate::utils::{EvalError, Value};
+use std::io::Write;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TraceFormat {
+    Human,
+    JsonLines,
+}
+
+pub struct Tracer {
+    output: Box<dyn Write>,
+    format: TraceFormat,
+}
+
+impl Tracer {
+    pub fn new(output: Box<dyn Write>, format: TraceFormat) -> Self {
+        Tracer { output, format }
+    }
+
+    fn write(&mut self, human: String, json: impl FnOnce() -> Json) -> EvalResult<()> {
+        let line = match self.format {
+            TraceFormat::Human => human,
+            TraceFormat::JsonLines => json().to_string(),
+        };
+        writeln!(self.output, "{}", line).map_err(|err| EvalError::IOError(err.to_string()))
+    }
+}
+
+impl Hook for Tracer {
+    fn before_statement(
+        &mut self,
+        _evaluator: &mut Evaluator,
+        statement: &Statement,
+    ) -> EvalResult<()> {
+        let text = format_header(statement);
+        let human = format!("{} {}", statement.span, text);
+        self.write(human, || {
+            Json::object(vec![
+                ("event", Json::from("statement")),
+                ("span", statement.span.to_json()),
+                ("statement", Json::from(text.as_str())),
+            ])
+        })
+    }
+
+    fn after_assignment(
+        &mut self,
+        evaluator: &mut Evaluator,
+        id: &str,
+        old: Option<&Value>,
+        new: &Value,
+    ) -> EvalResult<()> {
+        let span = evaluator.current_span();
+        let human = match old {
+            Some(old) => format!("{}   {}: {} -> {}", span, id, show(old), show(new)),
+            None => format!("{}   {} = {}", span, id, show(new)),
+        };
+        self.write(human, || {
+            Json::object(vec![
+                ("event", Json::from("assignment")),
+                ("span", span.to_json()),
+                ("variable", Json::from(id)),
+                ("old", old.map_or(Json::Null, ToJson::to_json)),
+                ("new", new.to_json()),
+            ])
+        })
+    }
+
+    fn before_iteration(
+        &mut self,
+        _evaluator: &mut Evaluator,
+        frame: &LoopFrame,
+    ) -> EvalResult<()> {
+        let human = format!(
+            "{}   iteration {}: {} = {}",
+            frame.span, frame.iteration, frame.variable, frame.value
+        );
+        self.write(human, || {
+            Json::object(vec![
+                ("event", Json::from("iteration")),
+                ("span", frame.span.to_json()),
+                ("variable", Json::from(frame.variable.as_str())),
+                ("value", Json::from(frame.value)),
+                ("iteration", Json::from(frame.iteration as i64)),
+            ])
+        })
+    }
+}
+
+/// Strings are quoted so that trailing whitespace and newlines are visible.
+fn show(val: &Value) -> String {
+    match val {
+        Value::String(s) => format!("{:?}", s),
+        val => val.to_string(),
+    }
+}
@@ -1,7 +1,69 @@
+use crate::diagnostic::Label;
 use crate::token::Token;
 use std::fmt;
 
-#[derive(Debug, PartialEq)]
+/// A 1-based line and column in the source text.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Position { line, column }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Source range covered by a token or syntax node. `end` points one column
+/// past the last character.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.start)
+    }
+}
+
+/// Why the lexer produced a `Token::Illegal`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum LexError {
+    IllegalCharacter(char),
+    UnterminatedString,
+    InvalidEscape(char),
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexError::IllegalCharacter(ch) => write!(f, "Illegal character {:?}", ch),
+            LexError::UnterminatedString => write!(f, "Unterminated string literal"),
+            LexError::InvalidEscape(ch) => write!(f, "Invalid escape sequence \\{}", ch),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum ParseError {
     UnexpectedToken(Token),
     ExpectedColon(Token),
@@ -16,6 +78,41 @@ pub enum ParseError {
     ExpectedDo(Token),
     ExpectedRange(Token),
     ExpectedFor(Token),
+    ExpectedProcedure(Token),
+    ExpectedFunction(Token),
+    ExpectedOf(Token),
+    ExpectedLeftSquareBracket(Token),
+    ExpectedClosingSquareBracket(Token),
+    InvalidArraySize(String),
+    IntegerOutOfRange(String),
+    NestingTooDeep(Token),
+}
+
+impl ParseError {
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            ParseError::UnexpectedToken(t)
+            | ParseError::ExpectedColon(t)
+            | ParseError::ExpectedTypeDefinition(t)
+            | ParseError::ExpectedAssignment(t)
+            | ParseError::ExpectedIdentifier(t)
+            | ParseError::ExpectedOperand(t)
+            | ParseError::ExpectedSemiColon(t)
+            | ParseError::ExpectedClosingBracket(t)
+            | ParseError::ExpectedLeftBracket(t)
+            | ParseError::ExpectedIn(t)
+            | ParseError::ExpectedDo(t)
+            | ParseError::ExpectedRange(t)
+            | ParseError::ExpectedFor(t)
+            | ParseError::ExpectedProcedure(t)
+            | ParseError::ExpectedFunction(t)
+            | ParseError::ExpectedOf(t)
+            | ParseError::ExpectedLeftSquareBracket(t)
+            | ParseError::ExpectedClosingSquareBracket(t)
+            | ParseError::NestingTooDeep(t) => Some(t),
+            ParseError::IntegerOutOfRange(_) | ParseError::InvalidArraySize(_) => None,
+        }
+    }
 }
 
 impl fmt::Display for ParseError {
@@ -34,12 +131,91 @@ impl fmt::Display for ParseError {
             ParseError::ExpectedDo(t) => msg("do keyword", t),
             ParseError::ExpectedRange(t) => msg("..", t),
             ParseError::ExpectedFor(t) => msg("for keyword", t),
+            ParseError::ExpectedProcedure(t) => msg("procedure keyword", t),
+            ParseError::ExpectedFunction(t) => msg("function keyword", t),
+            ParseError::ExpectedOf(t) => msg("of keyword", t),
+            ParseError::ExpectedLeftSquareBracket(t) => msg("[", t),
+            ParseError::ExpectedClosingSquareBracket(t) => msg("]", t),
+            ParseError::InvalidArraySize(size) => format!("Invalid array size {}", size),
             ParseError::ExpectedIdentifier(t) => msg("identifier", t),
+            ParseError::IntegerOutOfRange(int) => format!("Integer {} is out of range", int),
+            ParseError::NestingTooDeep(t) => format!("Nesting too deep at {}", t),
         };
         write!(f, "{}", output)
     }
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum CheckError {
+    MismatchedTypes(Type, Type),
+    UnsupportedOperation(String, Type),
+    UndeclaredVariable(String),
+    VariableAlreadyDeclared(String),
+    VariableOutOfScope(String),
+    UndeclaredRoutine(String),
+    RoutineAlreadyDeclared(String),
+    NestedRoutine(String),
+    ArgumentCount(String, usize, usize),
+    NoReturnValue(String),
+    ReturnOutsideRoutine,
+    MissingReturnValue(String, Type),
+    UnexpectedReturnValue(String),
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::MismatchedTypes(expected, found) => {
+                write!(f, "Mismatched types: expected {} got {}", expected, found)
+            }
+            CheckError::UnsupportedOperation(op, type_def) => {
+                write!(f, "Operator {} is not supported for {}", op, type_def)
+            }
+            CheckError::UndeclaredVariable(id) => write!(f, "Variable {} is not declared", id),
+            CheckError::VariableAlreadyDeclared(id) => {
+                write!(f, "Variable {} is already declared", id)
+            }
+            CheckError::VariableOutOfScope(id) => {
+                write!(
+                    f,
+                    "Variable {} is used outside of the block declaring it",
+                    id
+                )
+            }
+            CheckError::UndeclaredRoutine(name) => {
+                write!(f, "Procedure or function {} is not declared", name)
+            }
+            CheckError::RoutineAlreadyDeclared(name) => {
+                write!(f, "Procedure or function {} is already declared", name)
+            }
+            CheckError::NestedRoutine(name) => write!(
+                f,
+                "Procedure or function {} must be declared at the top level",
+                name
+            ),
+            CheckError::ArgumentCount(name, expected, found) => {
+                write!(f, "{} expects {} arguments, got {}", name, expected, found)
+            }
+            CheckError::NoReturnValue(name) => {
+                write!(f, "Procedure {} does not return a value", name)
+            }
+            CheckError::ReturnOutsideRoutine => {
+                write!(f, "return outside of a procedure or function")
+            }
+            CheckError::MissingReturnValue(name, type_def) => {
+                write!(
+                    f,
+                    "Function {} must return a value of type {}",
+                    name, type_def
+                )
+            }
+            CheckError::UnexpectedReturnValue(name) => {
+                write!(f, "Procedure {} cannot return a value", name)
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum EvalError {
     MismatchedTypes,
@@ -48,12 +224,24 @@ pub enum EvalError {
     VariableAlreadyInitialized(String),
     SyntaxError,
     IOError(String),
+    DivisionByZero,
+    IntegerOverflow,
+    StepLimitExceeded(u64),
+    OutputLimitExceeded(u64),
+    StringLimitExceeded(usize),
+    DeadlineExceeded,
+    UndeclaredRoutine(String),
+    MissingReturn(String),
+    CallDepthExceeded(usize),
+    IndexOutOfBounds(i64, usize),
+    InvalidArgument(String, String),
+    Interrupted,
 }
 
-impl fmt::Display for EvalError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let msg = |err| format!("{}", err);
-        let output = match self {
+impl EvalError {
+    pub fn message(&self) -> String {
+        let msg = |err: &str| err.to_string();
+        match self {
             EvalError::SyntaxError => msg("Syntax Error"),
             EvalError::MismatchedTypes => msg("Mismatched types"),
             EvalError::UnsupportedOperation => msg("Unsupported operation"),
@@ -62,8 +250,93 @@ impl fmt::Display for EvalError {
             }
             EvalError::VariableNotInitialized(id) => format!("Variable {} not initialized", id),
             EvalError::IOError(err) => msg(err),
-        };
-        write!(f, "Failed with Error: {}", output)
+            EvalError::DivisionByZero => msg("Division by zero"),
+            EvalError::IntegerOverflow => msg("Integer overflow"),
+            EvalError::StepLimitExceeded(limit) => {
+                format!("Step limit of {} exceeded", limit)
+            }
+            EvalError::OutputLimitExceeded(limit) => {
+                format!("Output limit of {} bytes exceeded", limit)
+            }
+            EvalError::StringLimitExceeded(limit) => {
+                format!("String longer than the limit of {} bytes", limit)
+            }
+            EvalError::DeadlineExceeded => msg("Time limit exceeded"),
+            EvalError::UndeclaredRoutine(name) => {
+                format!("Procedure or function {} is not declared", name)
+            }
+            EvalError::MissingReturn(name) => {
+                format!("Function {} ended without returning a value", name)
+            }
+            EvalError::CallDepthExceeded(depth) => {
+                format!("Calls nested deeper than {}", depth)
+            }
+            EvalError::IndexOutOfBounds(index, size) => {
+                format!(
+                    "Index {} is out of bounds for an array of size {}",
+                    index, size
+                )
+            }
+            EvalError::InvalidArgument(name, message) => {
+                format!("Invalid argument to {}: {}", name, message)
+            }
+            EvalError::Interrupted => msg("Execution interrupted"),
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed with Error: {}", self.message())
+    }
+}
+
+/// A problem found before the program runs, by the optimizer or a static
+/// analysis, that the program may run into.
+#[derive(Debug)]
+pub enum Warning {
+    /// A runtime error in a constant expression.
+    Failure(EvalError),
+    /// A read of a variable that may not have been assigned yet.
+    UninitializedRead(String),
+}
+
+impl Warning {
+    pub fn message(&self) -> String {
+        match self {
+            Warning::Failure(err) => err.message(),
+            Warning::UninitializedRead(id) => {
+                format!("Variable {} may be read before it is assigned", id)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Warning: {}", self.message())
+    }
+}
+
+pub type SpannedWarning = (Warning, Span);
+
+/// An error of the parser or checker with the notes that explain it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Reported<E> {
+    pub error: (E, Span),
+    /// A suggestion, such as the name a misspelled identifier likely means.
+    pub help: Option<String>,
+    /// Related places, such as earlier declarations.
+    pub labels: Vec<Label>,
+}
+
+impl<E> Reported<E> {
+    pub fn new(err: E, span: Span) -> Self {
+        Reported {
+            error: (err, span),
+            help: None,
+            labels: Vec::new(),
+        }
     }
 }
 
@@ -72,6 +345,27 @@ pub enum Type {
     Boolean,
     String,
     Integer,
+    /// Fixed-size array of a scalar element type.
+    Array(usize, Box<Type>),
+}
+
+impl Type {
+    /// Value a variable of this type starts with when it is declared
+    /// without one, or `None` if it stays unassigned until written.
+    /// Arrays start out filled with `0`, `""` or `false`.
+    pub fn default_value(&self) -> Option<Value> {
+        match self {
+            Type::Array(size, element) => {
+                let element = match element.as_ref() {
+                    Type::Boolean => Value::Bool(false),
+                    Type::String => Value::String(String::new()),
+                    _ => Value::Integer(0),
+                };
+                Some(Value::Array(vec![element; *size]))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Type {
@@ -80,6 +374,7 @@ impl fmt::Display for Type {
             Type::Boolean => write!(f, "bool"),
             Type::String => write!(f, "string"),
             Type::Integer => write!(f, "int"),
+            Type::Array(size, element) => write!(f, "array[{}] of {}", size, element),
         }
     }
 }
@@ -88,7 +383,8 @@ impl fmt::Display for Type {
 pub enum Value {
     Bool(bool),
     String(String),
-    Integer(i32),
+    Integer(i64),
+    Array(Vec<Value>),
 }
 
 impl fmt::Display for Value {
@@ -97,6 +393,60 @@ impl fmt::Display for Value {
             Value::Bool(b) => write!(f, "{}", b),
             Value::Integer(int) => write!(f, "{}", int),
             Value::String(s) => write!(f, "{}", s),
+            Value::Array(values) => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|val| match val {
+                        Value::String(s) => format!("{:?}", s),
+                        val => val.to_string(),
+                    })
+                    .collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+/// Width of the `int` type, chosen when the parser and evaluator are
+/// constructed. Literals, `read` input and arithmetic results outside its
+/// range are errors.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum IntegerWidth {
+    #[default]
+    I32,
+    I64,
+}
+
+impl IntegerWidth {
+    pub fn min(self) -> i64 {
+        match self {
+            IntegerWidth::I32 => i32::MIN as i64,
+            IntegerWidth::I64 => i64::MIN,
+        }
+    }
+
+    pub fn max(self) -> i64 {
+        match self {
+            IntegerWidth::I32 => i32::MAX as i64,
+            IntegerWidth::I64 => i64::MAX,
+        }
+    }
+
+    pub fn contains(self, int: i64) -> bool {
+        self.min() <= int && int <= self.max()
+    }
+
+    /// Parses a decimal integer, returning `None` if it does not fit.
+    pub fn parse(self, int: &str) -> Option<i64> {
+        int.parse::<i64>().ok().filter(|&val| self.contains(val))
+    }
+}
+
+impl fmt::Display for IntegerWidth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntegerWidth::I32 => write!(f, "i32"),
+            IntegerWidth::I64 => write!(f, "i64"),
         }
     }
 }
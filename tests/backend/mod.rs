@@ -0,0 +1,216 @@
+//! Helpers shared by the tests of the backends, which compare the behaviour
+//! of compiled programs with that of the evaluator.
+
+use crate::common::Shared;
+use minipl_interpreter::checker::Checker;
+use minipl_interpreter::diagnostic::{Renderer, Report, Style, ToDiagnostic};
+use minipl_interpreter::evaluator::Evaluator;
+use minipl_interpreter::lexer::Lexer;
+use minipl_interpreter::parser::Parser;
+use minipl_interpreter::utils::{EvalError, IntegerWidth, Span};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    pub stdout: String,
+    pub stderr: String,
+    pub success: bool,
+}
+
+/// Runs the program in the evaluator, reporting errors like the command
+/// line interpreter does. `None` if it does not pass the checker.
+fn evaluate(source: &str, input: &str, width: IntegerWidth) -> Option<Outcome> {
+    let mut parser = Parser::new(Lexer::new(source.to_string())).with_integer_width(width);
+    let program = parser.parse_program();
+    let mut checker = Checker::new();
+    checker.check_program(&program);
+    if !parser.get_errors().is_empty() || !checker.get_errors().is_empty() {
+        return None;
+    }
+    let output = Shared::default();
+    let mut evaluator = Evaluator::new(program)
+        .with_io(
+            Box::new(io::Cursor::new(input.to_string())),
+            Box::new(output.clone()),
+        )
+        .with_integer_width(width);
+    let (stderr, success) = match evaluator.evaluate_program() {
+        Ok(()) => (String::new(), true),
+        Err(err) => (runtime_error(source, err, evaluator.current_span()), false),
+    };
+    Some(Outcome {
+        stdout: output.text(),
+        stderr,
+        success,
+    })
+}
+
+/// The standard error of the command line interpreter for a runtime error
+/// at `span`, with `--diagnostics=plain`. Compiled programs do not know the
+/// calls in progress, so the labels and notes naming them are left out.
+pub fn runtime_error(source: &str, err: EvalError, span: Span) -> String {
+    let mut report = Report::new();
+    report.push((err, span).to_diagnostic());
+    let renderer = Renderer::new(source, Style::Plain);
+    // The interpreter separates the error from the output of the program.
+    format!("\n{}", renderer.render_report(&report))
+}
+
+/// A program that every backend must run like the evaluator.
+pub struct Case {
+    pub name: String,
+    pub source: String,
+    pub input: String,
+    pub width: IntegerWidth,
+}
+
+impl Case {
+    fn new(name: &str, source: &str, input: &str, width: IntegerWidth) -> Self {
+        Case {
+            name: name.to_string(),
+            source: source.to_string(),
+            input: input.to_string(),
+            width,
+        }
+    }
+}
+
+/// The golden programs that pass the checker with `i32` integers, with
+/// their input. Programs run with options are left out, as they may depend
+/// on them, like `--set`.
+pub fn golden_programs() -> Vec<Case> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let mut programs = Vec::new();
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        let is_program = path.extension().is_some_and(|ext| ext == "mini");
+        if !is_program || path.with_extension("args").exists() {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        let input = fs::read_to_string(path.with_extension("stdin")).unwrap_or_default();
+        if evaluate(&source, &input, IntegerWidth::I32).is_none() {
+            continue;
+        }
+        let name = path.file_stem().unwrap().to_string_lossy();
+        programs.push(Case::new(&name, &source, &input, IntegerWidth::I32));
+    }
+    assert!(programs.len() > 10);
+    programs
+}
+
+/// Runtime errors and corner cases where the backends are most likely to
+/// differ from the evaluator.
+pub fn runtime_errors() -> Vec<Case> {
+    let cases = [
+        ("unassigned", "var x : int;\nprint \"a\";\nprint x;"),
+        (
+            "unassigned_before_call",
+            "function f() : int\n    print \"f\";\n    return 1;\nend function;\n\
+             var x : int;\nprint x + f();",
+        ),
+        (
+            "fresh_scope",
+            "var i : int;\nfor i in 1..2 do\n    var x : int;\n    for x in i..1 do\n        print \"\";\n    end for;\n    print x;\nend for;",
+        ),
+        (
+            "missing_return",
+            "function f(n : int) : int\n    var i : int;\n    for i in 1..n do\n        return i;\n    end for;\nend function;\nprint f(1);\nprint f(0);",
+        ),
+        (
+            "call_depth",
+            "procedure down(n : int)\n    print n;\n    print \" \";\n    down(n + 1);\nend procedure;\ndown(1);",
+        ),
+        (
+            "loop_end",
+            "var n : int := 3;\nvar i : int;\nfor i in 1..n do\n    n := n + 1;\n    print i;\nend for;\nprint n;",
+        ),
+        (
+            "empty_loop",
+            "var i : int;\nfor i in 2..1 do\n    print i;\nend for;\nprint i;",
+        ),
+        (
+            "redeclared",
+            "var i : int;\nfor i in 1..2 do\n    var x : int;\n    print i;\n    print x;\n    x := i;\nend for;",
+        ),
+        ("overflow", "print 2147483647 + 1;"),
+        ("division", "print (0 - 7) / 2;\nprint 7 / (0 - 2);\nprint 1 / 0;"),
+        ("to_int", "print to_int(\" 12\\n\") * 2;\nprint to_int(\"1\\t\\\"x\\\"\");"),
+        ("substr", "print substr(\"h\u{e4}ll\u{1f600}\", 1, 3);\nprint substr(\"abc\", 2, 2);"),
+        ("case", "print upper(\"abc\") + lower(\"DEF\");\nprint contains(\"abc\", \"bc\");"),
+        (
+            "strings",
+            "print length(\"\u{1f600}a\");\nprint \"\u{ff61}\" < \"\u{1f600}\";\nprint contains(upper(\"abc\"), \"BC\");\nprint lower(\"ABC\") + to_string(0 - 5);",
+        ),
+        (
+            "booleans",
+            "var t : bool := true;\nprint (!t) < t;\nprint t > t;\nprint (1 < 2) & t;\nprint (!t) = false;",
+        ),
+        (
+            "evaluation_order",
+            "function f(s : string) : int\n    print s;\n    return 1;\nend function;\nvar a : array[2] of int;\na[f(\"i\")] := f(\"v\");\nprint f(\"a\") + (f(\"b\") / 0);",
+        ),
+        (
+            "copies",
+            "var a : array[3] of string;\nvar b : array[3] of string := a;\nb[0] := \"x\";\nprint a[0] + b[0];\nprint size(b);\nprint b[3];",
+        ),
+        ("negative_index", "var a : array[2] of int;\nprint a[0 - 1];"),
+        (
+            "reserved_names",
+            "var len : int := 1;\nvar None : string := \"n\";\nfunction check(let : int) : int\n    var str : int := let + 1;\n    return str;\nend function;\nprint check(len);\nprint None;",
+        ),
+        ("asserts", "assert (1 < 2);\nassert (\"b\" < \"a\");\nassert (false > true);"),
+    ];
+    let mut cases: Vec<Case> = cases
+        .iter()
+        .map(|(name, source)| Case::new(name, source, "", IntegerWidth::I32))
+        .collect();
+    let read =
+        "var n : int;\nvar s : string;\nread s;\nread n;\nprint s + to_string(n * n);\nread n;";
+    let long_line = format!("{}\n", "x".repeat(1000));
+    let wide = "print 4611686018427387904 - 1;\nprint (0 - 1) * (2 * 4611686018427387903);";
+    let last = "var i : int;\nfor i in 9223372036854775806..9223372036854775807 do\n    print i;\nend for;";
+    cases.extend([
+        Case::new("read", read, "ab\r\n 3000 \nx\n", IntegerWidth::I32),
+        Case::new("read_overflow", read, "ab\n3000000000\n", IntegerWidth::I32),
+        Case::new("read_end", read, "ab\n", IntegerWidth::I32),
+        Case::new(
+            "read_long",
+            "var s : string;\nread s;\nprint length(s);",
+            &long_line,
+            IntegerWidth::I32,
+        ),
+        Case::new(
+            "read_wide",
+            read,
+            "ab\n 3000000000 \nx\n",
+            IntegerWidth::I64,
+        ),
+        Case::new("wide", wide, "", IntegerWidth::I64),
+        Case::new("last_integer", last, "", IntegerWidth::I64),
+    ]);
+    cases
+}
+
+/// Checks that `run` gives the output, errors and exit status of the
+/// evaluator for every case. Without `locations`, for backends that do not
+/// know where an error happened, errors are compared without their line and
+/// column.
+pub fn assert_same_behaviour<F>(cases: &[Case], locations: bool, mut run: F)
+where
+    F: FnMut(&Case) -> Outcome,
+{
+    for case in cases {
+        let mut expected =
+            evaluate(&case.source, &case.input, case.width).expect("program does not check");
+        if !locations {
+            if let Some((_, message)) = expected.stderr.split_once(": ") {
+                expected.stderr = format!("\n{}", message);
+            }
+        }
+        assert_eq!(run(case), expected, "{}", case.name);
+    }
+}
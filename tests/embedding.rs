@@ -0,0 +1,107 @@
+//! Runs programs through the public API the way a host application embeds
+//! the interpreter, with Rust functions exposed to the program.
+
+mod common;
+
+use common::Shared;
+use minipl_interpreter::checker::Checker;
+use minipl_interpreter::evaluator::Evaluator;
+use minipl_interpreter::lexer::Lexer;
+use minipl_interpreter::parser::Parser;
+use minipl_interpreter::utils::{CheckError, EvalError, Type, Value};
+use std::collections::HashMap;
+use std::io;
+
+fn config() -> HashMap<String, String> {
+    let mut config = HashMap::new();
+    config.insert("greeting".to_string(), "Hello".to_string());
+    config
+}
+
+fn checker(source: &str) -> Vec<CheckError> {
+    let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+    let mut checker = Checker::new().with_function("config", vec![Type::String], Type::String);
+    checker.check_program(&program);
+    checker
+        .get_errors()
+        .iter()
+        .map(|reported| reported.error.0.clone())
+        .collect()
+}
+
+fn run(source: &str) -> (Result<(), EvalError>, String) {
+    let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+    let output = Shared::default();
+    let config = config();
+    let mut evaluator = Evaluator::new(program)
+        .with_io(Box::new(io::empty()), Box::new(output.clone()))
+        .with_function(
+            "config",
+            vec![Type::String],
+            Type::String,
+            move |args| match args {
+                [Value::String(key)] => match config.get(key) {
+                    Some(val) => Ok(Value::String(val.clone())),
+                    None => Err(EvalError::InvalidArgument(
+                        "config".to_string(),
+                        format!("no setting {:?}", key),
+                    )),
+                },
+                _ => Err(EvalError::MismatchedTypes),
+            },
+        )
+        .with_function("broken", vec![], Type::Integer, |_| Ok(Value::Bool(true)));
+    let result = evaluator.evaluate_program();
+    (result, output.text())
+}
+
+#[test]
+fn call_host_function() {
+    let source = "print config(\"greeting\") + \", World!\";";
+    assert_eq!(checker(source), vec![]);
+    let (result, output) = run(source);
+    assert!(result.is_ok());
+    assert_eq!(output, "Hello, World!");
+}
+
+#[test]
+fn check_host_function_calls() {
+    let source = "var n : int := config(1);\nfunction config() : int\n    return 1;\nend function;";
+    assert_eq!(
+        checker(source),
+        vec![
+            CheckError::MismatchedTypes(Type::String, Type::Integer),
+            CheckError::MismatchedTypes(Type::Integer, Type::String),
+            CheckError::RoutineAlreadyDeclared("config".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn propagate_host_function_errors() {
+    let (result, _) = run("print config(\"missing\");");
+    assert!(matches!(result, Err(EvalError::InvalidArgument(name, _)) if name == "config"));
+    let (result, _) = run("print broken();");
+    assert!(matches!(result, Err(EvalError::MismatchedTypes)));
+}
+
+#[test]
+fn seed_and_read_variables() {
+    let source = "var doubled : int := limit * 2;\nlimit := 0;";
+    let program = Parser::new(Lexer::new(source.to_string())).parse_program();
+    let mut checker = Checker::new().with_variable("limit", Type::Integer);
+    checker.check_program(&program);
+    assert!(checker.get_errors().is_empty());
+
+    let mut evaluator = Evaluator::new(program);
+    assert!(evaluator
+        .define_variable("limit", Type::Integer, Value::String("21".to_string()))
+        .is_err());
+    assert!(evaluator
+        .define_variable("limit", Type::Integer, Value::Integer(21))
+        .is_ok());
+    assert!(evaluator.evaluate_program().is_ok());
+    assert_eq!(evaluator.variable("doubled"), Some(&Value::Integer(42)));
+    assert_eq!(evaluator.variable("limit"), Some(&Value::Integer(0)));
+    assert_eq!(evaluator.variable("missing"), None);
+}
@@ -0,0 +1,93 @@
+//! Compiles programs with the C backend and the system `cc`, runs them and
+//! compares their output and errors with those of the evaluator. Skipped
+//! when no C compiler is installed.
+
+mod backend;
+mod common;
+
+use backend::{assert_same_behaviour, golden_programs, runtime_errors, Case, Outcome};
+use minipl_interpreter::backend::c::CBackend;
+use minipl_interpreter::lexer::Lexer;
+use minipl_interpreter::parser::Parser;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+fn has_compiler() -> bool {
+    let found = Command::new("cc").arg("--version").output().is_ok();
+    if !found {
+        eprintln!("cc not found, skipping");
+    }
+    found
+}
+
+/// Directory for the sources and executables of one test, removed with
+/// everything in it when the test ends.
+struct BuildDir(PathBuf);
+
+impl BuildDir {
+    fn new(test: &str) -> Self {
+        let name = format!("minipl-emit-c-{}-{}", std::process::id(), test);
+        let dir = env::temp_dir().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        BuildDir(dir)
+    }
+}
+
+impl Drop for BuildDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn compile_and_run(dir: &BuildDir, case: &Case) -> Outcome {
+    let program = Parser::new(Lexer::new(case.source.clone()))
+        .with_integer_width(case.width)
+        .parse_program();
+    let c_source = CBackend::new()
+        .with_integer_width(case.width)
+        .emit_program(&program)
+        .unwrap();
+    let c_file = dir.0.join(format!("{}.c", case.name));
+    let executable = dir.0.join(&case.name);
+    fs::write(&c_file, c_source).unwrap();
+    let status = Command::new("cc")
+        .args(["-std=c99", "-o"])
+        .arg(&executable)
+        .arg(&c_file)
+        .status()
+        .unwrap();
+    assert!(status.success(), "{} did not compile", c_file.display());
+
+    let mut child = Command::new(&executable)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let _ = child.stdin.take().unwrap().write_all(case.input.as_bytes());
+    let output = child.wait_with_output().unwrap();
+    Outcome {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        success: output.status.success(),
+    }
+}
+
+#[test]
+fn compile_golden_programs() {
+    if has_compiler() {
+        let dir = BuildDir::new("golden");
+        assert_same_behaviour(&golden_programs(), true, |case| compile_and_run(&dir, case));
+    }
+}
+
+#[test]
+fn compile_runtime_errors() {
+    if has_compiler() {
+        let dir = BuildDir::new("errors");
+        assert_same_behaviour(&runtime_errors(), true, |case| compile_and_run(&dir, case));
+    }
+}
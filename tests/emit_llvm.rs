@@ -0,0 +1,138 @@
+//! Compiles programs with the LLVM backend and a local `clang`, or `llc`
+//! and `cc`, runs them and compares their output and errors with those of
+//! the evaluator. Skipped when neither is installed.
+
+mod backend;
+mod common;
+
+use backend::{assert_same_behaviour, golden_programs, runtime_errors, Case, Outcome};
+use minipl_interpreter::backend::llvm::LlvmBackend;
+use minipl_interpreter::lexer::Lexer;
+use minipl_interpreter::parser::Parser;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[derive(Clone, Copy)]
+enum Toolchain {
+    Clang,
+    /// `llc` compiles the IR to an object file, which `cc` links.
+    Llc,
+}
+
+fn toolchain() -> Option<Toolchain> {
+    let found = |tool: &str| Command::new(tool).arg("--version").output().is_ok();
+    if found("clang") {
+        Some(Toolchain::Clang)
+    } else if found("llc") && found("cc") {
+        Some(Toolchain::Llc)
+    } else {
+        eprintln!("clang and llc not found, skipping");
+        None
+    }
+}
+
+/// Directory for the sources and executables of one test, removed with
+/// everything in it when the test ends.
+struct BuildDir(PathBuf);
+
+impl BuildDir {
+    fn new(test: &str) -> Self {
+        let name = format!("minipl-emit-llvm-{}-{}", std::process::id(), test);
+        let dir = env::temp_dir().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        BuildDir(dir)
+    }
+}
+
+impl Drop for BuildDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn build(toolchain: Toolchain, ir_file: &Path, runtime: &Path, executable: &Path) -> bool {
+    let status = match toolchain {
+        Toolchain::Clang => Command::new("clang")
+            .arg("-o")
+            .arg(executable)
+            .arg(ir_file)
+            .arg(runtime)
+            .status(),
+        Toolchain::Llc => {
+            let object = ir_file.with_extension("o");
+            let compiled = Command::new("llc")
+                .args(["-filetype=obj", "-relocation-model=pic", "-o"])
+                .arg(&object)
+                .arg(ir_file)
+                .status()
+                .unwrap();
+            if !compiled.success() {
+                return false;
+            }
+            Command::new("cc")
+                .args(["-std=c99", "-o"])
+                .arg(executable)
+                .arg(&object)
+                .arg(runtime)
+                .status()
+        }
+    };
+    status.unwrap().success()
+}
+
+fn compile_and_run(toolchain: Toolchain, dir: &BuildDir, case: &Case) -> Outcome {
+    let program = Parser::new(Lexer::new(case.source.clone()))
+        .with_integer_width(case.width)
+        .parse_program();
+    let ir = LlvmBackend::new()
+        .with_integer_width(case.width)
+        .emit_program(&program)
+        .unwrap();
+    let ir_file = dir.0.join(format!("{}.ll", case.name));
+    let runtime = dir.0.join("runtime.c");
+    let executable = dir.0.join(&case.name);
+    fs::write(&ir_file, ir).unwrap();
+    fs::write(&runtime, LlvmBackend::runtime()).unwrap();
+    assert!(
+        build(toolchain, &ir_file, &runtime, &executable),
+        "{} did not compile",
+        ir_file.display()
+    );
+
+    let mut child = Command::new(&executable)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let _ = child.stdin.take().unwrap().write_all(case.input.as_bytes());
+    let output = child.wait_with_output().unwrap();
+    Outcome {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        success: output.status.success(),
+    }
+}
+
+#[test]
+fn compile_golden_programs() {
+    if let Some(toolchain) = toolchain() {
+        let dir = BuildDir::new("golden");
+        assert_same_behaviour(&golden_programs(), true, |case| {
+            compile_and_run(toolchain, &dir, case)
+        });
+    }
+}
+
+#[test]
+fn compile_runtime_errors() {
+    if let Some(toolchain) = toolchain() {
+        let dir = BuildDir::new("errors");
+        assert_same_behaviour(&runtime_errors(), true, |case| {
+            compile_and_run(toolchain, &dir, case)
+        });
+    }
+}
@@ -0,0 +1,154 @@
+//! Compiles programs with the WebAssembly backend, validates and runs them
+//! with wasmi and compares their output and errors with those of the
+//! evaluator.
+
+mod backend;
+mod common;
+
+use backend::{assert_same_behaviour, golden_programs, runtime_errors, Case, Outcome};
+use minipl_interpreter::backend::wat::WatBackend;
+use minipl_interpreter::lexer::Lexer;
+use minipl_interpreter::parser::Parser;
+use minipl_interpreter::utils::IntegerWidth;
+use std::collections::VecDeque;
+use wasmi::{Caller, Engine, Extern, Linker, Module, Store};
+
+/// The host side of the imports of a compiled program.
+#[derive(Default)]
+struct Host {
+    lines: VecDeque<Vec<u8>>,
+    stdout: Vec<u8>,
+    error: Option<String>,
+}
+
+fn memory<'a>(caller: &'a mut Caller<'_, Host>) -> (&'a mut [u8], &'a mut Host) {
+    let memory = match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => memory,
+        _ => panic!("no memory exported"),
+    };
+    memory.data_and_store_mut(caller)
+}
+
+fn compile(source: &str, string_limit: Option<usize>) -> Vec<u8> {
+    let program = Parser::new(Lexer::new(source.to_string()))
+        .with_integer_width(IntegerWidth::I32)
+        .parse_program();
+    let mut backend = WatBackend::new();
+    if let Some(limit) = string_limit {
+        backend = backend.with_string_limit(limit);
+    }
+    let text = backend.emit_program(&program).unwrap();
+    wat::parse_str(&text).unwrap_or_else(|err| panic!("{}\n{}", err, text))
+}
+
+fn run(wasm: &[u8], input: &str) -> Outcome {
+    let engine = Engine::default();
+    let module = Module::new(&engine, wasm).unwrap();
+    let host = Host {
+        lines: input
+            .split_inclusive('\n')
+            .map(|line| line.as_bytes().to_vec())
+            .collect(),
+        ..Host::default()
+    };
+    let mut store = Store::new(&engine, host);
+    let mut linker = Linker::<Host>::new(&engine);
+    linker
+        .func_wrap(
+            "env",
+            "print",
+            |mut caller: Caller<'_, Host>, address: i32, length: i32| {
+                let (data, host) = memory(&mut caller);
+                let start = address as usize;
+                host.stdout
+                    .extend_from_slice(&data[start..start + length as usize]);
+            },
+        )
+        .unwrap();
+    linker
+        .func_wrap(
+            "env",
+            "read",
+            |mut caller: Caller<'_, Host>, address: i32, capacity: i32| -> i32 {
+                let (data, host) = memory(&mut caller);
+                let length = match host.lines.front() {
+                    Some(line) => line.len(),
+                    None => return 0,
+                };
+                if length <= capacity as usize {
+                    let line = host.lines.pop_front().unwrap();
+                    let start = address as usize;
+                    data[start..start + length].copy_from_slice(&line);
+                }
+                length as i32
+            },
+        )
+        .unwrap();
+    linker
+        .func_wrap(
+            "env",
+            "fail",
+            |mut caller: Caller<'_, Host>, line: i32, column: i32, address: i32, length: i32| {
+                let (data, host) = memory(&mut caller);
+                let start = address as usize;
+                let message = String::from_utf8_lossy(&data[start..start + length as usize]);
+                host.error = Some(format!(
+                    "\n{}:{}: Error: {}\nFound 1 error\n",
+                    line, column, message
+                ));
+            },
+        )
+        .unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let main = instance.get_typed_func::<(), ()>(&store, "main").unwrap();
+    let result = main.call(&mut store, ());
+    let host = store.into_data();
+    if result.is_err() {
+        assert!(
+            host.error.is_some(),
+            "trapped without an error: {:?}",
+            result
+        );
+    }
+    Outcome {
+        stdout: String::from_utf8(host.stdout).unwrap(),
+        stderr: host.error.clone().unwrap_or_default(),
+        success: host.error.is_none(),
+    }
+}
+
+fn compile_and_run(case: &Case) -> Outcome {
+    run(&compile(&case.source, None), &case.input)
+}
+
+#[test]
+fn run_golden_programs() {
+    assert_same_behaviour(&golden_programs(), true, compile_and_run);
+}
+
+#[test]
+fn run_runtime_errors() {
+    // Only `i32` integers are supported.
+    let cases: Vec<Case> = runtime_errors()
+        .into_iter()
+        .filter(|case| case.width == IntegerWidth::I32)
+        .collect();
+    assert_same_behaviour(&cases, true, compile_and_run);
+}
+
+#[test]
+fn limit_strings() {
+    let outcome = run(
+        &compile("print \"ab\" + \"cd\";\nprint \"abc\" + \"de\";", Some(4)),
+        "",
+    );
+    assert_eq!(outcome.stdout, "abcd");
+    assert_eq!(
+        outcome.stderr,
+        "\n2:1: Error: String longer than the limit of 4 bytes [E0211]\nFound 1 error\n"
+    );
+}
@@ -0,0 +1,168 @@
+//! Self-contained fuzzing: runs the fuzz entry points over the seed corpus
+//! (the golden programs, `test.mini` and `fuzz/regressions`) and over a fixed
+//! number of deterministic mutations of it. Longer, coverage-guided runs use
+//! the cargo-fuzz targets in `fuzz/`.
+
+use minipl_interpreter::fuzz;
+use std::fs;
+use std::panic;
+use std::path::Path;
+use std::thread;
+
+const MUTATIONS: usize = 3000;
+
+/// Deep nesting and recursion need more than the 2 MiB stack of test
+/// threads, so the targets get the 8 MiB of a main thread.
+const STACK_SIZE: usize = 8 << 20;
+
+const DICTIONARY: &[&str] = &[
+    "var ",
+    "for ",
+    " in ",
+    "..",
+    " do ",
+    "end for;",
+    "print ",
+    "read ",
+    "assert (",
+    ":=",
+    ": int",
+    ": string",
+    ": bool",
+    ";",
+    "(",
+    ")",
+    "!",
+    "&",
+    "+",
+    "-",
+    "*",
+    "/ 0",
+    "=",
+    "<",
+    ">",
+    "\"",
+    "\\q",
+    "\\",
+    "99999999999",
+    "2147483647",
+    "true",
+    "false",
+    "x",
+    "\n",
+    "procedure p(",
+    "function f(",
+    ") : int",
+    "end procedure;",
+    "end function;",
+    "return ",
+    "f(",
+    ", ",
+    ": array[1048576] of int",
+    "[",
+    "]",
+    "size(x)",
+    "substr(x, 0, 1)",
+    "to_int(x)",
+    "upper(",
+];
+
+fn seeds() -> Vec<Vec<u8>> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut seeds = vec![fs::read(root.join("test.mini")).unwrap()];
+    for dir in &["tests/programs", "fuzz/regressions"] {
+        let mut paths: Vec<_> = fs::read_dir(root.join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "mini"))
+            .collect();
+        paths.sort();
+        seeds.extend(paths.iter().map(|path| fs::read(path).unwrap()));
+    }
+    seeds
+}
+
+fn on_large_stack(test: impl FnOnce() + Send + 'static) {
+    let runner = thread::Builder::new().stack_size(STACK_SIZE);
+    runner.spawn(test).unwrap().join().unwrap();
+}
+
+fn run_targets(data: &[u8]) {
+    let result = panic::catch_unwind(|| {
+        fuzz::lex(data);
+        fuzz::parse(data);
+        fuzz::evaluate(data);
+    });
+    if result.is_err() {
+        panic!(
+            "fuzz target panicked on input:\n{}",
+            String::from_utf8_lossy(data)
+        );
+    }
+}
+
+/// xorshift64, so every run explores the same inputs.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+}
+
+fn mutate(rng: &mut Rng, data: &mut Vec<u8>, seeds: &[Vec<u8>]) {
+    let at = rng.below(data.len() + 1);
+    match rng.below(5) {
+        0 if !data.is_empty() => {
+            let i = rng.below(data.len());
+            data[i] ^= 1 << rng.below(8);
+        }
+        1 => data.insert(at, rng.next() as u8),
+        2 if !data.is_empty() => {
+            let end = (at + rng.below(16)).min(data.len());
+            data.drain(at.min(end)..end);
+        }
+        3 => {
+            let seed = &seeds[rng.below(seeds.len())];
+            let from = rng.below(seed.len());
+            let end = (from + rng.below(64)).min(seed.len());
+            let chunk = seed[from..end].to_vec();
+            data.splice(at..at, chunk);
+        }
+        _ => {
+            let word = DICTIONARY[rng.below(DICTIONARY.len())].as_bytes();
+            data.splice(at..at, word.iter().cloned());
+        }
+    }
+}
+
+#[test]
+fn seeds_do_not_panic() {
+    on_large_stack(|| {
+        for seed in seeds() {
+            run_targets(&seed);
+        }
+    });
+}
+
+#[test]
+fn mutations_do_not_panic() {
+    on_large_stack(|| {
+        let seeds = seeds();
+        let mut rng = Rng(0x5eed_1234_abcd_ef01);
+        for _ in 0..MUTATIONS {
+            let mut data = seeds[rng.below(seeds.len())].clone();
+            for _ in 0..=rng.below(4) {
+                mutate(&mut rng, &mut data, &seeds);
+            }
+            run_targets(&data);
+        }
+    });
+}
@@ -0,0 +1,185 @@
+//! Golden-file tests for whole programs.
+//!
+//! Every `tests/programs/<name>.mini` is run through the interpreter. Its
+//! siblings hold the expectations:
+//!
+//! - `<name>.stdin`  input fed to the program (optional)
+//! - `<name>.args`   extra command line options, one per line (optional)
+//! - `<name>.stdout` expected standard output (missing means empty)
+//! - `<name>.stderr` expected standard error (missing means empty)
+//! - `<name>.exit`   expected exit code (missing means 0)
+//!
+//! Run `cargo test --test golden -- --bless` to rewrite the expectations from
+//! the current behaviour, and pass names to run a subset.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+struct Outcome {
+    stdout: String,
+    stderr: String,
+    exit: i32,
+}
+
+fn main() {
+    let mut bless = env::var_os("MINIPL_BLESS").is_some();
+    let mut filters = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--bless" => bless = true,
+            flag if flag.starts_with('-') => {}
+            name => filters.push(name.to_string()),
+        }
+    }
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let mut cases: Vec<PathBuf> = fs::read_dir(&dir)
+        .expect("tests/programs is missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "mini"))
+        .filter(|path| filters.is_empty() || filters.iter().any(|f| stem(path).contains(f)))
+        .collect();
+    cases.sort();
+
+    let mut failures = 0;
+    for case in &cases {
+        let outcome = run(case);
+        if bless {
+            write_expectation(case, "stdout", &outcome.stdout);
+            write_expectation(case, "stderr", &outcome.stderr);
+            let exit = if outcome.exit == 0 {
+                String::new()
+            } else {
+                format!("{}\n", outcome.exit)
+            };
+            write_expectation(case, "exit", &exit);
+            println!("golden {} ... blessed", stem(case));
+            continue;
+        }
+        let mut report = String::new();
+        compare(
+            "stdout",
+            &read_expectation(case, "stdout"),
+            &outcome.stdout,
+            &mut report,
+        );
+        compare(
+            "stderr",
+            &read_expectation(case, "stderr"),
+            &outcome.stderr,
+            &mut report,
+        );
+        let expected_exit = read_expectation(case, "exit").trim().parse().unwrap_or(0);
+        if expected_exit != outcome.exit {
+            report.push_str(&format!(
+                "exit code: expected {} got {}\n",
+                expected_exit, outcome.exit
+            ));
+        }
+        if report.is_empty() {
+            println!("golden {} ... ok", stem(case));
+        } else {
+            failures += 1;
+            println!("golden {} ... FAILED\n{}", stem(case), report);
+        }
+    }
+
+    println!(
+        "\ngolden result: {} passed; {} failed",
+        cases.len() - failures,
+        failures
+    );
+    if failures > 0 {
+        println!("run `cargo test --test golden -- --bless` to accept the new output");
+        process::exit(1);
+    }
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem().unwrap().to_string_lossy().into_owned()
+}
+
+fn sibling(case: &Path, extension: &str) -> PathBuf {
+    case.with_extension(extension)
+}
+
+fn read_expectation(case: &Path, extension: &str) -> String {
+    fs::read_to_string(sibling(case, extension)).unwrap_or_default()
+}
+
+fn write_expectation(case: &Path, extension: &str, contents: &str) {
+    let path = sibling(case, extension);
+    if contents.is_empty() {
+        let _ = fs::remove_file(path);
+    } else {
+        fs::write(path, contents).unwrap();
+    }
+}
+
+fn run(case: &Path) -> Outcome {
+    let args = read_expectation(case, "args");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_minipl-interpreter"))
+        .current_dir(case.parent().unwrap())
+        .args(args.lines().filter(|line| !line.is_empty()))
+        .arg(case.file_name().unwrap())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start the interpreter");
+    let input = read_expectation(case, "stdin");
+    // The program may exit before reading all of its input.
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    let output = child.wait_with_output().unwrap();
+    Outcome {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        exit: output.status.code().unwrap_or(-1),
+    }
+}
+
+fn compare(stream: &str, expected: &str, actual: &str, report: &mut String) {
+    if expected == actual {
+        return;
+    }
+    report.push_str(&format!("--- expected {}\n+++ actual {}\n", stream, stream));
+    for line in diff(expected, actual) {
+        report.push_str(&line);
+        report.push('\n');
+    }
+}
+
+/// Line diff based on the longest common subsequence.
+fn diff(expected: &str, actual: &str) -> Vec<String> {
+    let old: Vec<&str> = expected.split('\n').collect();
+    let new: Vec<&str> = actual.split('\n').collect();
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(format!(" {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            lines.push(format!("+{}", new[j]));
+            j += 1;
+        } else {
+            lines.push(format!("-{}", old[i]));
+            i += 1;
+        }
+    }
+    lines
+}
@@ -0,0 +1,52 @@
+//! Runs programs lowered to the intermediate representation in its
+//! interpreter and compares their output and errors with those of the
+//! evaluator.
+
+mod backend;
+mod common;
+
+use backend::{
+    assert_same_behaviour, golden_programs, runtime_error, runtime_errors, Case, Outcome,
+};
+use common::Shared;
+use minipl_interpreter::ir::interpreter::Interpreter;
+use minipl_interpreter::ir::lower::Lowering;
+use minipl_interpreter::lexer::Lexer;
+use minipl_interpreter::parser::Parser;
+use std::io;
+
+fn interpret(case: &Case) -> Outcome {
+    let program = Parser::new(Lexer::new(case.source.clone()))
+        .with_integer_width(case.width)
+        .parse_program();
+    let module = Lowering::new().lower_program(&program).unwrap();
+    let output = Shared::default();
+    let mut interpreter = Interpreter::new(module)
+        .with_io(
+            Box::new(io::Cursor::new(case.input.clone())),
+            Box::new(output.clone()),
+        )
+        .with_integer_width(case.width);
+    let (stderr, success) = match interpreter.run_program() {
+        Ok(()) => (String::new(), true),
+        Err(err) => {
+            let span = interpreter.current_span();
+            (runtime_error(&case.source, err, span), false)
+        }
+    };
+    Outcome {
+        stdout: output.text(),
+        stderr,
+        success,
+    }
+}
+
+#[test]
+fn interpret_golden_programs() {
+    assert_same_behaviour(&golden_programs(), true, interpret);
+}
+
+#[test]
+fn interpret_runtime_errors() {
+    assert_same_behaviour(&runtime_errors(), true, interpret);
+}
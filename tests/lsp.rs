@@ -0,0 +1,145 @@
+//! Drives the `minipl-lsp` binary over stdio the way an editor would.
+
+use minipl_interpreter::json::Json;
+use minipl_interpreter::lsp::{read_message, write_message};
+use std::io::BufReader;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_minipl-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to start minipl-lsp");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Client {
+            child,
+            stdin,
+            stdout,
+            next_id: 1,
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Json) {
+        let message = Json::object(vec![
+            ("jsonrpc", "2.0".into()),
+            ("method", method.into()),
+            ("params", params),
+        ]);
+        write_message(&mut self.stdin, &message).unwrap();
+    }
+
+    fn request(&mut self, method: &str, params: Json) -> Json {
+        let id = self.next_id;
+        self.next_id += 1;
+        let message = Json::object(vec![
+            ("jsonrpc", "2.0".into()),
+            ("id", id.into()),
+            ("method", method.into()),
+            ("params", params),
+        ]);
+        write_message(&mut self.stdin, &message).unwrap();
+        loop {
+            let reply = self.receive();
+            if reply.get("id").and_then(Json::as_i64) == Some(id) {
+                return reply.get("result").cloned().unwrap();
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Json {
+        read_message(&mut self.stdout)
+            .unwrap()
+            .expect("server closed its output")
+    }
+}
+
+fn document(uri: &str) -> Json {
+    Json::object(vec![("uri", uri.into())])
+}
+
+fn position(uri: &str, line: i64, character: i64) -> Json {
+    Json::object(vec![
+        ("textDocument", document(uri)),
+        (
+            "position",
+            Json::object(vec![("line", line.into()), ("character", character.into())]),
+        ),
+    ])
+}
+
+#[test]
+fn scripted_session() {
+    let uri = "file:///work/loop.mini";
+    let mut client = Client::start();
+
+    let init = client.request("initialize", Json::object(vec![]));
+    let capabilities = init.get("capabilities").unwrap();
+    assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+    client.notify("initialized", Json::object(vec![]));
+
+    let text = "var n : int := 3;\nvar i : int;\nfor i in 1..n do\nprint i+\"x\";\nend for;";
+    let opened = Json::object(vec![
+        ("uri", uri.into()),
+        ("languageId", "minipl".into()),
+        ("version", 1_i64.into()),
+        ("text", text.into()),
+    ]);
+    client.notify(
+        "textDocument/didOpen",
+        Json::object(vec![("textDocument", opened)]),
+    );
+    let published = client.receive();
+    assert_eq!(
+        published.get("method").and_then(Json::as_str),
+        Some("textDocument/publishDiagnostics")
+    );
+    let diagnostics = published
+        .get("params")
+        .and_then(|p| p.get("diagnostics"))
+        .and_then(Json::as_array)
+        .unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].get("range").map(Json::to_string),
+        Some(r#"{"start":{"line":3,"character":8},"end":{"line":3,"character":11}}"#.to_string())
+    );
+
+    let hover = client.request("textDocument/hover", position(uri, 2, 12));
+    let contents = hover.get("contents").and_then(|c| c.get("value"));
+    assert_eq!(contents, Some(&Json::from("```minipl\nvar n : int\n```")));
+
+    let definition = client.request("textDocument/definition", position(uri, 3, 6));
+    assert_eq!(
+        definition.get("range").map(Json::to_string),
+        Some(r#"{"start":{"line":1,"character":0},"end":{"line":1,"character":12}}"#.to_string())
+    );
+
+    let formatted = client.request(
+        "textDocument/formatting",
+        Json::object(vec![("textDocument", document(uri))]),
+    );
+    let new_text = formatted.as_array().unwrap()[0]
+        .get("newText")
+        .and_then(Json::as_str)
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        new_text,
+        "var n : int := 3;\nvar i : int;\nfor i in 1..n do\n    print i + \"x\";\nend for;\n"
+    );
+
+    assert_eq!(client.request("shutdown", Json::Null), Json::Null);
+    client.notify("exit", Json::Null);
+    let status = client.child.wait().unwrap();
+    assert_eq!(status.code(), Some(0));
+}
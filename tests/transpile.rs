@@ -0,0 +1,178 @@
+//! Tests of the Python and JavaScript translations.
+//!
+//! The translation of every golden program is compared with
+//! `tests/transpiled/<name>.py` and `<name>.js`, without the runtime that
+//! starts every file. Run with `MINIPL_BLESS=1` to rewrite them from the
+//! current output. When `python3` or `node` is installed, the translations
+//! also run and must behave like the evaluator, apart from the line and
+//! column of errors.
+
+mod backend;
+mod common;
+
+use backend::{assert_same_behaviour, golden_programs, runtime_errors, Case, Outcome};
+use minipl_interpreter::backend::javascript::JavaScriptBackend;
+use minipl_interpreter::backend::python::PythonBackend;
+use minipl_interpreter::lexer::Lexer;
+use minipl_interpreter::parser::Parser;
+use minipl_interpreter::utils::IntegerWidth;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Directory for the translations run by one test, removed with everything
+/// in it when the test ends.
+struct BuildDir(PathBuf);
+
+impl BuildDir {
+    fn new(test: &str) -> Self {
+        let name = format!("minipl-transpile-{}-{}", std::process::id(), test);
+        let dir = env::temp_dir().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        BuildDir(dir)
+    }
+}
+
+impl Drop for BuildDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Language {
+    Python,
+    JavaScript,
+}
+
+impl Language {
+    fn extension(self) -> &'static str {
+        match self {
+            Language::Python => "py",
+            Language::JavaScript => "js",
+        }
+    }
+
+    fn interpreter(self) -> &'static str {
+        match self {
+            Language::Python => "python3",
+            Language::JavaScript => "node",
+        }
+    }
+
+    /// Start of the translated program after the runtime.
+    fn marker(self) -> &'static str {
+        match self {
+            Language::Python => "# Translated from Mini-PL.\n",
+            Language::JavaScript => "// Translated from Mini-PL.\n",
+        }
+    }
+
+    fn translate(self, source: &str, width: IntegerWidth) -> String {
+        let program = Parser::new(Lexer::new(source.to_string()))
+            .with_integer_width(width)
+            .parse_program();
+        let translation = match self {
+            Language::Python => PythonBackend::new()
+                .with_integer_width(width)
+                .emit_program(&program),
+            Language::JavaScript => JavaScriptBackend::new().emit_program(&program),
+        };
+        translation.unwrap()
+    }
+
+    fn is_installed(self) -> bool {
+        let found = Command::new(self.interpreter())
+            .arg("--version")
+            .output()
+            .is_ok();
+        if !found {
+            eprintln!("{} not found, skipping", self.interpreter());
+        }
+        found
+    }
+
+    fn translate_and_run(self, dir: &BuildDir, case: &Case) -> Outcome {
+        let translation = self.translate(&case.source, case.width);
+        let file = dir.0.join(format!("{}.{}", case.name, self.extension()));
+        fs::write(&file, translation).unwrap();
+        let mut child = Command::new(self.interpreter())
+            .arg(&file)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let _ = child.stdin.take().unwrap().write_all(case.input.as_bytes());
+        let output = child.wait_with_output().unwrap();
+        Outcome {
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            success: output.status.success(),
+        }
+    }
+
+    /// Whether the translation supports `i64` integers.
+    fn is_wide(self) -> bool {
+        match self {
+            Language::Python => true,
+            Language::JavaScript => false,
+        }
+    }
+}
+
+const LANGUAGES: [Language; 2] = [Language::Python, Language::JavaScript];
+
+#[test]
+fn translate_golden_programs() {
+    let bless = env::var_os("MINIPL_BLESS").is_some();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/transpiled");
+    let mut translated = 0;
+    for case in golden_programs() {
+        for language in LANGUAGES {
+            let translation = language.translate(&case.source, case.width);
+            let (_, program) = translation.split_once(language.marker()).unwrap();
+            let path = dir.join(format!("{}.{}", case.name, language.extension()));
+            if bless {
+                fs::create_dir_all(&dir).unwrap();
+                fs::write(&path, program).unwrap();
+                continue;
+            }
+            let expected = fs::read_to_string(&path).unwrap_or_default();
+            assert_eq!(program, expected, "{} differs", path.display());
+            translated += 1;
+        }
+    }
+    assert!(bless || translated > 20);
+}
+
+// The translations do not know where an error happened.
+
+#[test]
+fn run_golden_programs() {
+    let dir = BuildDir::new("golden");
+    for language in LANGUAGES {
+        if language.is_installed() {
+            assert_same_behaviour(&golden_programs(), false, |case| {
+                language.translate_and_run(&dir, case)
+            });
+        }
+    }
+}
+
+#[test]
+fn run_runtime_errors() {
+    let dir = BuildDir::new("errors");
+    for language in LANGUAGES {
+        if !language.is_installed() {
+            continue;
+        }
+        let cases: Vec<Case> = runtime_errors()
+            .into_iter()
+            .filter(|case| language.is_wide() || case.width == IntegerWidth::I32)
+            .collect();
+        assert_same_behaviour(&cases, false, |case| language.translate_and_run(&dir, case));
+    }
+}
@@ -0,0 +1,16 @@
+
+run(() => {
+  let a = 7;
+  let b = check(a * 3);
+  write(check(b - 1));
+  write("\n");
+  write(check(div(b, 2) + 1));
+  write("\n");
+  write(a === 7);
+  write("\n");
+  let s = "foo" + "bar";
+  write(s);
+  write("\n");
+  write(!and((compare(s, "zzz") < 0), true));
+  write("\n");
+});
@@ -0,0 +1,19 @@
+
+
+def main():
+    a = 7
+    b = check(a * 3)
+    write(check(b - 1))
+    write("\n")
+    write(check(div(b, 2) + 1))
+    write("\n")
+    write(a == 7)
+    write("\n")
+    s = "foo" + "bar"
+    write(s)
+    write("\n")
+    write(not ((s < "zzz") & True))
+    write("\n")
+
+
+run(main)
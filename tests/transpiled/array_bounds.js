@@ -0,0 +1,9 @@
+
+run(() => {
+  let a = new Array(3).fill(0);
+  let i;
+  for (i of range(0, 3)) {
+    store(a, i, i);
+    write(i);
+  }
+});
@@ -0,0 +1,11 @@
+
+
+def main():
+    a = [0] * 3
+    i = None
+    for i in range(0, 3 + 1):
+        store(a, i, i)
+        write(i)
+
+
+run(main)
@@ -0,0 +1,36 @@
+
+const reversed = func("reversed", (words) => {
+  let result = new Array(3).fill("");
+  let last = check(words.length - 1);
+  let i;
+  for (i of range(0, check(words.length - 1))) {
+    store(result, check(last - i), words[at(words, i)]);
+  }
+  return [...result];
+});
+
+run(() => {
+  let squares = new Array(5).fill(0);
+  let i;
+  for (i of range(0, check(squares.length - 1))) {
+    store(squares, i, check(i * i));
+  }
+  let total = 0;
+  for (i of range(0, check(squares.length - 1))) {
+    total = check(total + squares[at(squares, i)]);
+  }
+  write(total);
+  write("\n");
+  let words = new Array(3).fill("");
+  store(words, 0, "one");
+  store(words, 1, "two");
+  store(words, 2, "three");
+  let backwards = reversed([...words]);
+  for (i of range(0, check(backwards.length - 1))) {
+    write(backwards[at(backwards, i)]);
+    write(" ");
+  }
+  write("\n");
+  let flags = new Array(2).fill(false);
+  assert(!flags[at(flags, 1)], "(! flags[1])");
+});
@@ -0,0 +1,36 @@
+
+
+@function("reversed")
+def reversed(words):
+    result = [""] * 3
+    last = check(len(words) - 1)
+    i = None
+    for i in range(0, check(len(words) - 1) + 1):
+        store(result, check(last - i), words[at(words, i)])
+    return list(result)
+
+
+def main():
+    squares = [0] * 5
+    i = None
+    for i in range(0, check(len(squares) - 1) + 1):
+        store(squares, i, check(i * i))
+    total = 0
+    for i in range(0, check(len(squares) - 1) + 1):
+        total = check(total + squares[at(squares, i)])
+    write(total)
+    write("\n")
+    words = [""] * 3
+    store(words, 0, "one")
+    store(words, 1, "two")
+    store(words, 2, "three")
+    backwards = reversed(list(words))
+    for i in range(0, check(len(backwards) - 1) + 1):
+        write(backwards[at(backwards, i)])
+        write(" ")
+    write("\n")
+    flags = [False] * 2
+    assert_(not flags[at(flags, 1)], "(! flags[1])")
+
+
+run(main)
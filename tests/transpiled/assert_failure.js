@@ -0,0 +1,6 @@
+
+run(() => {
+  let x = 3;
+  assert(x === 4, "(x, =, 4)");
+  write("still running");
+});
@@ -0,0 +1,9 @@
+
+
+def main():
+    x = 3
+    assert_(x == 4, "(x, =, 4)")
+    write("still running")
+
+
+run(main)
@@ -0,0 +1,13 @@
+
+run(() => {
+  let i;
+  for (i of range(1, 3)) {
+    let line = "row ";
+    let j;
+    for (j of range(1, i)) {
+      let cell = "*";
+      line = line + cell;
+    }
+    write(line + "\n");
+  }
+});
@@ -0,0 +1,14 @@
+
+
+def main():
+    i = None
+    for i in range(1, 3 + 1):
+        line = "row "
+        j = None
+        for j in range(1, i + 1):
+            cell = "*"
+            line = line + cell
+        write(line + "\n")
+
+
+run(main)
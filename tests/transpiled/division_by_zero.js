@@ -0,0 +1,5 @@
+
+run(() => {
+  let x = 0;
+  write(div(1, x));
+});
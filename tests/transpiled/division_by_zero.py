@@ -0,0 +1,8 @@
+
+
+def main():
+    x = 0
+    write(div(1, x))
+
+
+run(main)
@@ -0,0 +1,17 @@
+
+run(() => {
+  let i;
+  let total = 0;
+  for (i of range(1, 5)) {
+    total = check(total + i);
+    write(i);
+    write(" ");
+  }
+  write("\n");
+  write(total);
+  write("\n");
+  assert(init(i, "i") === 5, "(i, =, 5)");
+  for (i of range(3, 1)) {
+    write("never");
+  }
+});
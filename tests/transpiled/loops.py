@@ -0,0 +1,18 @@
+
+
+def main():
+    i = None
+    total = 0
+    for i in range(1, 5 + 1):
+        total = check(total + i)
+        write(i)
+        write(" ")
+    write("\n")
+    write(total)
+    write("\n")
+    assert_(init(i, "i") == 5, "(i, =, 5)")
+    for i in range(3, 1 + 1):
+        write("never")
+
+
+run(main)
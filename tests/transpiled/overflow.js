@@ -0,0 +1,7 @@
+
+run(() => {
+  let x = 2147483647;
+  write(x);
+  x = check(x + 1);
+  write(x);
+});
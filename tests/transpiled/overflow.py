@@ -0,0 +1,10 @@
+
+
+def main():
+    x = 2147483647
+    write(x)
+    x = check(x + 1)
+    write(x)
+
+
+run(main)
@@ -0,0 +1,9 @@
+
+run(() => {
+  let name;
+  let n;
+  name = readString();
+  n = readInt();
+  write("Hello " + name);
+  write(check(n * 2));
+});
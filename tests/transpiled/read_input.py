@@ -0,0 +1,12 @@
+
+
+def main():
+    name = None
+    n = None
+    name = read_string()
+    n = read_int()
+    write("Hello " + name)
+    write(check(n * 2))
+
+
+run(main)
@@ -0,0 +1,27 @@
+
+const fib = func("fib", (n) => {
+  let i;
+  for (i of range(2, n)) {
+    return check(fib(check(n - 1)) + fib(check(n - 2)));
+  }
+  return n;
+});
+
+const greet = procedure((name, times) => {
+  let i;
+  for (i of range(1, times)) {
+    write("Hello, " + name);
+    write("!\n");
+  }
+});
+
+run(() => {
+  let n;
+  for (n of range(0, 10)) {
+    write(fib(n));
+    write(" ");
+  }
+  write("\n");
+  greet("Ada", 2);
+  greet("Bob", 0);
+});
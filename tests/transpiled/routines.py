@@ -0,0 +1,29 @@
+
+
+@function("fib")
+def fib(n):
+    i = None
+    for i in range(2, n + 1):
+        return check(fib(check(n - 1)) + fib(check(n - 2)))
+    return n
+
+
+@procedure
+def greet(name, times):
+    i = None
+    for i in range(1, times + 1):
+        write("Hello, " + name)
+        write("!\n")
+
+
+def main():
+    n = None
+    for n in range(0, 10 + 1):
+        write(fib(n))
+        write(" ")
+    write("\n")
+    greet("Ada", 2)
+    greet("Bob", 0)
+
+
+run(main)
@@ -0,0 +1,7 @@
+
+run(() => {
+  let n;
+  write("before ");
+  n = readInt();
+  write("after");
+});
@@ -0,0 +1,10 @@
+
+
+def main():
+    n = None
+    write("before ")
+    n = read_int()
+    write("after")
+
+
+run(main)
@@ -0,0 +1,15 @@
+
+run(() => {
+  let X = check(1 + check(2 * 6));
+  assert(12 > X, "(12, >, X)");
+  let n = 0;
+  write("How many times? ");
+  n = readInt();
+  let x;
+  for (x of range(0, n)) {
+    write("Hello" + " World!\n");
+  }
+  assert(init(x, "x") === n, "(x, =, n)");
+  let X1_ = check(check(1 + 1) + check(2 * check(6 - check(4 + 2))));
+  write(X1_);
+});
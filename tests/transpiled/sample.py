@@ -0,0 +1,17 @@
+
+
+def main():
+    X = check(1 + check(2 * 6))
+    assert_(12 > X, "(12, >, X)")
+    n = 0
+    write("How many times? ")
+    n = read_int()
+    x = None
+    for x in range(0, n + 1):
+        write("Hello" + " World!\n")
+    assert_(init(x, "x") == n, "(x, =, n)")
+    X1_ = check(check(1 + 1) + check(2 * check(6 - check(4 + 2))))
+    write(X1_)
+
+
+run(main)
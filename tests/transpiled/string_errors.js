@@ -0,0 +1,7 @@
+
+run(() => {
+  let word = "minipl";
+  write(substr(word, 4, 2));
+  write("\n");
+  write(substr(word, 4, 3));
+});
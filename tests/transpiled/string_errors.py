@@ -0,0 +1,10 @@
+
+
+def main():
+    word = "minipl"
+    write(substr(word, 4, 2))
+    write("\n")
+    write(substr(word, 4, 3))
+
+
+run(main)
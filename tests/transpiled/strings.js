@@ -0,0 +1,21 @@
+
+run(() => {
+  let name = "Mini-PL";
+  write(length(name));
+  write("\n");
+  write(name.toUpperCase() + name.toLowerCase());
+  write("\n");
+  write(substr(name, 5, 2));
+  write("\n");
+  assert(name.includes("PL"), "contains(name, \"PL\")");
+  assert(!name.includes("pl"), "(! contains(name, \"pl\"))");
+  let total = 0;
+  let line;
+  let i;
+  for (i of range(1, 3)) {
+    line = readString();
+    total = check(total + toInt(line));
+  }
+  write("total " + String(total));
+  write("\n");
+});
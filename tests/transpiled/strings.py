@@ -0,0 +1,23 @@
+
+
+def main():
+    name = "Mini-PL"
+    write(len(name))
+    write("\n")
+    write(name.upper() + name.lower())
+    write("\n")
+    write(substr(name, 5, 2))
+    write("\n")
+    assert_(contains(name, "PL"), "contains(name, \"PL\")")
+    assert_(not contains(name, "pl"), "(! contains(name, \"pl\"))")
+    total = 0
+    line = None
+    i = None
+    for i in range(1, 3 + 1):
+        line = read_string()
+        total = check(total + to_int(line))
+    write("total " + str(total))
+    write("\n")
+
+
+run(main)